@@ -29,6 +29,39 @@ fn main() {
             "start_server",
             "download_file",
             "upload_file",
+            "upload_file_chunked",
+            "upload_resume_info",
+            "transfer_enqueue",
+            "transfer_pause",
+            "transfer_resume",
+            "transfer_cancel",
+            "transfer_list",
+            "transfer_set_concurrency",
+            "bandwidth_set_policy",
+            "bandwidth_status",
+            "network_set_metered",
+            "set_network_config",
+            "get_network_config",
+            "opds_fetch",
+            "opds_search",
+            "opds_server_start",
+            "opds_server_stop",
+            "opds_server_status",
+            "opds_server_set_library",
+            "webdav_sync_book_config",
+            "webdav_upload_book_file",
+            "kosync_connect",
+            "kosync_pull",
+            "kosync_push",
+            "kosync_xpointer_to_cfi",
+            "kosync_cfi_to_xpointer",
+            "kosync_server_start",
+            "kosync_server_add_user",
+            "kosync_server_stop",
+            "kosync_server_status",
+            "export_annotations",
+            "import_kindle_clippings",
+            "import_koreader_sidecars",
             "get_environment_variable",
             "get_executable_dir",
             "set_webview_info",
@@ -36,11 +69,22 @@ fn main() {
             "allow_paths_in_scopes",
             "optimize_cover_thumbnails",
             "read_dir",
+            "read_dir_dedup",
+            "library_watch_start",
+            "library_watch_stop",
+            "library_watch_list",
             "parse_epub_metadata",
             "extract_epub_cover_full",
             "parse_epub_full",
+            "epub_validate",
+            "fulltext_index_book",
+            "fulltext_remove_book",
+            "fulltext_list_books",
+            "fulltext_search",
             "parse_mobi_metadata",
             "extract_mobi_cover_full",
+            "parse_fb2_metadata",
+            "parse_comic_archive",
             "auth_with_safari",
             "start_apple_sign_in",
             "set_traffic_lights",
@@ -59,7 +103,12 @@ fn main() {
             "localsend_respond",
             "localsend_cancel_receive",
             "localsend_send_files",
+            "localsend_send_folder",
             "localsend_cancel_send",
+            "localsend_list_trusted",
+            "localsend_set_trusted",
+            "localsend_remove_trusted",
+            "localsend_take_imported",
         ]),
     ))
     .expect("failed to run tauri-build");
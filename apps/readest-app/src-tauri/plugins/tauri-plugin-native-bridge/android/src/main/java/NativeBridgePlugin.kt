@@ -9,6 +9,10 @@ import android.content.ComponentName
 import android.content.ContentValues
 import android.content.Context
 import android.content.Intent
+import android.net.ConnectivityManager
+import android.net.Network
+import android.net.NetworkCapabilities
+import android.net.NetworkRequest
 import android.net.Uri
 import android.provider.MediaStore
 import android.util.Log
@@ -224,6 +228,17 @@ class NativeBridgePlugin(private val activity: Activity): Plugin(activity) {
     // packets without it. Released in onDestroy.
     private var multicastLock: android.net.wifi.WifiManager.MulticastLock? = null
 
+    // Reports connectivity changes as `network-status-changed` so the Rust
+    // bandwidth limiter can hold internet transfers back on metered networks.
+    private var connectivityManager: ConnectivityManager? = null
+    private var lastNetworkStatus: Pair<Boolean, Boolean>? = null
+    private val networkCallback = object : ConnectivityManager.NetworkCallback() {
+        override fun onAvailable(network: Network) = emitNetworkStatusIfChanged()
+        override fun onLost(network: Network) = emitNetworkStatusIfChanged()
+        override fun onCapabilitiesChanged(network: Network, capabilities: NetworkCapabilities) =
+            emitNetworkStatusIfChanged()
+    }
+
     private var sensorManager: SensorManager? = null
     private var ambientLightListening = false
     private var lastEmittedLux: Float = Float.NaN
@@ -284,6 +299,12 @@ class NativeBridgePlugin(private val activity: Activity): Plugin(activity) {
             // Releasing an unheld lock throws on some OEM builds; ignore.
         }
         multicastLock = null
+        try {
+            connectivityManager?.unregisterNetworkCallback(networkCallback)
+        } catch (_: Exception) {
+            // Never registered (no ConnectivityManager); nothing to undo.
+        }
+        connectivityManager = null
         pluginScope.cancel()
         activity.application.unregisterActivityLifecycleCallbacks(lifecycleCallbacks)
         instance = null
@@ -324,6 +345,7 @@ class NativeBridgePlugin(private val activity: Activity): Plugin(activity) {
         gamepadConnected = hasConnectedGamepad()
         inputManager?.registerInputDeviceListener(gamepadInputListener, null)
         activity.application.registerActivityLifecycleCallbacks(lifecycleCallbacks)
+        registerNetworkCallback()
         handleIntent(activity.intent)
         pendingFilePickerData?.let { data ->
             pendingFilePickerData = null
@@ -556,6 +578,56 @@ class NativeBridgePlugin(private val activity: Activity): Plugin(activity) {
         }
     }
 
+    private fun registerNetworkCallback() {
+        val cm = activity.getSystemService(Context.CONNECTIVITY_SERVICE) as? ConnectivityManager ?: return
+        try {
+            val request = NetworkRequest.Builder()
+                .addCapability(NetworkCapabilities.NET_CAPABILITY_INTERNET)
+                .build()
+            cm.registerNetworkCallback(request, networkCallback)
+            connectivityManager = cm
+        } catch (e: Exception) {
+            Log.w("NativeBridgePlugin", "Failed to register network callback", e)
+        }
+    }
+
+    private fun currentNetworkStatus(): Pair<Boolean, Boolean> {
+        val cm = connectivityManager
+            ?: activity.getSystemService(Context.CONNECTIVITY_SERVICE) as? ConnectivityManager
+            ?: return Pair(true, false)
+        val connected = if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.M) {
+            cm.getNetworkCapabilities(cm.activeNetwork)
+                ?.hasCapability(NetworkCapabilities.NET_CAPABILITY_INTERNET) == true
+        } else {
+            @Suppress("DEPRECATION")
+            cm.activeNetworkInfo?.isConnected == true
+        }
+        // isActiveNetworkMetered covers cellular and Wi-Fi the user (or the
+        // hotspot) marked as metered.
+        return Pair(connected, connected && cm.isActiveNetworkMetered)
+    }
+
+    private fun networkStatusPayload(status: Pair<Boolean, Boolean>) = JSObject().apply {
+        put("connected", status.first)
+        put("metered", status.second)
+    }
+
+    private fun emitNetworkStatusIfChanged() {
+        val status = currentNetworkStatus()
+        if (status == lastNetworkStatus) return
+        lastNetworkStatus = status
+        triggerEvent("network-status-changed", networkStatusPayload(status))
+    }
+
+    @Command
+    fun get_network_status(invoke: Invoke) {
+        try {
+            invoke.resolve(networkStatusPayload(currentNetworkStatus()))
+        } catch (e: Exception) {
+            invoke.reject(e.message ?: "network status unavailable")
+        }
+    }
+
     @Command
     fun copy_uri_to_path(invoke: Invoke) {
         val args = invoke.parseArgs(CopyURIRequestArgs::class.java)
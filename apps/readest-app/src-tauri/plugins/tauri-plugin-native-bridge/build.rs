@@ -49,6 +49,7 @@ const COMMANDS: &[&str] = &[
     "read_share_clip_html",
     "icloud_container_status",
     "icloud_ensure_downloaded",
+    "get_network_status",
 ];
 
 fn main() {
@@ -2,6 +2,7 @@ import AVFoundation
 import AuthenticationServices
 import CoreText
 import MediaPlayer
+import Network
 import ObjectiveC
 import StoreKit
 import SwiftRs
@@ -586,10 +587,26 @@ class NativeBridgePlugin: Plugin {
   private var appDesiredBrightness: CGFloat?
   private var systemBrightnessBeforeOverride: CGFloat?
 
+  // Connectivity for the Rust bandwidth limiter: "metered" is an expensive
+  // path (cellular, personal hotspot) or Low Data Mode. Changes are pushed as
+  // `network-status-changed`; get_network_status reads the latest path.
+  private let pathMonitor = NWPathMonitor()
+  private let pathMonitorQueue = DispatchQueue(label: "com.readest.native_bridge.network")
+  private var lastNetworkStatus: (connected: Bool, metered: Bool)?
+
   @objc public override func load(webview: WKWebView) {
     self.webView = webview
     logger.log("NativeBridgePlugin loaded")
 
+    pathMonitor.pathUpdateHandler = { [weak self] path in
+      guard let self = self else { return }
+      let status = Self.networkStatus(path)
+      if let last = self.lastNetworkStatus, last == status { return }
+      self.lastNetworkStatus = status
+      self.trigger("network-status-changed", data: Self.networkStatusPayload(status))
+    }
+    pathMonitor.start(queue: pathMonitorQueue)
+
     // Suppress the iOS system text-selection edit menu so it never
     // covers Readest's annotation toolbar. See ContextMenuSuppressor.
     ContextMenuSuppressor.installIfNeeded()
@@ -1713,6 +1730,21 @@ class NativeBridgePlugin: Plugin {
     }
   }
 
+  private static func networkStatus(_ path: NWPath) -> (connected: Bool, metered: Bool) {
+    let connected = path.status == .satisfied
+    return (connected, connected && (path.isExpensive || path.isConstrained))
+  }
+
+  private static func networkStatusPayload(_ status: (connected: Bool, metered: Bool)) -> JSObject {
+    return ["connected": status.connected, "metered": status.metered]
+  }
+
+  @objc public func get_network_status(_ invoke: Invoke) {
+    pathMonitorQueue.async {
+      invoke.resolve(Self.networkStatusPayload(Self.networkStatus(self.pathMonitor.currentPath)))
+    }
+  }
+
   /// Hold a strong reference to the active folder picker delegate so
   /// it survives until the user dismisses the picker. `present`
   /// only retains the controller; the delegate is `weak` from
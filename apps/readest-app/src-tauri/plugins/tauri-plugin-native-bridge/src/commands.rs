@@ -369,3 +369,10 @@ pub(crate) async fn icloud_ensure_downloaded<R: Runtime>(
 ) -> Result<ICloudEnsureDownloadedResponse> {
     app.native_bridge().icloud_ensure_downloaded(payload)
 }
+
+#[command]
+pub(crate) async fn get_network_status<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<GetNetworkStatusResponse> {
+    app.native_bridge().get_network_status()
+}
@@ -455,6 +455,11 @@ impl<R: Runtime> NativeBridge<R> {
             Err(crate::Error::UnsupportedPlatformError)
         }
     }
+
+    /// Desktop webviews answer this through the Network Information API.
+    pub fn get_network_status(&self) -> crate::Result<GetNetworkStatusResponse> {
+        Err(crate::Error::UnsupportedPlatformError)
+    }
 }
 
 const KEYRING_SERVICE: &str = "Readest Safe Storage";
@@ -100,6 +100,7 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             commands::read_share_clip_html,
             commands::icloud_container_status,
             commands::icloud_ensure_downloaded,
+            commands::get_network_status,
         ])
         .setup(|app, api| {
             #[cfg(mobile)]
@@ -474,3 +474,11 @@ impl<R: Runtime> NativeBridge<R> {
             .map_err(Into::into)
     }
 }
+
+impl<R: Runtime> NativeBridge<R> {
+    pub fn get_network_status(&self) -> crate::Result<GetNetworkStatusResponse> {
+        self.0
+            .run_mobile_plugin("get_network_status", ())
+            .map_err(Into::into)
+    }
+}
@@ -543,3 +543,11 @@ pub struct ICloudEnsureDownloadedResponse {
     /// "ready" | "notFound" | "timeout"
     pub status: String,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetNetworkStatusResponse {
+    pub connected: bool,
+    /// Cellular, a metered Wi-Fi hotspot, or Low Data Mode on iOS.
+    pub metered: bool,
+}
@@ -0,0 +1,572 @@
+//! Native annotation export: renders one book's highlights and notes as
+//! Markdown (optionally with Obsidian front matter and block IDs), a
+//! Readwise import CSV, or the `readest-annotations` JSON envelope the
+//! webview's own export writes (`services/annotation/providers/readest.ts`),
+//! and writes the result atomically. The webview passes the booknote groups
+//! it already has, so nothing here reads the book or the database.
+
+use std::path::{Path, PathBuf};
+
+use serde::{ser::Serializer, Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{command, AppHandle};
+
+use crate::transfer_file::ensure_path_allowed;
+
+const READEST_WEB_BASE_URL: &str = "https://web.readest.com";
+const READEST_ANNOTATION_FORMAT: &str = "readest-annotations";
+const READEST_ANNOTATION_VERSION: u32 = 1;
+/// Readwise's CSV import template.
+const READWISE_CSV_HEADER: &str = "Highlight,Title,Author,URL,Note,Location,Date";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Forbidden(#[from] crate::transfer_file::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBook {
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// A `BookNote`, with only the fields an export uses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportNote {
+    pub id: String,
+    /// `annotation`, `excerpt` or `bookmark`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub cfi: String,
+    #[serde(default)]
+    pub xpointer0: Option<String>,
+    #[serde(default)]
+    pub xpointer1: Option<String>,
+    #[serde(default)]
+    pub page: Option<u32>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub style: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub global: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
+}
+
+/// A `BooknoteGroup`: one chapter's notes, in reading order.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportGroup {
+    #[serde(default)]
+    pub label: String,
+    pub booknotes: Vec<ExportNote>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkType {
+    /// `readest://` deep links.
+    #[default]
+    App,
+    /// Universal links on web.readest.com.
+    Web,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationsPayload {
+    pub book: ExportBook,
+    pub groups: Vec<ExportGroup>,
+    /// `[current, total]` pages, carried into the JSON envelope.
+    #[serde(default)]
+    pub progress: Option<(u32, u32)>,
+    #[serde(default)]
+    pub location: Option<String>,
+    /// For the page links in Markdown; the CSV always uses web links, which
+    /// is what Readwise can open.
+    #[serde(default)]
+    pub link_type: LinkType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    Markdown,
+    /// Markdown with YAML front matter and a `^block-id` per annotation, so
+    /// notes in a vault can link to single highlights.
+    Obsidian,
+    ReadwiseCsv,
+    Json,
+}
+
+fn live_notes(payload: &AnnotationsPayload) -> impl Iterator<Item = (&ExportGroup, &ExportNote)> {
+    payload
+        .groups
+        .iter()
+        .flat_map(|group| group.booknotes.iter().map(move |note| (group, note)))
+        .filter(|(_, note)| note.deleted_at.is_none())
+}
+
+/// Highlights and notes; bookmarks only mark a place and stay in JSON.
+fn is_highlight(note: &ExportNote) -> bool {
+    note.kind != "bookmark"
+        && (note.text.as_deref().is_some_and(|t| !t.is_empty()) || !note.note.is_empty())
+}
+
+fn annotation_url(hash: &str, note: &ExportNote, link_type: LinkType) -> String {
+    let base = match link_type {
+        LinkType::App => format!("readest://book/{hash}/annotation/{}", note.id),
+        LinkType::Web => format!(
+            "{READEST_WEB_BASE_URL}/o/book/{hash}/annotation/{}",
+            note.id
+        ),
+    };
+    if note.cfi.is_empty() {
+        return base;
+    }
+    let cfi = percent_encoding::utf8_percent_encode(&note.cfi, percent_encoding::NON_ALPHANUMERIC);
+    format!("{base}?cfi={cfi}")
+}
+
+/// `(year, month, day, hour, minute, second)` in UTC for a millisecond
+/// timestamp (Howard Hinnant's `civil_from_days`).
+fn utc_fields(ms: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let secs = ms.div_euclid(1000);
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400) as u32;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+fn format_date(ms: i64) -> String {
+    let (y, m, d, ..) = utc_fields(ms);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+fn format_datetime(ms: i64) -> String {
+    let (y, m, d, h, mi, s) = utc_fields(ms);
+    format!("{y:04}-{m:02}-{d:02} {h:02}:{mi:02}:{s:02}")
+}
+
+fn block_quote(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Obsidian block IDs allow only letters, digits and dashes.
+fn block_id(id: &str) -> String {
+    let id: String = id
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' => Some(c),
+            'A'..='Z' => Some(c.to_ascii_lowercase()),
+            '_' => Some('-'),
+            _ => None,
+        })
+        .collect();
+    format!("rd-{id}")
+}
+
+/// A double-quoted YAML scalar; JSON string escaping is valid YAML.
+fn yaml_string(value: &str) -> String {
+    Value::from(value).to_string()
+}
+
+fn render_markdown(payload: &AnnotationsPayload, obsidian: bool, exported_at: i64) -> String {
+    let book = &payload.book;
+    let count = live_notes(payload).filter(|(_, n)| is_highlight(n)).count();
+    let mut lines: Vec<String> = Vec::new();
+    if obsidian {
+        lines.push("---".into());
+        lines.push(format!("title: {}", yaml_string(&book.title)));
+        if !book.author.is_empty() {
+            lines.push(format!("author: {}", yaml_string(&book.author)));
+        }
+        if let Some(format) = &book.format {
+            lines.push(format!("format: {}", yaml_string(format)));
+        }
+        if let Some(hash) = &book.hash {
+            lines.push(format!("readest-hash: {hash}"));
+        }
+        lines.push(format!("exported: {}", format_date(exported_at)));
+        lines.push(format!("highlights: {count}"));
+        lines.push("tags:".into());
+        lines.push("  - readest".into());
+        lines.push("---".into());
+        lines.push(String::new());
+    }
+
+    lines.push(format!("# {}", book.title));
+    if !book.author.is_empty() {
+        lines.push(format!("**Author**: {}", book.author));
+        lines.push(String::new());
+    }
+    lines.push(format!(
+        "**Exported from Readest**: {}",
+        format_date(exported_at)
+    ));
+    lines.push(String::new());
+    lines.push("---".into());
+    lines.push(String::new());
+    lines.push("## Highlights & Annotations".into());
+    lines.push(String::new());
+
+    for group in &payload.groups {
+        let notes: Vec<&ExportNote> = group
+            .booknotes
+            .iter()
+            .filter(|n| n.deleted_at.is_none() && is_highlight(n))
+            .collect();
+        if notes.is_empty() {
+            continue;
+        }
+        let label = if group.label.is_empty() {
+            "Untitled"
+        } else {
+            &group.label
+        };
+        lines.push(format!("### {label}"));
+        for note in notes {
+            // The block ID goes on the annotation's first paragraph: the
+            // quote, or the note when there's nothing quoted.
+            let mut id = obsidian.then(|| format!(" ^{}", block_id(&note.id)));
+            if let Some(text) = note.text.as_deref().filter(|t| !t.trim().is_empty()) {
+                lines.push(format!(
+                    "{}{}",
+                    block_quote(text),
+                    id.take().unwrap_or_default()
+                ));
+            }
+            if !note.note.is_empty() {
+                lines.push(String::new());
+                lines.push(format!(
+                    "**Note**: {}{}",
+                    note.note,
+                    id.take().unwrap_or_default()
+                ));
+            }
+            if let Some(page) = note.page.filter(|&p| p > 0) {
+                let page = format!("Page: {page}");
+                let info = match book.hash.as_deref().filter(|h| !h.is_empty()) {
+                    Some(hash) => format!(
+                        "[{page}]({})",
+                        annotation_url(hash, note, payload.link_type)
+                    ),
+                    None => page,
+                };
+                lines.push(String::new());
+                lines.push(format!("*{info}*"));
+            }
+            lines.push("\n\n".into());
+        }
+    }
+    lines.join("\n")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_readwise_csv(payload: &AnnotationsPayload) -> String {
+    let book = &payload.book;
+    let mut out = String::from(READWISE_CSV_HEADER);
+    out.push_str("\r\n");
+    // Readwise needs something to highlight; a bare note keeps its text as
+    // the highlight.
+    for (_, note) in live_notes(payload).filter(|(_, n)| is_highlight(n)) {
+        let (highlight, comment) = match note.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(text) => (text, note.note.as_str()),
+            None => (note.note.as_str(), ""),
+        };
+        let url = book
+            .hash
+            .as_deref()
+            .filter(|h| !h.is_empty())
+            .map(|hash| annotation_url(hash, note, LinkType::Web))
+            .unwrap_or_default();
+        let location = note.page.filter(|&p| p > 0).map(|p| p.to_string());
+        let fields = [
+            highlight.trim(),
+            &book.title,
+            &book.author,
+            &url,
+            comment,
+            location.as_deref().unwrap_or_default(),
+            &format_datetime(note.created_at),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// The same envelope as `buildAnnotationExport`, so the webview can import it.
+fn render_json(payload: &AnnotationsPayload, exported_at: i64) -> String {
+    let annotations: Vec<Value> = live_notes(payload)
+        .map(|(_, note)| {
+            let mut entry = Map::new();
+            entry.insert("id".into(), json!(note.id));
+            entry.insert("type".into(), json!(note.kind));
+            entry.insert("cfi".into(), json!(note.cfi));
+            entry.insert("note".into(), json!(note.note));
+            entry.insert("createdAt".into(), json!(note.created_at));
+            entry.insert("updatedAt".into(), json!(note.updated_at));
+            let optional = [
+                ("text", &note.text),
+                ("style", &note.style),
+                ("color", &note.color),
+                ("xpointer0", &note.xpointer0),
+                ("xpointer1", &note.xpointer1),
+            ];
+            for (key, value) in optional {
+                if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+                    entry.insert(key.into(), json!(value));
+                }
+            }
+            if note.global {
+                entry.insert("global".into(), json!(true));
+            }
+            Value::Object(entry)
+        })
+        .collect();
+    let mut envelope = json!({
+        "$format": READEST_ANNOTATION_FORMAT,
+        "version": READEST_ANNOTATION_VERSION,
+        "exportedAt": exported_at,
+        "book": payload.book,
+        "annotations": annotations,
+    });
+    if let Some(progress) = payload.progress {
+        envelope["progress"] = json!(progress);
+    }
+    if let Some(location) = &payload.location {
+        envelope["location"] = json!(location);
+    }
+    serde_json::to_string_pretty(&envelope).unwrap_or_default()
+}
+
+pub fn render(payload: &AnnotationsPayload, format: ExportFormat, exported_at: i64) -> String {
+    match format {
+        ExportFormat::Markdown => render_markdown(payload, false, exported_at),
+        ExportFormat::Obsidian => render_markdown(payload, true, exported_at),
+        ExportFormat::ReadwiseCsv => render_readwise_csv(payload),
+        ExportFormat::Json => render_json(payload, exported_at),
+    }
+}
+
+/// Write through a sibling temp file and rename, so a vault watcher or a
+/// nightly job never reads half a file.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// Render `payload` as `format` and write it to `file_path`, which must be
+/// inside the fs scope. Returns the number of annotations exported.
+#[command]
+pub async fn export_annotations(
+    app: AppHandle,
+    payload: AnnotationsPayload,
+    format: ExportFormat,
+    file_path: String,
+) -> Result<usize, Error> {
+    ensure_path_allowed(&app, &file_path)?;
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let count = match format {
+        ExportFormat::Json => live_notes(&payload).count(),
+        _ => live_notes(&payload)
+            .filter(|(_, n)| is_highlight(n))
+            .count(),
+    };
+    let contents = render(&payload, format, exported_at);
+    tauri::async_runtime::spawn_blocking(move || {
+        write_atomically(Path::new(&file_path), contents.as_bytes())
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-18 09:30:05 UTC
+    const AT: i64 = 1_792_315_805_000;
+
+    fn note(id: &str, kind: &str, text: &str, note: &str) -> ExportNote {
+        ExportNote {
+            id: id.into(),
+            kind: kind.into(),
+            cfi: "epubcfi(/6/4!/4/2,/1:0,/1:5)".into(),
+            page: Some(12),
+            text: Some(text.into()).filter(|t: &String| !t.is_empty()),
+            note: note.into(),
+            created_at: AT,
+            updated_at: AT,
+            ..Default::default()
+        }
+    }
+
+    fn payload() -> AnnotationsPayload {
+        let mut deleted = note("gone", "annotation", "Deleted", "");
+        deleted.deleted_at = Some(AT);
+        AnnotationsPayload {
+            book: ExportBook {
+                title: "Dune".into(),
+                author: "Frank Herbert".into(),
+                hash: Some("0123456789abcdef0123456789abcdef".into()),
+                format: Some("EPUB".into()),
+                ..Default::default()
+            },
+            groups: vec![ExportGroup {
+                label: "Book One".into(),
+                booknotes: vec![
+                    note(
+                        "a_1",
+                        "annotation",
+                        "Fear is the\nmind-killer.",
+                        "Litany, \"again\"",
+                    ),
+                    note("b", "bookmark", "", ""),
+                    note("c", "annotation", "", "Only a note"),
+                    deleted,
+                ],
+            }],
+            link_type: LinkType::Web,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(format_datetime(AT), "2026-10-18 09:30:05");
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400_000), "2000-02-29");
+    }
+
+    #[test]
+    fn renders_obsidian_markdown() {
+        let md = render(&payload(), ExportFormat::Obsidian, AT);
+        assert!(
+            md.starts_with("---\ntitle: \"Dune\"\nauthor: \"Frank Herbert\"\n"),
+            "{md}"
+        );
+        assert!(md.contains("highlights: 2\n"), "{md}");
+        assert!(
+            md.contains("### Book One\n> Fear is the\n> mind-killer. ^rd-a-1\n"),
+            "{md}"
+        );
+        assert!(md.contains("**Note**: Only a note ^rd-c\n"), "{md}");
+        assert!(md.contains(
+            "*[Page: 12](https://web.readest.com/o/book/0123456789abcdef0123456789abcdef/annotation/a_1?cfi=epubcfi%28"
+        ));
+        assert!(!md.contains("Deleted"));
+
+        let plain = render(&payload(), ExportFormat::Markdown, AT);
+        assert!(
+            plain.starts_with("# Dune\n**Author**: Frank Herbert\n"),
+            "{plain}"
+        );
+        assert!(!plain.contains("^rd-"));
+    }
+
+    #[test]
+    fn renders_readwise_csv() {
+        let csv = render(&payload(), ExportFormat::ReadwiseCsv, AT);
+        let rows: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(rows[0], READWISE_CSV_HEADER);
+        assert!(rows[1].starts_with(
+            "\"Fear is the\nmind-killer.\",Dune,Frank Herbert,https://web.readest.com/"
+        ));
+        assert!(rows[1].ends_with(",\"Litany, \"\"again\"\"\",12,2026-10-18 09:30:05"));
+        assert!(rows[2].starts_with("Only a note,Dune,"));
+        assert_eq!(rows.len(), 4);
+    }
+
+    #[test]
+    fn renders_the_readest_json_envelope() {
+        let json: Value =
+            serde_json::from_str(&render(&payload(), ExportFormat::Json, AT)).unwrap();
+        assert_eq!(json["$format"], READEST_ANNOTATION_FORMAT);
+        assert_eq!(json["book"]["title"], "Dune");
+        let annotations = json["annotations"].as_array().unwrap();
+        assert_eq!(annotations.len(), 3);
+        assert_eq!(annotations[1]["type"], "bookmark");
+        assert!(annotations[1].get("text").is_none());
+    }
+
+    #[test]
+    fn writes_atomically() {
+        let dir = std::env::temp_dir().join(format!("annotation-export-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("vault/Dune.md");
+        write_atomically(&path, b"one").unwrap();
+        write_atomically(&path, b"two").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"two");
+        assert!(!dir.join("vault/Dune.md.tmp").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
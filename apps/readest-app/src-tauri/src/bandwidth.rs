@@ -0,0 +1,253 @@
+//! Global transfer rate limiting.
+//!
+//! One token bucket is shared by every byte `transfer_file`, `chunked_upload`
+//! and LocalSend put on the wire, so a configured limit caps the app as a
+//! whole rather than each transfer. Next to the limit sits an opt-in "pause
+//! on metered connection" policy: the webview reports the connection state
+//! (from the native bridge on mobile, the Network Information API elsewhere)
+//! and the background transfer queue holds its transfers until the network is
+//! unmetered again. Transfers the user started directly, and LAN traffic
+//! (LocalSend, the OPDS server), are only rate limited.
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter};
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const EV_BANDWIDTH_STATUS: &str = "bandwidth:status";
+
+/// Largest slice a body is cut into before being metered, so a multi-MB part
+/// upload is paced smoothly instead of in one burst followed by a long wait.
+const THROTTLE_SLICE: usize = 64 * 1024;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthPolicy {
+    /// Bytes per second across all transfers; `None` or 0 is unlimited.
+    pub limit_bytes_per_sec: Option<u64>,
+    pub pause_on_metered: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthStatus {
+    #[serde(flatten)]
+    pub policy: BandwidthPolicy,
+    pub metered: bool,
+    /// Whether queued transfers are currently held back.
+    pub paused: bool,
+}
+
+/// Classic token bucket holding up to one second of traffic. Takes may drive
+/// the balance negative: a chunk bigger than the bucket still goes through,
+/// and the caller sleeps until the debt is paid off.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last: now,
+        }
+    }
+
+    fn set_rate(&mut self, rate: u64, now: Instant) {
+        self.refill(now);
+        self.rate = rate as f64;
+        self.tokens = self.tokens.min(self.rate);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+    }
+
+    /// Takes `bytes` tokens and returns how long to wait before sending them.
+    fn take(&mut self, bytes: u64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    policy: BandwidthPolicy,
+    metered: bool,
+    bucket: Option<TokenBucket>,
+}
+
+impl LimiterState {
+    fn paused(&self) -> bool {
+        self.policy.pause_on_metered && self.metered
+    }
+
+    fn status(&self) -> BandwidthStatus {
+        BandwidthStatus {
+            policy: self.policy,
+            metered: self.metered,
+            paused: self.paused(),
+        }
+    }
+
+    fn set_policy(&mut self, policy: BandwidthPolicy) {
+        let now = Instant::now();
+        self.bucket = match (
+            policy.limit_bytes_per_sec.filter(|&r| r > 0),
+            self.bucket.take(),
+        ) {
+            (None, _) => None,
+            (Some(rate), Some(mut bucket)) => {
+                bucket.set_rate(rate, now);
+                Some(bucket)
+            }
+            (Some(rate), None) => Some(TokenBucket::new(rate, now)),
+        };
+        self.policy = policy;
+    }
+}
+
+#[derive(Default)]
+struct Limiter {
+    state: Mutex<LimiterState>,
+}
+
+fn limiter() -> &'static Limiter {
+    static LIMITER: OnceLock<Limiter> = OnceLock::new();
+    LIMITER.get_or_init(Limiter::default)
+}
+
+fn update(f: impl FnOnce(&mut LimiterState)) -> BandwidthStatus {
+    let mut state = limiter().state.lock().unwrap();
+    f(&mut state);
+    state.status()
+}
+
+/// Whether the metered policy holds queued transfers back. The transfer
+/// queue checks this before starting anything.
+pub(crate) fn is_paused() -> bool {
+    limiter().state.lock().unwrap().paused()
+}
+
+/// Waits until `bytes` more may be sent under the rate limit.
+pub(crate) async fn throttle(bytes: usize) {
+    let delay = {
+        let mut state = limiter().state.lock().unwrap();
+        match state.bucket.as_mut() {
+            Some(bucket) => bucket.take(bytes as u64, Instant::now()),
+            None => Duration::ZERO,
+        }
+    };
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Meters `bytes` in slices of at most [`THROTTLE_SLICE`].
+pub(crate) async fn throttle_bytes(bytes: &bytes::Bytes) {
+    let mut remaining = bytes.len();
+    while remaining > 0 {
+        let slice = remaining.min(THROTTLE_SLICE);
+        throttle(slice).await;
+        remaining -= slice;
+    }
+}
+
+/// An upload body that releases `bytes` no faster than the limiter allows.
+pub(crate) fn throttled_body(bytes: bytes::Bytes) -> reqwest::Body {
+    let slices = (0..bytes.len())
+        .step_by(THROTTLE_SLICE)
+        .map(move |start| bytes.slice(start..(start + THROTTLE_SLICE).min(bytes.len())));
+    let stream = futures_util::stream::iter(slices).then(|slice| async move {
+        throttle(slice.len()).await;
+        Ok::<_, std::io::Error>(slice)
+    });
+    reqwest::Body::wrap_stream(stream)
+}
+
+fn apply(app: &AppHandle, status: BandwidthStatus) -> BandwidthStatus {
+    let _ = app.emit(EV_BANDWIDTH_STATUS, status.clone());
+    status
+}
+
+#[command]
+pub fn bandwidth_set_policy(app: AppHandle, policy: BandwidthPolicy) -> BandwidthStatus {
+    let status = apply(&app, update(|state| state.set_policy(policy)));
+    crate::transfer_manager::config_pushed(&app, crate::transfer_manager::ConfigPush::Bandwidth);
+    status
+}
+
+/// Fed by the webview whenever the connection changes.
+#[command]
+pub fn network_set_metered(app: AppHandle, metered: bool) -> BandwidthStatus {
+    let status = apply(&app, update(|state| state.metered = metered));
+    crate::transfer_manager::schedule(&app);
+    status
+}
+
+#[command]
+pub fn bandwidth_status() -> BandwidthStatus {
+    limiter().state.lock().unwrap().status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BandwidthPolicy, LimiterState, TokenBucket};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn bucket_allows_a_burst_then_paces() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+
+        assert_eq!(bucket.take(1000, start), Duration::ZERO);
+        assert_eq!(bucket.take(500, start), Duration::from_millis(500));
+        // Half a second later the debt is paid and nothing is banked.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(0, later), Duration::ZERO);
+        assert_eq!(bucket.take(250, later), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn bucket_caps_savings_at_one_second() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+        let idle = start + Duration::from_secs(60);
+
+        assert_eq!(bucket.take(1000, idle), Duration::ZERO);
+        assert_eq!(bucket.take(100, idle), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn policy_pauses_only_when_opted_in_and_metered() {
+        let mut state = LimiterState {
+            metered: true,
+            ..LimiterState::default()
+        };
+        // Off unless the user asks for it.
+        assert!(!state.paused());
+
+        state.set_policy(BandwidthPolicy {
+            limit_bytes_per_sec: Some(0),
+            pause_on_metered: true,
+        });
+        assert!(state.paused());
+        state.metered = false;
+        assert!(!state.paused());
+        // A zero limit means unlimited, not stalled.
+        assert!(state.bucket.is_none());
+    }
+}
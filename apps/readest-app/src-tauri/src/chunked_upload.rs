@@ -0,0 +1,689 @@
+//! Chunked, resumable uploads for large files (comics, audiobooks).
+//!
+//! `transfer_file::upload_file` streams the whole file in one request, so a
+//! dropped connection at 90% starts over. `upload_file_chunked` sends the
+//! file in parts, retries each part, and checkpoints what the server already
+//! has under `<app_data_dir>/uploads/`, so an upload interrupted by a network
+//! drop or an app restart continues where it stopped. Two protocols:
+//!
+//!   - `s3-multipart`: S3 multipart upload through presigned URLs. The caller
+//!     creates the upload and presigns one `UploadPart` PUT per part plus the
+//!     `CompleteMultipartUpload` POST; parts go up a few at a time and their
+//!     ETags are checkpointed as they finish. To resume, the caller reads the
+//!     checkpointed upload ID back with `upload_resume_info` and presigns for
+//!     it again.
+//!   - `tus`: tus 1.0 (core + creation). The upload URL returned by the
+//!     creation request is checkpointed; resuming asks the server for its
+//!     `Upload-Offset` and continues from there.
+//!
+//! Progress goes through the same `ProgressPayload` channel as `upload_file`.
+
+use futures::stream::{self, StreamExt};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{command, ipc::Channel, AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
+
+use crate::bandwidth;
+use crate::http_client;
+use crate::transfer_file::{
+    channel_sink, ensure_path_allowed, retry_transient, Error, ProgressPayload, ProgressSink,
+    Result, TransferStats, PART_ATTEMPTS, PART_RETRY_DELAY,
+};
+
+/// S3 parts uploaded at once.
+const S3_CONCURRENCY: usize = 4;
+/// tus `PATCH` size when the caller doesn't choose one.
+const TUS_DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const TUS_VERSION: &str = "1.0.0";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "protocol", rename_all = "kebab-case")]
+pub enum ChunkedUpload {
+    #[serde(rename_all = "camelCase")]
+    S3Multipart {
+        upload_id: String,
+        part_size: u64,
+        /// Presigned `UploadPart` URL for part `i + 1`.
+        part_urls: Vec<String>,
+        /// Presigned `CompleteMultipartUpload` URL.
+        complete_url: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Tus {
+        /// Creation endpoint.
+        endpoint: String,
+        chunk_size: Option<u64>,
+        /// Sent as `Upload-Metadata` when the upload is created.
+        #[serde(default)]
+        metadata: HashMap<String, String>,
+    },
+}
+
+/// Arguments of a chunked upload, shared by `upload_file_chunked` and queued
+/// transfers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkedUploadRequest {
+    pub file_path: String,
+    pub target: String,
+    pub upload: ChunkedUpload,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// What the server already has, as checkpointed after each part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "protocol", rename_all = "kebab-case")]
+pub enum ResumeState {
+    #[serde(rename_all = "camelCase")]
+    S3Multipart {
+        upload_id: String,
+        part_size: u64,
+        /// Sorted by part number. A list rather than a number-keyed map:
+        /// serde can't read integer map keys back inside a tagged enum.
+        parts: Vec<UploadedPart>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Tus { upload_url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadCheckpoint {
+    file_path: String,
+    target: String,
+    /// Size and mtime of the file when the upload started; a checkpoint for
+    /// a file that has since changed is discarded.
+    file_len: u64,
+    file_mtime: u64,
+    state: ResumeState,
+}
+
+/// Upload `file_path` in parts. `target` identifies the destination (e.g. the
+/// remote path) and keys the checkpoint together with `file_path`. Returns the
+/// `CompleteMultipartUpload` response body for S3 and the upload URL for tus.
+#[command]
+pub async fn upload_file_chunked(
+    app: AppHandle,
+    file_path: &str,
+    target: &str,
+    upload: ChunkedUpload,
+    headers: HashMap<String, String>,
+    on_progress: Channel<ProgressPayload>,
+) -> Result<String> {
+    ensure_path_allowed(&app, file_path)?;
+
+    let request = ChunkedUploadRequest {
+        file_path: file_path.to_string(),
+        target: target.to_string(),
+        upload,
+        headers,
+    };
+    upload_chunked(&app, request, channel_sink(on_progress)).await
+}
+
+/// The body of `upload_file_chunked`; the caller has already checked the path.
+pub(crate) async fn upload_chunked(
+    app: &AppHandle,
+    request: ChunkedUploadRequest,
+    on_progress: ProgressSink,
+) -> Result<String> {
+    let ChunkedUploadRequest {
+        file_path,
+        target,
+        upload,
+        headers,
+    } = request;
+
+    let (file_len, file_mtime) = file_stamp(&file_path).await?;
+    let checkpoint_path = checkpoint_path(app, &file_path, &target)?;
+    let saved = load_checkpoint(&checkpoint_path)
+        .await
+        .filter(|c| c.file_len == file_len && c.file_mtime == file_mtime)
+        .map(|c| c.state);
+
+    let job = UploadJob {
+        client: http_client::client()?,
+        file_path,
+        file_len,
+        headers,
+        file_mtime,
+        target,
+        checkpoint_path: checkpoint_path.clone(),
+        state: Mutex::new(None),
+        stats: Mutex::new(TransferStats::default()),
+        on_progress,
+    };
+
+    let result = match upload {
+        ChunkedUpload::S3Multipart {
+            upload_id,
+            part_size,
+            part_urls,
+            complete_url,
+        } => {
+            let parts = match saved {
+                Some(ResumeState::S3Multipart {
+                    upload_id: saved_id,
+                    part_size: saved_size,
+                    parts,
+                }) if saved_id == upload_id && saved_size == part_size => parts,
+                _ => Vec::new(),
+            };
+            job.upload_s3_multipart(upload_id, part_size, part_urls, complete_url, parts)
+                .await
+        }
+        ChunkedUpload::Tus {
+            endpoint,
+            chunk_size,
+            metadata,
+        } => {
+            let upload_url = match saved {
+                Some(ResumeState::Tus { upload_url }) => Some(upload_url),
+                _ => None,
+            };
+            let chunk_size = chunk_size.unwrap_or(TUS_DEFAULT_CHUNK_SIZE).max(1);
+            job.upload_tus(&endpoint, chunk_size, &metadata, upload_url)
+                .await
+        }
+    };
+
+    if result.is_ok() {
+        let _ = tokio::fs::remove_file(&checkpoint_path).await;
+    }
+    result
+}
+
+/// The checkpointed state of an interrupted upload of `file_path` to
+/// `target`, if any. S3 callers use the upload ID to presign the remaining
+/// parts instead of starting a new multipart upload.
+#[command]
+pub async fn upload_resume_info(
+    app: AppHandle,
+    file_path: &str,
+    target: &str,
+) -> Result<Option<ResumeState>> {
+    ensure_path_allowed(&app, file_path)?;
+    let Ok((file_len, file_mtime)) = file_stamp(file_path).await else {
+        return Ok(None);
+    };
+    Ok(load_checkpoint(&checkpoint_path(&app, file_path, target)?)
+        .await
+        .filter(|c| c.file_len == file_len && c.file_mtime == file_mtime)
+        .map(|c| c.state))
+}
+
+struct UploadJob {
+    client: reqwest::Client,
+    file_path: String,
+    file_len: u64,
+    file_mtime: u64,
+    target: String,
+    headers: HashMap<String, String>,
+    checkpoint_path: PathBuf,
+    /// Checkpointed after every part; `None` until the protocol has decided
+    /// whether it resumes or starts over.
+    state: Mutex<Option<ResumeState>>,
+    stats: Mutex<TransferStats>,
+    on_progress: ProgressSink,
+}
+
+impl UploadJob {
+    async fn upload_s3_multipart(
+        &self,
+        upload_id: String,
+        part_size: u64,
+        part_urls: Vec<String>,
+        complete_url: String,
+        parts: Vec<UploadedPart>,
+    ) -> Result<String> {
+        let count = s3_part_count(self.file_len, part_size)?;
+        if part_urls.len() != count as usize {
+            return Err(Error::Upload(format!(
+                "expected {count} part URLs, got {}",
+                part_urls.len()
+            )));
+        }
+
+        let sent: BTreeSet<u32> = parts.iter().map(|p| p.part_number).collect();
+        let done: u64 = sent
+            .iter()
+            .map(|&n| s3_part_range(self.file_len, part_size, n).1)
+            .sum();
+        let missing: Vec<u32> = (1..=count).filter(|n| !sent.contains(n)).collect();
+        if !sent.is_empty() {
+            log::info!(
+                "resuming upload {upload_id}: {} of {count} parts already sent",
+                sent.len()
+            );
+        }
+        *self.state.lock().await = Some(ResumeState::S3Multipart {
+            upload_id,
+            part_size,
+            parts,
+        });
+        self.save_checkpoint().await;
+        self.report(done).await;
+
+        let errors: Vec<Error> = stream::iter(missing)
+            .map(|number| {
+                let url = &part_urls[number as usize - 1];
+                async move {
+                    let (start, len) = s3_part_range(self.file_len, part_size, number);
+                    let body = read_range(&self.file_path, start, len).await?;
+                    let etag = retry_transient(&format!("upload part {number}"), || {
+                        self.put_part(url, body.clone())
+                    })
+                    .await?;
+                    if let Some(ResumeState::S3Multipart { parts, .. }) =
+                        self.state.lock().await.as_mut()
+                    {
+                        let at = parts.partition_point(|p| p.part_number < number);
+                        parts.insert(
+                            at,
+                            UploadedPart {
+                                part_number: number,
+                                etag,
+                            },
+                        );
+                    }
+                    self.save_checkpoint().await;
+                    self.report(len).await;
+                    Ok(())
+                }
+            })
+            .buffer_unordered(S3_CONCURRENCY)
+            .filter_map(|result: Result<()>| async move { result.err() })
+            .collect()
+            .await;
+        if let Some(first) = errors.into_iter().next() {
+            return Err(first);
+        }
+
+        let body = match self.state.lock().await.as_ref() {
+            Some(ResumeState::S3Multipart { parts, .. }) => complete_multipart_body(parts),
+            _ => unreachable!("S3 state is set above"),
+        };
+        retry_transient("complete multipart upload", || {
+            self.complete_multipart(&complete_url, body.clone())
+        })
+        .await
+    }
+
+    async fn put_part(&self, url: &str, body: bytes::Bytes) -> Result<String> {
+        let mut request = self
+            .client
+            .put(url)
+            .header(reqwest::header::CONTENT_LENGTH, body.len())
+            .body(bandwidth::throttled_body(body));
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = check_status(request.send().await?).await?;
+        response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| Error::Upload("part response has no ETag".into()))
+    }
+
+    async fn complete_multipart(&self, url: &str, body: String) -> Result<String> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/xml")
+            .body(body);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = check_status(request.send().await?).await?;
+        let text = response.text().await?;
+        // S3 may answer 200 and still report the failure in the body.
+        if text.contains("<Error>") {
+            return Err(Error::HttpErrorCode(200, text));
+        }
+        Ok(text)
+    }
+
+    async fn upload_tus(
+        &self,
+        endpoint: &str,
+        chunk_size: u64,
+        metadata: &HashMap<String, String>,
+        saved_url: Option<String>,
+    ) -> Result<String> {
+        let resumed = match saved_url {
+            Some(url) => match self.tus_offset(&url).await {
+                Ok(offset) => Some((url, offset)),
+                // The server expired or dropped the upload; start a new one.
+                Err(Error::HttpErrorCode(404 | 410, _)) => None,
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        let (upload_url, mut offset) = match resumed {
+            Some((url, offset)) => {
+                log::info!("resuming tus upload {url} at {offset}/{}", self.file_len);
+                (url, offset)
+            }
+            None => {
+                let url =
+                    retry_transient("create tus upload", || self.tus_create(endpoint, metadata))
+                        .await?;
+                (url, 0)
+            }
+        };
+        *self.state.lock().await = Some(ResumeState::Tus {
+            upload_url: upload_url.clone(),
+        });
+        self.save_checkpoint().await;
+        self.report(offset).await;
+
+        let mut attempt = 0;
+        while offset < self.file_len {
+            let len = chunk_size.min(self.file_len - offset);
+            let body = read_range(&self.file_path, offset, len).await?;
+            match self.tus_patch(&upload_url, offset, body).await {
+                Ok(next) => {
+                    self.report(next.saturating_sub(offset)).await;
+                    offset = next;
+                    attempt = 0;
+                }
+                Err(e) if e.is_retryable() && attempt + 1 < PART_ATTEMPTS => {
+                    let delay = PART_RETRY_DELAY * 2u32.pow(attempt);
+                    log::warn!("tus PATCH at {offset} failed: {e}; retrying in {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    // The server may have stored part of the failed chunk.
+                    if let Ok(server) = self.tus_offset(&upload_url).await {
+                        self.report(server.saturating_sub(offset)).await;
+                        offset = server;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(upload_url)
+    }
+
+    async fn tus_create(
+        &self,
+        endpoint: &str,
+        metadata: &HashMap<String, String>,
+    ) -> Result<String> {
+        let mut request = self
+            .client
+            .post(endpoint)
+            .header("Tus-Resumable", TUS_VERSION)
+            .header("Upload-Length", self.file_len)
+            .header(reqwest::header::CONTENT_LENGTH, 0);
+        if !metadata.is_empty() {
+            request = request.header("Upload-Metadata", tus_metadata(metadata));
+        }
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = check_status(request.send().await?).await?;
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Error::Upload("tus creation response has no Location".into()))?;
+        // Location may be relative to the creation endpoint.
+        reqwest::Url::parse(endpoint)
+            .and_then(|base| base.join(location))
+            .map(|url| url.to_string())
+            .map_err(|e| Error::Upload(format!("invalid tus Location {location}: {e}")))
+    }
+
+    async fn tus_offset(&self, upload_url: &str) -> Result<u64> {
+        let mut request = self
+            .client
+            .head(upload_url)
+            .header("Tus-Resumable", TUS_VERSION);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = check_status(request.send().await?).await?;
+        upload_offset(&response)
+    }
+
+    async fn tus_patch(&self, upload_url: &str, offset: u64, body: bytes::Bytes) -> Result<u64> {
+        let mut request = self
+            .client
+            .patch(upload_url)
+            .header("Tus-Resumable", TUS_VERSION)
+            .header("Upload-Offset", offset)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/offset+octet-stream",
+            )
+            .header(reqwest::header::CONTENT_LENGTH, body.len())
+            .body(bandwidth::throttled_body(body));
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = check_status(request.send().await?).await?;
+        upload_offset(&response)
+    }
+
+    async fn save_checkpoint(&self) {
+        let state = self.state.lock().await;
+        let Some(state) = state.as_ref() else {
+            return;
+        };
+        let checkpoint = UploadCheckpoint {
+            file_path: self.file_path.clone(),
+            target: self.target.clone(),
+            file_len: self.file_len,
+            file_mtime: self.file_mtime,
+            state: state.clone(),
+        };
+        if let Err(e) = save_checkpoint(&self.checkpoint_path, &checkpoint).await {
+            log::warn!("failed to save upload checkpoint: {e}");
+        }
+    }
+
+    async fn report(&self, bytes: u64) {
+        let mut stats = self.stats.lock().await;
+        stats.record_chunk_transfer(bytes as usize);
+        (self.on_progress)(ProgressPayload {
+            progress: stats.total_transferred,
+            total: self.file_len,
+            transfer_speed: stats.transfer_speed,
+        });
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(Error::HttpErrorCode(
+            response.status().as_u16(),
+            response.text().await.unwrap_or_default(),
+        ))
+    }
+}
+
+fn upload_offset(response: &reqwest::Response) -> Result<u64> {
+    response
+        .headers()
+        .get("upload-offset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .ok_or_else(|| Error::Upload("tus response has no Upload-Offset".into()))
+}
+
+/// S3 allows at most 10,000 parts; an empty file is still one (empty) part.
+fn s3_part_count(file_len: u64, part_size: u64) -> Result<u32> {
+    if part_size == 0 {
+        return Err(Error::Upload("part size must be positive".into()));
+    }
+    let count = file_len.div_ceil(part_size).max(1);
+    if count > 10_000 {
+        return Err(Error::Upload(format!(
+            "{count} parts exceeds the S3 limit of 10000; use a larger part size"
+        )));
+    }
+    Ok(count as u32)
+}
+
+/// Offset and length of 1-based part `number`.
+fn s3_part_range(file_len: u64, part_size: u64, number: u32) -> (u64, u64) {
+    let start = (number as u64 - 1) * part_size;
+    (start, part_size.min(file_len.saturating_sub(start)))
+}
+
+/// `parts` must be sorted by part number, as S3 requires.
+fn complete_multipart_body(parts: &[UploadedPart]) -> String {
+    let mut body = String::from("<CompleteMultipartUpload>");
+    for UploadedPart { part_number, etag } in parts {
+        let etag = etag
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('<', "&lt;");
+        body.push_str(&format!(
+            "<Part><PartNumber>{part_number}</PartNumber><ETag>{etag}</ETag></Part>"
+        ));
+    }
+    body.push_str("</CompleteMultipartUpload>");
+    body
+}
+
+/// `Upload-Metadata`: comma-separated `key base64(value)` pairs.
+fn tus_metadata(metadata: &HashMap<String, String>) -> String {
+    use base64::Engine;
+    let mut pairs: Vec<String> = metadata
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{key} {}",
+                base64::engine::general_purpose::STANDARD.encode(value)
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs.join(",")
+}
+
+async fn read_range(file_path: &str, start: u64, len: u64) -> Result<bytes::Bytes> {
+    let mut file = tokio::fs::File::open(file_path).await?;
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut buf = vec![0u8; len as usize];
+    file.read_exact(&mut buf).await?;
+    Ok(buf.into())
+}
+
+async fn file_stamp(file_path: &str) -> Result<(u64, u64)> {
+    let meta = tokio::fs::metadata(file_path).await?;
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+    Ok((meta.len(), mtime))
+}
+
+fn checkpoint_path(app: &AppHandle, file_path: &str, target: &str) -> Result<PathBuf> {
+    let digest = Md5::digest(format!("{file_path}\n{target}").as_bytes());
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| Error::Upload(e.to_string()))?;
+    Ok(dir.join("uploads").join(format!("{digest:x}.json")))
+}
+
+async fn load_checkpoint(path: &Path) -> Option<UploadCheckpoint> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+async fn save_checkpoint(path: &Path, checkpoint: &UploadCheckpoint) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let bytes = serde_json::to_vec(checkpoint).map_err(std::io::Error::other)?;
+    tokio::fs::write(&tmp, bytes).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s3_parts_cover_the_file() {
+        assert_eq!(s3_part_count(25, 10).unwrap(), 3);
+        assert_eq!(s3_part_range(25, 10, 3), (20, 5));
+        // Empty files still upload a single empty part.
+        assert_eq!(s3_part_count(0, 10).unwrap(), 1);
+        assert_eq!(s3_part_range(0, 10, 1), (0, 0));
+        assert!(s3_part_count(10_001, 1).is_err());
+    }
+
+    #[test]
+    fn complete_multipart_body_escapes_etags() {
+        let parts = [(1, "\"a\""), (2, "\"b\"")].map(|(part_number, etag)| UploadedPart {
+            part_number,
+            etag: etag.to_string(),
+        });
+        assert_eq!(
+            complete_multipart_body(&parts),
+            "<CompleteMultipartUpload>\
+             <Part><PartNumber>1</PartNumber><ETag>&quot;a&quot;</ETag></Part>\
+             <Part><PartNumber>2</PartNumber><ETag>&quot;b&quot;</ETag></Part>\
+             </CompleteMultipartUpload>"
+        );
+    }
+
+    #[test]
+    fn tus_metadata_base64_encodes_values() {
+        let metadata: HashMap<String, String> = [
+            ("filename".to_string(), "book.cbz".to_string()),
+            ("filetype".to_string(), "application/zip".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            tus_metadata(&metadata),
+            "filename Ym9vay5jYno=,filetype YXBwbGljYXRpb24vemlw"
+        );
+    }
+
+    #[test]
+    fn resume_state_round_trips_as_tagged_json() {
+        let state = ResumeState::S3Multipart {
+            upload_id: "abc".into(),
+            part_size: 5,
+            parts: vec![UploadedPart {
+                part_number: 1,
+                etag: "\"e\"".into(),
+            }],
+        };
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["protocol"], "s3-multipart");
+        assert_eq!(json["uploadId"], "abc");
+        assert_eq!(serde_json::from_value::<ResumeState>(json).unwrap(), state);
+
+        let upload: ChunkedUpload =
+            serde_json::from_str(r#"{"protocol":"tus","endpoint":"https://example.com/files/"}"#)
+                .unwrap();
+        assert!(matches!(upload, ChunkedUpload::Tus { metadata, .. } if metadata.is_empty()));
+    }
+}
@@ -635,6 +635,17 @@ pub async fn clip_url(
         win_builder
     };
 
+    // Route the page through the configured proxy. The webview only takes
+    // unauthenticated http:// and socks5:// proxies (and on macOS only with
+    // Tauri's `macos-proxy` feature), so anything else loads directly.
+    #[cfg(not(target_os = "macos"))]
+    let win_builder = match crate::http_client::proxy_url()
+        .filter(|p| matches!(p.scheme(), "http" | "socks5") && p.username().is_empty())
+    {
+        Some(proxy) => win_builder.proxy_url(proxy),
+        None => win_builder,
+    };
+
     #[cfg(target_os = "macos")]
     let win_builder = win_builder
         .decorations(true)
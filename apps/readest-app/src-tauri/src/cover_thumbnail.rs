@@ -118,10 +118,23 @@ fn catch_worker_panic<T>(work: impl FnOnce() -> T) -> std::thread::Result<T> {
     std::panic::catch_unwind(std::panic::AssertUnwindSafe(work))
 }
 
-fn is_md5(value: &str) -> bool {
+pub(crate) fn is_md5(value: &str) -> bool {
     value.len() == 32 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
 }
 
+/// Where the thumbnail for a book's current cover is (or will be) cached.
+pub(crate) fn thumbnail_path(
+    cache_dir: &Path,
+    book_hash: &str,
+    cover_hash: Option<&str>,
+) -> PathBuf {
+    let cache_key = cover_hash.unwrap_or("legacy");
+    cache_dir
+        .join(COVER_THUMBNAIL_CACHE_DIR)
+        .join(COVER_THUMBNAIL_VERSION)
+        .join(format!("{book_hash}-{cache_key}.jpg"))
+}
+
 fn build_jobs(
     books_dir: &Path,
     cache_dir: &Path,
@@ -145,13 +158,9 @@ fn build_jobs(
                 }
                 None => None,
             };
-            let cache_key = cover_hash.as_deref().unwrap_or("legacy");
             Some(CoverJob {
                 source: books_dir.join(&cover.book_hash).join("cover.png"),
-                destination: cache_dir
-                    .join(COVER_THUMBNAIL_CACHE_DIR)
-                    .join(COVER_THUMBNAIL_VERSION)
-                    .join(format!("{}-{cache_key}.jpg", cover.book_hash)),
+                destination: thumbnail_path(cache_dir, &cover.book_hash, cover_hash.as_deref()),
                 book_hash: cover.book_hash,
                 cover_hash,
             })
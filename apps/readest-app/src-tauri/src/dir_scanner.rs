@@ -1,12 +1,35 @@
+use std::collections::BTreeMap;
 use std::path::Path;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime};
 use tauri_plugin_fs::FsExt;
 use walkdir::WalkDir;
 
+use crate::parser_common::compute_partial_md5;
+
 #[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ScannedFile {
     pub path: String,
     pub size: u64,
+    /// Library book hash (`utils/md5.ts::partialMD5`), only from
+    /// `read_dir_dedup`. `None` as well when the file could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_md5: Option<String>,
+}
+
+/// Files under the scanned folder that share a `partialMD5`, i.e. that the
+/// library would treat as the same book.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub partial_md5: String,
+    pub paths: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DirScan {
+    pub files: Vec<ScannedFile>,
+    pub duplicates: Vec<DuplicateGroup>,
 }
 
 #[tauri::command]
@@ -16,12 +39,7 @@ pub async fn read_dir(
     recursive: bool,
     extensions: Vec<String>,
 ) -> Result<Vec<ScannedFile>, String> {
-    let scope = app.fs_scope();
-    let path_buf = std::path::PathBuf::from(&path);
-
-    if !scope.is_allowed(&path_buf) && !path_buf.to_string_lossy().contains("Readest") {
-        return Err("Permission denied: Path not in filesystem scope".to_string());
-    }
+    ensure_scan_allowed(&app, Path::new(&path))?;
 
     // The walk stats every matching file; on a large watched folder that is
     // thousands of syscalls. A sync command would run them inline on the IPC
@@ -32,6 +50,78 @@ pub async fn read_dir(
         .map_err(|e| format!("join error: {e}"))?
 }
 
+/// `read_dir` plus each file's `partialMD5`, and the groups of files the
+/// library would import as the same book.
+#[tauri::command]
+pub async fn read_dir_dedup(
+    app: AppHandle,
+    path: String,
+    recursive: bool,
+    extensions: Vec<String>,
+) -> Result<DirScan, String> {
+    let mut files = read_dir(app, path, recursive, extensions).await?;
+
+    // Hashing is a dozen small reads per file, so a big library is seek-bound
+    // rather than CPU-bound: fan the files out over several blocking tasks
+    // so the reads overlap instead of running one after another.
+    let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk_size = files.len().div_ceil(workers).max(1);
+    let mut tasks = Vec::new();
+    while !files.is_empty() {
+        let rest = files.split_off(chunk_size.min(files.len()));
+        let mut chunk = std::mem::replace(&mut files, rest);
+        tasks.push(tauri::async_runtime::spawn_blocking(move || {
+            hash_files(&mut chunk);
+            chunk
+        }));
+    }
+    for task in futures::future::join_all(tasks).await {
+        files.extend(task.map_err(|e| format!("join error: {e}"))?);
+    }
+
+    let duplicates = group_duplicates(&files);
+    Ok(DirScan { files, duplicates })
+}
+
+fn hash_files(files: &mut [ScannedFile]) {
+    for file in files {
+        match compute_partial_md5(Path::new(&file.path)) {
+            Ok(hash) => file.partial_md5 = Some(hash),
+            Err(e) => log::warn!("RUST: Skipping hash of {} due to error: {}", file.path, e),
+        }
+    }
+}
+
+fn group_duplicates(files: &[ScannedFile]) -> Vec<DuplicateGroup> {
+    let mut by_hash: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for file in files {
+        if let Some(hash) = &file.partial_md5 {
+            by_hash.entry(hash).or_default().push(file.path.clone());
+        }
+    }
+    by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(hash, paths)| DuplicateGroup {
+            partial_md5: hash.to_string(),
+            paths,
+        })
+        .collect()
+}
+
+/// Shared with `library_watcher` and LocalSend's folder send: a folder may be
+/// scanned or watched when the fs scope grants it (persisted dialog grants)
+/// or it is app storage.
+pub(crate) fn ensure_scan_allowed<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+) -> Result<(), String> {
+    if !app.fs_scope().is_allowed(path) && !path.to_string_lossy().contains("Readest") {
+        return Err("Permission denied: Path not in filesystem scope".to_string());
+    }
+    Ok(())
+}
+
 fn read_dir_sync(
     path: &str,
     recursive: bool,
@@ -91,21 +181,53 @@ fn read_dir_sync(
 }
 
 fn process_file_entry(path: &Path, extensions: &[String]) -> Option<ScannedFile> {
-    if extensions.is_empty() || extensions.contains(&"*".to_string()) {
-        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-        return Some(ScannedFile {
-            path: path.to_string_lossy().to_string(),
-            size,
-        });
-    } else if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy().to_lowercase();
-        if extensions.contains(&ext_str) {
-            let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-            return Some(ScannedFile {
-                path: path.to_string_lossy().to_string(),
-                size,
-            });
-        }
+    if !matches_extensions(path, extensions) {
+        return None;
+    }
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Some(ScannedFile {
+        path: path.to_string_lossy().to_string(),
+        size,
+        partial_md5: None,
+    })
+}
+
+/// `extensions` must already be lowercased. Empty or `"*"` matches anything.
+pub(crate) fn matches_extensions(path: &Path, extensions: &[String]) -> bool {
+    if extensions.is_empty() || extensions.iter().any(|ext| ext == "*") {
+        return true;
+    }
+    path.extension()
+        .map(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup_groups_files_with_the_same_partial_md5() {
+        let root = std::env::temp_dir().join(format!("readest-dedup-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.epub"), vec![1u8; 5000]).unwrap();
+        std::fs::write(root.join("sub/copy.epub"), vec![1u8; 5000]).unwrap();
+        std::fs::write(root.join("other.epub"), vec![2u8; 5000]).unwrap();
+
+        let mut files =
+            read_dir_sync(&root.to_string_lossy(), true, &["epub".to_string()]).unwrap();
+        hash_files(&mut files);
+        let groups = group_duplicates(&files);
+        std::fs::remove_dir_all(&root).ok();
+
+        assert!(files.iter().all(|f| f.partial_md5.is_some()));
+        assert_eq!(groups.len(), 1);
+        let mut names: Vec<&str> = groups[0]
+            .paths
+            .iter()
+            .map(|p| Path::new(p).file_name().unwrap().to_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.epub", "copy.epub"]);
     }
-    None
 }
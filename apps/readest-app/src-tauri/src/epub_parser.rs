@@ -1,20 +1,30 @@
 // Native EPUB import path (Q1).
 //
-// Scope after PR review: this command no longer extracts OPF metadata
-// (title / author / identifier / language / refines chains / ONIX5 …).
-// That graph-shaped XML processing belongs to foliate-js, which the JS
-// bridge runs against the same OPF bytes that `parse_epub_full` already
-// pre-fetches on the import path. Re-implementing it in Rust would
-// silently diverge from the primary platform parser.
+// Scope after PR review: foliate-js stays the source of truth for the
+// reader's `Book.metadata` (title / author / identifier / language /
+// refines chains / ONIX5 …), which it derives from the same OPF bytes
+// that `parse_epub_full` pre-fetches on the import path. Re-implementing
+// that in Rust for the reader would silently diverge from the primary
+// platform parser (and from the metaHash it produces).
 //
-// What `parse_epub_metadata` still does on the import hot path:
+// Bulk import and the library sort keys, however, only need a typed
+// summary, and paying a DOMParser round-trip per book for it was the
+// bottleneck on large imports. `parse_epub_metadata` therefore also
+// returns a structured `metadata` view built by `opf_metadata` — see
+// that module for exactly what it covers.
+//
+// What `parse_epub_metadata` does on the import hot path:
 //   - compute partialMD5 over the file (matches utils/md5.ts::partialMD5)
+//   - refuse DRM-protected books (Adobe ADEPT / Readium LCP, detected via
+//     META-INF/encryption.xml, rights.xml and license.lcpl) with
+//     `ParseError::DrmProtected` instead of importing ciphertext
 //   - read META-INF/container.xml -> rootfile (.opf)
-//   - mini-parse the OPF *only* for cover resolution: collect manifest
-//     items (id/href/media-type/properties) and the legacy
-//     `<meta name="cover" content="...">` id. We deliberately do NOT
-//     read any text content under `<metadata>` — title/author/etc. are
-//     foliate's job.
+//   - mini-parse the OPF for cover resolution: collect manifest items
+//     (id/href/media-type/properties) and the legacy
+//     `<meta name="cover" content="...">` id;
+//   - parse `<metadata>` into the structured `OpfMetadata` view (soft-
+//     fails to `None`: a malformed metadata block must not cost the
+//     book its cover or its import);
 //   - locate the cover image entry (manifest properties="cover-image"
 //     first, then meta name="cover" -> manifest item id, then heuristic
 //     name match)
@@ -44,7 +54,6 @@ use percent_encoding::percent_decode;
 use quick_xml::events::Event;
 use quick_xml::Reader;
 use serde::Serialize;
-use std::borrow::Cow;
 use std::fs::File;
 use std::io::{Read, Seek};
 use std::path::Path;
@@ -53,7 +62,11 @@ use zip::ZipArchive;
 // Cover constants + helpers + RawCoverImage type are shared with `mobi_parser`
 // via `parser_common`, so a single tweak (e.g. raising the thumbnail target)
 // applies to every native importer.
-use crate::parser_common::{compute_partial_md5, maybe_resize_cover, RawCoverImage};
+use crate::opf_metadata::{parse_opf_metadata, OpfMetadata};
+use crate::parser_common::{
+    compute_partial_md5, local_name, maybe_resize_cover, strip_xml_bom, DrmScheme, ParseError,
+    RawCoverImage,
+};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -79,10 +92,13 @@ pub struct ParsedEpubMetadata {
     /// cover resolution, so propagating them is essentially free and
     /// lets the importer skip a zip.js inflate of the OPF.
     pub opf_bytes: Vec<u8>,
+    /// Structured Dublin Core / EPUB3 metadata parsed from `opf_bytes`.
+    /// `None` only when the OPF's `<metadata>` block can't be parsed.
+    pub metadata: Option<OpfMetadata>,
 }
 
 #[tauri::command]
-pub async fn parse_epub_metadata(file_path: String) -> Result<ParsedEpubMetadata, String> {
+pub async fn parse_epub_metadata(file_path: String) -> Result<ParsedEpubMetadata, ParseError> {
     // The body is CPU+IO bound: zip central-directory parse, OPF parse,
     // cover decode/resize/encode. We must NOT run that on the Tauri
     // async runtime worker (the IPC dispatch thread), because then four
@@ -93,10 +109,10 @@ pub async fn parse_epub_metadata(file_path: String) -> Result<ParsedEpubMetadata
         .map_err(|e| format!("join error: {e}"))?
 }
 
-fn parse_epub_metadata_sync(file_path: &str) -> Result<ParsedEpubMetadata, String> {
+fn parse_epub_metadata_sync(file_path: &str) -> Result<ParsedEpubMetadata, ParseError> {
     let path = Path::new(file_path);
     if !path.exists() {
-        return Err(format!("file not found: {file_path}"));
+        return Err(format!("file not found: {file_path}").into());
     }
 
     let partial_md5 = compute_partial_md5(path).map_err(|e| format!("partial_md5 failed: {e}"))?;
@@ -104,16 +120,17 @@ fn parse_epub_metadata_sync(file_path: &str) -> Result<ParsedEpubMetadata, Strin
     let file = File::open(path).map_err(|e| format!("open failed: {e}"))?;
     let mut zip = ZipArchive::new(file).map_err(|e| format!("zip open failed: {e}"))?;
 
+    if let Some(scheme) = detect_epub_drm(&mut zip) {
+        return Err(ParseError::DrmProtected(scheme));
+    }
+
     let opf_path = read_rootfile_path(&mut zip).map_err(|e| format!("container.xml: {e}"))?;
 
     let opf_bytes =
         read_zip_entry(&mut zip, &opf_path).map_err(|e| format!("read opf {opf_path}: {e}"))?;
-    // Mini-parse the OPF for cover resolution only: we need the manifest
+    // Mini-parse the OPF for cover resolution: we need the manifest
     // (id → href/media-type/properties) and the legacy
-    // `<meta name="cover">` id. Metadata extraction is intentionally not
-    // done here — foliate-js is the single source of truth for OPF
-    // metadata across platforms, and it parses the same `opf_bytes` that
-    // `parse_epub_full` returns on the import hot path.
+    // `<meta name="cover">` id.
     let cover_inputs =
         parse_opf_cover_inputs(&opf_bytes).map_err(|e| format!("parse opf cover inputs: {e}"))?;
 
@@ -138,12 +155,21 @@ fn parse_epub_metadata_sync(file_path: &str) -> Result<ParsedEpubMetadata, Strin
         None => (None, None),
     };
 
+    let metadata = match parse_opf_metadata(&opf_bytes) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            log::warn!("epub: skipping structured metadata for {file_path}: {e}");
+            None
+        }
+    };
+
     Ok(ParsedEpubMetadata {
         partial_md5,
         cover,
         cover_mime,
         opf_path,
         opf_bytes,
+        metadata,
     })
 }
 
@@ -215,8 +241,9 @@ fn extract_epub_cover_full_sync(file_path: &str) -> Result<RawCoverImage, String
 //     versions. The OPF (and toc.ncx / nav.xhtml) is small XML — re-parsing
 //     it once in the WebView is cheap; what was expensive was *finding* it
 //     and unzipping it.
-//   - Encryption isn't handled here (yet). Encrypted EPUBs fall back to the
-//     foliate-js path; in practice Readest's EPUBs aren't encrypted.
+//   - DRM-protected EPUBs are rejected up-front (`detect_epub_drm`) with
+//     `ParseError::DrmProtected`. Font obfuscation is not DRM and passes
+//     through; foliate-js de-obfuscates those fonts itself.
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Serialize)]
@@ -250,7 +277,7 @@ pub struct ParsedEpubFull {
 }
 
 #[tauri::command]
-pub async fn parse_epub_full(file_path: String) -> Result<ParsedEpubFull, String> {
+pub async fn parse_epub_full(file_path: String) -> Result<ParsedEpubFull, ParseError> {
     // Same threading rationale as parse_epub_metadata — keep IPC dispatch off
     // the CPU-bound zip/parse work so concurrent opens stay parallel.
     tauri::async_runtime::spawn_blocking(move || parse_epub_full_sync(&file_path))
@@ -258,10 +285,10 @@ pub async fn parse_epub_full(file_path: String) -> Result<ParsedEpubFull, String
         .map_err(|e| format!("join error: {e}"))?
 }
 
-fn parse_epub_full_sync(file_path: &str) -> Result<ParsedEpubFull, String> {
+fn parse_epub_full_sync(file_path: &str) -> Result<ParsedEpubFull, ParseError> {
     let path = Path::new(file_path);
     if !path.exists() {
-        return Err(format!("file not found: {file_path}"));
+        return Err(format!("file not found: {file_path}").into());
     }
 
     let partial_md5 = compute_partial_md5(path).map_err(|e| format!("partial_md5 failed: {e}"))?;
@@ -269,6 +296,10 @@ fn parse_epub_full_sync(file_path: &str) -> Result<ParsedEpubFull, String> {
     let file = File::open(path).map_err(|e| format!("open failed: {e}"))?;
     let mut zip = ZipArchive::new(file).map_err(|e| format!("zip open failed: {e}"))?;
 
+    if let Some(scheme) = detect_epub_drm(&mut zip) {
+        return Err(ParseError::DrmProtected(scheme));
+    }
+
     let opf_path = read_rootfile_path(&mut zip).map_err(|e| format!("container.xml: {e}"))?;
 
     let opf_bytes =
@@ -306,7 +337,8 @@ fn parse_epub_full_sync(file_path: &str) -> Result<ParsedEpubFull, String> {
     for i in 0..zip.len() {
         let entry = match zip.by_index_raw(i) {
             Ok(e) => e,
-            // by_index_raw can fail on encrypted entries; skip silently.
+            // by_index_raw can fail on zip-level (password) encryption, which
+            // no EPUB reader supports anyway; skip the entry.
             Err(_) => continue,
         };
         if entry.is_dir() {
@@ -459,6 +491,55 @@ fn locate_toc_sources(opf_bytes: &[u8]) -> Result<LocatedTocSources, String> {
     Ok(LocatedTocSources { nav_href, ncx_href })
 }
 
+/// Zip paths of the spine items in reading order, resolved against
+/// `opf_path`. Itemrefs whose idref has no manifest item are skipped (see
+/// `epub_validator` for reporting them); `linear="no"` items are kept, since
+/// their text is as searchable as anything else. Used by `fulltext`.
+pub(crate) fn read_spine_paths(opf_bytes: &[u8], opf_path: &str) -> Result<Vec<String>, String> {
+    use std::collections::HashMap;
+
+    let normalized = strip_xml_bom(opf_bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().trim_text(true);
+    reader.config_mut().expand_empty_elements = true;
+    let mut buf = Vec::new();
+
+    let mut hrefs: HashMap<String, String> = HashMap::new();
+    let mut idrefs: Vec<String> = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref()).to_vec();
+                let attr = |key: &[u8]| {
+                    e.attributes()
+                        .flatten()
+                        .find(|a| a.key.as_ref() == key)
+                        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+                };
+                if name == b"item" {
+                    if let (Some(id), Some(href)) = (attr(b"id"), attr(b"href")) {
+                        hrefs.insert(id, href);
+                    }
+                } else if name == b"itemref" {
+                    if let Some(idref) = attr(b"idref") {
+                        idrefs.push(idref);
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("xml: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(idrefs
+        .iter()
+        .filter_map(|idref| hrefs.get(idref))
+        .map(|href| resolve_relative(opf_path, href))
+        .collect())
+}
+
 // `maybe_resize_cover` is now defined in `parser_common`; the description
 // below is retained here for navigation from EPUB-side call sites.
 //
@@ -486,7 +567,10 @@ fn locate_toc_sources(opf_bytes: &[u8]) -> Result<LocatedTocSources, String> {
 // block above is retained here for navigation from EPUB-side call sites.)
 // ---------------------------------------------------------------------------
 
-fn read_zip_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, path: &str) -> Result<Vec<u8>, String> {
+pub(crate) fn read_zip_entry<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    path: &str,
+) -> Result<Vec<u8>, String> {
     // Two-pass lookup, mirroring what epub-rs does (archive.rs) and what
     // foliate-js does on the JS side: many EPUBs declare manifest hrefs that
     // are percent-encoded (e.g. "Text/My%20Chapter.xhtml" or CJK %E4%BB%96)
@@ -516,7 +600,9 @@ fn read_by_name<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<V
     Ok(buf)
 }
 
-fn read_rootfile_path<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<String, String> {
+pub(crate) fn read_rootfile_path<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+) -> Result<String, String> {
     let bytes = read_zip_entry(zip, "META-INF/container.xml")?;
     let normalized = strip_xml_bom(&bytes);
     let mut reader = Reader::from_reader(normalized.as_ref());
@@ -542,17 +628,87 @@ fn read_rootfile_path<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Result<String,
     Err("rootfile not found".into())
 }
 
+// ---------------------------------------------------------------------------
+// DRM detection
+//
+// OCF puts every encrypted resource in META-INF/encryption.xml. Font
+// obfuscation uses the same file but is not DRM (every reader undoes it
+// with a key derived from the book identifier), so a book only counts as
+// protected when at least one entry uses another algorithm. The scheme is
+// then named from the licence files each system ships alongside:
+// META-INF/license.lcpl for Readium LCP, META-INF/rights.xml (or an Adobe
+// `KeyInfo` namespace) for ADEPT.
+// ---------------------------------------------------------------------------
+
+/// Font-obfuscation algorithms (IDPF and Adobe).
+pub(crate) const FONT_OBFUSCATION_ALGORITHMS: &[&str] = &[
+    "http://www.idpf.org/2008/embedding",
+    "http://ns.adobe.com/pdf/enc#RC",
+];
+
+const LCP_NAMESPACE: &[u8] = b"http://readium.org/2014/01/lcp";
+const ADEPT_NAMESPACE: &[u8] = b"http://ns.adobe.com/adept";
+
+pub(crate) fn detect_epub_drm<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Option<DrmScheme> {
+    let has_lcp_license = zip.index_for_name("META-INF/license.lcpl").is_some();
+    let has_adept_rights = zip.index_for_name("META-INF/rights.xml").is_some();
+    let Ok(bytes) = read_zip_entry(zip, "META-INF/encryption.xml") else {
+        // LCP always ships an encryption.xml, but a licence on its own is
+        // still a clear signal that the content is not readable here.
+        return has_lcp_license.then_some(DrmScheme::ReadiumLcp);
+    };
+    if !lists_drm_encrypted_entries(&bytes) {
+        return None;
+    }
+    let mentions = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    Some(if has_lcp_license || mentions(LCP_NAMESPACE) {
+        DrmScheme::ReadiumLcp
+    } else if has_adept_rights || mentions(ADEPT_NAMESPACE) {
+        DrmScheme::AdobeAdept
+    } else {
+        DrmScheme::Unknown
+    })
+}
+
+/// True when encryption.xml names at least one `EncryptionMethod` that is
+/// not font obfuscation. An unparseable file is treated as protected: a
+/// publisher does not ship encryption.xml for nothing.
+fn lists_drm_encrypted_entries(bytes: &[u8]) -> bool {
+    let normalized = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Empty(e)) | Ok(Event::Start(e))
+                if local_name_eq(e.name().as_ref(), b"EncryptionMethod") =>
+            {
+                let algorithm = e
+                    .attributes()
+                    .flatten()
+                    .find(|a| a.key.as_ref() == b"Algorithm")
+                    .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+                    .unwrap_or_default();
+                if !FONT_OBFUSCATION_ALGORITHMS.contains(&algorithm.as_str()) {
+                    return true;
+                }
+            }
+            Ok(Event::Eof) => return false,
+            Err(_) => return true,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // OPF parsing — *cover-only* slice
 //
-// We deliberately do NOT walk `<metadata>` text content here. The full
-// set of OPF metadata semantics (refines chains, marc-relator role
-// bucketing, language maps, EPUB3 `belongs-to-collection`, ONIX5
-// codelists, …) belongs to foliate-js, which the JS bridge invokes on
-// the OPF bytes pre-fetched by `parse_epub_full`. The Rust side only
-// walks the `<manifest>` (so it can pick a cover entry) and the legacy
-// `<meta name="cover" content="..."/>` shorthand; everything else is
-// ignored by design.
+// This pass does NOT walk `<metadata>` text content; the typed metadata
+// view lives in `opf_metadata`. It only walks the `<manifest>` (so it can
+// pick a cover entry) and the legacy `<meta name="cover" content="..."/>`
+// shorthand, so cover resolution keeps working even when the metadata
+// block is too broken for the structured parser.
 // ---------------------------------------------------------------------------
 #[derive(Debug, Default)]
 struct ManifestItem {
@@ -762,7 +918,7 @@ fn find_undeclared_cover_entry<R: Read + Seek>(zip: &ZipArchive<R>) -> Option<St
         .map(str::to_string)
 }
 
-fn resolve_relative(opf_path: &str, href: &str) -> String {
+pub(crate) fn resolve_relative(opf_path: &str, href: &str) -> String {
     // Strip query/fragment that occasionally appear in manifest hrefs.
     let href = href.split(['?', '#']).next().unwrap_or(href);
     let dir = match opf_path.rfind('/') {
@@ -809,56 +965,9 @@ fn guess_image_mime(path: &str) -> &'static str {
 }
 
 // ---------------------------------------------------------------------------
-// XML helpers
+// XML helpers (`strip_xml_bom` / `local_name` live in `parser_common`)
 // ---------------------------------------------------------------------------
 
-/// Normalize the byte payload of an XML document for `quick-xml`:
-///
-///   - strip a leading UTF-8 BOM (EF BB BF) — quick-xml otherwise emits a
-///     spurious `Text` event before the prolog and some declarations fail
-///     to parse;
-///   - if the document begins with a UTF-16 BOM (FE FF or FF FE), transcode
-///     to UTF-8 lossily so the rest of our pipeline can keep treating bytes
-///     as UTF-8. Real-world EPUBs are very rarely UTF-16 but a handful of
-///     publisher tools (notably old Adobe InDesign exports) still emit it.
-///
-/// Returns a `Cow` so the common (UTF-8, no BOM) case stays zero-copy.
-fn strip_xml_bom(bytes: &[u8]) -> Cow<'_, [u8]> {
-    if bytes.len() >= 3 && bytes[0] == 0xEF && bytes[1] == 0xBB && bytes[2] == 0xBF {
-        return Cow::Borrowed(&bytes[3..]);
-    }
-    if bytes.len() >= 2 {
-        let big_endian = bytes[0] == 0xFE && bytes[1] == 0xFF;
-        let little_endian = bytes[0] == 0xFF && bytes[1] == 0xFE;
-        if big_endian || little_endian {
-            let body = &bytes[2..];
-            // chunks_exact silently drops a trailing odd byte, which is what
-            // we want — a malformed UTF-16 stream still produces a best-
-            // effort UTF-8 transcoding rather than failing the whole import.
-            let units: Vec<u16> = body
-                .chunks_exact(2)
-                .map(|c| {
-                    if big_endian {
-                        u16::from_be_bytes([c[0], c[1]])
-                    } else {
-                        u16::from_le_bytes([c[0], c[1]])
-                    }
-                })
-                .collect();
-            let s = String::from_utf16_lossy(&units);
-            return Cow::Owned(s.into_bytes());
-        }
-    }
-    Cow::Borrowed(bytes)
-}
-
-fn local_name(qname: &[u8]) -> &[u8] {
-    match qname.iter().rposition(|b| *b == b':') {
-        Some(idx) => &qname[idx + 1..],
-        None => qname,
-    }
-}
-
 fn local_name_eq(qname: &[u8], local: &[u8]) -> bool {
     local_name(qname) == local
 }
@@ -873,6 +982,7 @@ mod tests {
     use crate::parser_common::COVER_MAX_LONG_EDGE;
     use image::GenericImageView;
     use md5::{Digest, Md5};
+    use std::borrow::Cow;
     use std::collections::HashMap;
     use std::io::Cursor;
 
@@ -882,7 +992,7 @@ mod tests {
         // items (with id/href/media-type/properties) and the OPF2 legacy
         // `<meta name="cover" content="...">` shorthand. Everything else
         // under `<metadata>` (title/author/dates/calibre:* etc.) is left
-        // entirely to foliate-js on the JS side.
+        // to `opf_metadata`.
         let xml = br#"<?xml version="1.0"?>
 <package xmlns="http://www.idpf.org/2007/opf" version="3.0">
   <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
@@ -983,6 +1093,19 @@ mod tests {
         assert_eq!(located.ncx_href.as_deref(), Some("toc.ncx"));
     }
 
+    #[test]
+    fn read_spine_paths_follows_spine_order() {
+        let opf = br#"<package xmlns="http://www.idpf.org/2007/opf">
+  <manifest>
+    <item id="b" href="Text/b.xhtml" media-type="application/xhtml+xml"/>
+    <item id="a" href="../a.xhtml" media-type="application/xhtml+xml"></item>
+  </manifest>
+  <spine><itemref idref="a"/><itemref idref="ghost"/><itemref idref="b" linear="no"/></spine>
+</package>"#;
+        let paths = read_spine_paths(opf, "OEBPS/content.opf").unwrap();
+        assert_eq!(paths, vec!["a.xhtml", "OEBPS/Text/b.xhtml"]);
+    }
+
     #[test]
     fn locate_toc_sources_handles_self_closing_manifest() {
         // Degenerate but valid: an empty self-closing `<manifest/>` must not
@@ -1401,6 +1524,68 @@ mod tests {
         assert!(read_zip_entry(&mut zip, "missing.txt").is_err());
     }
 
+    fn zip_with(entries: &[(&str, &str)]) -> ZipArchive<Cursor<Vec<u8>>> {
+        use std::io::Write;
+        let mut buf = Vec::<u8>::new();
+        {
+            let mut w = zip::ZipWriter::new(Cursor::new(&mut buf));
+            let opts = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            for (name, body) in entries {
+                w.start_file(*name, opts).unwrap();
+                w.write_all(body.as_bytes()).unwrap();
+            }
+            w.finish().unwrap();
+        }
+        ZipArchive::new(Cursor::new(buf)).unwrap()
+    }
+
+    fn encryption_xml(algorithm: &str, key_info: &str) -> String {
+        format!(
+            r#"<encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+  <enc:EncryptedData>
+    <enc:EncryptionMethod Algorithm="{algorithm}"/>
+    {key_info}
+    <enc:CipherData><enc:CipherReference URI="OEBPS/c1.xhtml"/></enc:CipherData>
+  </enc:EncryptedData>
+</encryption>"#
+        )
+    }
+
+    #[test]
+    fn detect_epub_drm_ignores_font_obfuscation() {
+        let xml = encryption_xml("http://www.idpf.org/2008/embedding", "");
+        let mut zip = zip_with(&[("META-INF/encryption.xml", &xml)]);
+        assert_eq!(detect_epub_drm(&mut zip), None);
+        let mut plain = zip_with(&[("mimetype", "application/epub+zip")]);
+        assert_eq!(detect_epub_drm(&mut plain), None);
+    }
+
+    #[test]
+    fn detect_epub_drm_names_the_scheme() {
+        let aes = "http://www.w3.org/2001/04/xmlenc#aes128-cbc";
+        let adept = encryption_xml(
+            aes,
+            r#"<KeyInfo xmlns="http://www.w3.org/2000/09/xmldsig#"><resource xmlns="http://ns.adobe.com/adept">urn:uuid:1</resource></KeyInfo>"#,
+        );
+        let mut zip = zip_with(&[("META-INF/encryption.xml", &adept)]);
+        assert_eq!(detect_epub_drm(&mut zip), Some(DrmScheme::AdobeAdept));
+
+        let lcp = encryption_xml(aes, "");
+        let mut zip = zip_with(&[
+            ("META-INF/encryption.xml", &lcp),
+            ("META-INF/license.lcpl", "{}"),
+        ]);
+        assert_eq!(detect_epub_drm(&mut zip), Some(DrmScheme::ReadiumLcp));
+
+        let mut zip = zip_with(&[("META-INF/encryption.xml", &lcp)]);
+        assert_eq!(detect_epub_drm(&mut zip), Some(DrmScheme::Unknown));
+        assert!(ParseError::DrmProtected(DrmScheme::Unknown)
+            .to_string()
+            .starts_with(crate::parser_common::DRM_ERROR_PREFIX));
+    }
+
     #[test]
     fn partial_md5_medium_file_uses_step_windows() {
         // For a >2 KiB file the i = 0 iteration reads bytes [1024..2048],
@@ -0,0 +1,731 @@
+// EPUB structural validator.
+//
+// `epub_parser` deliberately tolerates a lot of broken input (BOMs, UTF-16
+// OPFs, percent-encoded zip names, undeclared covers) so that imports keep
+// working, but that leaves users with no explanation when a book renders
+// badly. `epub_validate` walks the same container -> OPF -> manifest/spine
+// -> nav/ncx chain the importer follows and reports every structural
+// problem it finds as a typed `ValidationFinding`:
+//   - `mimetype` entry missing, not first, compressed, or with the wrong
+//     content (readers that sniff the OCF signature reject these);
+//   - container.xml / rootfile / OPF missing or unparseable (fatal — the
+//     walk stops there);
+//   - duplicate `id` attributes anywhere in the OPF;
+//   - manifest items whose href does not resolve to a zip entry;
+//   - spine itemrefs (and `<spine toc>`) pointing at unknown manifest ids;
+//   - nav.xhtml `<a href>` / toc.ncx `<content src>` links that do not
+//     resolve to a zip entry;
+//   - entries listed in `META-INF/encryption.xml`: font obfuscation is
+//     reported as info, anything else as DRM-encrypted content.
+//
+// Lookups go through the same `read_zip_entry` percent-decoding fallback
+// the importer uses, so an href the importer can open is never flagged.
+// The validator is read-only and never touches the on-disk library.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::epub_parser::{
+    detect_epub_drm, read_rootfile_path, read_zip_entry, resolve_relative,
+    FONT_OBFUSCATION_ALGORITHMS,
+};
+use crate::parser_common::{local_name, strip_xml_bom};
+
+const EPUB_MIMETYPE: &[u8] = b"application/epub+zip";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationSeverity {
+    /// Informational; the book reads fine.
+    Info,
+    /// Likely to cause visible glitches (broken TOC entries, strict readers
+    /// rejecting the file) but the content is still reachable.
+    Warning,
+    /// Content is missing or unreadable.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValidationCode {
+    MimetypeMissing,
+    MimetypeNotFirst,
+    MimetypeCompressed,
+    MimetypeInvalid,
+    ContainerInvalid,
+    OpfUnreadable,
+    DuplicateId,
+    ManifestTargetMissing,
+    SpineItemNotInManifest,
+    SpineEmpty,
+    NcxNotInManifest,
+    NavLinkUnresolved,
+    NcxLinkUnresolved,
+    ObfuscatedFont,
+    EncryptedEntry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationFinding {
+    pub severity: ValidationSeverity,
+    pub code: ValidationCode,
+    /// Human-readable explanation, suitable for a diagnostics panel.
+    pub message: String,
+    /// Zip path the finding refers to, when there is one.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpubValidationReport {
+    /// Resolved OPF zip path, or `None` when container.xml is broken.
+    pub opf_path: Option<String>,
+    /// Findings in the order they were discovered (mimetype, encryption,
+    /// container, OPF, manifest, spine, TOC).
+    pub findings: Vec<ValidationFinding>,
+    pub error_count: usize,
+    pub warning_count: usize,
+}
+
+impl EpubValidationReport {
+    fn push(
+        &mut self,
+        severity: ValidationSeverity,
+        code: ValidationCode,
+        message: impl Into<String>,
+        path: Option<&str>,
+    ) {
+        match severity {
+            ValidationSeverity::Error => self.error_count += 1,
+            ValidationSeverity::Warning => self.warning_count += 1,
+            ValidationSeverity::Info => {}
+        }
+        self.findings.push(ValidationFinding {
+            severity,
+            code,
+            message: message.into(),
+            path: path.map(str::to_string),
+        });
+    }
+}
+
+/// Validate the EPUB at `file_path`. Only I/O and zip-level failures are
+/// returned as `Err`; everything structural is reported as a finding.
+#[tauri::command]
+pub async fn epub_validate(file_path: String) -> Result<EpubValidationReport, String> {
+    tauri::async_runtime::spawn_blocking(move || epub_validate_sync(&file_path))
+        .await
+        .map_err(|e| format!("join error: {e}"))?
+}
+
+fn epub_validate_sync(file_path: &str) -> Result<EpubValidationReport, String> {
+    let path = Path::new(file_path);
+    if !path.exists() {
+        return Err(format!("file not found: {file_path}"));
+    }
+    let file = File::open(path).map_err(|e| format!("open failed: {e}"))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("zip open failed: {e}"))?;
+    Ok(validate_archive(&mut zip))
+}
+
+fn validate_archive<R: Read + Seek>(zip: &mut ZipArchive<R>) -> EpubValidationReport {
+    use ValidationCode::*;
+    use ValidationSeverity::*;
+
+    let mut report = EpubValidationReport::default();
+    check_mimetype(zip, &mut report);
+
+    // Encryption is independent of the OPF, so report it even when the
+    // package document turns out to be unreadable.
+    let encrypted = check_encryption(zip, &mut report);
+
+    let opf_path = match read_rootfile_path(zip) {
+        Ok(p) => p,
+        Err(e) => {
+            report.push(
+                Error,
+                ContainerInvalid,
+                format!("META-INF/container.xml: {e}"),
+                Some("META-INF/container.xml"),
+            );
+            return report;
+        }
+    };
+    report.opf_path = Some(opf_path.clone());
+
+    let opf = match read_zip_entry(zip, &opf_path).and_then(|b| scan_opf(&b)) {
+        Ok(opf) => opf,
+        Err(e) => {
+            report.push(
+                Error,
+                OpfUnreadable,
+                format!("package document: {e}"),
+                Some(&opf_path),
+            );
+            return report;
+        }
+    };
+
+    for id in &opf.duplicate_ids {
+        report.push(
+            Error,
+            DuplicateId,
+            format!("id \"{id}\" is declared more than once"),
+            Some(&opf_path),
+        );
+    }
+
+    let mut manifest_ids: HashSet<&str> = HashSet::new();
+    let mut nav_path = None;
+    let mut ncx_path = None;
+    for item in &opf.manifest {
+        manifest_ids.insert(item.id.as_str());
+        if is_external_href(&item.href) {
+            continue;
+        }
+        let target = resolve_relative(&opf_path, &item.href);
+        if item.properties.split_whitespace().any(|p| p == "nav") {
+            nav_path = Some(target.clone());
+        }
+        if item.media_type == "application/x-dtbncx+xml"
+            && (opf.spine_toc.as_deref() == Some(item.id.as_str()) || ncx_path.is_none())
+        {
+            ncx_path = Some(target.clone());
+        }
+        if !entry_exists(zip, &target) {
+            report.push(
+                Error,
+                ManifestTargetMissing,
+                format!(
+                    "manifest item \"{}\" points at {target}, which is not in the archive",
+                    item.id
+                ),
+                Some(&target),
+            );
+        }
+    }
+
+    if opf.spine.is_empty() {
+        report.push(
+            Error,
+            SpineEmpty,
+            "spine has no itemrefs; there is nothing to read",
+            Some(&opf_path),
+        );
+    }
+    for idref in &opf.spine {
+        if !manifest_ids.contains(idref.as_str()) {
+            report.push(
+                Error,
+                SpineItemNotInManifest,
+                format!("spine itemref \"{idref}\" has no manifest item"),
+                Some(&opf_path),
+            );
+        }
+    }
+    if let Some(toc) = opf.spine_toc.as_deref() {
+        if !manifest_ids.contains(toc) {
+            report.push(
+                Warning,
+                NcxNotInManifest,
+                format!("spine toc=\"{toc}\" has no manifest item"),
+                Some(&opf_path),
+            );
+        }
+    }
+
+    for (doc, code) in [(nav_path, NavLinkUnresolved), (ncx_path, NcxLinkUnresolved)] {
+        let Some(doc) = doc else { continue };
+        // A missing nav/ncx document is already reported as a missing
+        // manifest target; only walk the links of documents we can read.
+        let Ok(bytes) = read_zip_entry(zip, &doc) else {
+            continue;
+        };
+        let links = match code {
+            NavLinkUnresolved => collect_links(&bytes, b"a", b"href"),
+            _ => collect_links(&bytes, b"content", b"src"),
+        };
+        let mut reported = HashSet::new();
+        for href in links {
+            if href.starts_with('#') || is_external_href(&href) {
+                continue;
+            }
+            let target = resolve_relative(&doc, &href);
+            if !reported.contains(&target) && !entry_exists(zip, &target) {
+                report.push(
+                    Warning,
+                    code,
+                    format!("table-of-contents link \"{href}\" does not resolve"),
+                    Some(&doc),
+                );
+                reported.insert(target);
+            }
+        }
+    }
+
+    // Point at the spine content that is DRM-encrypted: the book will open
+    // but those sections render as garbage. The DRM itself is already one
+    // error in the encryption summary, so each section is only a warning.
+    if !encrypted.is_empty() {
+        for item in &opf.manifest {
+            let target = resolve_relative(&opf_path, &item.href);
+            if encrypted.contains(&target) && opf.spine.contains(&item.id) {
+                report.push(
+                    Warning,
+                    EncryptedEntry,
+                    format!("spine item \"{}\" is encrypted and cannot be read", item.id),
+                    Some(&target),
+                );
+            }
+        }
+    }
+
+    report
+}
+
+// ---------------------------------------------------------------------------
+// mimetype
+// ---------------------------------------------------------------------------
+
+fn check_mimetype<R: Read + Seek>(zip: &mut ZipArchive<R>, report: &mut EpubValidationReport) {
+    use ValidationCode::*;
+    use ValidationSeverity::*;
+
+    let Some(index) = zip.index_for_name("mimetype") else {
+        report.push(
+            Warning,
+            MimetypeMissing,
+            "the archive has no `mimetype` entry",
+            None,
+        );
+        return;
+    };
+    if index != 0 {
+        report.push(
+            Warning,
+            MimetypeNotFirst,
+            "`mimetype` must be the first entry in the archive",
+            Some("mimetype"),
+        );
+    }
+    let Ok(mut entry) = zip.by_index(index) else {
+        return;
+    };
+    if entry.compression() != CompressionMethod::Stored {
+        report.push(
+            Warning,
+            MimetypeCompressed,
+            "`mimetype` must be stored uncompressed",
+            Some("mimetype"),
+        );
+    }
+    let mut content = Vec::new();
+    if entry.read_to_end(&mut content).is_ok()
+        && String::from_utf8_lossy(&content).trim().as_bytes() != EPUB_MIMETYPE
+    {
+        report.push(
+            Error,
+            MimetypeInvalid,
+            format!(
+                "`mimetype` contains \"{}\" instead of \"application/epub+zip\"",
+                String::from_utf8_lossy(&content).trim()
+            ),
+            Some("mimetype"),
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// encryption.xml
+// ---------------------------------------------------------------------------
+
+/// Reports every `<CipherReference>` in `META-INF/encryption.xml` and
+/// returns the zip paths that are encrypted with a non-obfuscation
+/// algorithm (i.e. DRM).
+fn check_encryption<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    report: &mut EpubValidationReport,
+) -> HashSet<String> {
+    let mut drm = HashSet::new();
+    let Ok(bytes) = read_zip_entry(zip, "META-INF/encryption.xml") else {
+        return drm;
+    };
+    let normalized = strip_xml_bom(&bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut algorithm = String::new();
+    let mut obfuscated_fonts = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => match local_name(e.name().as_ref()) {
+                b"EncryptedData" => algorithm.clear(),
+                b"EncryptionMethod" => {
+                    if let Some(v) = attr_value(&e, b"Algorithm") {
+                        algorithm = v;
+                    }
+                }
+                b"CipherReference" => {
+                    // OCF: CipherReference URIs are relative to the
+                    // container root, not to META-INF/.
+                    if let Some(uri) = attr_value(&e, b"URI") {
+                        if FONT_OBFUSCATION_ALGORITHMS.contains(&algorithm.as_str()) {
+                            obfuscated_fonts += 1;
+                        } else {
+                            drm.insert(resolve_relative("", &uri));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            // A broken encryption.xml is itself suspicious, but what we
+            // could read so far is still worth reporting.
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if obfuscated_fonts > 0 {
+        report.push(
+            ValidationSeverity::Info,
+            ValidationCode::ObfuscatedFont,
+            format!("{obfuscated_fonts} embedded font(s) use standard font obfuscation"),
+            Some("META-INF/encryption.xml"),
+        );
+    }
+    if !drm.is_empty() {
+        let scheme = detect_epub_drm(zip).map_or("unknown", |s| s.as_str());
+        report.push(
+            ValidationSeverity::Error,
+            ValidationCode::EncryptedEntry,
+            format!(
+                "{} entr{} encrypted with DRM ({scheme}); the book needs the publisher's reader",
+                drm.len(),
+                if drm.len() == 1 { "y is" } else { "ies are" }
+            ),
+            Some("META-INF/encryption.xml"),
+        );
+    }
+    drm
+}
+
+// ---------------------------------------------------------------------------
+// OPF / TOC scanning
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct ValidatorManifestItem {
+    id: String,
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+#[derive(Debug, Default)]
+struct OpfStructure {
+    manifest: Vec<ValidatorManifestItem>,
+    spine: Vec<String>,
+    spine_toc: Option<String>,
+    duplicate_ids: Vec<String>,
+}
+
+/// Single streaming pass over the OPF collecting the manifest, the spine
+/// and every `id` attribute (XML ids are document-wide, so a metadata
+/// `<meta id>` colliding with a manifest item id counts too).
+fn scan_opf(bytes: &[u8]) -> Result<OpfStructure, String> {
+    let normalized = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().trim_text(true);
+    let mut out = OpfStructure::default();
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let mut attrs: HashMap<Vec<u8>, String> = HashMap::new();
+                for a in e.attributes().flatten() {
+                    let value = a
+                        .unescape_value()
+                        .map(|v| v.into_owned())
+                        .unwrap_or_else(|_| String::from_utf8_lossy(&a.value).into_owned());
+                    attrs.insert(local_name(a.key.as_ref()).to_vec(), value);
+                }
+                if let Some(id) = attrs.get(b"id".as_slice()) {
+                    if !seen_ids.insert(id.clone()) && !out.duplicate_ids.contains(id) {
+                        out.duplicate_ids.push(id.clone());
+                    }
+                }
+                let mut take = |k: &[u8]| attrs.remove(k).unwrap_or_default();
+                match local_name(e.name().as_ref()) {
+                    b"item" => out.manifest.push(ValidatorManifestItem {
+                        id: take(b"id"),
+                        href: take(b"href"),
+                        media_type: take(b"media-type"),
+                        properties: take(b"properties"),
+                    }),
+                    b"itemref" => out.spine.push(take(b"idref")),
+                    b"spine" => out.spine_toc = Some(take(b"toc")).filter(|t| !t.is_empty()),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("xml: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+/// Collect `attr` values of every `element` in a nav / ncx document.
+/// Parse errors truncate the list instead of failing: a half-broken TOC
+/// still has links worth checking.
+fn collect_links(bytes: &[u8], element: &[u8], attr: &[u8]) -> Vec<String> {
+    let normalized = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().trim_text(true);
+    let mut out = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e))
+                if local_name(e.name().as_ref()) == element =>
+            {
+                if let Some(v) = attr_value(&e, attr) {
+                    out.push(v);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    out
+}
+
+fn attr_value(e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == key)
+        .map(|a| {
+            a.unescape_value()
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&a.value).into_owned())
+        })
+}
+
+/// `http:`, `https:`, `mailto:`, `data:` … — anything with a URI scheme
+/// before the first path separator is outside the container.
+fn is_external_href(href: &str) -> bool {
+    match href.find(':') {
+        Some(colon) => !href[..colon].contains('/'),
+        None => false,
+    }
+}
+
+fn entry_exists<R: Read + Seek>(zip: &ZipArchive<R>, path: &str) -> bool {
+    if zip.index_for_name(path).is_some() {
+        return true;
+    }
+    let decoded = percent_encoding::percent_decode(path.as_bytes()).decode_utf8_lossy();
+    decoded.as_ref() != path && zip.index_for_name(decoded.as_ref()).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    fn build_epub(entries: &[(&str, &str, CompressionMethod)]) -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut buf = Vec::<u8>::new();
+        {
+            let mut w = zip::ZipWriter::new(Cursor::new(&mut buf));
+            for (name, body, method) in entries {
+                let opts = zip::write::SimpleFileOptions::default().compression_method(*method);
+                w.start_file(*name, opts).unwrap();
+                w.write_all(body.as_bytes()).unwrap();
+            }
+            w.finish().unwrap();
+        }
+        ZipArchive::new(Cursor::new(buf)).unwrap()
+    }
+
+    fn codes(report: &EpubValidationReport) -> Vec<ValidationCode> {
+        report.findings.iter().map(|f| f.code).collect()
+    }
+
+    #[test]
+    fn well_formed_epub_has_no_findings() {
+        let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata/>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="Text/Chapter%201.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="c1"/></spine>
+</package>"#;
+        let nav = r##"<html xmlns="http://www.w3.org/1999/xhtml"><body><nav>
+  <ol><li><a href="Text/Chapter%201.xhtml#start">One</a></li><li><a href="#top">Top</a></li></ol>
+</nav></body></html>"##;
+        let mut zip = build_epub(&[
+            (
+                "mimetype",
+                "application/epub+zip",
+                CompressionMethod::Stored,
+            ),
+            (
+                "META-INF/container.xml",
+                CONTAINER,
+                CompressionMethod::Deflated,
+            ),
+            ("OEBPS/content.opf", opf, CompressionMethod::Deflated),
+            ("OEBPS/nav.xhtml", nav, CompressionMethod::Deflated),
+            (
+                "OEBPS/Text/Chapter 1.xhtml",
+                "<html/>",
+                CompressionMethod::Deflated,
+            ),
+        ]);
+        let report = validate_archive(&mut zip);
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!(report.opf_path.as_deref(), Some("OEBPS/content.opf"));
+    }
+
+    #[test]
+    fn reports_structural_problems() {
+        let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+  <metadata><meta id="c1" property="x">dup</meta></metadata>
+  <manifest>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="c1" href="c1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="missing.xhtml" media-type="application/xhtml+xml"/>
+    <item id="remote" href="https://example.com/a.mp3" media-type="audio/mpeg"/>
+  </manifest>
+  <spine toc="ncx"><itemref idref="c1"/><itemref idref="ghost"/></spine>
+</package>"#;
+        let ncx = r#"<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/"><navMap>
+  <navPoint><content src="c1.xhtml"/></navPoint>
+  <navPoint><content src="gone.xhtml#p2"/></navPoint>
+</navMap></ncx>"#;
+        let mut zip = build_epub(&[
+            (
+                "META-INF/container.xml",
+                CONTAINER,
+                CompressionMethod::Deflated,
+            ),
+            ("mimetype", "application/zip", CompressionMethod::Deflated),
+            ("OEBPS/content.opf", opf, CompressionMethod::Deflated),
+            ("OEBPS/toc.ncx", ncx, CompressionMethod::Deflated),
+            ("OEBPS/c1.xhtml", "<html/>", CompressionMethod::Deflated),
+        ]);
+        let report = validate_archive(&mut zip);
+        assert_eq!(
+            codes(&report),
+            vec![
+                ValidationCode::MimetypeNotFirst,
+                ValidationCode::MimetypeCompressed,
+                ValidationCode::MimetypeInvalid,
+                ValidationCode::DuplicateId,
+                ValidationCode::ManifestTargetMissing,
+                ValidationCode::SpineItemNotInManifest,
+                ValidationCode::NcxLinkUnresolved,
+            ]
+        );
+        assert_eq!(report.error_count, 4);
+        assert_eq!(report.warning_count, 3);
+        assert_eq!(
+            report.findings[4].path.as_deref(),
+            Some("OEBPS/missing.xhtml")
+        );
+    }
+
+    #[test]
+    fn separates_font_obfuscation_from_drm() {
+        let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="c1" href="c1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="f" href="font.otf" media-type="font/otf"/>
+  </manifest>
+  <spine><itemref idref="c1"/></spine>
+</package>"#;
+        let encryption = r#"<encryption xmlns="urn:oasis:names:tc:opendocument:xmlns:container"
+    xmlns:enc="http://www.w3.org/2001/04/xmlenc#">
+  <enc:EncryptedData>
+    <enc:EncryptionMethod Algorithm="http://www.idpf.org/2008/embedding"/>
+    <enc:CipherData><enc:CipherReference URI="OEBPS/font.otf"/></enc:CipherData>
+  </enc:EncryptedData>
+  <enc:EncryptedData>
+    <enc:EncryptionMethod Algorithm="http://www.w3.org/2001/04/xmlenc#aes128-cbc"/>
+    <enc:CipherData><enc:CipherReference URI="OEBPS/c1.xhtml"/></enc:CipherData>
+  </enc:EncryptedData>
+</encryption>"#;
+        let mut zip = build_epub(&[
+            (
+                "mimetype",
+                "application/epub+zip",
+                CompressionMethod::Stored,
+            ),
+            (
+                "META-INF/container.xml",
+                CONTAINER,
+                CompressionMethod::Deflated,
+            ),
+            (
+                "META-INF/encryption.xml",
+                encryption,
+                CompressionMethod::Deflated,
+            ),
+            ("OEBPS/content.opf", opf, CompressionMethod::Deflated),
+            ("OEBPS/c1.xhtml", "xx", CompressionMethod::Stored),
+            ("OEBPS/font.otf", "xx", CompressionMethod::Stored),
+        ]);
+        let report = validate_archive(&mut zip);
+        assert_eq!(
+            codes(&report),
+            vec![
+                ValidationCode::ObfuscatedFont,
+                ValidationCode::EncryptedEntry,
+                ValidationCode::EncryptedEntry,
+            ]
+        );
+        assert_eq!(report.findings[0].severity, ValidationSeverity::Info);
+        assert_eq!(report.findings[1].severity, ValidationSeverity::Error);
+        assert_eq!(report.findings[2].severity, ValidationSeverity::Warning);
+        assert_eq!(report.findings[2].path.as_deref(), Some("OEBPS/c1.xhtml"));
+        assert_eq!(report.error_count, 1);
+    }
+
+    #[test]
+    fn missing_container_stops_the_walk() {
+        let mut zip = build_epub(&[(
+            "mimetype",
+            "application/epub+zip",
+            CompressionMethod::Stored,
+        )]);
+        let report = validate_archive(&mut zip);
+        assert_eq!(codes(&report), vec![ValidationCode::ContainerInvalid]);
+        assert!(report.opf_path.is_none());
+    }
+
+    #[test]
+    fn external_hrefs_are_not_archive_paths() {
+        assert!(is_external_href("https://example.com/x"));
+        assert!(is_external_href("mailto:a@b.c"));
+        assert!(!is_external_href("Text/ch1.xhtml"));
+        assert!(!is_external_href("Text/odd:name.xhtml"));
+    }
+}
@@ -0,0 +1,560 @@
+// Native FictionBook (FB2 / FB2.ZIP) import path.
+//
+// Unlike EPUB and MOBI, FB2 has no JS-side parser we'd risk diverging
+// from on the reader hot path for metadata: the whole book is one XML
+// document whose `<description><title-info>` block carries everything the
+// library needs. The JS import path currently inflates (for `.fb2.zip`),
+// decodes and DOMParses the *entire* document — including every base64
+// `<binary>` illustration — just to read that header, which is what makes
+// large Russian-language libraries slow to import.
+//
+// What `parse_fb2_metadata` does:
+//   - compute partialMD5 over the file on disk (the `.fb2.zip` container,
+//     not the inflated document, matching what `utils/md5.ts` hashes);
+//   - if the file is a zip (sniffed from the `PK\x03\x04` magic, not the
+//     extension), inflate the first `*.fb2` entry;
+//   - transcode the document to UTF-8 using the XML declaration's
+//     `encoding` (windows-1251 / KOI8-R are still common in the wild);
+//   - stream the XML once with quick-xml, reading title / authors /
+//     series / language / annotation from `<title-info>` and the cover id
+//     from `<coverpage><image l:href="#id"/>`;
+//   - base64-decode only the cover `<binary>` (falling back to the first
+//     image binary when no `<coverpage>` is declared, like the Windows
+//     thumbnail provider does) and clamp it via `maybe_resize_cover`.
+//
+// `<src-title-info>` (the original-language record of translated books)
+// and `<document-info>` are ignored: the library shows the translated
+// title and authors, which is what `<title-info>` holds.
+
+use base64::{engine::general_purpose, Engine as _};
+use encoding_rs::Encoding;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::parser_common::{
+    compute_partial_md5, local_name, maybe_resize_cover, strip_xml_bom, RawCoverImage,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedFb2Metadata {
+    pub partial_md5: String,
+    pub title: Option<String>,
+    /// Display names ("First Middle Last", or the nickname when the
+    /// author element only carries one), in document order.
+    pub authors: Vec<String>,
+    /// `<sequence name="...">` directly under `<title-info>`.
+    pub series: Option<String>,
+    /// `<sequence number="...">`. FB2 declares it as an integer, but a
+    /// handful of generators emit `1.5`-style values for novellas, so it is
+    /// surfaced as a float like the JS `seriesIndex`.
+    pub series_index: Option<f64>,
+    pub language: Option<String>,
+    /// Plain-text annotation, one paragraph per line.
+    pub annotation: Option<String>,
+    /// Pre-resized cover (after `maybe_resize_cover`), or `None` when the
+    /// document carries no image binaries at all.
+    pub cover: Option<RawCoverImage>,
+}
+
+#[tauri::command]
+pub async fn parse_fb2_metadata(file_path: String) -> Result<ParsedFb2Metadata, String> {
+    // Same threading rationale as `parse_epub_metadata`: zip inflate, XML
+    // scan and cover resize are CPU-bound and must stay off the IPC worker.
+    tauri::async_runtime::spawn_blocking(move || parse_fb2_metadata_sync(&file_path))
+        .await
+        .map_err(|e| format!("join error: {e}"))?
+}
+
+fn parse_fb2_metadata_sync(file_path: &str) -> Result<ParsedFb2Metadata, String> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        return Err(format!("file not found: {file_path}"));
+    }
+
+    let partial_md5 = compute_partial_md5(path).map_err(|e| format!("partial_md5 failed: {e}"))?;
+
+    let raw = std::fs::read(path).map_err(|e| format!("read failed: {e}"))?;
+    let document = if is_zip(&raw) {
+        read_fb2_from_zip(Cursor::new(raw)).map_err(|e| format!("fb2.zip: {e}"))?
+    } else {
+        raw
+    };
+
+    let info = parse_fb2_document(&document).map_err(|e| format!("parse fb2: {e}"))?;
+
+    let cover = info.cover.map(|raw| {
+        let (bytes, mime) = maybe_resize_cover(raw.bytes, &raw.mime);
+        RawCoverImage { bytes, mime }
+    });
+
+    Ok(ParsedFb2Metadata {
+        partial_md5,
+        title: info.title,
+        authors: info.authors,
+        series: info.series,
+        series_index: info.series_index,
+        language: info.language,
+        annotation: info.annotation,
+        cover,
+    })
+}
+
+fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+}
+
+/// Inflate the FictionBook document out of a `.fb2.zip` container. The
+/// convention is a single `<name>.fb2` entry, but archives repacked by
+/// hand sometimes carry a stray `.txt`/`.nfo` next to it, so we look for
+/// the `.fb2` extension first and only then fall back to the first file.
+fn read_fb2_from_zip<R: Read + Seek>(reader: R) -> Result<Vec<u8>, String> {
+    let mut zip = ZipArchive::new(reader).map_err(|e| format!("zip open failed: {e}"))?;
+    let index = (0..zip.len())
+        .find(|&i| {
+            zip.name_for_index(i)
+                .is_some_and(|n| n.to_ascii_lowercase().ends_with(".fb2"))
+        })
+        .or_else(|| {
+            (0..zip.len()).find(|&i| zip.name_for_index(i).is_some_and(|n| !n.ends_with('/')))
+        })
+        .ok_or_else(|| "no fb2 entry in archive".to_string())?;
+    let mut entry = zip
+        .by_index(index)
+        .map_err(|e| format!("entry {index}: {e}"))?;
+    let mut buf = Vec::with_capacity(entry.size() as usize);
+    entry
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("inflate: {e}"))?;
+    Ok(buf)
+}
+
+/// Transcode an FB2 document to UTF-8. A BOM wins (and is handled by the
+/// shared `strip_xml_bom`); otherwise the `encoding="..."` pseudo-attribute
+/// of the XML declaration picks the decoder. Unknown or missing labels are
+/// treated as UTF-8, which is the FB2 default.
+fn decode_fb2_text(bytes: &[u8]) -> Cow<'_, [u8]> {
+    let has_bom = bytes.starts_with(&[0xEF, 0xBB, 0xBF])
+        || bytes.starts_with(&[0xFE, 0xFF])
+        || bytes.starts_with(&[0xFF, 0xFE]);
+    if has_bom {
+        return strip_xml_bom(bytes);
+    }
+    match declared_encoding(bytes) {
+        Some(encoding) if encoding != encoding_rs::UTF_8 => {
+            let (decoded, _, _) = encoding.decode(bytes);
+            Cow::Owned(decoded.into_owned().into_bytes())
+        }
+        _ => Cow::Borrowed(bytes),
+    }
+}
+
+/// Pull the `encoding` label out of `<?xml ... encoding="..."?>`. Only the
+/// first few hundred bytes are inspected — the declaration must open the
+/// document, and the label itself is plain ASCII in every encoding FB2
+/// files are found in.
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(256)];
+    let head = String::from_utf8_lossy(head);
+    let decl = head.strip_prefix("<?xml")?;
+    let decl = &decl[..decl.find("?>")?];
+    let rest = &decl[decl.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    let label = &rest[..rest.find(quote)?];
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Everything `parse_fb2_document` reads out of the XML, before the cover
+/// is resized.
+#[derive(Debug, Default)]
+struct Fb2Info {
+    title: Option<String>,
+    authors: Vec<String>,
+    series: Option<String>,
+    series_index: Option<f64>,
+    language: Option<String>,
+    annotation: Option<String>,
+    cover: Option<RawCoverImage>,
+}
+
+#[derive(Debug, Default)]
+struct AuthorParts {
+    first: String,
+    middle: String,
+    last: String,
+    nickname: String,
+}
+
+impl AuthorParts {
+    fn display_name(&self) -> Option<String> {
+        let full = [&self.first, &self.middle, &self.last]
+            .iter()
+            .map(|s| collapse_whitespace(s))
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !full.is_empty() {
+            return Some(full);
+        }
+        let nick = collapse_whitespace(&self.nickname);
+        (!nick.is_empty()).then_some(nick)
+    }
+}
+
+/// A `<binary>` currently being collected.
+struct PendingBinary {
+    id: String,
+    content_type: String,
+    data: String,
+}
+
+/// Element names that end a paragraph inside `<annotation>`.
+const ANNOTATION_BLOCKS: &[&[u8]] = &[b"p", b"v", b"subtitle", b"text-author", b"empty-line"];
+
+/// Single streaming pass over an FB2 document. The `<description>` header
+/// precedes `<body>` and the `<binary>` sections, so by the time a binary
+/// is reached the cover id (if any) is already known and only that one
+/// payload is buffered and decoded.
+fn parse_fb2_document(bytes: &[u8]) -> Result<Fb2Info, String> {
+    let text = decode_fb2_text(bytes);
+    let mut reader = Reader::from_reader(text.as_ref());
+    // Whitespace is significant for mixed content (`Hello <emphasis>world
+    // </emphasis>`), so it is collapsed per field rather than trimmed by the
+    // reader. `<sequence/>` / `<image/>` are expanded so every element goes
+    // through the Start/End path.
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut info = Fb2Info::default();
+    let mut stack: Vec<Vec<u8>> = Vec::new();
+    let mut title = String::new();
+    let mut language = String::new();
+    let mut author: Option<AuthorParts> = None;
+    let mut annotation: Option<Vec<String>> = None;
+    let mut paragraph = String::new();
+    let mut cover_id: Option<String> = None;
+    let mut binary: Option<PendingBinary> = None;
+    let mut fallback_cover: Option<RawCoverImage> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref()).to_vec();
+                stack.push(name);
+                let depth = stack.len();
+                let name = stack[depth - 1].as_slice();
+                let title_info = in_title_info(&stack);
+
+                if title_info && depth == 4 {
+                    match name {
+                        b"author" => author = Some(AuthorParts::default()),
+                        b"annotation" => annotation = Some(Vec::new()),
+                        b"sequence" if info.series.is_none() => {
+                            read_sequence(&e, &mut info.series, &mut info.series_index)
+                        }
+                        _ => {}
+                    }
+                } else if title_info
+                    && name == b"image"
+                    && stack.iter().any(|s| s == b"coverpage")
+                    && cover_id.is_none()
+                {
+                    cover_id = attr_value(&e, b"href")
+                        .map(|h| h.trim_start_matches('#').to_string())
+                        .filter(|h| !h.is_empty());
+                } else if depth == 2 && name == b"binary" {
+                    let id = attr_value(&e, b"id").unwrap_or_default();
+                    let content_type = attr_value(&e, b"content-type").unwrap_or_default();
+                    // Besides the declared cover, buffer the first image binary
+                    // as a fallback for documents without a `<coverpage>` (or
+                    // whose `l:href` points at a binary that doesn't exist).
+                    let wanted = cover_id.as_deref() == Some(id.as_str())
+                        || (fallback_cover.is_none() && content_type.starts_with("image/"));
+                    if wanted {
+                        binary = Some(PendingBinary {
+                            id,
+                            content_type,
+                            data: String::new(),
+                        });
+                    }
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let text = match e.unescape() {
+                    Ok(t) => t,
+                    // FB2 generators love HTML entities (`&nbsp;`) that XML
+                    // doesn't define; keep the raw text rather than failing.
+                    Err(_) => Cow::Owned(String::from_utf8_lossy(&e).into_owned()),
+                };
+                let top = stack.last().map(Vec::as_slice).unwrap_or_default();
+                if let Some(bin) = binary.as_mut() {
+                    bin.data.push_str(&text);
+                } else if annotation.is_some() {
+                    paragraph.push_str(&text);
+                } else if let Some(parts) = author.as_mut() {
+                    match top {
+                        b"first-name" => parts.first.push_str(&text),
+                        b"middle-name" => parts.middle.push_str(&text),
+                        b"last-name" => parts.last.push_str(&text),
+                        b"nickname" => parts.nickname.push_str(&text),
+                        _ => {}
+                    }
+                } else if in_title_info(&stack) && stack.len() == 4 {
+                    match top {
+                        b"book-title" => title.push_str(&text),
+                        b"lang" => language.push_str(&text),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::CData(e)) if annotation.is_some() => {
+                paragraph.push_str(&String::from_utf8_lossy(&e));
+            }
+            Ok(Event::End(_)) => {
+                let Some(name) = stack.pop() else {
+                    continue;
+                };
+                let depth = stack.len() + 1;
+                let leaving_title_info_child = depth == 4 && in_title_info(&stack);
+                match name.as_slice() {
+                    b"author" if leaving_title_info_child => {
+                        if let Some(name) = author.take().and_then(|a| a.display_name()) {
+                            info.authors.push(name);
+                        }
+                    }
+                    b"annotation" if leaving_title_info_child => {
+                        if let Some(mut paragraphs) = annotation.take() {
+                            flush_paragraph(&mut paragraph, &mut paragraphs);
+                            if !paragraphs.is_empty() {
+                                info.annotation = Some(paragraphs.join("\n"));
+                            }
+                        }
+                    }
+                    b"binary" if depth == 2 => {
+                        if let Some(bin) = binary.take() {
+                            let is_cover = cover_id.as_deref() == Some(bin.id.as_str());
+                            if let Some(image) = decode_binary(bin) {
+                                if is_cover {
+                                    info.cover = Some(image);
+                                    // Nothing after the cover binary matters.
+                                    break;
+                                }
+                                fallback_cover = Some(image);
+                            }
+                        }
+                    }
+                    block if annotation.is_some() && ANNOTATION_BLOCKS.contains(&block) => {
+                        if let Some(paragraphs) = annotation.as_mut() {
+                            flush_paragraph(&mut paragraph, paragraphs);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                // A truncated or sloppy body must not cost us a header we
+                // already read: only fail when the description never parsed.
+                if title.is_empty() && info.authors.is_empty() {
+                    return Err(format!("xml: {e}"));
+                }
+                log::warn!("fb2: stopping at malformed xml: {e}");
+                break;
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    info.title = Some(collapse_whitespace(&title)).filter(|s| !s.is_empty());
+    info.language = Some(collapse_whitespace(&language)).filter(|s| !s.is_empty());
+    if info.cover.is_none() {
+        info.cover = fallback_cover;
+    }
+    Ok(info)
+}
+
+/// `true` while the element stack is inside `FictionBook/description/title-info`.
+fn in_title_info(stack: &[Vec<u8>]) -> bool {
+    stack.len() >= 3 && stack[1] == b"description" && stack[2] == b"title-info"
+}
+
+fn read_sequence(e: &BytesStart<'_>, series: &mut Option<String>, index: &mut Option<f64>) {
+    let name = attr_value(e, b"name")
+        .map(|n| collapse_whitespace(&n))
+        .filter(|n| !n.is_empty());
+    if name.is_some() {
+        *series = name;
+        *index = attr_value(e, b"number").and_then(|n| n.trim().parse::<f64>().ok());
+    }
+}
+
+/// Attribute lookup by local name, so `l:href`, `xlink:href` and a bare
+/// `href` all resolve the same way.
+fn attr_value(e: &BytesStart<'_>, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| local_name(a.key.as_ref()) == key)
+        .map(|a| match a.unescape_value() {
+            Ok(v) => v.into_owned(),
+            Err(_) => String::from_utf8_lossy(&a.value).into_owned(),
+        })
+}
+
+fn decode_binary(bin: PendingBinary) -> Option<RawCoverImage> {
+    let cleaned: String = bin.data.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = general_purpose::STANDARD.decode(cleaned).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+    let mime = if bin.content_type.starts_with("image/") {
+        bin.content_type
+    } else {
+        "image/jpeg".to_string()
+    };
+    Some(RawCoverImage { bytes, mime })
+}
+
+fn flush_paragraph(paragraph: &mut String, paragraphs: &mut Vec<String>) {
+    let text = collapse_whitespace(paragraph);
+    if !text.is_empty() {
+        paragraphs.push(text);
+    }
+    paragraph.clear();
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    // 1x1 transparent PNG.
+    const PNG_B64: &str =
+        "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+    fn sample_fb2(encoding: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="{encoding}"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+  <description>
+    <title-info>
+      <genre>sf</genre>
+      <author><first-name>Аркадий</first-name><middle-name>Натанович</middle-name><last-name>Стругацкий</last-name></author>
+      <author><nickname>anon</nickname></author>
+      <book-title>Пикник на обочине</book-title>
+      <annotation>
+        <p>First <emphasis>paragraph</emphasis> &amp; more.</p>
+        <empty-line/>
+        <p>Second
+          paragraph.</p>
+      </annotation>
+      <coverpage><image l:href="#cover.png"/></coverpage>
+      <lang>ru</lang>
+      <sequence name="Мир Полудня" number="7"/>
+    </title-info>
+    <src-title-info>
+      <author><first-name>Ignored</first-name></author>
+      <book-title>Ignored</book-title>
+    </src-title-info>
+  </description>
+  <body><section><p>Text</p></section></body>
+  <binary id="other.jpg" content-type="image/jpeg">AAAA</binary>
+  <binary id="cover.png" content-type="image/png">{PNG_B64}</binary>
+</FictionBook>"##
+        )
+    }
+
+    #[test]
+    fn parse_fb2_document_reads_title_info() {
+        let info = parse_fb2_document(sample_fb2("utf-8").as_bytes()).expect("fb2 parses");
+        assert_eq!(info.title.as_deref(), Some("Пикник на обочине"));
+        assert_eq!(
+            info.authors,
+            vec![
+                "Аркадий Натанович Стругацкий".to_string(),
+                "anon".to_string()
+            ]
+        );
+        assert_eq!(info.series.as_deref(), Some("Мир Полудня"));
+        assert_eq!(info.series_index, Some(7.0));
+        assert_eq!(info.language.as_deref(), Some("ru"));
+        assert_eq!(
+            info.annotation.as_deref(),
+            Some("First paragraph & more.\nSecond paragraph.")
+        );
+        let cover = info.cover.expect("cover");
+        assert_eq!(cover.mime, "image/png");
+        assert!(cover.bytes.starts_with(&[0x89, b'P', b'N', b'G']));
+    }
+
+    #[test]
+    fn parse_fb2_document_decodes_windows_1251() {
+        let xml = sample_fb2("windows-1251");
+        let (encoded, _, had_errors) = encoding_rs::WINDOWS_1251.encode(&xml);
+        assert!(!had_errors);
+        let info = parse_fb2_document(&encoded).expect("fb2 parses");
+        assert_eq!(info.title.as_deref(), Some("Пикник на обочине"));
+        assert_eq!(info.series.as_deref(), Some("Мир Полудня"));
+    }
+
+    #[test]
+    fn parse_fb2_document_falls_back_to_first_image_binary() {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0">
+  <description><title-info><book-title>No Coverpage</book-title></title-info></description>
+  <body><section><p>Text</p></section></body>
+  <binary id="note.txt" content-type="text/plain">aGk=</binary>
+  <binary id="img1" content-type="image/png">{PNG_B64}</binary>
+</FictionBook>"#
+        );
+        let info = parse_fb2_document(xml.as_bytes()).expect("fb2 parses");
+        assert_eq!(info.title.as_deref(), Some("No Coverpage"));
+        assert!(info.authors.is_empty());
+        assert_eq!(info.cover.map(|c| c.mime).as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn declared_encoding_reads_xml_declaration() {
+        assert_eq!(
+            declared_encoding(br#"<?xml version="1.0" encoding="windows-1251"?><a/>"#),
+            Some(encoding_rs::WINDOWS_1251)
+        );
+        assert_eq!(
+            declared_encoding(b"<?xml version='1.0' encoding = 'koi8-r' ?><a/>"),
+            Some(encoding_rs::KOI8_R)
+        );
+        assert_eq!(declared_encoding(br#"<?xml version="1.0"?><a/>"#), None);
+        assert_eq!(declared_encoding(b"<a/>"), None);
+    }
+
+    #[test]
+    fn read_fb2_from_zip_prefers_fb2_entry() {
+        let mut buf = Vec::<u8>::new();
+        {
+            let mut w = zip::ZipWriter::new(Cursor::new(&mut buf));
+            let opts = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            w.start_file("readme.txt", opts).unwrap();
+            w.write_all(b"not the book").unwrap();
+            w.start_file("book.FB2", opts).unwrap();
+            w.write_all(sample_fb2("utf-8").as_bytes()).unwrap();
+            w.finish().unwrap();
+        }
+        assert!(is_zip(&buf));
+        let doc = read_fb2_from_zip(Cursor::new(buf)).expect("inflates");
+        let info = parse_fb2_document(&doc).expect("fb2 parses");
+        assert_eq!(info.title.as_deref(), Some("Пикник на обочине"));
+    }
+}
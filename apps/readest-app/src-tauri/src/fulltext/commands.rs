@@ -0,0 +1,79 @@
+use super::extract::extract_book_text;
+use super::index::{FulltextHit, FulltextIndex, IndexedBook};
+use super::FulltextState;
+use crate::parser_common::ParseError;
+use std::path::Path;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Default cap on `fulltext_search` results.
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+
+/// Run `f` against the (lazily opened) index on the blocking pool.
+async fn with_index<R, T, F>(app: AppHandle<R>, f: F) -> Result<T, String>
+where
+    R: Runtime,
+    T: Send + 'static,
+    F: FnOnce(&mut FulltextIndex) -> std::io::Result<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<FulltextState>();
+        let mut guard = state.0.lock().map_err(|e| e.to_string())?;
+        if guard.is_none() {
+            let root = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| e.to_string())?
+                .join("fulltext");
+            *guard = Some(FulltextIndex::open(root));
+        }
+        let index = guard.as_mut().expect("index opened above");
+        f(index).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))?
+}
+
+/// (Re-)index the book at `file_path` under `book_hash`. DRM-protected books
+/// fail with `ParseError::DrmProtected`, like the import parsers.
+#[tauri::command]
+pub async fn fulltext_index_book<R: Runtime>(
+    app: AppHandle<R>,
+    file_path: String,
+    book_hash: String,
+) -> Result<IndexedBook, ParseError> {
+    // Extraction is the slow part; keep it outside the index lock so
+    // searches aren't blocked while a large book is being read.
+    let sections =
+        tauri::async_runtime::spawn_blocking(move || extract_book_text(Path::new(&file_path)))
+            .await
+            .map_err(|e| format!("join error: {e}"))??;
+    Ok(with_index(app, move |index| index.add_book(&book_hash, sections)).await?)
+}
+
+#[tauri::command]
+pub async fn fulltext_remove_book<R: Runtime>(
+    app: AppHandle<R>,
+    book_hash: String,
+) -> Result<bool, String> {
+    with_index(app, move |index| index.remove_book(&book_hash)).await
+}
+
+#[tauri::command]
+pub async fn fulltext_list_books<R: Runtime>(app: AppHandle<R>) -> Result<Vec<String>, String> {
+    with_index(app, |index| index.list_books()).await
+}
+
+/// Phrase search across `book_hashes` (every indexed book when `None`).
+#[tauri::command]
+pub async fn fulltext_search<R: Runtime>(
+    app: AppHandle<R>,
+    query: String,
+    book_hashes: Option<Vec<String>>,
+    limit: Option<usize>,
+) -> Result<Vec<FulltextHit>, String> {
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    with_index(app, move |index| {
+        index.search(&query, book_hashes.as_deref(), limit)
+    })
+    .await
+}
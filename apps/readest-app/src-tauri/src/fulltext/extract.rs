@@ -0,0 +1,404 @@
+//! Plain-text extraction for the full-text index.
+//!
+//! EPUB: one section per spine item, opened through the same zip helpers
+//! `epub_parser` uses (container.xml -> OPF -> spine, percent-decoding
+//! fallback). The section text is the `<body>` `textContent` the reader's
+//! DOM will have — every text node, whitespace included, entities decoded —
+//! so an offset into it can be turned into a DOM range (and from there a
+//! CFI) by walking text nodes. `<script>` / `<style>` content is blanked
+//! rather than removed: it still occupies offsets in `textContent`, but
+//! must never match a query.
+//!
+//! MOBI / AZW / AZW3: the decoded markup is cut into synthetic sections of
+//! `MOBI_SECTION_BYTES`. They do not line up with PalmDOC records, which
+//! are measured on the compressed, original-encoding text, so a section is
+//! addressed as `offset:<n>`, the UTF-8 byte offset in the decoded markup
+//! where it starts. Text is attributed to the section its source markup
+//! starts in.
+//!
+//! The HTML walker is deliberately lenient instead of going through
+//! quick-xml: MOBI markup is tag soup, and plenty of EPUB XHTML is not
+//! well-formed either. Losing a book's text to one stray `&` would make it
+//! silently unsearchable.
+
+use mobi::Mobi;
+use std::fs::File;
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::epub_parser::{detect_epub_drm, read_rootfile_path, read_spine_paths, read_zip_entry};
+use crate::mobi_parser::read_palmdoc_encryption;
+use crate::parser_common::{strip_xml_bom, DrmScheme, ParseError};
+
+/// Size of a synthetic MOBI section, in bytes of decoded markup.
+const MOBI_SECTION_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedSection {
+    /// EPUB: zip path of the spine item. MOBI: `offset:<n>`.
+    pub href: String,
+    pub text: String,
+}
+
+pub fn extract_book_text(path: &Path) -> Result<Vec<ExtractedSection>, ParseError> {
+    if !path.is_file() {
+        return Err(format!("file not found: {}", path.display()).into());
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "epub" => extract_epub(path),
+        "mobi" | "azw" | "azw3" | "prc" => extract_mobi(path),
+        _ => Err(format!("full-text extraction not supported for .{ext} files").into()),
+    }
+}
+
+fn extract_epub(path: &Path) -> Result<Vec<ExtractedSection>, ParseError> {
+    let file = File::open(path).map_err(|e| format!("open failed: {e}"))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("zip open failed: {e}"))?;
+    if let Some(scheme) = detect_epub_drm(&mut zip) {
+        return Err(ParseError::DrmProtected(scheme));
+    }
+    let opf_path = read_rootfile_path(&mut zip).map_err(|e| format!("container.xml: {e}"))?;
+    let opf_bytes =
+        read_zip_entry(&mut zip, &opf_path).map_err(|e| format!("read opf {opf_path}: {e}"))?;
+    let spine = read_spine_paths(&opf_bytes, &opf_path).map_err(|e| format!("spine: {e}"))?;
+
+    let mut sections = Vec::with_capacity(spine.len());
+    for href in spine {
+        // A missing spine document is a broken book, not a reason to leave
+        // the rest of it unsearchable.
+        let Ok(bytes) = read_zip_entry(&mut zip, &href) else {
+            continue;
+        };
+        let markup = strip_xml_bom(&bytes);
+        let markup = String::from_utf8_lossy(&markup);
+        let text = runs_to_text(&html_text_runs(&markup));
+        sections.push(ExtractedSection { href, text });
+    }
+    Ok(sections)
+}
+
+fn extract_mobi(path: &Path) -> Result<Vec<ExtractedSection>, ParseError> {
+    if read_palmdoc_encryption(path).is_ok_and(|enc| enc != 0) {
+        return Err(ParseError::DrmProtected(DrmScheme::Kindle));
+    }
+    let mobi = Mobi::from_path(path).map_err(|e| format!("parse mobi: {e}"))?;
+    Ok(split_markup_sections(&mobi.content_as_string_lossy()))
+}
+
+fn split_markup_sections(markup: &str) -> Vec<ExtractedSection> {
+    let mut sections: Vec<ExtractedSection> = Vec::new();
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut start = 0usize;
+    for run in html_text_runs(markup) {
+        let run_start = run.source - run.source % MOBI_SECTION_BYTES;
+        if run_start != start && !runs.is_empty() {
+            sections.push(ExtractedSection {
+                href: format!("offset:{start}"),
+                text: runs_to_text(&runs),
+            });
+            runs.clear();
+        }
+        start = run_start;
+        runs.push(run);
+    }
+    if !runs.is_empty() {
+        sections.push(ExtractedSection {
+            href: format!("offset:{start}"),
+            text: runs_to_text(&runs),
+        });
+    }
+    sections
+}
+
+/// One text node's worth of content.
+#[derive(Debug)]
+struct TextRun {
+    /// Byte offset of the run's source in the markup.
+    source: usize,
+    text: String,
+    /// Inside `<script>` / `<style>`: counts toward offsets, never matches.
+    hidden: bool,
+}
+
+fn runs_to_text(runs: &[TextRun]) -> String {
+    let mut out = String::new();
+    for run in runs {
+        if run.hidden {
+            // Same UTF-16 length, so later offsets still line up.
+            let units: usize = run.text.chars().map(char::len_utf16).sum();
+            out.extend(std::iter::repeat(' ').take(units));
+        } else {
+            out.push_str(&run.text);
+        }
+    }
+    out
+}
+
+/// Walk `markup` and return the text content of its `<body>` (the whole
+/// document when there is no body element), in document order.
+fn html_text_runs(markup: &str) -> Vec<TextRun> {
+    let bytes = markup.as_bytes();
+    let has_body = find_ci(bytes, 0, b"<body").is_some();
+    let mut in_body = !has_body;
+    let mut runs = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'<' {
+            let end = memchr(bytes, i, b'<').unwrap_or(bytes.len());
+            if in_body {
+                runs.push(TextRun {
+                    source: i,
+                    text: decode_entities(&markup[i..end]),
+                    hidden: false,
+                });
+            }
+            i = end;
+            continue;
+        }
+
+        let rest = &bytes[i..];
+        if rest.starts_with(b"<!--") {
+            i = find(bytes, i + 4, b"-->").map_or(bytes.len(), |p| p + 3);
+        } else if rest.starts_with(b"<![CDATA[") {
+            let start = i + 9;
+            let end = find(bytes, start, b"]]>").unwrap_or(bytes.len());
+            if in_body {
+                runs.push(TextRun {
+                    source: start,
+                    text: markup[start..end].to_string(),
+                    hidden: false,
+                });
+            }
+            i = (end + 3).min(bytes.len());
+        } else if rest.len() > 1 && (rest[1] == b'!' || rest[1] == b'?') {
+            i = memchr(bytes, i, b'>').map_or(bytes.len(), |p| p + 1);
+        } else if rest.len() > 1 && (rest[1] == b'/' || rest[1].is_ascii_alphabetic()) {
+            let closing = rest[1] == b'/';
+            let name_start = i + if closing { 2 } else { 1 };
+            let name_end = bytes[name_start..]
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || *b == b'/' || *b == b'>')
+                .map_or(bytes.len(), |p| name_start + p);
+            let name = markup[name_start..name_end].to_ascii_lowercase();
+            let tag_end = tag_end(bytes, name_end);
+            let self_closing = tag_end >= 2 && bytes[tag_end - 2] == b'/';
+            i = tag_end;
+
+            // Drop a namespace prefix (`<xhtml:body>`, `<mbp:pagebreak>`).
+            let local = name.rsplit(':').next().unwrap_or(&name);
+            match local {
+                "body" => in_body = !closing,
+                "script" | "style" if !closing && !self_closing => {
+                    let close = format!("</{name}");
+                    let end = find_ci(bytes, i, close.as_bytes()).unwrap_or(bytes.len());
+                    if in_body {
+                        runs.push(TextRun {
+                            source: i,
+                            text: markup[i..end].to_string(),
+                            hidden: true,
+                        });
+                    }
+                    i = end;
+                }
+                _ => {}
+            }
+        } else {
+            // A bare '<' in text (tag soup): keep it as text.
+            let end = memchr(bytes, i + 1, b'<').unwrap_or(bytes.len());
+            if in_body {
+                runs.push(TextRun {
+                    source: i,
+                    text: decode_entities(&markup[i..end]),
+                    hidden: false,
+                });
+            }
+            i = end;
+        }
+    }
+    runs
+}
+
+/// Index just past the `>` closing the tag whose attributes start at `from`,
+/// skipping `>` inside quoted attribute values.
+fn tag_end(bytes: &[u8], from: usize) -> usize {
+    let mut quote: Option<u8> = None;
+    for (offset, &b) in bytes[from..].iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            None if b == b'>' => return from + offset + 1,
+            None => {}
+        }
+    }
+    bytes.len()
+}
+
+fn memchr(bytes: &[u8], from: usize, needle: u8) -> Option<usize> {
+    bytes[from..]
+        .iter()
+        .position(|b| *b == needle)
+        .map(|p| from + p)
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from.min(bytes.len())..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+fn find_ci(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes[from.min(bytes.len())..]
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle))
+        .map(|p| from + p)
+}
+
+/// Decode character references. Unknown named entities are kept verbatim,
+/// which is what a browser shows for them too.
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&semi| semi <= 10)
+            .and_then(|semi| decode_entity(&rest[1..=semi]).map(|c| (c, semi + 2)));
+        match decoded {
+            Some((c, consumed)) => {
+                out.push(c);
+                rest = &rest[consumed..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(num) = name.strip_prefix('#') {
+        let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "hellip" => '\u{2026}',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_text(markup: &str) -> String {
+        runs_to_text(&html_text_runs(markup))
+    }
+
+    #[test]
+    fn extracts_body_text_content() {
+        let xhtml = r#"<?xml version="1.0"?>
+<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Skip me</title></head>
+<body><p>Fish &amp; chips</p><!-- no --><p class="a>b">&#8220;Hi&#x201D;<br/>there</p></body></html>"#;
+        assert_eq!(body_text(xhtml), "Fish & chips\u{201c}Hi\u{201d}there");
+    }
+
+    #[test]
+    fn script_text_keeps_its_offsets_but_is_blanked() {
+        let text = body_text("<body>a<script>if (x<y) {}</script>b</body>");
+        assert_eq!(text, format!("a{}b", " ".repeat("if (x<y) {}".len())));
+    }
+
+    #[test]
+    fn tolerates_tag_soup() {
+        let text = body_text("<p>1 < 2 &bogus; R&D<mbp:pagebreak/>Next</p>");
+        assert_eq!(text, "1 < 2 &bogus; R&DNext");
+    }
+
+    #[test]
+    fn extract_epub_follows_the_spine() {
+        use std::io::Write;
+        let path =
+            std::env::temp_dir().join(format!("readest-fulltext-{}.epub", std::process::id()));
+        {
+            let mut w = zip::ZipWriter::new(File::create(&path).unwrap());
+            let opts = zip::write::SimpleFileOptions::default();
+            for (name, body) in [
+                ("mimetype", "application/epub+zip"),
+                (
+                    "META-INF/container.xml",
+                    r#"<container><rootfiles><rootfile full-path="OPS/book.opf"/></rootfiles></container>"#,
+                ),
+                (
+                    "OPS/book.opf",
+                    r#"<package><manifest><item id="a" href="a.xhtml"/><item id="b" href="b.xhtml"/></manifest>
+                       <spine><itemref idref="b"/><itemref idref="a"/></spine></package>"#,
+                ),
+                ("OPS/a.xhtml", "<html><body>second</body></html>"),
+                ("OPS/b.xhtml", "<html><body>first</body></html>"),
+            ] {
+                w.start_file(name, opts).unwrap();
+                w.write_all(body.as_bytes()).unwrap();
+            }
+            w.finish().unwrap();
+        }
+        let sections = extract_book_text(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            sections,
+            vec![
+                ExtractedSection {
+                    href: "OPS/b.xhtml".into(),
+                    text: "first".into()
+                },
+                ExtractedSection {
+                    href: "OPS/a.xhtml".into(),
+                    text: "second".into()
+                },
+            ]
+        );
+    }
+    #[test]
+    fn mobi_sections_are_named_by_decoded_byte_offset() {
+        // "é" is two bytes in the decoded markup, so the second paragraph
+        // starts past byte 4096 with only ~2070 characters before it.
+        let first = "é".repeat(2048);
+        let markup = format!("<html><body><p>{first}</p><p>tail</p></body></html>");
+        let sections = split_markup_sections(&markup);
+        let hrefs: Vec<_> = sections.iter().map(|s| s.href.as_str()).collect();
+        assert_eq!(hrefs, vec!["offset:0", "offset:4096"]);
+        assert_eq!(sections[0].text, first);
+        assert_eq!(sections[1].text, "tail");
+    }
+}
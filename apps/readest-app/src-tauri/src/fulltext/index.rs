@@ -0,0 +1,396 @@
+//! On-disk inverted index.
+//!
+//! Layout under `<app_data_dir>/fulltext/`:
+//!
+//! ```text
+//! <book hash>/terms.json   version + section hrefs + term -> section ids
+//! <book hash>/text.json    extracted section text, in section order
+//! ```
+//!
+//! The term map is coarse on purpose: it records *which sections* contain a
+//! term, not every position. That keeps it small enough to hold all books'
+//! maps in memory (loaded lazily, cached until the book is re-indexed or
+//! removed). A query intersects the maps to find candidate sections, then
+//! re-tokenizes only those sections' text to confirm phrase matches and
+//! compute exact offsets. Per-book directories make re-indexing and removal
+//! a matter of replacing or deleting one directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::extract::ExtractedSection;
+use super::tokenize::tokenize;
+
+/// Bump when the tokenizer or the on-disk format changes; books indexed
+/// with another version are treated as not indexed.
+const INDEX_VERSION: u32 = 1;
+
+/// Characters of context on each side of a hit in `FulltextHit::snippet`.
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BookTerms {
+    version: u32,
+    hrefs: Vec<String>,
+    /// Term -> ascending section indices into `hrefs`.
+    terms: HashMap<String, Vec<u32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BookText {
+    sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedBook {
+    pub book_hash: String,
+    pub sections: usize,
+    pub terms: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FulltextHit {
+    pub book_hash: String,
+    /// EPUB: zip path of the spine item. MOBI: `offset:<n>`.
+    pub href: String,
+    /// Offset of the match in the section's `textContent`, in UTF-16 code
+    /// units (JavaScript string indices).
+    pub offset: usize,
+    /// Length of the match in UTF-16 code units.
+    pub length: usize,
+    /// The match with surrounding context, whitespace collapsed.
+    pub snippet: String,
+}
+
+pub struct FulltextIndex {
+    root: PathBuf,
+    cache: HashMap<String, Arc<BookTerms>>,
+}
+
+impl FulltextIndex {
+    pub fn open(root: PathBuf) -> Self {
+        Self {
+            root,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn add_book(
+        &mut self,
+        book_hash: &str,
+        sections: Vec<ExtractedSection>,
+    ) -> io::Result<IndexedBook> {
+        let dir = self.book_dir(book_hash)?;
+        let mut terms: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut hrefs = Vec::with_capacity(sections.len());
+        let mut texts = Vec::with_capacity(sections.len());
+        for (index, section) in sections.into_iter().enumerate() {
+            let index = index as u32;
+            let unique: BTreeSet<String> = tokenize(&section.text)
+                .into_iter()
+                .map(|t| t.term)
+                .collect();
+            for term in unique {
+                terms.entry(term).or_default().push(index);
+            }
+            hrefs.push(section.href);
+            texts.push(section.text);
+        }
+        let book = BookTerms {
+            version: INDEX_VERSION,
+            hrefs,
+            terms,
+        };
+        let summary = IndexedBook {
+            book_hash: book_hash.to_string(),
+            sections: book.hrefs.len(),
+            terms: book.terms.len(),
+        };
+
+        fs::create_dir_all(&dir)?;
+        // Text first: a terms.json on disk promises its text.json exists.
+        write_json_atomic(&dir.join("text.json"), &BookText { sections: texts })?;
+        write_json_atomic(&dir.join("terms.json"), &book)?;
+        self.cache.insert(book_hash.to_string(), Arc::new(book));
+        Ok(summary)
+    }
+
+    /// Returns `false` when the book was not indexed.
+    pub fn remove_book(&mut self, book_hash: &str) -> io::Result<bool> {
+        let dir = self.book_dir(book_hash)?;
+        self.cache.remove(book_hash);
+        match fs::remove_dir_all(dir) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn list_books(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut books: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().join("terms.json").is_file())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|name| is_valid_book_hash(name))
+            .collect();
+        books.sort();
+        Ok(books)
+    }
+
+    /// Phrase search: every query token must appear, consecutively and in
+    /// order, with only non-word characters in between. Hits come back in
+    /// book order (as given by `book_hashes`, or sorted by hash), then
+    /// reading order, and stop at `limit`.
+    pub fn search(
+        &mut self,
+        query: &str,
+        book_hashes: Option<&[String]>,
+        limit: usize,
+    ) -> io::Result<Vec<FulltextHit>> {
+        let query: Vec<String> = tokenize(query).into_iter().map(|t| t.term).collect();
+        if query.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let books = match book_hashes {
+            Some(hashes) => hashes.to_vec(),
+            None => self.list_books()?,
+        };
+
+        let mut hits = Vec::new();
+        for book_hash in books {
+            let Some(terms) = self.load_terms(&book_hash)? else {
+                continue;
+            };
+            let candidates = candidate_sections(&terms, &query);
+            if candidates.is_empty() {
+                continue;
+            }
+            let text: BookText = read_json(&self.book_dir(&book_hash)?.join("text.json"))?;
+            for section in candidates {
+                let (Some(href), Some(body)) = (
+                    terms.hrefs.get(section as usize),
+                    text.sections.get(section as usize),
+                ) else {
+                    continue;
+                };
+                for (offset, length, start, end) in phrase_matches(body, &query) {
+                    hits.push(FulltextHit {
+                        book_hash: book_hash.clone(),
+                        href: href.clone(),
+                        offset,
+                        length,
+                        snippet: snippet(body, start, end),
+                    });
+                    if hits.len() >= limit {
+                        return Ok(hits);
+                    }
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    fn load_terms(&mut self, book_hash: &str) -> io::Result<Option<Arc<BookTerms>>> {
+        if let Some(terms) = self.cache.get(book_hash) {
+            return Ok(Some(terms.clone()));
+        }
+        let path = self.book_dir(book_hash)?.join("terms.json");
+        let terms: BookTerms = match read_json(&path) {
+            Ok(terms) => terms,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if terms.version != INDEX_VERSION {
+            return Ok(None);
+        }
+        let terms = Arc::new(terms);
+        self.cache.insert(book_hash.to_string(), terms.clone());
+        Ok(Some(terms))
+    }
+
+    fn book_dir(&self, book_hash: &str) -> io::Result<PathBuf> {
+        if !is_valid_book_hash(book_hash) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid book hash: {book_hash}"),
+            ));
+        }
+        Ok(self.root.join(book_hash))
+    }
+}
+
+/// Book hashes are hex MD5 digests; anything else must not become a path.
+fn is_valid_book_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.len() <= 64 && hash.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Sections containing every query term, ascending.
+fn candidate_sections(terms: &BookTerms, query: &[String]) -> Vec<u32> {
+    let mut lists: Vec<&Vec<u32>> = Vec::with_capacity(query.len());
+    for term in query {
+        match terms.terms.get(term) {
+            Some(list) => lists.push(list),
+            None => return Vec::new(),
+        }
+    }
+    lists.sort_by_key(|l| l.len());
+    let (first, rest) = lists.split_first().expect("query is non-empty");
+    first
+        .iter()
+        .copied()
+        .filter(|s| rest.iter().all(|l| l.binary_search(s).is_ok()))
+        .collect()
+}
+
+/// `(utf16 offset, utf16 length, byte start, byte end)` of every phrase
+/// occurrence in `text`.
+fn phrase_matches(text: &str, query: &[String]) -> Vec<(usize, usize, usize, usize)> {
+    let tokens = tokenize(text);
+    tokens
+        .windows(query.len())
+        .filter(|w| w.iter().zip(query).all(|(t, q)| &t.term == q))
+        .map(|w| {
+            let (first, last) = (&w[0], &w[w.len() - 1]);
+            (
+                first.utf16_start,
+                last.utf16_end - first.utf16_start,
+                first.start,
+                last.end,
+            )
+        })
+        .collect()
+}
+
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = text[..start]
+        .chars()
+        .rev()
+        .take(SNIPPET_CONTEXT_CHARS)
+        .collect();
+    let after: String = text[end..].chars().take(SNIPPET_CONTEXT_CHARS).collect();
+    let mut out = String::new();
+    if before.len() == SNIPPET_CONTEXT_CHARS {
+        out.push('…');
+    }
+    out.extend(before.into_iter().rev());
+    out.push_str(&text[start..end]);
+    out.push_str(&after);
+    if text[end..].chars().nth(SNIPPET_CONTEXT_CHARS).is_some() {
+        out.push('…');
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let bytes = fs::read(path)?;
+    serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write to a sibling temp file and rename over the target, so a crash
+/// mid-write never leaves a truncated index behind.
+fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let bytes = serde_json::to_vec(value).map_err(io::Error::other)?;
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(href: &str, text: &str) -> ExtractedSection {
+        ExtractedSection {
+            href: href.into(),
+            text: text.into(),
+        }
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("readest-fti-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn phrase_search_returns_offsets_and_snippets() {
+        let root = temp_root("phrase");
+        let mut index = FulltextIndex::open(root.clone());
+        index
+            .add_book(
+                "aaa111",
+                vec![
+                    section("c1.xhtml", "It is a truth universally acknowledged."),
+                    section(
+                        "c2.xhtml",
+                        "A truth? Universally — acknowledged, “truth universally”.",
+                    ),
+                ],
+            )
+            .unwrap();
+        index
+            .add_book("bbb222", vec![section("c1.xhtml", "universally truth")])
+            .unwrap();
+
+        // Fresh instance: everything must come back from disk.
+        let mut index = FulltextIndex::open(root.clone());
+        assert_eq!(index.list_books().unwrap(), ["aaa111", "bbb222"]);
+        let hits = index.search("Truth, universally", None, 10).unwrap();
+        fs::remove_dir_all(&root).ok();
+
+        let found: Vec<(&str, &str, usize, usize)> = hits
+            .iter()
+            .map(|h| (h.book_hash.as_str(), h.href.as_str(), h.offset, h.length))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("aaa111", "c1.xhtml", 8, 17),
+                ("aaa111", "c2.xhtml", 2, 18),
+                ("aaa111", "c2.xhtml", 38, 17),
+            ]
+        );
+        assert_eq!(hits[0].snippet, "It is a truth universally acknowledged.");
+    }
+
+    #[test]
+    fn remove_book_drops_it_from_results() {
+        let root = temp_root("remove");
+        let mut index = FulltextIndex::open(root.clone());
+        index
+            .add_book("ccc333", vec![section("a", "needle")])
+            .unwrap();
+        assert_eq!(index.search("needle", None, 10).unwrap().len(), 1);
+        assert!(index.remove_book("ccc333").unwrap());
+        assert!(!index.remove_book("ccc333").unwrap());
+        assert!(index.search("needle", None, 10).unwrap().is_empty());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn rejects_path_like_hashes() {
+        let mut index = FulltextIndex::open(temp_root("hash"));
+        assert!(index.add_book("../etc", Vec::new()).is_err());
+        assert!(index.search("x", Some(&["a/b".to_string()]), 10).is_err());
+    }
+
+    #[test]
+    fn snippet_trims_long_context() {
+        let text = format!("{} needle {}", "x ".repeat(100), "y ".repeat(100));
+        let start = text.find("needle").unwrap();
+        let s = snippet(&text, start, start + 6);
+        assert!(s.starts_with('…') && s.ends_with('…'));
+        assert!(s.contains("x needle y"));
+    }
+}
@@ -0,0 +1,22 @@
+//! Native full-text search across the library.
+//!
+//! `commands::fulltext_index_book` extracts plain text from an imported book
+//! (EPUB spine items, synthetic MOBI sections — see `extract`), tokenizes it
+//! (`tokenize`) and stores a per-book inverted index under the app data dir
+//! keyed by book hash (`index`). `commands::fulltext_search` runs a phrase
+//! query across every indexed book and returns book hash, section href, a
+//! UTF-16 character offset into the section's `textContent` (what the
+//! reader needs to build a range and CFI) and a snippet.
+
+pub mod commands;
+mod extract;
+mod index;
+mod tokenize;
+
+use std::sync::Mutex;
+
+/// Tauri managed state: the index, opened on first use. A plain mutex is
+/// enough — every command runs on the blocking pool, and the lock only
+/// serializes index reads/writes, never extraction.
+#[derive(Default)]
+pub struct FulltextState(pub Mutex<Option<index::FulltextIndex>>);
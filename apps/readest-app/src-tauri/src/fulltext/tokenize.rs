@@ -0,0 +1,103 @@
+//! Tokenizer shared by indexing and querying. Both sides must agree exactly,
+//! so there is deliberately only one entry point.
+//!
+//! - A token is a maximal run of alphanumeric characters, lowercased.
+//! - CJK ideographs, kana and hangul syllables are one token each: those
+//!   scripts don't separate words with spaces, and single-character tokens
+//!   combined with phrase matching find any quoted run of characters.
+//! - Everything else (punctuation, whitespace, symbols) separates tokens and
+//!   is ignored, so "don't" matches "don t" and "Mr. Darcy" matches "mr darcy".
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    /// Byte range in the source text.
+    pub start: usize,
+    pub end: usize,
+    /// The same range in UTF-16 code units, i.e. JavaScript string
+    /// offsets into the section's `textContent`.
+    pub utf16_start: usize,
+    pub utf16_end: usize,
+}
+
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut utf16 = 0usize;
+
+    for (pos, c) in text.char_indices() {
+        let width = c.len_utf16();
+        if is_cjk(c) {
+            if let Some(token) = current.take() {
+                tokens.push(token);
+            }
+            tokens.push(Token {
+                term: c.to_string(),
+                start: pos,
+                end: pos + c.len_utf8(),
+                utf16_start: utf16,
+                utf16_end: utf16 + width,
+            });
+        } else if c.is_alphanumeric() {
+            let token = current.get_or_insert_with(|| Token {
+                term: String::new(),
+                start: pos,
+                end: pos,
+                utf16_start: utf16,
+                utf16_end: utf16,
+            });
+            token.term.extend(c.to_lowercase());
+            token.end = pos + c.len_utf8();
+            token.utf16_end = utf16 + width;
+        } else if let Some(token) = current.take() {
+            tokens.push(token);
+        }
+        utf16 += width;
+    }
+    if let Some(token) = current {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // hiragana, katakana
+        | 0x3400..=0x4DBF   // CJK extension A
+        | 0x4E00..=0x9FFF   // CJK unified ideographs
+        | 0xAC00..=0xD7AF   // hangul syllables
+        | 0xF900..=0xFAFF   // CJK compatibility ideographs
+        | 0x20000..=0x2FA1F // CJK extensions B-F, compatibility supplement
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(text: &str) -> Vec<String> {
+        tokenize(text).into_iter().map(|t| t.term).collect()
+    }
+
+    #[test]
+    fn splits_on_punctuation_and_lowercases() {
+        assert_eq!(
+            terms("It's a truth, UNIVERSALLY acknowledged"),
+            ["it", "s", "a", "truth", "universally", "acknowledged"]
+        );
+    }
+
+    #[test]
+    fn cjk_characters_are_single_tokens() {
+        assert_eq!(terms("他说hello世界"), ["他", "说", "hello", "世", "界"]);
+    }
+
+    #[test]
+    fn offsets_are_utf16_code_units() {
+        // "𝒜" is outside the BMP: 4 UTF-8 bytes, 2 UTF-16 units.
+        let tokens = tokenize("𝒜 café");
+        assert_eq!(tokens[1].term, "café");
+        assert_eq!((tokens[1].start, tokens[1].end), (5, 10));
+        assert_eq!((tokens[1].utf16_start, tokens[1].utf16_end), (3, 7));
+    }
+}
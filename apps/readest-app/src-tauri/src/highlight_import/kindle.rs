@@ -0,0 +1,563 @@
+//! Kindle `My Clippings.txt`. Each clipping is a title line, a metadata
+//! line, a blank line and the clipped text, ending in `==========`:
+//!
+//! ```text
+//! Dune (Frank Herbert)
+//! - Your Highlight on page 12 | Location 180-182 | Added on Sunday, 18 October 2026 09:30:05
+//!
+//! I must not fear.
+//! ==========
+//! ```
+//!
+//! The metadata line is in the Kindle's UI language, so kinds, page and
+//! location labels and dates are recognized across the locales Kindles ship
+//! with. A note is written as its own clipping right after its highlight, at
+//! the highlight's last location; it's folded back into that highlight.
+
+use super::{civil_to_ms, note_id, ImportedAnnotation, ImportedBook, TextAnchor};
+
+const SEPARATOR: &str = "==========";
+
+const BOOKMARK_WORDS: &[&str] = &[
+    "bookmark",
+    "lesezeichen",
+    "signet",
+    "marcador",
+    "segnalibro",
+    "bladwijzer",
+    "ブックマーク",
+    "书签",
+];
+const NOTE_WORDS: &[&str] = &["note", "notiz", "nota", "notitie", "メモ", "笔记"];
+const HIGHLIGHT_WORDS: &[&str] = &[
+    "highlight",
+    "markierung",
+    "surlignement",
+    "subrayado",
+    "evidenziazione",
+    "destaque",
+    "markering",
+    "ハイライト",
+    "标注",
+];
+const LOCATION_WORDS: &[&str] = &[
+    "location",
+    "loc.",
+    "position",
+    "emplacement",
+    "posición",
+    "posizione",
+    "posição",
+    "locatie",
+    "位置",
+];
+const PAGE_WORDS: &[&str] = &["page", "seite", "página", "pagina", "ページ", "页"];
+
+/// Month names in the Kindle UI languages that spell them out, plus the
+/// English abbreviations older firmware used.
+const MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("february", 2),
+    ("march", 3),
+    ("april", 4),
+    ("may", 5),
+    ("june", 6),
+    ("july", 7),
+    ("august", 8),
+    ("september", 9),
+    ("october", 10),
+    ("november", 11),
+    ("december", 12),
+    ("jan", 1),
+    ("feb", 2),
+    ("mar", 3),
+    ("apr", 4),
+    ("jun", 6),
+    ("jul", 7),
+    ("aug", 8),
+    ("sep", 9),
+    ("sept", 9),
+    ("oct", 10),
+    ("nov", 11),
+    ("dec", 12),
+    // de
+    ("januar", 1),
+    ("februar", 2),
+    ("märz", 3),
+    ("mai", 5),
+    ("juni", 6),
+    ("juli", 7),
+    ("oktober", 10),
+    ("dezember", 12),
+    // fr
+    ("janvier", 1),
+    ("février", 2),
+    ("mars", 3),
+    ("avril", 4),
+    ("juin", 6),
+    ("juillet", 7),
+    ("août", 8),
+    ("septembre", 9),
+    ("octobre", 10),
+    ("novembre", 11),
+    ("décembre", 12),
+    // es
+    ("enero", 1),
+    ("febrero", 2),
+    ("marzo", 3),
+    ("abril", 4),
+    ("mayo", 5),
+    ("junio", 6),
+    ("julio", 7),
+    ("agosto", 8),
+    ("septiembre", 9),
+    ("setiembre", 9),
+    ("octubre", 10),
+    ("noviembre", 11),
+    ("diciembre", 12),
+    // it
+    ("gennaio", 1),
+    ("febbraio", 2),
+    ("aprile", 4),
+    ("maggio", 5),
+    ("giugno", 6),
+    ("luglio", 7),
+    ("settembre", 9),
+    ("ottobre", 10),
+    ("dicembre", 12),
+    // pt
+    ("janeiro", 1),
+    ("fevereiro", 2),
+    ("março", 3),
+    ("maio", 5),
+    ("junho", 6),
+    ("julho", 7),
+    ("setembro", 9),
+    ("outubro", 10),
+    ("novembro", 11),
+    ("dezembro", 12),
+    // nl
+    ("januari", 1),
+    ("februari", 2),
+    ("maart", 3),
+    ("mei", 5),
+    ("augustus", 8),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Highlight,
+    Note,
+    Bookmark,
+}
+
+#[derive(Debug)]
+struct Clipping {
+    title: String,
+    author: String,
+    kind: Kind,
+    page: Option<u32>,
+    location: Option<(u32, u32)>,
+    added: Option<i64>,
+    text: String,
+}
+
+/// `Title (Author)`: the author is the last parenthesized group, since
+/// titles can carry their own (`Dune (Dune Chronicles, Book 1)`).
+fn split_title_line(line: &str) -> (String, String) {
+    let line = line.trim_start_matches('\u{feff}').trim();
+    if let Some(inner) = line.strip_suffix(')') {
+        let mut depth = 0usize;
+        for (i, c) in inner.char_indices().rev() {
+            match c {
+                ')' => depth += 1,
+                '(' if depth == 0 => {
+                    let title = inner[..i].trim();
+                    if !title.is_empty() {
+                        return (title.to_string(), inner[i + 1..].trim().to_string());
+                    }
+                    break;
+                }
+                '(' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    (line.to_string(), String::new())
+}
+
+/// The first `N` or `N-M` in `s`. Old firmware abbreviated the end
+/// (`Loc. 1234-40` for 1234-1240).
+fn number_range(s: &str) -> Option<(u32, u32)> {
+    let start_at = s.find(|c: char| c.is_ascii_digit())?;
+    let rest = &s[start_at..];
+    let digits = |s: &str| -> String { s.chars().take_while(char::is_ascii_digit).collect() };
+    let first = digits(rest);
+    let start: u32 = first.parse().ok()?;
+    let Some(after) = rest[first.len()..].strip_prefix('-') else {
+        return Some((start, start));
+    };
+    let second = digits(after);
+    let Ok(mut end) = second.parse::<u32>() else {
+        return Some((start, start));
+    };
+    if end < start && second.len() < first.len() {
+        let prefix = &first[..first.len() - second.len()];
+        end = format!("{prefix}{second}").parse().unwrap_or(start);
+    }
+    Some((start, end.max(start)))
+}
+
+fn contains_any(haystack: &str, needles: &[&str]) -> bool {
+    needles.iter().any(|n| haystack.contains(n))
+}
+
+/// Byte offset of the first `h:mm` in `s`. Labels end in colons too
+/// (`Adicionado:`, `作成日:`), so a colon only counts between digits.
+fn time_start(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let colon = (1..bytes.len().saturating_sub(1)).find(|&i| {
+        bytes[i] == b':' && bytes[i - 1].is_ascii_digit() && bytes[i + 1].is_ascii_digit()
+    })?;
+    Some(digits_start(&s[..colon]))
+}
+
+/// Byte offset of the run of ASCII digits that ends `s`.
+fn digits_start(s: &str) -> usize {
+    s.char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// The time of day in `s` on a 24-hour clock, once AM/PM markers in any of
+/// the supported languages are applied; midnight when there is none.
+fn parse_time(s: &str) -> (u32, u32, u32) {
+    let Some(start) = time_start(s) else {
+        return (0, 0, 0);
+    };
+    let lower = s.to_lowercase();
+    let fields: Vec<u32> = s[start..]
+        .split(|c: char| !c.is_ascii_digit() && c != ':')
+        .next()
+        .unwrap_or_default()
+        .split(':')
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    let (mut hour, minute, second) = match fields.as_slice() {
+        [h, m, s, ..] => (*h, *m, *s),
+        [h, m] => (*h, *m, 0),
+        _ => return (0, 0, 0),
+    };
+    // Latin markers follow the time; "am" before it is German for "on".
+    let after = s[start..].to_lowercase();
+    let has_word = |words: &[&str]| after.split_whitespace().any(|w| words.contains(&w));
+    let pm = has_word(&["pm", "p.m."]) || lower.contains("下午") || lower.contains("午後");
+    let am = has_word(&["am", "a.m."]) || lower.contains("上午") || lower.contains("午前");
+    if pm && hour < 12 {
+        hour += 12;
+    } else if am && hour == 12 {
+        hour = 0;
+    }
+    (hour, minute, second)
+}
+
+/// The "Added on …" part of a metadata line, in any supported language:
+/// `Sunday, October 18, 2026 9:30:05 AM`, `Sonntag, 18. Oktober 2026
+/// 09:30:05`, `2026年10月18日星期日 上午9:30:05`, …
+fn parse_added(s: &str) -> Option<i64> {
+    let time = parse_time(s);
+    // Drop the time so its numbers aren't read as a day.
+    let date_part = &s[..time_start(s).unwrap_or(s.len())];
+    if let Some(year_end) = date_part.find('年') {
+        let digits_before = |s: &str| -> Option<u32> { s[digits_start(s)..].parse().ok() };
+        let year = digits_before(&date_part[..year_end])?;
+        // Search on from the year: labels like `作成日` come before it.
+        let rest = &date_part[year_end..];
+        let month_end = rest.find('月')?;
+        let month = digits_before(&rest[..month_end])?;
+        let day_end = month_end + rest[month_end..].find('日')?;
+        let day = digits_before(&rest[..day_end])?;
+        return civil_to_ms((i64::from(year), month, day), time);
+    }
+
+    let mut year = None;
+    let mut month = None;
+    let mut day = None;
+    for token in date_part.split(|c: char| c.is_whitespace() || c == ',' || c == '/') {
+        let token = token
+            .trim_matches(|c: char| c == '.' || c == ':')
+            .to_lowercase();
+        if token.is_empty() {
+            continue;
+        }
+        if let Ok(n) = token.parse::<u32>() {
+            if token.len() == 4 {
+                year = Some(n);
+            } else if day.is_none() {
+                day = Some(n);
+            }
+        } else if month.is_none() {
+            month = MONTHS
+                .iter()
+                .find(|(name, _)| *name == token)
+                .map(|(_, m)| *m);
+        }
+    }
+    civil_to_ms((i64::from(year?), month?, day?), time)
+}
+
+fn parse_clipping(block: &str) -> Option<Clipping> {
+    let mut lines = block.lines().skip_while(|l| l.trim().is_empty());
+    let (title, author) = split_title_line(lines.next()?);
+    let meta = lines.next()?.trim();
+    let text = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+    let lower = meta.to_lowercase();
+    let kind = if contains_any(&lower, BOOKMARK_WORDS) {
+        Kind::Bookmark
+    } else if contains_any(&lower, NOTE_WORDS) && !contains_any(&lower, HIGHLIGHT_WORDS) {
+        Kind::Note
+    } else {
+        Kind::Highlight
+    };
+
+    let mut segments: Vec<&str> = meta.split('|').collect();
+    let added = if segments.len() > 1 {
+        segments.pop().and_then(parse_added)
+    } else {
+        None
+    };
+    let mut page = None;
+    let mut location = None;
+    for segment in segments {
+        let lower = segment.to_lowercase();
+        if contains_any(&lower, LOCATION_WORDS) {
+            location = location.or_else(|| number_range(segment));
+        } else if contains_any(&lower, PAGE_WORDS) {
+            page = page.or_else(|| number_range(segment).map(|(start, _)| start));
+        }
+    }
+    Some(Clipping {
+        title,
+        author,
+        kind,
+        page,
+        location,
+        added,
+        text,
+    })
+}
+
+fn contains_point(range: Option<(u32, u32)>, point: Option<(u32, u32)>) -> bool {
+    match (range, point) {
+        (Some((start, end)), Some((at, _))) => start <= at && at <= end,
+        _ => false,
+    }
+}
+
+fn add_clipping(book: &mut ImportedBook, clipping: Clipping) {
+    let created_at = clipping.added.unwrap_or_default();
+    match clipping.kind {
+        Kind::Note => {
+            let target = book.annotations.iter_mut().rev().find(|a| {
+                a.kind == "annotation" && contains_point(a.anchor.location, clipping.location)
+            });
+            if let Some(highlight) = target {
+                highlight.note = clipping.text;
+                highlight.updated_at = highlight.updated_at.max(created_at);
+                return;
+            }
+        }
+        Kind::Highlight => {
+            // Extending a highlight on the Kindle appends a new clipping from
+            // the same start; the newer text wins, keeping any note.
+            let start = clipping.location.map(|(start, _)| start);
+            let replaced = book.annotations.iter().position(|a| {
+                a.kind == "annotation"
+                    && start.is_some()
+                    && a.anchor.location.map(|(s, _)| s) == start
+                    && !a.anchor.exact.is_empty()
+                    && (clipping.text.contains(&a.anchor.exact)
+                        || a.anchor.exact.contains(&clipping.text))
+            });
+            if let Some(index) = replaced {
+                let previous = book.annotations.remove(index);
+                let mut annotation = annotation(book, &clipping, created_at);
+                annotation.note = previous.note;
+                book.annotations.push(annotation);
+                return;
+            }
+        }
+        Kind::Bookmark => {}
+    }
+    let annotation = annotation(book, &clipping, created_at);
+    book.annotations.push(annotation);
+}
+
+fn annotation(book: &ImportedBook, clipping: &Clipping, created_at: i64) -> ImportedAnnotation {
+    let (kind, exact, note) = match clipping.kind {
+        Kind::Highlight => ("annotation", clipping.text.clone(), String::new()),
+        // A note with no highlight left to attach to.
+        Kind::Note => ("annotation", String::new(), clipping.text.clone()),
+        Kind::Bookmark => ("bookmark", String::new(), String::new()),
+    };
+    let start = clipping
+        .location
+        .map(|(start, _)| start)
+        .or(clipping.page)
+        .unwrap_or_default()
+        .to_string();
+    ImportedAnnotation {
+        id: note_id(&["kindle", &book.title, kind, &start, &exact, &note]),
+        kind: kind.to_string(),
+        anchor: TextAnchor {
+            exact,
+            location: clipping.location,
+            page: clipping.page,
+            ..Default::default()
+        },
+        note,
+        style: (clipping.kind == Kind::Highlight).then(|| "highlight".to_string()),
+        color: (clipping.kind == Kind::Highlight).then(|| "yellow".to_string()),
+        created_at,
+        updated_at: created_at,
+    }
+}
+
+/// Every book in a clippings file, in the order they first appear.
+pub fn parse_clippings(content: &str, source_path: &str) -> Vec<ImportedBook> {
+    let mut books: Vec<ImportedBook> = Vec::new();
+    let content = content.replace("\r\n", "\n");
+    for block in content.split(SEPARATOR) {
+        let Some(clipping) = parse_clipping(block) else {
+            continue;
+        };
+        if clipping.kind != Kind::Bookmark && clipping.text.is_empty() {
+            continue;
+        }
+        let index = match books
+            .iter()
+            .position(|b| b.title == clipping.title && b.author == clipping.author)
+        {
+            Some(index) => index,
+            None => {
+                books.push(ImportedBook {
+                    title: clipping.title.clone(),
+                    author: clipping.author.clone(),
+                    source_path: source_path.to_string(),
+                    ..Default::default()
+                });
+                books.len() - 1
+            }
+        };
+        add_clipping(&mut books[index], clipping);
+    }
+    books
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-18 09:30:05
+    const AT: i64 = 1_792_315_805_000;
+
+    #[test]
+    fn parses_dates_in_kindle_locales() {
+        let cases = [
+            "Added on Sunday, October 18, 2026 9:30:05 AM",
+            "Added on Sunday, 18 October 2026 09:30:05",
+            "Hinzugefügt am Sonntag, 18. Oktober 2026 09:30:05",
+            "Ajouté le dimanche 18 octobre 2026 09:30:05",
+            "Añadido el domingo, 18 de octubre de 2026 9:30:05",
+            "Aggiunto in data domenica 18 ottobre 2026 09:30:05",
+            "Adicionado: domingo, 18 de outubro de 2026 09:30:05",
+            "Toegevoegd op zondag 18 oktober 2026 09:30:05",
+            "作成日: 2026年10月18日日曜日 9:30:05",
+            "添加于 2026年10月18日星期日 上午9:30:05",
+        ];
+        for case in cases {
+            assert_eq!(parse_added(case), Some(AT), "{case}");
+        }
+        assert_eq!(
+            parse_added("Added on Sunday, October 18, 2026 9:30:05 PM"),
+            Some(AT + 12 * 3600 * 1000)
+        );
+        assert_eq!(
+            parse_added("Hinzugefügt am Sonntag, 18. Oktober 2026 12:30:05"),
+            Some(AT + 3 * 3600 * 1000)
+        );
+        assert_eq!(
+            parse_added("Added on Sunday, October 18, 2026 12:30:05 AM"),
+            Some(AT - 9 * 3600 * 1000)
+        );
+        assert_eq!(parse_added("Added on someday"), None);
+    }
+
+    #[test]
+    fn reads_locations_and_pages() {
+        assert_eq!(number_range("Location 1234-1240"), Some((1234, 1240)));
+        assert_eq!(number_range("Loc. 1234-40"), Some((1234, 1240)));
+        assert_eq!(number_range("位置No. 77のブックマーク"), Some((77, 77)));
+        assert_eq!(number_range("page xii"), None);
+    }
+
+    #[test]
+    fn parses_a_clippings_file() {
+        let file = "\u{feff}Dune (Dune Chronicles, Book 1) (Herbert, Frank)\r
+- Your Highlight on page 12 | Location 180-181 | Added on Sunday, October 18, 2026 9:30:05 AM\r
+\r
+I must not fear.\r
+==========\r
+Dune (Dune Chronicles, Book 1) (Herbert, Frank)\r
+- Your Highlight on page 12 | Location 180-182 | Added on Sunday, October 18, 2026 9:31:00 AM\r
+\r
+I must not fear. Fear is the mind-killer.\r
+==========\r
+Dune (Dune Chronicles, Book 1) (Herbert, Frank)\r
+- Your Note on page 12 | Location 182 | Added on Sunday, October 18, 2026 9:32:00 AM\r
+\r
+The litany\r
+==========\r
+Der Process (Franz Kafka)\r
+- Ihre Markierung bei Position 50-51 | Hinzugefügt am Sonntag, 18. Oktober 2026 09:30:05\r
+\r
+Jemand musste Josef K. verleumdet haben,\r
+denn ohne dass er etwas Böses getan hätte\r
+==========\r
+Der Process (Franz Kafka)\r
+- Ihr Lesezeichen bei Position 90 | Hinzugefügt am Sonntag, 18. Oktober 2026 09:30:05\r
+\r
+\r
+==========\r
+";
+        let books = parse_clippings(file, "/k/My Clippings.txt");
+        assert_eq!(books.len(), 2);
+
+        let dune = &books[0];
+        assert_eq!(dune.title, "Dune (Dune Chronicles, Book 1)");
+        assert_eq!(dune.author, "Herbert, Frank");
+        assert_eq!(dune.annotations.len(), 1);
+        let highlight = &dune.annotations[0];
+        assert_eq!(
+            highlight.anchor.exact,
+            "I must not fear. Fear is the mind-killer."
+        );
+        assert_eq!(highlight.anchor.location, Some((180, 182)));
+        assert_eq!(highlight.anchor.page, Some(12));
+        assert_eq!(highlight.note, "The litany");
+        assert_eq!(highlight.created_at, AT + 55_000);
+        assert_eq!(highlight.updated_at, AT + 115_000);
+
+        let process = &books[1];
+        assert_eq!(process.annotations.len(), 2);
+        assert_eq!(
+            process.annotations[0].anchor.exact,
+            "Jemand musste Josef K. verleumdet haben,\ndenn ohne dass er etwas Böses getan hätte"
+        );
+        assert_eq!(process.annotations[1].kind, "bookmark");
+        assert_eq!(process.annotations[1].anchor.location, Some((90, 90)));
+        assert_eq!(process.annotations[0].created_at, AT);
+    }
+}
@@ -0,0 +1,359 @@
+//! KOReader sidecars: `<book>.sdr/metadata.<ext>.lua`, next to the book or
+//! under KOReader's `docsettings`/`hashdocsettings` folders. Annotations are
+//! read from the `annotations` list KOReader has written since 2024, or from
+//! the older per-page `highlight` table. Mapped the way
+//! `readest_syncannotations.lua`'s `buildNoteDescriptor` maps them, IDs
+//! included, so a book later synced through the plugin doesn't duplicate.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use super::{civil_to_ms, lua, note_id, ImportedAnnotation, ImportedBook, TextAnchor};
+use crate::parser_common::compute_partial_md5;
+
+/// KOReader color names, as the plugin's `KO_TO_READEST_COLOR` maps them.
+const COLORS: &[(&str, &str)] = &[
+    ("yellow", "yellow"),
+    ("red", "red"),
+    ("green", "green"),
+    ("blue", "blue"),
+    ("purple", "violet"),
+    ("orange", "#ff8800"),
+    ("cyan", "#00bcd4"),
+    ("olive", "#808000"),
+    ("gray", "#9e9e9e"),
+];
+
+fn is_sidecar(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    name.starts_with("metadata.") && name.ends_with(".lua")
+}
+
+/// The sidecar files at or under `path`.
+pub fn find_sidecars(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return if is_sidecar(path) {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+    }
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && is_sidecar(entry.path())
+                && entry
+                    .path()
+                    .parent()
+                    .and_then(|p| p.extension())
+                    .is_some_and(|ext| ext == "sdr")
+        })
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// `YYYY-MM-DD HH:MM:SS`, KOReader's `datetime` format.
+fn parse_datetime(value: &Value) -> Option<i64> {
+    let s = value.as_str()?;
+    let (date, time) = s.trim().split_once(' ')?;
+    let mut date = date.split('-').map(|n| n.parse::<u32>().ok());
+    let mut time = time.split(':').map(|n| n.parse::<u32>().ok());
+    let year = date.next()??;
+    let month = date.next()??;
+    let day = date.next()??;
+    let hour = time.next()??;
+    let minute = time.next()??;
+    let second = time.next().flatten().unwrap_or(0);
+    civil_to_ms((i64::from(year), month, day), (hour, minute, second))
+}
+
+fn text(value: &Value) -> Option<String> {
+    value.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+fn number(value: &Value) -> Option<u32> {
+    value.as_u64().and_then(|n| u32::try_from(n).ok())
+}
+
+/// The book file a `.sdr` sidecar belongs to, when it sits next to it:
+/// `Dune.sdr/metadata.epub.lua` is for `Dune.epub`.
+fn book_file(sidecar: &Path) -> Option<PathBuf> {
+    let sdr = sidecar.parent()?;
+    let stem = sdr.file_stem()?.to_string_lossy();
+    let name = sidecar.file_name()?.to_string_lossy();
+    let ext = name.strip_prefix("metadata.")?.strip_suffix(".lua")?;
+    Some(sdr.with_file_name(format!("{stem}.{ext}")))
+}
+
+/// One `annotations` (or legacy `highlight`) entry.
+fn annotation(item: &Value, book_key: &str, doc_pages: Option<u32>) -> Option<ImportedAnnotation> {
+    let pos0 = text(&item["pos0"]);
+    let pos1 = text(&item["pos1"]);
+    let drawer = text(&item["drawer"]);
+    let pageno = number(&item["pageno"]).or_else(|| number(&item["page"]));
+    let created_at = parse_datetime(&item["datetime"]).unwrap_or_default();
+    let updated_at = parse_datetime(&item["datetime_updated"]).unwrap_or(created_at);
+    let exact = text(&item["text"]).unwrap_or_default();
+
+    let (kind, xpointer0, xpointer1, style, color, id) = match &drawer {
+        Some(drawer) => {
+            let style = match drawer.as_str() {
+                "underscore" => "underline",
+                "strikeout" => "squiggly",
+                _ => "highlight",
+            };
+            let color = text(&item["color"]).unwrap_or_else(|| "yellow".into());
+            let color = COLORS
+                .iter()
+                .find(|(ko, _)| *ko == color)
+                .map_or(color.clone(), |(_, readest)| readest.to_string());
+            // Fixed-layout positions are tables, which the plugin can't
+            // address either; page and text identify those.
+            let id = match &pos0 {
+                Some(pos0) => note_id(&[
+                    "ko",
+                    book_key,
+                    "annotation",
+                    pos0,
+                    pos1.as_deref().unwrap_or_default(),
+                ]),
+                None => note_id(&[
+                    "ko",
+                    book_key,
+                    "annotation",
+                    &pageno.unwrap_or_default().to_string(),
+                    &exact,
+                ]),
+            };
+            (
+                "annotation",
+                pos0,
+                pos1,
+                Some(style.to_string()),
+                Some(color),
+                id,
+            )
+        }
+        None => {
+            // A bookmark: no drawer, the page's XPointer in `page`.
+            let page = text(&item["page"])?;
+            let id = note_id(&["ko", book_key, "bookmark", &page, ""]);
+            ("bookmark", Some(page), None, None, None, id)
+        }
+    };
+    let id = text(&item["id"]).unwrap_or(id);
+    let fraction = match (pageno, doc_pages) {
+        (Some(page), Some(pages)) if pages > 0 => {
+            Some((f64::from(page) / f64::from(pages)).min(1.0))
+        }
+        _ => None,
+    };
+    Some(ImportedAnnotation {
+        id,
+        kind: kind.to_string(),
+        anchor: TextAnchor {
+            exact,
+            xpointer0,
+            xpointer1,
+            page: pageno,
+            chapter: text(&item["chapter"]),
+            fraction,
+            ..Default::default()
+        },
+        note: text(&item["note"]).unwrap_or_default(),
+        style,
+        color,
+        created_at,
+        updated_at,
+    })
+}
+
+/// The book a sidecar describes, or `None` when it has no annotations.
+pub fn read_sidecar(path: &Path) -> Result<Option<ImportedBook>, String> {
+    let src = std::fs::read(path).map_err(|e| e.to_string())?;
+    let settings = lua::parse(&String::from_utf8_lossy(&src))?;
+
+    let book_file = book_file(path);
+    let partial_md5 = text(&settings["partial_md5_checksum"]).or_else(|| {
+        book_file
+            .as_deref()
+            .filter(|file| file.is_file())
+            .and_then(|file| compute_partial_md5(file).ok())
+    });
+    let props = &settings["doc_props"];
+    let title = text(&props["title"])
+        .or_else(|| {
+            let doc_path = text(&settings["doc_path"])
+                .map(PathBuf::from)
+                .or(book_file)?;
+            Some(doc_path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    // Multiple authors are newline-separated.
+    let author = text(&props["authors"])
+        .map(|a| a.lines().map(str::trim).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+    let book_key = partial_md5.clone().unwrap_or_else(|| title.clone());
+    let doc_pages = number(&settings["doc_pages"]);
+
+    let items: Vec<&Value> = match settings["annotations"].as_array() {
+        Some(annotations) => annotations.iter().collect(),
+        // Pre-2024: `highlight[page] = { entries }`, then bookmarks apart.
+        None => {
+            let mut items: Vec<&Value> = settings["highlight"]
+                .as_object()
+                .into_iter()
+                .flat_map(|pages| pages.values())
+                .chain(settings["highlight"].as_array().into_iter().flatten())
+                .flat_map(|entries| entries.as_array().into_iter().flatten())
+                .collect();
+            items.sort_by_key(|item| parse_datetime(&item["datetime"]));
+            items
+        }
+    };
+    let annotations: Vec<ImportedAnnotation> = items
+        .into_iter()
+        .filter_map(|item| annotation(item, &book_key, doc_pages))
+        .collect();
+    if annotations.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ImportedBook {
+        title,
+        author,
+        partial_md5,
+        source_path: path.to_string_lossy().into_owned(),
+        annotations,
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIDECAR: &str = r#"-- ./Dune.sdr/metadata.epub.lua
+return {
+    ["annotations"] = {
+        [1] = {
+            ["chapter"] = "Book One",
+            ["color"] = "purple",
+            ["datetime"] = "2026-10-18 09:30:05",
+            ["datetime_updated"] = "2026-10-18 10:00:00",
+            ["drawer"] = "underscore",
+            ["note"] = "The litany",
+            ["page"] = "/body/DocFragment[3]/body/p[2]/text().0",
+            ["pageno"] = 30,
+            ["pos0"] = "/body/DocFragment[3]/body/p[2]/text().0",
+            ["pos1"] = "/body/DocFragment[3]/body/p[2]/text().16",
+            ["text"] = "I must not fear.",
+        },
+        [2] = {
+            ["chapter"] = "Book One",
+            ["datetime"] = "2026-10-18 09:40:00",
+            ["page"] = "/body/DocFragment[4]/body/p[1]",
+            ["pageno"] = 45,
+            ["text"] = "in the bookmark",
+        },
+    },
+    ["doc_pages"] = 300,
+    ["doc_props"] = {
+        ["authors"] = "Frank Herbert\
+Someone Else",
+        ["title"] = "Dune",
+    },
+}
+"#;
+
+    #[test]
+    fn reads_annotations_and_hashes_the_book_beside_it() {
+        let dir = std::env::temp_dir().join(format!("koreader-sidecar-{}", uuid::Uuid::new_v4()));
+        let sdr = dir.join("Dune.sdr");
+        std::fs::create_dir_all(&sdr).unwrap();
+        std::fs::write(sdr.join("metadata.epub.lua"), SIDECAR).unwrap();
+        std::fs::write(sdr.join("metadata.epub.lua.old"), SIDECAR).unwrap();
+        std::fs::write(dir.join("Dune.epub"), b"not really an epub").unwrap();
+
+        let sidecars = find_sidecars(&dir);
+        assert_eq!(sidecars, vec![sdr.join("metadata.epub.lua")]);
+        let book = read_sidecar(&sidecars[0]).unwrap().unwrap();
+        let md5 = compute_partial_md5(&dir.join("Dune.epub")).unwrap();
+        assert_eq!(book.partial_md5.as_deref(), Some(md5.as_str()));
+        assert_eq!(book.title, "Dune");
+        assert_eq!(book.author, "Frank Herbert, Someone Else");
+
+        let highlight = &book.annotations[0];
+        assert_eq!(highlight.kind, "annotation");
+        assert_eq!(highlight.style.as_deref(), Some("underline"));
+        assert_eq!(highlight.color.as_deref(), Some("violet"));
+        assert_eq!(highlight.note, "The litany");
+        assert_eq!(highlight.anchor.exact, "I must not fear.");
+        assert_eq!(
+            highlight.anchor.xpointer1.as_deref(),
+            Some("/body/DocFragment[3]/body/p[2]/text().16")
+        );
+        assert_eq!(highlight.anchor.fraction, Some(0.1));
+        assert_eq!(highlight.created_at, 1_792_315_805_000);
+        assert_eq!(highlight.updated_at, 1_792_317_600_000);
+        assert_eq!(
+            highlight.id,
+            note_id(&[
+                "ko",
+                &md5,
+                "annotation",
+                "/body/DocFragment[3]/body/p[2]/text().0",
+                "/body/DocFragment[3]/body/p[2]/text().16",
+            ])
+        );
+
+        let bookmark = &book.annotations[1];
+        assert_eq!(bookmark.kind, "bookmark");
+        assert_eq!(
+            bookmark.anchor.xpointer0.as_deref(),
+            Some("/body/DocFragment[4]/body/p[1]")
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_the_legacy_highlight_table() {
+        let src = r#"return {
+    ["highlight"] = {
+        [12] = {
+            [1] = {
+                ["datetime"] = "2020-01-02 03:04:05",
+                ["drawer"] = "lighten",
+                ["pos0"] = { ["page"] = 12, ["x"] = 1, ["y"] = 2 },
+                ["pos1"] = { ["page"] = 12, ["x"] = 3, ["y"] = 4 },
+                ["text"] = "fixed layout",
+            },
+        },
+    },
+    ["partial_md5_checksum"] = "0123456789abcdef0123456789abcdef",
+}"#;
+        let dir = std::env::temp_dir().join(format!("koreader-legacy-{}", uuid::Uuid::new_v4()));
+        let sdr = dir.join("Scan.sdr");
+        std::fs::create_dir_all(&sdr).unwrap();
+        std::fs::write(sdr.join("metadata.pdf.lua"), src).unwrap();
+        let book = read_sidecar(&sdr.join("metadata.pdf.lua"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(book.title, "Scan");
+        assert_eq!(
+            book.partial_md5.as_deref(),
+            Some("0123456789abcdef0123456789abcdef")
+        );
+        let highlight = &book.annotations[0];
+        assert_eq!(highlight.anchor.exact, "fixed layout");
+        assert_eq!(highlight.anchor.xpointer0, None);
+        assert_eq!(highlight.style.as_deref(), Some("highlight"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
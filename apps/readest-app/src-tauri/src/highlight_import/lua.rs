@@ -0,0 +1,324 @@
+//! Reads the Lua table literals KOReader writes for its sidecars
+//! (`return { ["key"] = value, ... }`) into JSON values. Only the subset
+//! `DocSettings` dumps is understood: nested tables, bracketed and bare keys,
+//! strings, numbers, booleans and `nil`. Tables keyed `1..n` become arrays.
+
+use serde_json::{Map, Number, Value};
+
+type Result<T> = std::result::Result<T, String>;
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+pub fn parse(src: &str) -> Result<Value> {
+    let mut parser = Parser {
+        src: src.as_bytes(),
+        pos: 0,
+    };
+    parser.skip_trivia();
+    if parser.src[parser.pos..].starts_with(b"return") {
+        parser.pos += "return".len();
+    }
+    let value = parser.value()?;
+    parser.skip_trivia();
+    if parser.pos != parser.src.len() {
+        return Err(parser.error("trailing input"));
+    }
+    Ok(value)
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        format!("{what} at byte {}", self.pos)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+                self.pos += 1;
+            }
+            if !self.src[self.pos..].starts_with(b"--") {
+                return;
+            }
+            self.pos += 2;
+            if let Some(level) = self.long_bracket_level() {
+                let _ = self.long_string(level);
+            } else {
+                while self.peek().is_some_and(|b| b != b'\n') {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_trivia();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_trivia();
+        match self.peek() {
+            Some(b'{') => self.table(),
+            Some(b'"' | b'\'') => self.quoted().map(Value::String),
+            Some(b'[') => {
+                let level = self
+                    .long_bracket_level()
+                    .ok_or_else(|| self.error("unexpected '['"))?;
+                self.long_string(level).map(Value::String)
+            }
+            Some(b'-' | b'.' | b'0'..=b'9') => self.number(),
+            Some(b) if b.is_ascii_alphabetic() => match self.name().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "nil" => Ok(Value::Null),
+                other => Err(self.error(&format!("unexpected '{other}'"))),
+            },
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_')
+        {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.src[start..self.pos]).into_owned()
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.'))
+        {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.src[start..self.pos]).unwrap_or_default();
+        if let Ok(n) = text.parse::<i64>() {
+            return Ok(Value::Number(n.into()));
+        }
+        text.parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("bad number '{text}'")))
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.src[self.pos];
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            let Some(b) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match b {
+                _ if b == quote => break,
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' | b'\n' => out.push(b'\n'),
+                        b't' => out.push(b'\t'),
+                        b'r' => out.push(b'\r'),
+                        b'a' => out.push(0x07),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0c),
+                        b'v' => out.push(0x0b),
+                        b'0'..=b'9' => {
+                            // `\ddd`: up to three decimal digits, one byte.
+                            let mut n = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'9') => {
+                                        n = n * 10 + u32::from(d - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(n.min(255) as u8);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                _ => out.push(b),
+            }
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// The `=` count of a `[[`/`[==[` opener at the cursor, if there is one.
+    fn long_bracket_level(&self) -> Option<usize> {
+        let rest = &self.src[self.pos..];
+        if rest.first() != Some(&b'[') {
+            return None;
+        }
+        let level = rest[1..].iter().take_while(|&&b| b == b'=').count();
+        (rest.get(level + 1) == Some(&b'[')).then_some(level)
+    }
+
+    fn long_string(&mut self, level: usize) -> Result<String> {
+        self.pos += level + 2;
+        // A newline right after the opener is not part of the string.
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+        let close = format!("]{}]", "=".repeat(level));
+        let rest = &self.src[self.pos..];
+        let end = rest
+            .windows(close.len())
+            .position(|w| w == close.as_bytes())
+            .ok_or_else(|| self.error("unterminated long string"))?;
+        let text = String::from_utf8_lossy(&rest[..end]).into_owned();
+        self.pos += end + close.len();
+        Ok(text)
+    }
+
+    fn table(&mut self) -> Result<Value> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(Value, Value)> = Vec::new();
+        let mut next_index = 1i64;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    break;
+                }
+                None => return Err(self.error("unterminated table")),
+                _ => {}
+            }
+            let key = if self.peek() == Some(b'[') && self.long_bracket_level().is_none() {
+                self.pos += 1;
+                let key = self.value()?;
+                self.expect(b']')?;
+                self.expect(b'=')?;
+                key
+            } else if self
+                .peek()
+                .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+            {
+                let save = self.pos;
+                let name = self.name();
+                self.skip_trivia();
+                if self.peek() == Some(b'=') {
+                    self.pos += 1;
+                    Value::String(name)
+                } else {
+                    // A positional `true`/`false`/`nil`.
+                    self.pos = save;
+                    next_index += 1;
+                    Value::Number((next_index - 1).into())
+                }
+            } else {
+                next_index += 1;
+                Value::Number((next_index - 1).into())
+            };
+            let value = self.value()?;
+            entries.push((key, value));
+            self.skip_trivia();
+            if matches!(self.peek(), Some(b',' | b';')) {
+                self.pos += 1;
+            }
+        }
+        Ok(into_json(entries))
+    }
+}
+
+fn into_json(mut entries: Vec<(Value, Value)>) -> Value {
+    let is_sequence = !entries.is_empty()
+        && entries
+            .iter()
+            .all(|(key, _)| key.as_i64().is_some_and(|n| n >= 1))
+        && {
+            entries.sort_by_key(|(key, _)| key.as_i64());
+            entries
+                .iter()
+                .enumerate()
+                .all(|(i, (key, _))| key.as_i64() == Some(i as i64 + 1))
+        };
+    if is_sequence {
+        return Value::Array(entries.into_iter().map(|(_, v)| v).collect());
+    }
+    let mut map = Map::new();
+    for (key, value) in entries {
+        let key = match key {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        map.insert(key, value);
+    }
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_a_docsettings_dump() {
+        let src = r#"-- we can read Lua syntax here!
+return {
+    ["annotations"] = {
+        [1] = {
+            ["chapter"] = "Chapter \"One\"",
+            ["datetime"] = "2024-01-02 10:11:12",
+            ["pos0"] = "/body/DocFragment[3]/body/p[2]/text().0",
+            ["text"] = "caf\195\169\
+line",
+            ["pageno"] = 12,
+        },
+        [2] = {
+            ["page"] = 7,
+            ["pos0"] = { ["x"] = 1.5, ["page"] = 7, },
+        },
+    },
+    ["doc_pages"] = 300,
+    ["highlight_drawer"] = [[lighten]],
+    ["partial_md5_checksum"] = "0123456789abcdef0123456789abcdef",
+    ["summary"] = { status = "reading", modified = nil },
+    [42] = true,
+}
+"#;
+        let value = parse(src).unwrap();
+        assert_eq!(value["annotations"][0]["text"], "café\nline");
+        assert_eq!(value["annotations"][0]["chapter"], "Chapter \"One\"");
+        assert_eq!(
+            value["annotations"][1]["pos0"],
+            json!({ "x": 1.5, "page": 7 })
+        );
+        assert_eq!(value["doc_pages"], 300);
+        assert_eq!(value["highlight_drawer"], "lighten");
+        assert_eq!(value["summary"]["status"], "reading");
+        assert_eq!(value["42"], true);
+    }
+
+    #[test]
+    fn keeps_sparse_tables_as_objects() {
+        assert_eq!(
+            parse("return { [2] = 'b', [5] = 'e' }").unwrap(),
+            json!({ "2": "b", "5": "e" })
+        );
+        assert_eq!(parse("{ 'a', 'b' }").unwrap(), json!(["a", "b"]));
+        assert!(parse("return { [1] = ").is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! Highlight import for readers migrating from a Kindle (`My Clippings.txt`)
+//! or KOReader (`*.sdr/metadata.*.lua` sidecars).
+//!
+//! Both sources are parsed into [`ImportedBook`]s of normalized
+//! [`ImportedAnnotation`]s and matched to library books, by the partial MD5
+//! KOReader records (the same `compute_partial_md5` the library hashes with)
+//! or else by title and author. Neither source carries a CFI, so each
+//! annotation comes with a [`TextAnchor`] instead — the quoted text plus
+//! whatever position hints the source had (XPointers, Kindle locations,
+//! pages, chapter) — for the reader to resolve once the book is open.
+
+mod kindle;
+mod koreader;
+mod lua;
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use md5::{Digest, Md5};
+use serde::{ser::Serializer, Deserialize, Serialize};
+use tauri::{command, AppHandle};
+
+use crate::transfer_file::ensure_path_allowed;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Forbidden(#[from] crate::transfer_file::Error),
+    #[error("{0}")]
+    Parse(String),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A library book to match against, as the webview lists it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryBook {
+    pub hash: String,
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+}
+
+/// Where an annotation sits, for the reader to turn into a CFI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextAnchor {
+    /// The highlighted text, to search for in the book.
+    pub exact: String,
+    /// KOReader's start/end XPointers (EPUB only), as `utils/xcfi.ts` takes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xpointer0: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xpointer1: Option<String>,
+    /// Kindle location range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<(u32, u32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<String>,
+    /// Position through the book, `0.0..=1.0`, when the source knows the
+    /// page count; narrows the search for short or repeated text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fraction: Option<f64>,
+}
+
+/// A `BookNote` without its CFI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedAnnotation {
+    /// Stable across imports, so importing twice doesn't duplicate.
+    pub id: String,
+    /// `annotation` or `bookmark`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub anchor: TextAnchor,
+    pub note: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Milliseconds since the epoch. Both sources record wall-clock time
+    /// without a zone, so this is that time read as UTC.
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedBook {
+    pub title: String,
+    pub author: String,
+    /// The partial MD5 of the source's copy of the book, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_md5: Option<String>,
+    /// The matched library book's hash; `None` when nothing matched or the
+    /// match was ambiguous.
+    pub book_hash: Option<String>,
+    /// Where the annotations came from: the clippings file or the sidecar.
+    pub source_path: String,
+    pub annotations: Vec<ImportedAnnotation>,
+}
+
+/// A seven-hex ID from `parts`, the same shape as the KOReader plugin's
+/// `generateNoteId`, so the plugin and this importer agree on a note's ID.
+fn note_id(parts: &[&str]) -> String {
+    format!("{:x}", Md5::digest(parts.join(":").as_bytes()))[..7].to_string()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_to_ms(date: (i64, u32, u32), time: (u32, u32, u32)) -> Option<i64> {
+    let (year, month, day) = date;
+    let (hour, minute, second) = time;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    let secs = days_from_civil(year, month, day) * 86_400
+        + i64::from(hour * 3600 + minute * 60 + second.min(59));
+    Some(secs * 1000)
+}
+
+/// Lowercased words of `s`, dropping punctuation.
+fn words(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// A title for comparison: no series or edition in brackets, no subtitle.
+fn title_key(title: &str) -> String {
+    let mut plain = String::new();
+    let mut depth = 0usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    let main = plain.split([':', '|']).next().unwrap_or_default();
+    let key = words(main).join(" ");
+    if key.is_empty() {
+        words(title).join(" ")
+    } else {
+        key
+    }
+}
+
+/// Whether two author strings share a name. Word sets rather than strings
+/// so "Herbert, Frank" matches "Frank Herbert".
+fn authors_overlap(a: &str, b: &str) -> bool {
+    let a: HashSet<String> = words(a)
+        .into_iter()
+        .filter(|w| w.chars().count() > 1)
+        .collect();
+    words(b)
+        .into_iter()
+        .any(|w| w.chars().count() > 1 && a.contains(&w))
+}
+
+/// The library book `book` is, by hash first, then by title (and author to
+/// break ties).
+fn match_book(book: &ImportedBook, library: &[LibraryBook]) -> Option<String> {
+    if let Some(md5) = &book.partial_md5 {
+        if let Some(found) = library.iter().find(|b| b.hash.eq_ignore_ascii_case(md5)) {
+            return Some(found.hash.clone());
+        }
+    }
+    let key = title_key(&book.title);
+    if key.is_empty() {
+        return None;
+    }
+    let by_title: Vec<&LibraryBook> = library
+        .iter()
+        .filter(|b| title_key(&b.title) == key)
+        .collect();
+    match by_title.as_slice() {
+        [] => None,
+        [only] if book.author.is_empty() || only.author.is_empty() => Some(only.hash.clone()),
+        candidates => {
+            let by_author: Vec<&&LibraryBook> = candidates
+                .iter()
+                .filter(|b| authors_overlap(&b.author, &book.author))
+                .collect();
+            match by_author.as_slice() {
+                [only] => Some(only.hash.clone()),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn match_all(mut books: Vec<ImportedBook>, library: &[LibraryBook]) -> Vec<ImportedBook> {
+    for book in &mut books {
+        book.book_hash = match_book(book, library);
+    }
+    books
+}
+
+/// Parse a Kindle `My Clippings.txt` and match its books to `library`.
+#[command]
+pub async fn import_kindle_clippings(
+    app: AppHandle,
+    file_path: String,
+    library: Vec<LibraryBook>,
+) -> Result<Vec<ImportedBook>> {
+    ensure_path_allowed(&app, &file_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = std::fs::read(&file_path)?;
+        let books = kindle::parse_clippings(&String::from_utf8_lossy(&bytes), &file_path);
+        Ok(match_all(books, &library))
+    })
+    .await
+    .map_err(|e| Error::Parse(e.to_string()))?
+}
+
+/// Read KOReader sidecars and match them to `library`. `path` is a
+/// `metadata.*.lua` file, a `.sdr` directory, or any folder to search for
+/// them (a device's book folder, or KOReader's `docsettings`).
+#[command]
+pub async fn import_koreader_sidecars(
+    app: AppHandle,
+    path: String,
+    library: Vec<LibraryBook>,
+) -> Result<Vec<ImportedBook>> {
+    ensure_path_allowed(&app, &path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let books = koreader::find_sidecars(Path::new(&path))
+            .into_iter()
+            .filter_map(|sidecar| match koreader::read_sidecar(&sidecar) {
+                Ok(book) => book,
+                Err(e) => {
+                    log::warn!("skipping KOReader sidecar {}: {e}", sidecar.display());
+                    None
+                }
+            })
+            .collect();
+        Ok(match_all(books, &library))
+    })
+    .await
+    .map_err(|e| Error::Parse(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> Vec<LibraryBook> {
+        let book = |hash: &str, title: &str, author: &str| LibraryBook {
+            hash: hash.into(),
+            title: title.into(),
+            author: author.into(),
+        };
+        vec![
+            book("aa", "Dune", "Frank Herbert"),
+            book("bb", "Emma", "Jane Austen"),
+            book("cc", "Emma", "Emma Tennant"),
+            book("dd", "Thinking, Fast and Slow", "Daniel Kahneman"),
+        ]
+    }
+
+    fn imported(title: &str, author: &str, md5: Option<&str>) -> ImportedBook {
+        ImportedBook {
+            title: title.into(),
+            author: author.into(),
+            partial_md5: md5.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_by_hash_then_title_and_author() {
+        let library = library();
+        let matched = |book: ImportedBook| match_book(&book, &library);
+        assert_eq!(
+            matched(imported("Whatever", "", Some("DD"))),
+            Some("dd".into())
+        );
+        assert_eq!(
+            matched(imported(
+                "Dune (Dune Chronicles, Book 1)",
+                "Herbert, Frank",
+                None
+            )),
+            Some("aa".into())
+        );
+        assert_eq!(
+            matched(imported("Thinking, Fast and Slow: A Study", "", None)),
+            Some("dd".into())
+        );
+        assert_eq!(
+            matched(imported("Emma", "Austen, Jane", None)),
+            Some("bb".into())
+        );
+        // Two "Emma"s and no author to tell them apart.
+        assert_eq!(matched(imported("Emma", "", None)), None);
+        assert_eq!(
+            matched(imported("Dune Messiah", "Frank Herbert", None)),
+            None
+        );
+    }
+
+    #[test]
+    fn derives_plugin_compatible_ids() {
+        // `generateNoteId` in readest_syncannotations.lua.
+        let id = note_id(&["ko", "hash", "annotation", "/body/p", ""]);
+        assert_eq!(id.len(), 7);
+        assert_eq!(
+            id,
+            format!("{:x}", Md5::digest(b"ko:hash:annotation:/body/p:"))[..7]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Shared HTTP client factory.
+//!
+//! Every Rust-side HTTP path (`transfer_file`, `chunked_upload`, the nightly
+//! updater, the `clip_url` webview) takes its proxy and trust settings from
+//! here, so a network that needs an authenticated HTTP/SOCKS proxy or a
+//! private CA is configured once. The webview sets the configuration from
+//! settings at startup through `set_network_config`; until then clients use
+//! the system proxy and platform roots, which is also reqwest's default.
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, Url};
+
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum ProxyConfig {
+    /// The OS proxy settings and the `HTTP(S)_PROXY` / `ALL_PROXY` /
+    /// `NO_PROXY` environment.
+    #[default]
+    System,
+    /// Connect directly, ignoring both.
+    Direct,
+    /// One proxy for every request: `http://`, `https://`, `socks5://` or
+    /// `socks5h://` (proxy-side DNS).
+    #[serde(rename_all = "camelCase")]
+    Manual {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+        /// Hosts reached directly, in `NO_PROXY` syntax: `example.com`
+        /// (and its subdomains), `*.corp`, IPs and CIDR blocks.
+        #[serde(default)]
+        no_proxy: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConfig {
+    pub proxy: ProxyConfig,
+    /// PEM certificates trusted in addition to the platform roots. Each entry
+    /// may hold a whole bundle.
+    pub extra_root_certificates: Vec<String>,
+}
+
+/// A validated [`NetworkConfig`], ready to apply to any number of builders.
+#[derive(Clone, Default)]
+struct Prepared {
+    proxy: Option<reqwest::Proxy>,
+    direct: bool,
+    roots: Vec<reqwest::Certificate>,
+}
+
+impl Prepared {
+    fn new(config: &NetworkConfig) -> Result<Self, String> {
+        let mut prepared = Prepared::default();
+        match &config.proxy {
+            ProxyConfig::System => {}
+            ProxyConfig::Direct => prepared.direct = true,
+            ProxyConfig::Manual {
+                url,
+                username,
+                password,
+                no_proxy,
+            } => {
+                let mut proxy =
+                    reqwest::Proxy::all(url).map_err(|e| format!("invalid proxy URL: {e}"))?;
+                if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+                    proxy = proxy.basic_auth(username, password.as_deref().unwrap_or(""));
+                }
+                prepared.proxy =
+                    Some(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy.join(","))));
+            }
+        }
+        for (i, pem) in config.extra_root_certificates.iter().enumerate() {
+            let certs = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|e| format!("invalid certificate #{}: {e}", i + 1))?;
+            if certs.is_empty() {
+                return Err(format!("invalid certificate #{}: no PEM block", i + 1));
+            }
+            prepared.roots.extend(certs);
+        }
+        Ok(prepared)
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if self.direct {
+            builder = builder.no_proxy();
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        for root in &self.roots {
+            builder = builder.add_root_certificate(root.clone());
+        }
+        builder
+    }
+}
+
+#[derive(Default)]
+struct Factory {
+    config: NetworkConfig,
+    prepared: Prepared,
+    /// Built lazily and dropped on every configuration change, so
+    /// connections are pooled across transfers but never outlive a proxy.
+    client: Option<reqwest::Client>,
+}
+
+fn factory() -> &'static Mutex<Factory> {
+    static FACTORY: OnceLock<Mutex<Factory>> = OnceLock::new();
+    FACTORY.get_or_init(Mutex::default)
+}
+
+/// The shared client for ordinary requests.
+pub(crate) fn client() -> reqwest::Result<reqwest::Client> {
+    let mut factory = factory().lock().unwrap();
+    if let Some(client) = &factory.client {
+        return Ok(client.clone());
+    }
+    let client = factory.prepared.apply(reqwest::Client::builder()).build()?;
+    factory.client = Some(client.clone());
+    Ok(client)
+}
+
+/// A client that also accepts invalid certificates and hostnames, for the
+/// per-download `skipSslVerification` escape hatch. Trusting the server's
+/// CA through `extraRootCertificates` is the better fix.
+pub(crate) fn insecure_client() -> reqwest::Result<reqwest::Client> {
+    let prepared = factory().lock().unwrap().prepared.clone();
+    prepared
+        .apply(reqwest::Client::builder())
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+}
+
+/// The manual proxy, credentials included, for HTTP stacks that take a bare
+/// proxy URL instead of a builder (the updater, webviews).
+pub(crate) fn proxy_url() -> Option<Url> {
+    let factory = factory().lock().unwrap();
+    let ProxyConfig::Manual {
+        url,
+        username,
+        password,
+        ..
+    } = &factory.config.proxy
+    else {
+        return None;
+    };
+    let mut url = Url::parse(url).ok()?;
+    if let Some(username) = username.as_deref().filter(|u| !u.is_empty()) {
+        url.set_username(username).ok()?;
+        url.set_password(password.as_deref()).ok()?;
+    }
+    Some(url)
+}
+
+/// Queued transfers wait for the first call, successful or not.
+#[command]
+pub fn set_network_config(app: tauri::AppHandle, config: NetworkConfig) -> Result<(), String> {
+    let result = apply_network_config(config);
+    crate::transfer_manager::config_pushed(&app, crate::transfer_manager::ConfigPush::Network);
+    result
+}
+
+fn apply_network_config(config: NetworkConfig) -> Result<(), String> {
+    let prepared = Prepared::new(&config)?;
+    // Build once so a configuration the TLS backend rejects fails here,
+    // not on the next transfer.
+    prepared
+        .apply(reqwest::Client::builder())
+        .build()
+        .map_err(|e| e.to_string())?;
+    *factory().lock().unwrap() = Factory {
+        config,
+        prepared,
+        client: None,
+    };
+    Ok(())
+}
+
+#[command]
+pub fn get_network_config() -> NetworkConfig {
+    factory().lock().unwrap().config.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetworkConfig, Prepared, ProxyConfig};
+
+    #[test]
+    fn manual_proxy_deserializes_from_camel_case() {
+        let config: NetworkConfig = serde_json::from_str(
+            r#"{"proxy":{"mode":"manual","url":"socks5h://proxy.corp:1080",
+                "username":"me","noProxy":["localhost","10.0.0.0/8"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.proxy,
+            ProxyConfig::Manual {
+                url: "socks5h://proxy.corp:1080".into(),
+                username: Some("me".into()),
+                password: None,
+                no_proxy: vec!["localhost".into(), "10.0.0.0/8".into()],
+            }
+        );
+        assert!(config.extra_root_certificates.is_empty());
+        assert_eq!(
+            serde_json::from_str::<NetworkConfig>("{}").unwrap().proxy,
+            ProxyConfig::System
+        );
+    }
+
+    #[test]
+    fn rejects_bad_proxy_and_certificate() {
+        let bad_proxy = NetworkConfig {
+            proxy: ProxyConfig::Manual {
+                url: "not a url".into(),
+                username: None,
+                password: None,
+                no_proxy: Vec::new(),
+            },
+            ..Default::default()
+        };
+        assert!(Prepared::new(&bad_proxy)
+            .err()
+            .unwrap()
+            .starts_with("invalid proxy URL"));
+
+        let bad_cert = NetworkConfig {
+            extra_root_certificates: vec!["hello".into()],
+            ..Default::default()
+        };
+        assert!(Prepared::new(&bad_cert)
+            .err()
+            .unwrap()
+            .starts_with("invalid certificate #1"));
+    }
+}
@@ -0,0 +1,148 @@
+//! A KOReader sync server client, the native side of
+//! `services/sync/KOSyncClient.ts`.
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{Error, Progress, Result};
+
+const ACCEPT_KOSYNC: &str = "application/vnd.koreader.v1+json";
+
+/// A sync account, as `KOSyncSettings` stores it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KoSyncAccount {
+    pub server_url: String,
+    pub username: String,
+    /// MD5 of the password; see `super::userkey`.
+    pub userkey: String,
+    #[serde(default)]
+    pub device_name: String,
+    #[serde(default)]
+    pub device_id: String,
+}
+
+pub struct KoSyncClient {
+    http: reqwest::Client,
+    account: KoSyncAccount,
+}
+
+impl KoSyncClient {
+    pub fn new(http: reqwest::Client, mut account: KoSyncAccount) -> Self {
+        account.server_url = account.server_url.trim_end_matches('/').to_string();
+        Self { http, account }
+    }
+
+    pub fn account(&self) -> &KoSyncAccount {
+        &self.account
+    }
+
+    fn request(&self, method: Method, endpoint: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}{endpoint}", self.account.server_url))
+            .header(ACCEPT, ACCEPT_KOSYNC)
+            .header("x-auth-user", &self.account.username)
+            .header("x-auth-key", &self.account.userkey)
+    }
+
+    async fn error(response: reqwest::Response) -> Error {
+        match response.status() {
+            // Some servers (CWA) answer a bad login with 400.
+            StatusCode::UNAUTHORIZED | StatusCode::BAD_REQUEST => Error::Unauthorized,
+            status => {
+                Error::HttpErrorCode(status.as_u16(), response.text().await.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Check the credentials.
+    pub async fn authorize(&self) -> Result<()> {
+        let response = self.request(Method::GET, "/users/auth").send().await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+        Err(Self::error(response).await)
+    }
+
+    /// Create the account on the server.
+    pub async fn register(&self) -> Result<()> {
+        let response = self
+            .request(Method::POST, "/users/create")
+            .header(CONTENT_TYPE, "application/json")
+            .body(
+                json!({
+                    "username": self.account.username,
+                    "password": self.account.userkey,
+                })
+                .to_string(),
+            )
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::PAYMENT_REQUIRED => Err(Error::UserExists),
+            _ => Err(Self::error(response).await),
+        }
+    }
+
+    /// The last position pushed for `document`, if any device pushed one.
+    pub async fn get_progress(&self, document: &str) -> Result<Option<Progress>> {
+        let response = self
+            .request(Method::GET, &format!("/syncs/progress/{document}"))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        let value: serde_json::Value = response.json().await?;
+        // `{}` when nothing was pushed yet.
+        let Some(percentage) = value.get("percentage").and_then(|p| p.as_f64()) else {
+            return Ok(None);
+        };
+        let text = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        Ok(Some(Progress {
+            // Not every server echoes the document back.
+            document: document.to_string(),
+            progress: text("progress"),
+            percentage,
+            device: text("device"),
+            device_id: text("device_id"),
+            timestamp: value.get("timestamp").and_then(|t| t.as_i64()),
+        }))
+    }
+
+    /// Push this device's position; device name and ID come from the account.
+    pub async fn update_progress(
+        &self,
+        document: &str,
+        progress: &str,
+        percentage: f64,
+    ) -> Result<()> {
+        let body = Progress {
+            document: document.to_string(),
+            progress: progress.to_string(),
+            percentage,
+            device: self.account.device_name.clone(),
+            device_id: self.account.device_id.clone(),
+            timestamp: None,
+        };
+        let response = self
+            .request(Method::PUT, "/syncs/progress")
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&body).unwrap_or_default())
+            .send()
+            .await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+        Err(Self::error(response).await)
+    }
+}
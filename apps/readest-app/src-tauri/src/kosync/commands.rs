@@ -0,0 +1,242 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use super::position::{self, spine_index};
+use super::server::{self, RunningServer};
+use super::{
+    Error, KoSyncAccount, KoSyncClient, KoSyncServerState, Progress, Result, EV_SERVER_PROGRESS,
+};
+use crate::localsend::commands::local_ips;
+use crate::transfer_file::ensure_path_allowed;
+
+fn client(account: KoSyncAccount) -> Result<KoSyncClient> {
+    // Self-hosted sync servers often sit behind self-signed certificates;
+    // `KOSyncClient.ts` accepts them too.
+    Ok(KoSyncClient::new(
+        crate::http_client::insecure_client()?,
+        account,
+    ))
+}
+
+fn is_xpointer(position: &str) -> bool {
+    position.starts_with("/body/DocFragment[")
+}
+
+/// Translate a KOReader XPointer into a CFI, reading the spine document it
+/// points into from the EPUB at `file_path`.
+fn to_cfi(file_path: &Path, xpointer: &str) -> Result<String> {
+    let index = spine_index(xpointer)
+        .ok_or_else(|| Error::Position(format!("not an XPointer: {xpointer}")))?;
+    let html = position::load_spine_document(file_path, index).map_err(Error::Position)?;
+    position::xpointer_to_cfi(&html, xpointer).map_err(Error::Position)
+}
+
+fn to_xpointer(file_path: &Path, cfi: &str) -> Result<String> {
+    let index = spine_index(cfi).ok_or_else(|| Error::Position(format!("not a CFI: {cfi}")))?;
+    let html = position::load_spine_document(file_path, index).map_err(Error::Position)?;
+    position::cfi_to_xpointer(&html, cfi).map_err(Error::Position)
+}
+
+/// Log in, or create the account when `register` is set.
+#[command]
+pub async fn kosync_connect(account: KoSyncAccount, register: Option<bool>) -> Result<()> {
+    let client = client(account)?;
+    if register.unwrap_or(false) {
+        client.register().await
+    } else {
+        client.authorize().await
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PulledProgress {
+    #[serde(flatten)]
+    pub progress: Progress,
+    /// The XPointer as a CFI, when the book is an EPUB at `file_path`.
+    pub cfi: Option<String>,
+}
+
+/// The server's position for `document` (the book hash). With the EPUB's
+/// `file_path`, an XPointer is also translated into a CFI; a position that
+/// doesn't resolve against this edition of the book comes back without one,
+/// so the caller can fall back to the percentage.
+#[command]
+pub async fn kosync_pull(
+    app: AppHandle,
+    account: KoSyncAccount,
+    document: String,
+    file_path: Option<String>,
+) -> Result<Option<PulledProgress>> {
+    if let Some(path) = &file_path {
+        ensure_path_allowed(&app, path)?;
+    }
+    let Some(progress) = client(account)?.get_progress(&document).await? else {
+        return Ok(None);
+    };
+    let cfi = match &file_path {
+        Some(path) if is_xpointer(&progress.progress) => {
+            to_cfi(Path::new(path), &progress.progress)
+                .map_err(|e| log::debug!("kosync: {e}"))
+                .ok()
+        }
+        _ => None,
+    };
+    Ok(Some(PulledProgress { progress, cfi }))
+}
+
+/// Push a position. `position` is a CFI, translated into an XPointer against
+/// the EPUB at `file_path`, or a value sent as-is (a page number for fixed
+/// layout books, or an XPointer already known).
+#[command]
+pub async fn kosync_push(
+    app: AppHandle,
+    account: KoSyncAccount,
+    document: String,
+    position: String,
+    percentage: f64,
+    file_path: Option<String>,
+) -> Result<String> {
+    let progress = match &file_path {
+        Some(path) if position.starts_with("epubcfi(") => {
+            ensure_path_allowed(&app, path)?;
+            to_xpointer(Path::new(path), &position)?
+        }
+        _ => position,
+    };
+    client(account)?
+        .update_progress(&document, &progress, percentage.clamp(0.0, 1.0))
+        .await?;
+    Ok(progress)
+}
+
+#[command]
+pub async fn kosync_xpointer_to_cfi(
+    app: AppHandle,
+    file_path: String,
+    xpointer: String,
+) -> Result<String> {
+    ensure_path_allowed(&app, &file_path)?;
+    tauri::async_runtime::spawn_blocking(move || to_cfi(Path::new(&file_path), &xpointer))
+        .await
+        .map_err(|e| Error::Position(e.to_string()))?
+}
+
+#[command]
+pub async fn kosync_cfi_to_xpointer(
+    app: AppHandle,
+    file_path: String,
+    cfi: String,
+) -> Result<String> {
+    ensure_path_allowed(&app, &file_path)?;
+    tauri::async_runtime::spawn_blocking(move || to_xpointer(Path::new(&file_path), &cfi))
+        .await
+        .map_err(|e| Error::Position(e.to_string()))?
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KoSyncServerStatus {
+    pub running: bool,
+    pub port: u16,
+    pub allow_registration: bool,
+    /// Non-loopback IPv4 addresses, to show `http://<ip>:<port>`.
+    pub local_ips: Vec<String>,
+}
+
+fn status_of(server: Option<&RunningServer>) -> KoSyncServerStatus {
+    match server {
+        Some(server) => KoSyncServerStatus {
+            running: true,
+            port: server.port,
+            allow_registration: server.allow_registration,
+            local_ips: local_ips(),
+        },
+        None => KoSyncServerStatus {
+            running: false,
+            port: 0,
+            allow_registration: false,
+            local_ips: Vec::new(),
+        },
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerProgressEvent {
+    username: String,
+    #[serde(flatten)]
+    progress: Progress,
+}
+
+/// Start the sync server. Accounts persist in
+/// `<app_data_dir>/kosync-server.json`; `allow_registration`, off unless
+/// asked for, lets new devices create one, otherwise the owner adds them with
+/// `kosync_server_add_user`. Every update is also emitted as
+/// `kosync:server-progress`, so an open book can follow a KOReader device
+/// live.
+#[command]
+pub async fn kosync_server_start(
+    app: AppHandle,
+    state: State<'_, KoSyncServerState>,
+    allow_registration: Option<bool>,
+) -> std::result::Result<KoSyncServerStatus, String> {
+    let mut guard = state.0.lock().await;
+    if guard.is_none() {
+        let path = match app.path().app_data_dir() {
+            Ok(dir) => Some(dir.join("kosync-server.json")),
+            Err(e) => {
+                log::warn!("kosync server store not persisted: {e}");
+                None
+            }
+        };
+        let emitter = app.clone();
+        let on_progress: server::OnProgress = Arc::new(move |username, progress| {
+            let _ = emitter.emit(
+                EV_SERVER_PROGRESS,
+                ServerProgressEvent {
+                    username: username.to_string(),
+                    progress: progress.clone(),
+                },
+            );
+        });
+        *guard = Some(server::start(path, allow_registration.unwrap_or(false), on_progress).await?);
+    }
+    Ok(status_of(guard.as_ref()))
+}
+
+/// Create an account on the running server, or set a new password for an
+/// existing one. KOReader logs in with the MD5 of the password as its key.
+#[command]
+pub async fn kosync_server_add_user(
+    state: State<'_, KoSyncServerState>,
+    username: String,
+    password: String,
+) -> std::result::Result<(), String> {
+    let guard = state.0.lock().await;
+    let server = guard.as_ref().ok_or("kosync server is not running")?;
+    if password.is_empty() {
+        return Err("username and password are required".into());
+    }
+    server.add_user(username.trim(), &super::userkey(&password))
+}
+
+#[command]
+pub async fn kosync_server_stop(
+    state: State<'_, KoSyncServerState>,
+) -> std::result::Result<(), String> {
+    if let Some(mut server) = state.0.lock().await.take() {
+        server.stop();
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn kosync_server_status(
+    state: State<'_, KoSyncServerState>,
+) -> std::result::Result<KoSyncServerStatus, String> {
+    Ok(status_of(state.0.lock().await.as_ref()))
+}
@@ -0,0 +1,80 @@
+//! KOReader progress sync ("kosync").
+//!
+//! `client` speaks the KOReader sync server protocol (`/users/auth`,
+//! `/users/create`, `/syncs/progress[/:document]`) to any compatible server;
+//! `server` is a small one of our own, so KOReader devices on the LAN can
+//! sync with this app without a hosted account. Documents are keyed by the
+//! partial MD5 `parser_common::compute_partial_md5` computes — KOReader's
+//! "binary" checksum method — which is already every book's hash here.
+//!
+//! KOReader reports an EPUB position as a CREngine XPointer; `position`
+//! translates it to and from the CFIs Readest stores, reading the spine
+//! document straight from the EPUB. Other formats carry KOReader's own
+//! progress string (a page number) and the percentage through unchanged.
+
+mod client;
+pub mod commands;
+pub mod position;
+pub mod server;
+
+use md5::{Digest, Md5};
+use serde::{ser::Serializer, Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub use client::{KoSyncAccount, KoSyncClient};
+
+pub const EV_SERVER_PROGRESS: &str = "kosync:server-progress";
+
+/// Tauri managed state: the running sync server, or `None` while it's off.
+#[derive(Default)]
+pub struct KoSyncServerState(pub Arc<Mutex<Option<server::RunningServer>>>);
+
+/// One document's position, as the protocol carries it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub document: String,
+    /// An XPointer for reflowable books, a page number for fixed layout.
+    pub progress: String,
+    /// Of the whole book, `0.0..=1.0`.
+    pub percentage: f64,
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub device_id: String,
+    /// Seconds since the epoch, set by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+/// KOReader never sends the password itself, only its MD5 ("userkey").
+pub fn userkey(password: &str) -> String {
+    format!("{:x}", Md5::digest(password.as_bytes()))
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("the sync server rejected the credentials")]
+    Unauthorized,
+    #[error("the username is already taken")]
+    UserExists,
+    #[error("request failed with status code {0}: {1}")]
+    HttpErrorCode(u16, String),
+    #[error("{0}")]
+    Position(String),
+    #[error(transparent)]
+    Forbidden(#[from] crate::transfer_file::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
@@ -0,0 +1,500 @@
+//! Positions across the two apps. KOReader addresses a point in an EPUB as a
+//! CREngine XPointer (`/body/DocFragment[12]/body/div/p[3]/text().42`),
+//! Readest as an EPUB CFI (`epubcfi(/6/24!/4/2/6/1:42)`). Both are paths
+//! through the same spine document, so its XHTML is enough to translate one
+//! into the other without a webview — progress can follow the book while it
+//! is closed. `utils/xcfi.ts` does the same in the webview against a live DOM;
+//! the rules here match it:
+//!
+//! - `DocFragment[N]` is spine item `N - 1`; the CFI spine step is `2N`.
+//! - `tag[i]` counts same-named element siblings from 1; CFI steps count all
+//!   element children, doubled, with odd steps for the text between them.
+//! - `text().N` is a character offset across the element's text, `text()[K].N`
+//!   an offset in its K-th direct text node, and `tag[i].N` the same as
+//!   `tag[i]/text().N`.
+
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::epub_parser::{read_rootfile_path, read_spine_paths, read_zip_entry};
+use crate::parser_common::{local_name, strip_xml_bom};
+
+type Result<T> = std::result::Result<T, String>;
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Default)]
+pub struct Element {
+    name: String,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match c {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    fn text_len(&self) -> usize {
+        self.children
+            .iter()
+            .map(|c| match c {
+                Node::Element(e) => e.text_len(),
+                Node::Text(t) => t.chars().count(),
+            })
+            .sum()
+    }
+}
+
+/// One spine document, parsed leniently: HTML entities XML doesn't know are
+/// kept as written and mismatched end tags close the nearest open element.
+pub fn parse_document(bytes: &[u8]) -> Result<Element> {
+    let bytes = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(bytes.as_ref());
+    reader.config_mut().check_end_names = false;
+    let mut stack = vec![Element::default()];
+    let mut buf = Vec::new();
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("xhtml: {e}"))?;
+        match event {
+            Event::Start(e) => stack.push(Element {
+                name: String::from_utf8_lossy(local_name(e.name().as_ref())).to_lowercase(),
+                children: Vec::new(),
+            }),
+            Event::Empty(e) => push(
+                &mut stack,
+                Node::Element(Element {
+                    name: String::from_utf8_lossy(local_name(e.name().as_ref())).to_lowercase(),
+                    children: Vec::new(),
+                }),
+            ),
+            Event::End(_) if stack.len() > 1 => {
+                let element = stack.pop().unwrap_or_default();
+                push(&mut stack, Node::Element(element));
+            }
+            Event::Text(t) => {
+                let text = t
+                    .unescape()
+                    .map(|t| t.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&t).into_owned());
+                push(&mut stack, Node::Text(text));
+            }
+            Event::CData(t) => push(&mut stack, Node::Text(String::from_utf8_lossy(&t).into())),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap_or_default();
+        push(&mut stack, Node::Element(element));
+    }
+    let root = stack.pop().unwrap_or_default();
+    root.children
+        .into_iter()
+        .find_map(|c| match c {
+            Node::Element(e) if e.name == "html" => Some(e),
+            _ => None,
+        })
+        .ok_or_else(|| "no <html> element".to_string())
+}
+
+/// Appends to the innermost open element, merging adjacent text so CFI text
+/// chunks line up with the DOM's.
+fn push(stack: &mut [Element], node: Node) {
+    let Some(parent) = stack.last_mut() else {
+        return;
+    };
+    if let (Node::Text(text), Some(Node::Text(prev))) = (&node, parent.children.last_mut()) {
+        prev.push_str(text);
+        return;
+    }
+    parent.children.push(node);
+}
+
+/// The spine index a position points into, from either format.
+pub fn spine_index(position: &str) -> Option<usize> {
+    if let Some(rest) = position.strip_prefix("/body/DocFragment[") {
+        let n: usize = rest.split(']').next()?.parse().ok()?;
+        return n.checked_sub(1);
+    }
+    let inner = position.strip_prefix("epubcfi(")?;
+    let step = cfi_steps(inner.split('!').next()?).nth(1)?;
+    (step / 2).checked_sub(1)
+}
+
+/// The integer steps of a CFI path, ignoring `[id]` assertions and offsets.
+fn cfi_steps(path: &str) -> impl Iterator<Item = usize> + '_ {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.split(['[', ':', '~', '@']).next()?.parse().ok())
+}
+
+/// Where the text offset of an XPointer is counted from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextOffset {
+    /// Across all text inside the element.
+    Cumulative(usize),
+    /// Inside the K-th direct text child (1-based).
+    Node(usize, usize),
+}
+
+/// `(tag, 1-based index)` steps below `<body>`, and the text offset.
+type ParsedXPointer = (Vec<(String, usize)>, Option<TextOffset>);
+
+fn parse_xpointer(xpointer: &str) -> Result<ParsedXPointer> {
+    let invalid = || format!("unsupported XPointer: {xpointer}");
+    let rest = xpointer
+        .strip_prefix("/body/DocFragment[")
+        .and_then(|r| r.split_once(']'))
+        .map(|(_, r)| r)
+        .ok_or_else(invalid)?;
+    let rest = rest.strip_prefix("/body").ok_or_else(invalid)?;
+
+    let mut steps = Vec::new();
+    let mut offset = None;
+    for segment in rest.split('/').filter(|s| !s.is_empty()) {
+        if let Some(text) = segment.strip_prefix("text()") {
+            let (k, n) = match text.strip_prefix('[') {
+                Some(indexed) => {
+                    let (k, n) = indexed.split_once("].").ok_or_else(invalid)?;
+                    (Some(k.parse().map_err(|_| invalid())?), n)
+                }
+                None => (None, text.strip_prefix('.').ok_or_else(invalid)?),
+            };
+            let n = n.parse().map_err(|_| invalid())?;
+            offset = Some(match k {
+                Some(k) => TextOffset::Node(k, n),
+                None => TextOffset::Cumulative(n),
+            });
+            break;
+        }
+        let (step, trailing) = match segment.split_once('.') {
+            Some((step, n)) => (step, Some(n.parse().map_err(|_| invalid())?)),
+            None => (segment, None),
+        };
+        let (name, index) = match step.split_once('[') {
+            Some((name, index)) => {
+                let index = index.strip_suffix(']').ok_or_else(invalid)?;
+                (name, index.parse().map_err(|_| invalid())?)
+            }
+            None => (step, 1),
+        };
+        if name.is_empty() || index == 0 {
+            return Err(invalid());
+        }
+        steps.push((name.to_lowercase(), index));
+        if let Some(n) = trailing {
+            offset = Some(TextOffset::Cumulative(n));
+            break;
+        }
+    }
+    Ok((steps, offset))
+}
+
+/// Translate a KOReader XPointer into a CFI in `html`, the spine document it
+/// points into.
+pub fn xpointer_to_cfi(html: &Element, xpointer: &str) -> Result<String> {
+    let spine = spine_index(xpointer).ok_or_else(|| format!("no DocFragment in {xpointer}"))?;
+    let (steps, offset) = parse_xpointer(xpointer)?;
+
+    // CFI steps from <html> down to the element.
+    let mut path = Vec::new();
+    let mut current = html;
+    let body_index = current
+        .elements()
+        .position(|e| e.name == "body")
+        .ok_or("no <body> element")?;
+    path.push(2 * (body_index + 1));
+    current = current
+        .elements()
+        .nth(body_index)
+        .ok_or("no <body> element")?;
+    for (name, index) in &steps {
+        let (position, element) = current
+            .elements()
+            .enumerate()
+            .filter(|(_, e)| e.name == *name)
+            .nth(index - 1)
+            .ok_or_else(|| format!("{name}[{index}] not found in {xpointer}"))?;
+        path.push(2 * (position + 1));
+        current = element;
+    }
+
+    let cumulative = match offset {
+        None => None,
+        Some(TextOffset::Cumulative(n)) => Some(n),
+        Some(TextOffset::Node(k, n)) => Some(direct_text_offset(current, k, n)?),
+    };
+    let mut cfi = path.iter().map(|s| format!("/{s}")).collect::<String>();
+    if let Some(offset) = cumulative {
+        let (steps, offset) = text_point(current, offset);
+        for step in steps {
+            cfi.push_str(&format!("/{step}"));
+        }
+        cfi.push_str(&format!(":{offset}"));
+    }
+    Ok(format!("epubcfi(/6/{}!{cfi})", 2 * (spine + 1)))
+}
+
+/// `text()[k].n` as an offset across the whole element.
+fn direct_text_offset(element: &Element, k: usize, n: usize) -> Result<usize> {
+    let mut seen = 0;
+    let mut before = 0;
+    for child in &element.children {
+        match child {
+            Node::Text(t) if !t.trim().is_empty() => {
+                seen += 1;
+                if seen == k {
+                    return Ok(before + n);
+                }
+                before += t.chars().count();
+            }
+            Node::Text(t) => before += t.chars().count(),
+            Node::Element(e) => before += e.text_len(),
+        }
+    }
+    Err(format!("text()[{k}] out of bounds ({seen} text nodes)"))
+}
+
+/// CFI steps from `element` to the text chunk holding character `offset`,
+/// and the offset inside it. Past the end clamps to the last text.
+fn text_point(element: &Element, mut offset: usize) -> (Vec<usize>, usize) {
+    let mut last = None;
+    let mut elements_before = 0;
+    for child in &element.children {
+        match child {
+            Node::Text(t) => {
+                let len = t.chars().count();
+                let step = 2 * elements_before + 1;
+                if offset <= len {
+                    return (vec![step], offset);
+                }
+                offset -= len;
+                last = Some((vec![step], len));
+            }
+            Node::Element(e) => {
+                elements_before += 1;
+                let len = e.text_len();
+                if len > 0 && offset <= len {
+                    let (mut steps, offset) = text_point(e, offset);
+                    steps.insert(0, 2 * elements_before);
+                    return (steps, offset);
+                }
+                offset -= len;
+            }
+        }
+    }
+    last.unwrap_or((Vec::new(), 0))
+}
+
+/// Translate a Readest CFI into a KOReader XPointer in `html`, the spine
+/// document it points into. Range CFIs use their start.
+pub fn cfi_to_xpointer(html: &Element, cfi: &str) -> Result<String> {
+    let invalid = || format!("unsupported CFI: {cfi}");
+    let spine = spine_index(cfi).ok_or_else(invalid)?;
+    let inner = cfi
+        .strip_prefix("epubcfi(")
+        .and_then(|c| c.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+    // `parent,start,end` → `parent` + `start`.
+    let mut parts = inner.split(',');
+    let path = match (parts.next(), parts.next()) {
+        (Some(parent), Some(start)) => format!("{parent}{start}"),
+        (Some(parent), None) => parent.to_string(),
+        _ => return Err(invalid()),
+    };
+    let (_, local) = path.split_once('!').ok_or_else(invalid)?;
+    let offset = local
+        .rsplit('/')
+        .next()
+        .and_then(|last| last.split_once(':'))
+        .and_then(|(_, o)| o.split(['[', '~', '@']).next()?.parse::<usize>().ok());
+    let steps = cfi_steps(local).collect::<Vec<_>>();
+
+    // Walk from <html>: even steps are elements, an odd step the text chunk
+    // after `(step - 1) / 2` elements.
+    let mut chain: Vec<&Element> = vec![html];
+    let mut text_chunk = None;
+    for (i, &step) in steps.iter().enumerate() {
+        let current = chain[chain.len() - 1];
+        if step % 2 == 0 {
+            let element = current.elements().nth(step / 2 - 1).ok_or_else(invalid)?;
+            chain.push(element);
+        } else {
+            if i + 1 != steps.len() {
+                return Err(invalid());
+            }
+            text_chunk = Some((step - 1) / 2);
+        }
+    }
+    if chain.len() < 2 || chain[1].name != "body" {
+        return Err(format!("CFI points outside <body>: {cfi}"));
+    }
+
+    let mut xpointer = format!("/body/DocFragment[{}]/body", spine + 1);
+    for pair in chain[1..].windows(2) {
+        let (parent, element) = (pair[0], pair[1]);
+        let same: Vec<&Element> = parent
+            .elements()
+            .filter(|e| e.name == element.name)
+            .collect();
+        let index = same
+            .iter()
+            .position(|e| std::ptr::eq(*e, element))
+            .unwrap_or_default();
+        if same.len() > 1 {
+            xpointer.push_str(&format!("/{}[{}]", element.name, index + 1));
+        } else {
+            xpointer.push_str(&format!("/{}", element.name));
+        }
+    }
+
+    let element = chain[chain.len() - 1];
+    let (Some(elements_before), Some(offset)) = (text_chunk, offset) else {
+        return Ok(xpointer);
+    };
+    // Which direct text child the chunk is, skipping whitespace-only nodes
+    // as CREngine does, and whether any element precedes it.
+    let mut seen_elements = 0;
+    let mut k = 0;
+    for child in &element.children {
+        match child {
+            Node::Element(_) => seen_elements += 1,
+            Node::Text(t) => {
+                let is_target = seen_elements == elements_before;
+                if !t.trim().is_empty() || is_target {
+                    k += 1;
+                }
+                if is_target {
+                    break;
+                }
+            }
+        }
+    }
+    if k == 1 && elements_before == 0 {
+        xpointer.push_str(&format!("/text().{offset}"));
+    } else {
+        xpointer.push_str(&format!("/text()[{}].{offset}", k.max(1)));
+    }
+    Ok(xpointer)
+}
+
+/// The parsed spine document `index` of the EPUB at `path`.
+pub fn load_spine_document(path: &Path, index: usize) -> Result<Element> {
+    let file = std::fs::File::open(path).map_err(|e| format!("open: {e}"))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("zip: {e}"))?;
+    spine_document(&mut zip, index)
+}
+
+fn spine_document<R: Read + Seek>(zip: &mut zip::ZipArchive<R>, index: usize) -> Result<Element> {
+    let opf_path = read_rootfile_path(zip)?;
+    let opf = read_zip_entry(zip, &opf_path)?;
+    let spine = read_spine_paths(&opf, &opf_path)?;
+    let item = spine
+        .get(index)
+        .ok_or_else(|| format!("spine item {index} out of range ({})", spine.len()))?;
+    parse_document(&read_zip_entry(zip, item)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAPTER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>Chapter 3</title></head>
+<body>
+  <div class="chapter">
+    <h2>Three</h2>
+    <p>First paragraph.</p>
+    <p>Second <em>emphatic</em> paragraph&nbsp;here.</p>
+  </div>
+</body>
+</html>"#;
+
+    fn html() -> Element {
+        parse_document(CHAPTER.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn reads_the_spine_index_from_either_format() {
+        assert_eq!(spine_index("/body/DocFragment[12]/body/div/p[3]"), Some(11));
+        assert_eq!(spine_index("epubcfi(/6/24[ch3]!/4/2/6/1:4)"), Some(11));
+        assert_eq!(spine_index("/body/DocFragment[0]/body"), None);
+        assert_eq!(spine_index("page 12"), None);
+    }
+
+    #[test]
+    fn maps_xpointers_to_cfis() {
+        let html = html();
+        assert_eq!(
+            xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/p[2]").unwrap(),
+            "epubcfi(/6/6!/4/2/6)"
+        );
+        assert_eq!(
+            xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/p[1]/text().6").unwrap(),
+            "epubcfi(/6/6!/4/2/4/1:6)"
+        );
+        // "Second " is 7 characters, "emphatic" 8: offset 10 is inside <em>.
+        assert_eq!(
+            xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/p[2]/text().10").unwrap(),
+            "epubcfi(/6/6!/4/2/6/2/1:3)"
+        );
+        assert_eq!(
+            xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/p[2]/text()[2].1").unwrap(),
+            "epubcfi(/6/6!/4/2/6/3:1)"
+        );
+        assert_eq!(
+            xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/h2.2").unwrap(),
+            "epubcfi(/6/6!/4/2/2/1:2)"
+        );
+        assert!(xpointer_to_cfi(&html, "/body/DocFragment[3]/body/div/p[9]").is_err());
+    }
+
+    #[test]
+    fn maps_cfis_to_xpointers() {
+        let html = html();
+        assert_eq!(
+            cfi_to_xpointer(&html, "epubcfi(/6/6!/4/2/6)").unwrap(),
+            "/body/DocFragment[3]/body/div/p[2]"
+        );
+        assert_eq!(
+            cfi_to_xpointer(&html, "epubcfi(/6/6[c3]!/4[body]/2/4/1:6)").unwrap(),
+            "/body/DocFragment[3]/body/div/p[1]/text().6"
+        );
+        assert_eq!(
+            cfi_to_xpointer(&html, "epubcfi(/6/6!/4/2/6/3:1)").unwrap(),
+            "/body/DocFragment[3]/body/div/p[2]/text()[2].1"
+        );
+        assert_eq!(
+            cfi_to_xpointer(&html, "epubcfi(/6/6!/4/2/6,/2/1:0,/2/1:3)").unwrap(),
+            "/body/DocFragment[3]/body/div/p[2]/em/text().0"
+        );
+        assert!(cfi_to_xpointer(&html, "epubcfi(/6/6!/2/2)").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_both_formats() {
+        let html = html();
+        for xpointer in [
+            "/body/DocFragment[3]/body/div/p[1]/text().6",
+            "/body/DocFragment[3]/body/div/p[2]/text()[2].1",
+            "/body/DocFragment[3]/body/div/h2",
+        ] {
+            let cfi = xpointer_to_cfi(&html, xpointer).unwrap();
+            assert_eq!(cfi_to_xpointer(&html, &cfi).unwrap(), xpointer);
+        }
+    }
+}
@@ -0,0 +1,593 @@
+//! A minimal KOReader sync server, enough for KOReader's "Progress sync"
+//! plugin (and `KoSyncClient`) pointed at `http://<ip>:<port>`. Accounts and
+//! positions live in one JSON file under the app data directory; there is no
+//! history, only each user's latest position per document, which is all the
+//! protocol exposes.
+//!
+//! Plain HTTP/1.1 over a tokio listener, one request per connection, like
+//! the OPDS server, and like it, an address that keeps sending wrong user
+//! keys is locked out for a doubling while (`Lockout`).
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use super::Progress;
+use crate::opds::server::{constant_time_eq, Lockout};
+
+/// Next to the OPDS server's range; KOReader stores the full server URL, so
+/// a stable port keeps it working across restarts.
+pub const FIRST_PORT: u16 = 53340;
+pub const PORT_RANGE: std::ops::RangeInclusive<u16> = FIRST_PORT..=53349;
+
+const KOSYNC_JSON: &str = "application/vnd.koreader.v1+json";
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Called with the username after every accepted progress update.
+pub type OnProgress = Arc<dyn Fn(&str, &Progress) + Send + Sync>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// Username to userkey.
+    users: HashMap<String, String>,
+    /// Username to document to latest position.
+    progress: HashMap<String, HashMap<String, Progress>>,
+}
+
+struct Shared {
+    store: Mutex<Store>,
+    /// `None` keeps everything in memory.
+    path: Option<PathBuf>,
+    allow_registration: bool,
+    on_progress: OnProgress,
+    lockout: Mutex<Lockout>,
+}
+
+impl Shared {
+    fn persist(&self, store: &Store) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = (|| -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            let bytes = serde_json::to_vec(store).map_err(std::io::Error::other)?;
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, path)
+        })();
+        if let Err(e) = result {
+            log::warn!("failed to save kosync server store: {e}");
+        }
+    }
+}
+
+pub struct RunningServer {
+    pub port: u16,
+    pub allow_registration: bool,
+    shared: Arc<Shared>,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+impl RunningServer {
+    pub fn stop(&mut self) {
+        if let Some(tx) = self.stop.take() {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Creates the account, or gives an existing one a new `userkey`.
+    pub fn add_user(&self, username: &str, userkey: &str) -> Result<(), String> {
+        if username.is_empty() || userkey.is_empty() {
+            return Err("username and password are required".into());
+        }
+        let mut store = self.shared.store.lock().unwrap();
+        store
+            .users
+            .insert(username.to_string(), userkey.to_string());
+        self.shared.persist(&store);
+        Ok(())
+    }
+}
+
+/// Serve on the first free port of `PORT_RANGE`. `path` is the store, loaded
+/// now and rewritten on every change.
+pub async fn start(
+    path: Option<PathBuf>,
+    allow_registration: bool,
+    on_progress: OnProgress,
+) -> Result<RunningServer, String> {
+    let mut bound = None;
+    let mut last_err = String::new();
+    for port in PORT_RANGE {
+        match TcpListener::bind(("0.0.0.0", port)).await {
+            Ok(listener) => {
+                bound = Some((listener, port));
+                break;
+            }
+            Err(err) => last_err = err.to_string(),
+        }
+    }
+    let (listener, port) = bound.ok_or(format!(
+        "no free port in {FIRST_PORT}-{}: {last_err}",
+        PORT_RANGE.end()
+    ))?;
+    let store = path
+        .as_ref()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    let shared = Shared {
+        store: Mutex::new(store),
+        path,
+        allow_registration,
+        on_progress,
+        lockout: Mutex::default(),
+    };
+    Ok(serve(listener, port, shared))
+}
+
+fn serve(listener: TcpListener, port: u16, shared: Shared) -> RunningServer {
+    let allow_registration = shared.allow_registration;
+    let shared = Arc::new(shared);
+    let serving = shared.clone();
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut stop_rx => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, peer)) = accepted else { continue };
+                    let shared = serving.clone();
+                    tauri::async_runtime::spawn(async move {
+                        handle(stream, peer.ip(), &shared).await;
+                    });
+                }
+            }
+        }
+    });
+    RunningServer {
+        port,
+        allow_registration,
+        shared,
+        stop: Some(stop_tx),
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    user: Option<String>,
+    key: Option<String>,
+    content_length: usize,
+}
+
+fn parse_head(head: &str) -> Option<Request> {
+    let mut lines = head.lines();
+    let mut parts = lines.next()?.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let path = target.split('?').next().unwrap_or(target);
+    let mut request = Request {
+        method,
+        path: percent_encoding::percent_decode_str(path)
+            .decode_utf8_lossy()
+            .into_owned(),
+        user: None,
+        key: None,
+        content_length: 0,
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "x-auth-user" => request.user = Some(value.to_string()),
+            "x-auth-key" => request.key = Some(value.to_string()),
+            "content-length" => request.content_length = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    Some(request)
+}
+
+/// Status line and JSON body. The error codes are the reference server's,
+/// which KOReader shows to the user.
+type Response = (&'static str, Value);
+
+fn error(status: &'static str, code: u32, message: &str) -> Response {
+    (status, json!({ "code": code, "message": message }))
+}
+
+fn unauthorized() -> Response {
+    error("401 Unauthorized", 2001, "Unauthorized")
+}
+
+fn invalid() -> Response {
+    error("403 Forbidden", 2003, "Invalid request")
+}
+
+fn locked_out() -> Response {
+    error(
+        "429 Too Many Requests",
+        2001,
+        "Too many failed attempts, try again later.",
+    )
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// The username if the request's credentials match an account. The key is
+/// compared in constant time; a wrong one counts against `ip`, and a
+/// locked-out address is refused even with the right one.
+fn authenticate<'a>(
+    shared: &Shared,
+    store: &Store,
+    request: &'a Request,
+    ip: IpAddr,
+) -> Result<&'a str, Response> {
+    let now = Instant::now();
+    let mut lockout = shared.lockout.lock().unwrap();
+    if lockout.locked(ip, now) {
+        return Err(locked_out());
+    }
+    let (Some(user), Some(key)) = (request.user.as_deref(), request.key.as_deref()) else {
+        return Err(unauthorized());
+    };
+    let valid = !key.is_empty()
+        && store
+            .users
+            .get(user)
+            .is_some_and(|stored| constant_time_eq(stored.as_bytes(), key.as_bytes()));
+    if valid {
+        lockout.succeeded(ip);
+        return Ok(user);
+    }
+    lockout.failed(ip, now);
+    if lockout.locked(ip, now) {
+        log::warn!("kosync server: locked out {ip} after repeated wrong keys");
+    }
+    Err(unauthorized())
+}
+
+fn route(shared: &Shared, request: &Request, ip: IpAddr, body: &[u8]) -> Response {
+    let mut store = shared.store.lock().unwrap();
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["healthcheck"]) => ("200 OK", json!({ "state": "OK" })),
+        ("POST", ["users", "create"]) => {
+            if !shared.allow_registration {
+                return error("403 Forbidden", 2005, "User registration is disabled.");
+            }
+            let Ok(account) = serde_json::from_slice::<Value>(body) else {
+                return invalid();
+            };
+            let (Some(username), Some(password)) = (
+                account["username"].as_str().filter(|u| !u.is_empty()),
+                account["password"].as_str().filter(|p| !p.is_empty()),
+            ) else {
+                return invalid();
+            };
+            if store.users.contains_key(username) {
+                return error(
+                    "402 Payment Required",
+                    2002,
+                    "Username is already registered.",
+                );
+            }
+            store
+                .users
+                .insert(username.to_string(), password.to_string());
+            shared.persist(&store);
+            ("201 Created", json!({ "username": username }))
+        }
+        ("GET", ["users", "auth"]) => match authenticate(shared, &store, request, ip) {
+            Ok(_) => ("200 OK", json!({ "authorized": "OK" })),
+            Err(response) => response,
+        },
+        ("PUT", ["syncs", "progress"]) => {
+            let user = match authenticate(shared, &store, request, ip) {
+                Ok(user) => user,
+                Err(response) => return response,
+            };
+            let Ok(mut progress) = serde_json::from_slice::<Progress>(body) else {
+                return invalid();
+            };
+            if progress.document.is_empty() {
+                return invalid();
+            }
+            let timestamp = now();
+            progress.timestamp = Some(timestamp);
+            let document = progress.document.clone();
+            (shared.on_progress)(user, &progress);
+            store
+                .progress
+                .entry(user.to_string())
+                .or_default()
+                .insert(document.clone(), progress);
+            shared.persist(&store);
+            (
+                "200 OK",
+                json!({ "document": document, "timestamp": timestamp }),
+            )
+        }
+        ("GET", ["syncs", "progress", document]) => {
+            let user = match authenticate(shared, &store, request, ip) {
+                Ok(user) => user,
+                Err(response) => return response,
+            };
+            let progress = store
+                .progress
+                .get(user)
+                .and_then(|documents| documents.get(*document));
+            match progress {
+                Some(progress) => ("200 OK", json!(progress)),
+                None => ("200 OK", json!({})),
+            }
+        }
+        _ => ("404 Not Found", json!({})),
+    }
+}
+
+/// The head and as much of the body as `Content-Length` announces.
+async fn read_request(stream: &mut TcpStream) -> Option<(Request, Vec<u8>)> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    let (request, body_start) = loop {
+        let n = tokio::time::timeout(SOCKET_TIMEOUT, stream.read(&mut chunk))
+            .await
+            .ok()?
+            .ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = std::str::from_utf8(&buf[..end]).ok()?;
+            break (parse_head(head)?, end + 4);
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return None;
+        }
+    };
+    if request.content_length > MAX_BODY_BYTES {
+        return None;
+    }
+    let mut body = buf.split_off(body_start);
+    while body.len() < request.content_length {
+        let n = tokio::time::timeout(SOCKET_TIMEOUT, stream.read(&mut chunk))
+            .await
+            .ok()?
+            .ok()?;
+        if n == 0 {
+            return None;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(request.content_length);
+    Some((request, body))
+}
+
+async fn respond(stream: &mut TcpStream, (status, body): Response) -> std::io::Result<()> {
+    let body = body.to_string();
+    let head = format!(
+        "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: {KOSYNC_JSON}\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.flush().await
+}
+
+async fn handle(mut stream: TcpStream, ip: IpAddr, shared: &Shared) {
+    let Some((request, body)) = read_request(&mut stream).await else {
+        return;
+    };
+    let response = route(shared, &request, ip, &body);
+    if let Err(err) = respond(&mut stream, response).await {
+        log::debug!(
+            "kosync server: {} {} failed: {err}",
+            request.method,
+            request.path
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{userkey, Error, KoSyncAccount, KoSyncClient};
+    use super::*;
+
+    const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 1, 20));
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn state(allow_registration: bool) -> Shared {
+        Shared {
+            store: Mutex::new(Store::default()),
+            path: None,
+            allow_registration,
+            on_progress: Arc::new(|_, _| {}),
+            lockout: Mutex::default(),
+        }
+    }
+
+    fn request(method: &str, path: &str, user: &str, key: &str) -> Request {
+        parse_head(&format!(
+            "{method} {path} HTTP/1.1\r\nX-Auth-User: {user}\r\nx-auth-key: {key}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn registers_and_authenticates() {
+        let shared = state(true);
+        let create = request("POST", "/users/create", "", "");
+        let body = br#"{"username":"reader","password":"abc"}"#;
+        assert_eq!(route(&shared, &create, IP, body).0, "201 Created");
+        assert_eq!(route(&shared, &create, IP, body).0, "402 Payment Required");
+
+        let auth = request("GET", "/users/auth", "reader", "abc");
+        assert_eq!(route(&shared, &auth, IP, b"").0, "200 OK");
+        let wrong = request("GET", "/users/auth", "reader", "abd");
+        assert_eq!(route(&shared, &wrong, IP, b"").0, "401 Unauthorized");
+
+        let closed = state(false);
+        assert_eq!(route(&closed, &create, IP, body).0, "403 Forbidden");
+    }
+
+    #[test]
+    fn locks_out_an_address_that_keeps_guessing() {
+        let shared = state(true);
+        let create = request("POST", "/users/create", "", "");
+        route(
+            &shared,
+            &create,
+            IP,
+            br#"{"username":"reader","password":"abc"}"#,
+        );
+        let other: IpAddr = "192.168.1.21".parse().unwrap();
+        let auth = request("GET", "/users/auth", "reader", "abc");
+        let wrong = request("GET", "/users/auth", "reader", "abd");
+
+        let statuses: Vec<_> = (0..7).map(|_| route(&shared, &wrong, IP, b"").0).collect();
+        assert_eq!(statuses[..6], ["401 Unauthorized"; 6]);
+        assert_eq!(statuses[6], "429 Too Many Requests");
+        // Even the right key waits out the lockout; other addresses don't.
+        assert_eq!(route(&shared, &auth, IP, b"").0, "429 Too Many Requests");
+        assert_eq!(route(&shared, &auth, other, b"").0, "200 OK");
+        // Requests without credentials are refused but not counted.
+        let anonymous = parse_head("GET /users/auth HTTP/1.1").unwrap();
+        for _ in 0..10 {
+            assert_eq!(route(&shared, &anonymous, other, b"").0, "401 Unauthorized");
+        }
+        assert_eq!(route(&shared, &auth, other, b"").0, "200 OK");
+    }
+
+    #[test]
+    fn keeps_the_latest_position_per_user() {
+        let shared = state(true);
+        for (user, key) in [("a", "1"), ("b", "2")] {
+            let body = json!({ "username": user, "password": key }).to_string();
+            route(
+                &shared,
+                &request("POST", "/users/create", "", ""),
+                IP,
+                body.as_bytes(),
+            );
+        }
+        let put = request("PUT", "/syncs/progress", "a", "1");
+        for percentage in [0.1, 0.2] {
+            let body = json!({
+                "document": "d41d8cd98f00b204e9800998ecf8427e",
+                "progress": "/body/DocFragment[3]/body/p[2]/text().5",
+                "percentage": percentage,
+                "device": "Kobo",
+                "device_id": "K1",
+            });
+            let (status, _) = route(&shared, &put, IP, body.to_string().as_bytes());
+            assert_eq!(status, "200 OK");
+        }
+
+        let path = "/syncs/progress/d41d8cd98f00b204e9800998ecf8427e";
+        let (_, own) = route(&shared, &request("GET", path, "a", "1"), IP, b"");
+        assert_eq!(own["percentage"], 0.2);
+        assert_eq!(own["device_id"], "K1");
+        assert!(own["timestamp"].is_i64());
+        let (_, other) = route(&shared, &request("GET", path, "b", "2"), IP, b"");
+        assert_eq!(other, json!({}));
+    }
+
+    #[test]
+    fn serves_the_client() {
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let recorded = updates.clone();
+        let on_progress: OnProgress = Arc::new(move |user: &str, progress: &Progress| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((user.to_string(), progress.percentage));
+        });
+        block_on(async move {
+            let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let mut server = serve(
+                listener,
+                port,
+                Shared {
+                    on_progress,
+                    ..state(true)
+                },
+            );
+            let account = KoSyncAccount {
+                server_url: format!("http://127.0.0.1:{port}/"),
+                username: "reader".into(),
+                userkey: userkey("secret"),
+                device_name: "Readest".into(),
+                device_id: "R1".into(),
+            };
+            let client = KoSyncClient::new(reqwest::Client::new(), account.clone());
+            assert!(matches!(client.authorize().await, Err(Error::Unauthorized)));
+            client.register().await.unwrap();
+            assert!(matches!(client.register().await, Err(Error::UserExists)));
+            client.authorize().await.unwrap();
+
+            assert_eq!(client.get_progress("abc").await.unwrap(), None);
+            client
+                .update_progress("abc", "/body/DocFragment[2]/body/p", 0.5)
+                .await
+                .unwrap();
+            let progress = client.get_progress("abc").await.unwrap().unwrap();
+            assert_eq!(progress.progress, "/body/DocFragment[2]/body/p");
+            assert_eq!(progress.device, "Readest");
+            assert_eq!(progress.percentage, 0.5);
+            server.stop();
+        });
+        assert_eq!(*updates.lock().unwrap(), vec![("reader".to_string(), 0.5)]);
+    }
+
+    #[test]
+    fn owner_adds_accounts_while_registration_is_off() {
+        block_on(async {
+            let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let mut server = serve(listener, port, state(false));
+            let account = KoSyncAccount {
+                server_url: format!("http://127.0.0.1:{port}/"),
+                username: "reader".into(),
+                userkey: userkey("secret"),
+                device_name: "Kobo".into(),
+                device_id: "K1".into(),
+            };
+            let client = KoSyncClient::new(reqwest::Client::new(), account);
+            assert!(client.register().await.is_err());
+            assert!(server.add_user("", "key").is_err());
+            server.add_user("reader", &userkey("secret")).unwrap();
+            client.authorize().await.unwrap();
+            server.stop();
+        });
+    }
+}
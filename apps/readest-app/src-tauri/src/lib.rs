@@ -22,23 +22,38 @@ use tauri_plugin_fs::FsExt;
 
 #[cfg(desktop)]
 use tauri::{Listener, Url};
+mod annotation_export;
+mod bandwidth;
+mod chunked_upload;
 mod clip_url;
+mod comic_parser;
 mod cover_thumbnail;
 mod dir_scanner;
 #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 mod discord_rpc;
 mod epub_parser;
+mod epub_validator;
+mod fb2_parser;
+mod fulltext;
+mod highlight_import;
+mod http_client;
+mod kosync;
+mod library_watcher;
 mod localsend;
 #[cfg(target_os = "macos")]
 mod macos;
 mod mobi_parser;
 mod nightly_update;
+mod opds;
+mod opf_metadata;
 mod parser_common;
 mod range_file;
 mod sentry_config;
 #[cfg(desktop)]
 mod spawn_fresh_browser;
 mod transfer_file;
+mod transfer_manager;
+mod webdav;
 #[cfg(desktop)]
 mod window_state;
 #[cfg(target_os = "windows")]
@@ -407,6 +422,39 @@ pub fn run() {
             start_server,
             download_file,
             upload_file,
+            chunked_upload::upload_file_chunked,
+            chunked_upload::upload_resume_info,
+            transfer_manager::transfer_enqueue,
+            transfer_manager::transfer_pause,
+            transfer_manager::transfer_resume,
+            transfer_manager::transfer_cancel,
+            transfer_manager::transfer_list,
+            transfer_manager::transfer_set_concurrency,
+            bandwidth::bandwidth_set_policy,
+            bandwidth::bandwidth_status,
+            bandwidth::network_set_metered,
+            http_client::set_network_config,
+            http_client::get_network_config,
+            opds::commands::opds_fetch,
+            opds::commands::opds_search,
+            opds::commands::opds_server_start,
+            opds::commands::opds_server_stop,
+            opds::commands::opds_server_status,
+            opds::commands::opds_server_set_library,
+            webdav::webdav_sync_book_config,
+            webdav::webdav_upload_book_file,
+            kosync::commands::kosync_connect,
+            kosync::commands::kosync_pull,
+            kosync::commands::kosync_push,
+            kosync::commands::kosync_xpointer_to_cfi,
+            kosync::commands::kosync_cfi_to_xpointer,
+            kosync::commands::kosync_server_start,
+            kosync::commands::kosync_server_add_user,
+            kosync::commands::kosync_server_stop,
+            kosync::commands::kosync_server_status,
+            annotation_export::export_annotations,
+            highlight_import::import_kindle_clippings,
+            highlight_import::import_koreader_sidecars,
             get_environment_variable,
             get_executable_dir,
             set_webview_info,
@@ -415,11 +463,22 @@ pub fn run() {
             allow_paths_in_scopes,
             cover_thumbnail::optimize_cover_thumbnails,
             dir_scanner::read_dir,
+            dir_scanner::read_dir_dedup,
+            library_watcher::library_watch_start,
+            library_watcher::library_watch_stop,
+            library_watcher::library_watch_list,
             epub_parser::parse_epub_metadata,
             epub_parser::extract_epub_cover_full,
             epub_parser::parse_epub_full,
+            epub_validator::epub_validate,
+            fulltext::commands::fulltext_index_book,
+            fulltext::commands::fulltext_remove_book,
+            fulltext::commands::fulltext_list_books,
+            fulltext::commands::fulltext_search,
             mobi_parser::parse_mobi_metadata,
             mobi_parser::extract_mobi_cover_full,
+            fb2_parser::parse_fb2_metadata,
+            comic_parser::parse_comic_archive,
             #[cfg(target_os = "macos")]
             macos::safari_auth::auth_with_safari,
             #[cfg(target_os = "macos")]
@@ -441,7 +500,12 @@ pub fn run() {
             localsend::commands::localsend_respond,
             localsend::commands::localsend_cancel_receive,
             localsend::commands::localsend_send_files,
+            localsend::commands::localsend_send_folder,
             localsend::commands::localsend_cancel_send,
+            localsend::commands::localsend_list_trusted,
+            localsend::commands::localsend_set_trusted,
+            localsend::commands::localsend_remove_trusted,
+            localsend::commands::localsend_take_imported,
             #[cfg(desktop)]
             spawn_fresh_browser::spawn_fresh_browser,
             nightly_update::verify_update_signature,
@@ -543,6 +607,12 @@ pub fn run() {
                 app.manage(discord_client);
             }
             app.manage(localsend::LocalSendState::default());
+            app.manage(opds::OpdsServerState::default());
+            app.manage(kosync::KoSyncServerState::default());
+            app.manage(fulltext::FulltextState::default());
+            app.manage(library_watcher::LibraryWatchState::default());
+            app.manage(transfer_manager::TransferManagerState::default());
+            transfer_manager::restore(app.handle());
 
             #[cfg(desktop)]
             {
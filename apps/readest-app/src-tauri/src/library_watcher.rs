@@ -0,0 +1,623 @@
+//! Incremental library-folder watcher.
+//!
+//! `dir_scanner::read_dir` walks and stats a whole folder on every
+//! focus-triggered scan (issue #5494). `library_watch_start` replaces that
+//! polling with a persistent OS watcher (inotify on Linux/Android,
+//! FSEvents on macOS, ReadDirectoryChangesW on Windows, kqueue on iOS):
+//!
+//!   - raw notify events are debounced ([`DEBOUNCE`], capped at
+//!     [`MAX_BATCH_DELAY`] under a constant stream of writes), reduced to
+//!     the set of affected directories, and only those are re-listed;
+//!   - differences against the in-memory snapshot are emitted as
+//!     `library:files-added` / `library:files-removed` /
+//!     `library:files-changed` with size and mtime;
+//!   - the snapshot (directory mtimes + file size/mtime) is persisted under
+//!     `<app_data_dir>/library-watch/`, so the first rescan after a launch
+//!     only re-lists directories whose mtime moved. Adding, removing or
+//!     renaming an entry bumps its directory's mtime on every platform we
+//!     ship; rewriting a file in place does not, which is what the live
+//!     watcher is for.
+
+use md5::{Digest, Md5};
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+
+use crate::dir_scanner::{ensure_scan_allowed, matches_extensions};
+
+pub const EV_FILES_ADDED: &str = "library:files-added";
+pub const EV_FILES_REMOVED: &str = "library:files-removed";
+pub const EV_FILES_CHANGED: &str = "library:files-changed";
+
+/// Quiet period after the last raw event before a batch is processed.
+/// Copying a book produces a burst of create/modify events; one rescan per
+/// burst is enough.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+/// Upper bound on how long a batch may keep growing while events keep
+/// arriving (e.g. a large folder being copied in).
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(5);
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFile {
+    pub path: String,
+    pub size: u64,
+    /// Modification time, milliseconds since the Unix epoch.
+    pub mtime: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryChanges {
+    pub added: Vec<WatchedFile>,
+    pub removed: Vec<String>,
+    pub changed: Vec<WatchedFile>,
+}
+
+impl LibraryChanges {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesPayload {
+    /// The watched folder, as passed to `library_watch_start`.
+    pub root: String,
+    pub files: Vec<WatchedFile>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedPayload {
+    pub root: String,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    size: u64,
+    mtime: u64,
+}
+
+/// What we last saw under one watched root.
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    root: PathBuf,
+    recursive: bool,
+    extensions: Vec<String>,
+    /// Every directory under the root (root included) -> its mtime.
+    dirs: BTreeMap<PathBuf, u64>,
+    /// Every matching file -> size + mtime.
+    files: BTreeMap<PathBuf, Stamp>,
+}
+
+impl Snapshot {
+    fn new(root: PathBuf, recursive: bool, extensions: Vec<String>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            root,
+            recursive,
+            extensions,
+            dirs: BTreeMap::new(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Whether a persisted snapshot can seed a watch with these settings.
+    fn is_compatible(&self, root: &Path, recursive: bool, extensions: &[String]) -> bool {
+        self.version == SNAPSHOT_VERSION
+            && self.root == root
+            && self.recursive == recursive
+            && self.extensions == extensions
+    }
+
+    /// Startup pass: stat every known directory and re-list only those whose
+    /// mtime changed (or that disappeared). An empty snapshot lists the root.
+    fn refresh_stale(&mut self) -> LibraryChanges {
+        let stale: Vec<PathBuf> = if self.dirs.is_empty() {
+            vec![self.root.clone()]
+        } else {
+            self.dirs
+                .iter()
+                .filter(|(dir, mtime)| dir_mtime(dir) != Some(**mtime))
+                .map(|(dir, _)| dir.clone())
+                .collect()
+        };
+        self.rescan(stale)
+    }
+
+    /// Re-list `dirs` (non-recursively, plus any directories that are new
+    /// to the snapshot) and return what changed.
+    fn rescan(&mut self, dirs: impl IntoIterator<Item = PathBuf>) -> LibraryChanges {
+        let mut changes = LibraryChanges::default();
+        let mut queue: Vec<PathBuf> = dirs.into_iter().collect();
+        let mut seen = BTreeSet::new();
+        while let Some(dir) = queue.pop() {
+            if seen.insert(dir.clone()) {
+                self.rescan_dir(&dir, &mut changes, &mut queue);
+            }
+        }
+        changes
+    }
+
+    fn rescan_dir(&mut self, dir: &Path, changes: &mut LibraryChanges, queue: &mut Vec<PathBuf>) {
+        if !self.covers_dir(dir) {
+            return;
+        }
+        let Some(mtime) = dir_mtime(dir) else {
+            self.forget_subtree(dir, changes);
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            log::warn!("library watcher: cannot list {}", dir.display());
+            return;
+        };
+        self.dirs.insert(dir.to_path_buf(), mtime);
+
+        let mut present_files = BTreeSet::new();
+        let mut present_dirs = BTreeSet::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                if self.recursive {
+                    if !self.dirs.contains_key(&path) {
+                        queue.push(path.clone());
+                    }
+                    present_dirs.insert(path);
+                }
+            } else if meta.is_file() && matches_extensions(&path, &self.extensions) {
+                let stamp = Stamp {
+                    size: meta.len(),
+                    mtime: to_millis(meta.modified().ok()),
+                };
+                match self.files.insert(path.clone(), stamp) {
+                    None => changes.added.push(watched(&path, stamp)),
+                    Some(old) if old != stamp => changes.changed.push(watched(&path, stamp)),
+                    Some(_) => {}
+                }
+                present_files.insert(path);
+            }
+        }
+
+        let gone_files: Vec<PathBuf> = children(&self.files, dir)
+            .filter(|p| !present_files.contains(*p))
+            .cloned()
+            .collect();
+        for path in gone_files {
+            self.files.remove(&path);
+            changes.removed.push(path.to_string_lossy().into_owned());
+        }
+        let gone_dirs: Vec<PathBuf> = children(&self.dirs, dir)
+            .filter(|p| !present_dirs.contains(*p))
+            .cloned()
+            .collect();
+        for path in gone_dirs {
+            self.forget_subtree(&path, changes);
+        }
+    }
+
+    fn forget_subtree(&mut self, dir: &Path, changes: &mut LibraryChanges) {
+        self.dirs.retain(|d, _| !d.starts_with(dir));
+        let gone: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|p| p.starts_with(dir))
+            .cloned()
+            .collect();
+        for path in gone {
+            self.files.remove(&path);
+            changes.removed.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    fn covers_dir(&self, dir: &Path) -> bool {
+        if self.recursive {
+            dir.starts_with(&self.root)
+        } else {
+            dir == self.root
+        }
+    }
+
+    /// Directory to re-list for a raw event on `path`: the path itself when
+    /// it is (or was) a known directory, its parent otherwise.
+    fn affected_dir(&self, path: &Path) -> Option<PathBuf> {
+        let dir = if path.is_dir() || self.dirs.contains_key(path) {
+            path
+        } else {
+            path.parent()?
+        };
+        self.covers_dir(dir).then(|| dir.to_path_buf())
+    }
+}
+
+/// Direct children of `dir` among the keys of `map`.
+fn children<'a, V>(
+    map: &'a BTreeMap<PathBuf, V>,
+    dir: &'a Path,
+) -> impl Iterator<Item = &'a PathBuf> {
+    map.range(dir.to_path_buf()..)
+        .skip_while(move |(p, _)| p.as_path() == dir)
+        .take_while(move |(p, _)| p.starts_with(dir))
+        .map(|(p, _)| p)
+        .filter(move |p| p.parent() == Some(dir))
+}
+
+fn dir_mtime(dir: &Path) -> Option<u64> {
+    let meta = std::fs::metadata(dir).ok()?;
+    meta.is_dir().then(|| to_millis(meta.modified().ok()))
+}
+
+fn to_millis(time: Option<std::time::SystemTime>) -> u64 {
+    time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn watched(path: &Path, stamp: Stamp) -> WatchedFile {
+    WatchedFile {
+        path: path.to_string_lossy().into_owned(),
+        size: stamp.size,
+        mtime: stamp.mtime,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Persistence
+// ---------------------------------------------------------------------------
+
+fn snapshot_path(app: &AppHandle, root: &Path) -> Result<PathBuf, String> {
+    let digest = Md5::digest(root.to_string_lossy().as_bytes());
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("library-watch")
+        .join(format!("{digest:x}.json")))
+}
+
+fn load_snapshot(path: &Path) -> Option<Snapshot> {
+    let bytes = std::fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn save_snapshot(path: &Path, snapshot: &Snapshot) {
+    let result = (|| -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(
+            &tmp,
+            serde_json::to_vec(snapshot).map_err(std::io::Error::other)?,
+        )?;
+        std::fs::rename(&tmp, path)
+    })();
+    if let Err(e) = result {
+        log::warn!("library watcher: cannot save {}: {e}", path.display());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Service
+// ---------------------------------------------------------------------------
+
+struct ActiveWatch {
+    // Dropping the watcher drops its event sender, which ends the worker
+    // thread's receive loop.
+    _watcher: notify::RecommendedWatcher,
+}
+
+/// Tauri managed state: active watches keyed by root path.
+#[derive(Default)]
+pub struct LibraryWatchState(Mutex<HashMap<String, ActiveWatch>>);
+
+/// Start watching `path` (no-op if it is already watched) and return what
+/// changed since the previous session's snapshot. Those startup changes are
+/// returned rather than emitted; later ones arrive as `library:*` events.
+#[tauri::command]
+pub async fn library_watch_start(
+    app: AppHandle,
+    path: String,
+    recursive: bool,
+    extensions: Vec<String>,
+) -> Result<LibraryChanges, String> {
+    ensure_scan_allowed(&app, Path::new(&path))?;
+    {
+        let state = app.state::<LibraryWatchState>();
+        let watches = state.0.lock().map_err(|e| e.to_string())?;
+        if watches.contains_key(&path) {
+            return Ok(LibraryChanges::default());
+        }
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || start_sync(handle, path, recursive, extensions))
+        .await
+        .map_err(|e| format!("join error: {e}"))?
+}
+
+#[tauri::command]
+pub async fn library_watch_stop(
+    state: State<'_, LibraryWatchState>,
+    path: String,
+) -> Result<bool, String> {
+    let mut watches = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(watches.remove(&path).is_some())
+}
+
+#[tauri::command]
+pub async fn library_watch_list(
+    state: State<'_, LibraryWatchState>,
+) -> Result<Vec<String>, String> {
+    let watches = state.0.lock().map_err(|e| e.to_string())?;
+    let mut roots: Vec<String> = watches.keys().cloned().collect();
+    roots.sort();
+    Ok(roots)
+}
+
+fn start_sync(
+    app: AppHandle,
+    path: String,
+    recursive: bool,
+    extensions: Vec<String>,
+) -> Result<LibraryChanges, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Failed to read directory: {path}"));
+    }
+    let extensions: Vec<String> = extensions.iter().map(|e| e.to_lowercase()).collect();
+    let snapshot_file = snapshot_path(&app, &root)?;
+
+    // Start the OS watcher before the catch-up scan so nothing that happens
+    // during the scan is lost; duplicates just rescan an up-to-date dir.
+    let (tx, rx) = mpsc::channel::<PathBuf>();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+            Err(e) => log::warn!("library watcher: {e}"),
+        })
+        .map_err(|e| format!("watch failed: {e}"))?;
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&root, mode)
+        .map_err(|e| format!("watch failed: {e}"))?;
+
+    let mut snapshot = load_snapshot(&snapshot_file)
+        .filter(|s| s.is_compatible(&root, recursive, &extensions))
+        .unwrap_or_else(|| Snapshot::new(root.clone(), recursive, extensions));
+    let changes = if snapshot.dirs.is_empty() {
+        full_scan(&mut snapshot)
+    } else {
+        snapshot.refresh_stale()
+    };
+    save_snapshot(&snapshot_file, &snapshot);
+
+    {
+        let state = app.state::<LibraryWatchState>();
+        let mut watches = state.0.lock().map_err(|e| e.to_string())?;
+        if watches.contains_key(&path) {
+            // Lost a race with a concurrent start for the same root.
+            return Ok(LibraryChanges::default());
+        }
+        watches.insert(path.clone(), ActiveWatch { _watcher: watcher });
+    }
+
+    std::thread::Builder::new()
+        .name("library-watcher".into())
+        .spawn(move || run_worker(app, path, snapshot, snapshot_file, rx))
+        .map_err(|e| format!("watch failed: {e}"))?;
+    Ok(changes)
+}
+
+/// First scan of a root: a single WalkDir pass instead of one `read_dir`
+/// per directory through the queue.
+fn full_scan(snapshot: &mut Snapshot) -> LibraryChanges {
+    let mut changes = LibraryChanges::default();
+    let max_depth = if snapshot.recursive { usize::MAX } else { 1 };
+    for entry in WalkDir::new(&snapshot.root)
+        .max_depth(max_depth)
+        .into_iter()
+    {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::warn!("RUST: Skipping file due to error: {}", e);
+                continue;
+            }
+        };
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let path = entry.into_path();
+        if meta.is_dir() {
+            snapshot.dirs.insert(path, to_millis(meta.modified().ok()));
+        } else if meta.is_file() && matches_extensions(&path, &snapshot.extensions) {
+            let stamp = Stamp {
+                size: meta.len(),
+                mtime: to_millis(meta.modified().ok()),
+            };
+            changes.added.push(watched(&path, stamp));
+            snapshot.files.insert(path, stamp);
+        }
+    }
+    changes
+}
+
+fn run_worker(
+    app: AppHandle,
+    root: String,
+    mut snapshot: Snapshot,
+    snapshot_file: PathBuf,
+    rx: mpsc::Receiver<PathBuf>,
+) {
+    // Blocks until the next raw event; Err means the watch was stopped.
+    while let Ok(first) = rx.recv() {
+        let mut dirs = BTreeSet::new();
+        dirs.extend(snapshot.affected_dir(&first));
+        let started = Instant::now();
+        loop {
+            let remaining = MAX_BATCH_DELAY.saturating_sub(started.elapsed());
+            match rx.recv_timeout(DEBOUNCE.min(remaining)) {
+                Ok(path) => dirs.extend(snapshot.affected_dir(&path)),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            if remaining.is_zero() {
+                break;
+            }
+        }
+
+        let changes = snapshot.rescan(dirs);
+        if changes.is_empty() {
+            continue;
+        }
+        save_snapshot(&snapshot_file, &snapshot);
+        emit_changes(&app, &root, changes);
+    }
+}
+
+fn emit_changes(app: &AppHandle, root: &str, changes: LibraryChanges) {
+    if !changes.added.is_empty() {
+        let _ = app.emit(
+            EV_FILES_ADDED,
+            FilesPayload {
+                root: root.to_string(),
+                files: changes.added,
+            },
+        );
+    }
+    if !changes.removed.is_empty() {
+        let _ = app.emit(
+            EV_FILES_REMOVED,
+            RemovedPayload {
+                root: root.to_string(),
+                paths: changes.removed,
+            },
+        );
+    }
+    if !changes.changed.is_empty() {
+        let _ = app.emit(
+            EV_FILES_CHANGED,
+            FilesPayload {
+                root: root.to_string(),
+                files: changes.changed,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("readest-watch-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn names(files: &[WatchedFile]) -> Vec<String> {
+        let mut out: Vec<String> = files
+            .iter()
+            .map(|f| {
+                Path::new(&f.path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn rescan_reports_added_removed_and_changed_files() {
+        let root = temp_root("rescan");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.epub"), b"a").unwrap();
+        std::fs::write(root.join("skip.txt"), b"x").unwrap();
+        std::fs::write(root.join("sub/b.EPUB"), b"b").unwrap();
+
+        let mut snapshot = Snapshot::new(root.clone(), true, vec!["epub".into()]);
+        let initial = full_scan(&mut snapshot);
+        assert_eq!(names(&initial.added), ["a.epub", "b.EPUB"]);
+
+        std::fs::write(root.join("a.epub"), b"aaaa").unwrap();
+        std::fs::remove_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join("new/deeper")).unwrap();
+        std::fs::write(root.join("new/deeper/c.epub"), b"c").unwrap();
+
+        let changes = snapshot.rescan([root.clone()]);
+        std::fs::remove_dir_all(&root).ok();
+        assert_eq!(names(&changes.added), ["c.epub"]);
+        assert_eq!(names(&changes.changed), ["a.epub"]);
+        assert_eq!(changes.changed[0].size, 4);
+        assert_eq!(changes.removed.len(), 1);
+        assert!(changes.removed[0].ends_with("b.EPUB"));
+    }
+
+    #[test]
+    fn refresh_stale_only_relists_changed_directories() {
+        let root = temp_root("stale");
+        std::fs::create_dir_all(root.join("quiet")).unwrap();
+        std::fs::write(root.join("quiet/q.epub"), b"q").unwrap();
+
+        let mut snapshot = Snapshot::new(root.clone(), true, vec!["epub".into()]);
+        full_scan(&mut snapshot);
+        // Pretend the root changed while the app was closed, but `quiet/`
+        // did not. Rewriting q.epub in place leaves its directory's mtime
+        // alone, so the stale pass must not pick it up...
+        snapshot.dirs.insert(root.clone(), 0);
+        std::fs::write(root.join("quiet/q.epub"), b"qqqq").unwrap();
+        std::fs::write(root.join("fresh.epub"), b"f").unwrap();
+
+        let changes = snapshot.refresh_stale();
+        std::fs::remove_dir_all(&root).ok();
+        // ...while the new file in the stale root is found.
+        assert_eq!(names(&changes.added), ["fresh.epub"]);
+        assert!(changes.changed.is_empty());
+        assert!(changes.removed.is_empty());
+    }
+
+    #[test]
+    fn non_recursive_watch_ignores_subdirectories() {
+        let root = temp_root("flat");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/x.epub"), b"x").unwrap();
+        std::fs::write(root.join("y.epub"), b"y").unwrap();
+
+        let mut snapshot = Snapshot::new(root.clone(), false, Vec::new());
+        let initial = full_scan(&mut snapshot);
+        assert_eq!(names(&initial.added), ["y.epub"]);
+        assert_eq!(snapshot.affected_dir(&root.join("sub/x.epub")), None);
+        assert_eq!(
+            snapshot.affected_dir(&root.join("y.epub")),
+            Some(root.clone())
+        );
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
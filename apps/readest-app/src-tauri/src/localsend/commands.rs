@@ -1,10 +1,12 @@
 use super::events::*;
+use super::inbox;
 use super::service::{self, RunningService};
+use super::trust::{self, TrustList, TrustedDevice};
 use super::LocalSendState;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use tauri::{AppHandle, Emitter, Runtime, State};
 
-fn local_ips() -> Vec<String> {
+pub(crate) fn local_ips() -> Vec<String> {
     if_addrs::get_if_addrs()
         .unwrap_or_default()
         .iter()
@@ -32,6 +34,9 @@ fn status_of(service: &RunningService) -> LocalSendStatus {
         device_model: service.identity.device_model.clone(),
         local_ips: local_ips(),
         multicast_error: service.multicast_error.clone(),
+        pin_protected: service.options.pin().is_some(),
+        web_upload: service.options.web_upload,
+        receive_only: service.options.receive_only,
     }
 }
 
@@ -44,6 +49,9 @@ fn stopped_status() -> LocalSendStatus {
         device_model: String::new(),
         local_ips: Vec::new(),
         multicast_error: None,
+        pin_protected: false,
+        web_upload: false,
+        receive_only: false,
     }
 }
 
@@ -53,12 +61,14 @@ pub async fn localsend_start<R: Runtime>(
     state: State<'_, LocalSendState>,
     alias: String,
     device_model: String,
+    options: Option<LocalSendOptions>,
 ) -> Result<LocalSendStatus, String> {
     let mut guard = state.0.lock().await;
     if let Some(service) = guard.as_ref() {
         return Ok(status_of(service));
     }
-    match service::start(app.clone(), alias, device_model).await {
+    let options = options.unwrap_or_default();
+    match service::start(app.clone(), alias, device_model, options).await {
         Ok(service) => {
             let status = status_of(&service);
             let _ = app.emit(
@@ -135,6 +145,9 @@ pub async fn localsend_announce(
         let Some(service) = guard.as_ref() else {
             return Ok(());
         };
+        if service.options.receive_only {
+            return Ok(());
+        }
         service.discovery.clone()
     };
     discovery.announce().await;
@@ -188,36 +201,14 @@ pub async fn localsend_respond<R: Runtime>(
         let _ = pending.decision_tx.send(PrepareUploadDecisionV2::Decline);
         return Ok(true);
     }
-    let accepted: HashMap<String, localsend::model::transfer::FileDto> = pending
-        .files
-        .iter()
-        .filter(|(id, _)| ids.contains(*id))
-        .map(|(id, f)| (id.clone(), f.clone()))
-        .collect();
-    let bytes_total: u64 = accepted.values().map(|f| f.size).sum();
-    if pending
-        .decision_tx
-        .send(PrepareUploadDecisionV2::Accept(ids))
-        .is_err()
-    {
-        // The request already ended on the wire.
-        return Ok(false);
-    }
-    service.receiving.lock().unwrap().insert(
-        session_id.clone(),
-        service::ReceiveSession {
-            sender: pending.sender,
-            files: accepted,
-            bytes_total,
-            finished_files: 0,
-            failed_files: 0,
-            finalized_bytes: 0,
-            in_progress: HashMap::new(),
-            ended: None,
-        },
-    );
-    service::spawn_receive_progress_ticker(app, service.receiving.clone(), session_id);
-    Ok(true)
+    Ok(service::accept_pending(
+        &app,
+        &service.receiving,
+        service.inbox.clone(),
+        session_id,
+        pending,
+        ids,
+    ))
 }
 
 #[tauri::command]
@@ -269,21 +260,61 @@ pub async fn localsend_cancel_receive(
     Ok(())
 }
 
+/// `fileName`s may carry `/`-separated folders (a shelf sent as
+/// "Shelf/Title.epub"); the receiver recreates them under its inbox.
 #[tauri::command]
 pub async fn localsend_send_files<R: Runtime>(
     app: AppHandle<R>,
     state: State<'_, LocalSendState>,
     fingerprint: String,
     files: Vec<SendFileInput>,
+    pin: Option<String>,
 ) -> Result<(), String> {
     let guard = state.0.lock().await;
     let Some(service) = guard.as_ref() else {
         return Err("LocalSend is not running".into());
     };
+    start_send(app, service, &fingerprint, files, pin)
+}
+
+/// Sends everything below `path` as one session, keeping the folder tree.
+#[tauri::command]
+pub async fn localsend_send_folder<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, LocalSendState>,
+    fingerprint: String,
+    path: String,
+    pin: Option<String>,
+) -> Result<(), String> {
+    let root = std::path::PathBuf::from(path);
+    crate::dir_scanner::ensure_scan_allowed(&app, &root)?;
+    // Walking a large folder would otherwise stall the IPC thread.
+    let files = tauri::async_runtime::spawn_blocking(move || service::folder_inputs(&root))
+        .await
+        .map_err(|e| format!("join error: {e}"))??;
+    let guard = state.0.lock().await;
+    let Some(service) = guard.as_ref() else {
+        return Err("LocalSend is not running".into());
+    };
+    start_send(app, service, &fingerprint, files, pin)
+}
+
+/// `pin` answers a receiver that ended the previous attempt with
+/// `pin_required`.
+fn start_send<R: Runtime>(
+    app: AppHandle<R>,
+    service: &RunningService,
+    fingerprint: &str,
+    files: Vec<SendFileInput>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    if service.options.receive_only {
+        return Err("LocalSend is in receive-only mode".into());
+    }
     if service.send_cancel.lock().unwrap().is_some() {
         return Err("another transfer is in progress".into());
     }
-    let Some(device) = service.discovery.device_by_fingerprint(&fingerprint) else {
+    let Some(device) = service.discovery.device_by_fingerprint(fingerprint) else {
         return Err("device is no longer visible".into());
     };
     let mut jobs = Vec::new();
@@ -301,6 +332,7 @@ pub async fn localsend_send_files<R: Runtime>(
                 metadata: None,
             },
             path,
+            sidecar: input.sidecar,
         });
     }
     *service.send_cancel.lock().unwrap() = Some(service::SendCancel {
@@ -315,6 +347,7 @@ pub async fn localsend_send_files<R: Runtime>(
         service.port,
         device,
         jobs,
+        pin.filter(|pin| !pin.is_empty()),
         service.send_cancel.clone(),
     ));
     Ok(())
@@ -331,6 +364,62 @@ pub async fn localsend_cancel_send(state: State<'_, LocalSendState>) -> Result<(
     Ok(())
 }
 
+/// Saves `list` and hands the running service (if any) the new copy.
+async fn store_trust_list<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &State<'_, LocalSendState>,
+    list: TrustList,
+) -> Result<Vec<TrustedDevice>, String> {
+    list.save(&trust::store_path(app)?)
+        .map_err(|e| e.to_string())?;
+    let devices = list.devices().to_vec();
+    if let Some(service) = state.0.lock().await.as_ref() {
+        *service.trusted.lock().unwrap() = list;
+    }
+    Ok(devices)
+}
+
+#[tauri::command]
+pub async fn localsend_list_trusted<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<TrustedDevice>, String> {
+    Ok(TrustList::load(&trust::store_path(&app)?)
+        .devices()
+        .to_vec())
+}
+
+/// Trusts `device`, or changes the policy of an already trusted one.
+#[tauri::command]
+pub async fn localsend_set_trusted<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, LocalSendState>,
+    device: TrustedDevice,
+) -> Result<Vec<TrustedDevice>, String> {
+    let mut list = TrustList::load(&trust::store_path(&app)?);
+    list.upsert(device);
+    store_trust_list(&app, &state, list).await
+}
+
+#[tauri::command]
+pub async fn localsend_remove_trusted<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, LocalSendState>,
+    fingerprint: String,
+) -> Result<Vec<TrustedDevice>, String> {
+    let mut list = TrustList::load(&trust::store_path(&app)?);
+    list.remove(&fingerprint);
+    store_trust_list(&app, &state, list).await
+}
+
+/// Books imported natively while the webview wasn't listening (or missed the
+/// event). Drains the journal: each entry is returned once.
+#[tauri::command]
+pub async fn localsend_take_imported<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<ReceiveImportedPayload>, String> {
+    Ok(inbox::take_journal(&inbox::journal_path(&app)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -7,9 +7,17 @@ use serde::{Deserialize, Serialize};
 pub const EV_SERVER_STATE: &str = "localsend:server-state";
 pub const EV_DEVICES: &str = "localsend:devices";
 pub const EV_RECEIVE_REQUEST: &str = "localsend:receive-request";
+/// A request from a trusted device, accepted without asking; carries the
+/// same payload as `EV_RECEIVE_REQUEST`.
+pub const EV_RECEIVE_AUTO_ACCEPTED: &str = "localsend:receive-auto-accepted";
 pub const EV_RECEIVE_REQUEST_CLOSED: &str = "localsend:receive-request-closed";
 pub const EV_RECEIVE_PROGRESS: &str = "localsend:receive-progress";
 pub const EV_RECEIVE_FILE_DONE: &str = "localsend:receive-file-done";
+/// A Readest sidecar arrived; sent in place of `EV_RECEIVE_FILE_DONE`.
+pub const EV_RECEIVE_SIDECAR: &str = "localsend:receive-sidecar";
+/// A received book was placed in the Books directory by the native import;
+/// sent in place of `EV_RECEIVE_FILE_DONE`.
+pub const EV_RECEIVE_IMPORTED: &str = "localsend:receive-imported";
 pub const EV_RECEIVE_END: &str = "localsend:receive-end";
 pub const EV_SEND_PROGRESS: &str = "localsend:send-progress";
 pub const EV_SEND_END: &str = "localsend:send-end";
@@ -27,6 +35,49 @@ pub struct LocalSendStatus {
     /// "#<last-octet>" device tag from them.
     pub local_ips: Vec<String>,
     pub multicast_error: Option<String>,
+    pub pin_protected: bool,
+    pub web_upload: bool,
+    pub receive_only: bool,
+}
+
+/// `localsend_start` options; every field defaults to the open behavior.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalSendOptions {
+    /// Senders must enter this PIN; blank means none.
+    pub pin: Option<String>,
+    /// Serve the browser upload page. Turning it off also makes the server
+    /// demand a TLS client certificate, which the stable LocalSend app does
+    /// not present: only Readest and certificate-carrying senders get in.
+    pub web_upload: bool,
+    /// Neither announce this device nor track peers, and refuse to send.
+    /// Senders that already know the address (or scan the subnet) can
+    /// still reach the server.
+    pub receive_only: bool,
+    /// The webview's Books directory. When set, received books are imported
+    /// natively into it (see `inbox`) instead of being left in the inbox.
+    pub books_dir: Option<String>,
+}
+
+impl Default for LocalSendOptions {
+    fn default() -> Self {
+        Self {
+            pin: None,
+            web_upload: true,
+            receive_only: false,
+            books_dir: None,
+        }
+    }
+}
+
+impl LocalSendOptions {
+    pub fn pin(&self) -> Option<String> {
+        self.pin
+            .as_deref()
+            .map(str::trim)
+            .filter(|pin| !pin.is_empty())
+            .map(str::to_string)
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -76,6 +127,9 @@ pub struct SenderPayload {
     pub device_model: Option<String>,
     pub device_type: Option<String>,
     pub fingerprint: String,
+    /// The fingerprint is that of the sender's client certificate, the only
+    /// kind a trust entry matches.
+    pub verified: bool,
 }
 
 #[derive(Clone, Serialize)]
@@ -108,10 +162,45 @@ pub struct ReceiveFileDonePayload {
     pub session_id: String,
     pub file_id: String,
     pub file_name: String,
+    /// The library group, for a book from a folder send.
+    pub group_name: Option<String>,
     pub path: Option<String>,
     pub error: Option<String>,
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiveSidecarPayload {
+    pub session_id: String,
+    pub book_hash: String,
+    /// The manifest in the inbox; the webview deletes it once merged.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiveImportedPayload {
+    pub session_id: String,
+    pub file_id: String,
+    pub file_name: String,
+    /// Partial MD5, the library key.
+    pub book_hash: String,
+    /// `BookFormat`, e.g. "EPUB".
+    pub format: String,
+    /// The file under `Books/<hash>/`, or `None` for a duplicate of a book
+    /// already in the library, whose received copy was discarded.
+    pub path: Option<String>,
+    pub duplicate: bool,
+    /// Title and `sourceTitle` of a new library row; the file is named after
+    /// it.
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// The library group of a new row, for a book from a folder send: the
+    /// folder it was sent in. Absent from entries journaled before it.
+    #[serde(default)]
+    pub group_name: Option<String>,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReceiveEndPayload {
@@ -126,7 +215,7 @@ pub struct ReceiveEndPayload {
 #[serde(rename_all = "camelCase")]
 pub struct SendEndPayload {
     pub session_id: Option<String>,
-    /// "sent" | "declined" | "cancelled" | "error"
+    /// "sent" | "declined" | "cancelled" | "pin_required" | "error"
     pub status: String,
     pub error: Option<String>,
     pub files_sent: usize,
@@ -142,6 +231,17 @@ pub struct SendFileInput {
     /// Base64 thumbnail shown by the receiver (LocalSend `preview` field).
     #[serde(default)]
     pub preview: Option<String>,
+    /// Sent along as `<bookHash>.readest.json` when the peer is Readest.
+    #[serde(default)]
+    pub sidecar: Option<SendSidecarInput>,
+}
+
+/// A book's Readest sidecar manifest, built by the webview.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendSidecarInput {
+    pub book_hash: String,
+    pub manifest: serde_json::Value,
 }
 
 pub fn device_type_str(t: &Option<localsend::model::discovery::DeviceType>) -> Option<String> {
@@ -203,6 +303,7 @@ mod tests {
             session_id: "s".into(),
             file_id: "f".into(),
             file_name: "a.epub".into(),
+            group_name: None,
             path: Some("/tmp/a.epub".into()),
             error: None,
         })
@@ -211,6 +312,24 @@ mod tests {
         assert_eq!(json["fileName"], "a.epub");
     }
 
+    #[test]
+    fn options_default_to_open_behavior() {
+        let options: LocalSendOptions = serde_json::from_str("{}").unwrap();
+        assert!(options.web_upload);
+        assert!(!options.receive_only);
+        assert_eq!(options.pin(), None);
+        assert_eq!(options.books_dir, None);
+
+        let options: LocalSendOptions =
+            serde_json::from_str(r#"{"pin":" 1234 ","webUpload":false,"receiveOnly":true}"#)
+                .unwrap();
+        assert_eq!(options.pin().as_deref(), Some("1234"));
+        assert!(!options.web_upload);
+        assert!(options.receive_only);
+        let blank: LocalSendOptions = serde_json::from_str(r#"{"pin":"  "}"#).unwrap();
+        assert_eq!(blank.pin(), None);
+    }
+
     #[test]
     fn send_file_input_deserializes_camel_case() {
         let input: SendFileInput = serde_json::from_str(
@@ -219,5 +338,15 @@ mod tests {
         .unwrap();
         assert_eq!(input.file_name, "a.epub");
         assert_eq!(input.mime_type, "application/epub+zip");
+        assert!(input.sidecar.is_none());
+
+        let input: SendFileInput = serde_json::from_str(
+            r#"{"path":"/tmp/a.epub","fileName":"a.epub","mimeType":"application/epub+zip",
+                "sidecar":{"bookHash":"abc","manifest":{"bookHash":"abc"}}}"#,
+        )
+        .unwrap();
+        let sidecar = input.sidecar.unwrap();
+        assert_eq!(sidecar.book_hash, "abc");
+        assert_eq!(sidecar.manifest["bookHash"], "abc");
     }
 }
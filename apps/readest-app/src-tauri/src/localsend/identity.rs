@@ -2,6 +2,7 @@
 //! fingerprint identifies the device across restarts, so peers can remember
 //! it. Adapted from the upstream LocalSend CLI (Apache-2.0).
 
+use crate::localsend::sidecar::advertised_model;
 use anyhow::Context;
 use localsend::crypto::cert::fingerprint_from_cert_der;
 use localsend::discovery::DeviceIdentity;
@@ -15,8 +16,9 @@ use std::path::Path;
 pub struct Identity {
     pub alias: String,
     /// Shown as the device tag by other LocalSend clients — the OS name
-    /// ("macOS", "iOS", "Android", ...) resolved by the frontend. Not
-    /// persisted; supplied on every start.
+    /// ("macOS", "iOS", "Android", ...) resolved by the frontend, advertised
+    /// with the Readest marker (see [`advertised_model`]). Not persisted;
+    /// supplied on every start.
     pub device_model: String,
     pub cert_pem: String,
     pub key_pem: String,
@@ -116,7 +118,7 @@ impl Identity {
         ClientInfo {
             alias: self.alias.clone(),
             version: PROTOCOL_VERSION_V2.to_string(),
-            device_model: Some(self.device_model.clone()),
+            device_model: Some(advertised_model(&self.device_model)),
             device_type: Some(device_type()),
             token: self.fingerprint.clone(),
         }
@@ -126,7 +128,7 @@ impl Identity {
         RegisterDtoV2 {
             alias: self.alias.clone(),
             version: PROTOCOL_VERSION_V2.to_string(),
-            device_model: Some(self.device_model.clone()),
+            device_model: Some(advertised_model(&self.device_model)),
             device_type: Some(device_type()),
             fingerprint: self.fingerprint.clone(),
             port,
@@ -139,7 +141,7 @@ impl Identity {
         MulticastDevice {
             alias: self.alias.clone(),
             version: PROTOCOL_VERSION_V2.to_string(),
-            device_model: Some(self.device_model.clone()),
+            device_model: Some(advertised_model(&self.device_model)),
             device_type: Some(device_type()),
             fingerprint: self.fingerprint.clone(),
             port,
@@ -174,13 +176,16 @@ mod tests {
         let id = Identity::load_or_generate(&dir, "Readest".into(), "iPadOS".into()).unwrap();
         assert_eq!(
             id.register_dto(53318).device_model.as_deref(),
-            Some("iPadOS")
+            Some("iPadOS (Readest)")
         );
         assert_eq!(
             id.multicast_device(53318).device_model.as_deref(),
-            Some("iPadOS")
+            Some("iPadOS (Readest)")
+        );
+        assert_eq!(
+            id.client_info().device_model.as_deref(),
+            Some("iPadOS (Readest)")
         );
-        assert_eq!(id.client_info().device_model.as_deref(), Some("iPadOS"));
         let _ = std::fs::remove_dir_all(&dir);
     }
 }
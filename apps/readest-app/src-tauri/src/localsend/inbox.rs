@@ -0,0 +1,623 @@
+//! Native post-receive import. When the webview hands over its Books
+//! directory (`LocalSendOptions::books_dir`), each received book is sniffed,
+//! parsed and hashed here, checked against `library.json`, and moved to
+//! `Books/<hash>/<title>.<ext>` with its cover, the path `getLocalBookFilename`
+//! gives for the row, so it is in place even while the webview is suspended.
+//! Only the library row stays with the webview: every import is journaled in
+//! `<app_data>/localsend/imported.json` and announced as
+//! `localsend:receive-imported`, and `localsend_take_imported` drains the
+//! journal, including books that arrived while no window was listening.
+//!
+//! Files the pipeline cannot place fall back to `localsend:receive-file-done`
+//! and the webview's own import: TXT (converted to EPUB before hashing),
+//! DRM-protected or unreadable books, and anything unrecognized.
+
+use crate::localsend::events::*;
+use crate::parser_common::compute_partial_md5;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex as StdMutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::mpsc;
+
+/// Serializes journal read-modify-writes between the worker and the drain
+/// command.
+static JOURNAL_LOCK: StdMutex<()> = StdMutex::new(());
+
+pub enum InboxJob {
+    Book {
+        session_id: String,
+        file_id: String,
+        file_name: String,
+        /// The folder it sat in, for a folder send.
+        group_name: Option<String>,
+        path: PathBuf,
+    },
+    /// Queued behind the books of its session, so the webview only sees a
+    /// sidecar once the book it describes has been placed.
+    Sidecar(ReceiveSidecarPayload),
+    /// The session summary, after every import of the session.
+    End(ReceiveEndPayload),
+}
+
+/// Handle to the import worker; jobs run one at a time, in arrival order.
+#[derive(Clone)]
+pub struct InboxImporter(mpsc::UnboundedSender<InboxJob>);
+
+impl InboxImporter {
+    pub fn spawn<R: Runtime>(app: AppHandle<R>, books_dir: PathBuf, inbox_dir: PathBuf) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<InboxJob>();
+        tauri::async_runtime::spawn(async move {
+            while let Some(job) = rx.recv().await {
+                match job {
+                    InboxJob::Book {
+                        session_id,
+                        file_id,
+                        file_name,
+                        group_name,
+                        path,
+                    } => {
+                        let received = Received {
+                            session_id,
+                            file_id,
+                            file_name,
+                            group_name,
+                        };
+                        import_received(&app, &books_dir, &inbox_dir, received, path).await
+                    }
+                    InboxJob::Sidecar(payload) => {
+                        let _ = app.emit(EV_RECEIVE_SIDECAR, payload);
+                    }
+                    InboxJob::End(payload) => {
+                        let _ = app.emit(EV_RECEIVE_END, payload);
+                    }
+                }
+            }
+        });
+        Self(tx)
+    }
+
+    pub fn push(&self, job: InboxJob) {
+        // The worker lives as long as any handle does.
+        let _ = self.0.send(job);
+    }
+}
+
+struct Received {
+    session_id: String,
+    file_id: String,
+    file_name: String,
+    group_name: Option<String>,
+}
+
+async fn import_received<R: Runtime>(
+    app: &AppHandle<R>,
+    books_dir: &Path,
+    inbox_dir: &Path,
+    received: Received,
+    path: PathBuf,
+) {
+    let Received {
+        session_id,
+        file_id,
+        file_name,
+        group_name,
+    } = received;
+    match place_book(books_dir, &path, &file_name).await {
+        Ok(placed) => {
+            if let Some(dir) = path.parent() {
+                remove_empty_dirs(dir, inbox_dir);
+            }
+            let payload = ReceiveImportedPayload {
+                session_id,
+                file_id,
+                file_name,
+                book_hash: placed.hash,
+                format: placed.format.to_string(),
+                path: placed.path.map(|p| p.to_string_lossy().into_owned()),
+                duplicate: placed.duplicate,
+                title: placed.title,
+                authors: placed.authors,
+                group_name,
+            };
+            match journal_path(app) {
+                Ok(journal) => {
+                    if let Err(e) = append_journal(&journal, payload.clone()) {
+                        log::warn!("failed to journal LocalSend import: {e}");
+                    }
+                }
+                Err(e) => log::warn!("failed to journal LocalSend import: {e}"),
+            }
+            let _ = app.emit(EV_RECEIVE_IMPORTED, payload);
+        }
+        Err(err) => {
+            log::info!("leaving {file_name} to the webview importer: {err}");
+            let _ = app.emit(
+                EV_RECEIVE_FILE_DONE,
+                ReceiveFileDonePayload {
+                    session_id,
+                    file_id,
+                    file_name,
+                    group_name,
+                    path: Some(path.to_string_lossy().into_owned()),
+                    error: None,
+                },
+            );
+        }
+    }
+}
+
+/// Removes `dir` and then its parents, up to but not including `root`, for
+/// as long as they are empty: the folders a folder send left in the inbox
+/// once its books were moved out.
+fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut dir = dir;
+    while dir != root && dir.starts_with(root) && std::fs::remove_dir(dir).is_ok() {
+        match dir.parent() {
+            Some(parent) => dir = parent,
+            None => break,
+        }
+    }
+}
+
+/// Removes every empty folder below `root`, for those whose books the webview
+/// imported and deleted. Run before the server starts, while nothing is being
+/// received into them.
+pub fn remove_empty_subfolders(root: &Path) {
+    for entry in walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .flatten()
+    {
+        if entry.file_type().is_dir() {
+            // Fails, as intended, for folders that still hold files.
+            let _ = std::fs::remove_dir(entry.path());
+        }
+    }
+}
+
+struct PlacedBook {
+    hash: String,
+    format: &'static str,
+    /// `None` for a duplicate, whose received copy is discarded.
+    path: Option<PathBuf>,
+    duplicate: bool,
+    /// The title a new row gets, also its `sourceTitle`.
+    title: Option<String>,
+    authors: Vec<String>,
+}
+
+struct ParsedBook {
+    hash: String,
+    title: Option<String>,
+    authors: Vec<String>,
+    /// Downscaled cover bytes and their MIME.
+    cover: Option<(Vec<u8>, String)>,
+}
+
+async fn place_book(books_dir: &Path, path: &Path, file_name: &str) -> Result<PlacedBook, String> {
+    let format = sniff_format(path, file_name).ok_or("not a book Readest imports natively")?;
+    let parsed = parse_book(path, format).await?;
+    let title = source_title(parsed.title.as_deref(), file_name);
+    let row = library_row(books_dir, &parsed.hash);
+    if row.as_ref().is_some_and(LibraryRow::is_live) {
+        let _ = std::fs::remove_file(path);
+        return Ok(PlacedBook {
+            hash: parsed.hash,
+            format,
+            path: None,
+            duplicate: true,
+            title: Some(title),
+            authors: parsed.authors,
+        });
+    }
+
+    let dir = books_dir.join(&parsed.hash);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // A deleted row comes back under its own name, like a re-import.
+    let file_title = row
+        .map(LibraryRow::file_title)
+        .unwrap_or_else(|| title.clone());
+    let target = dir.join(format!(
+        "{}.{}",
+        make_safe_filename(&file_title),
+        format.to_ascii_lowercase()
+    ));
+    if std::fs::rename(path, &target).is_err() {
+        // The inbox and a custom Books directory may sit on different
+        // volumes.
+        std::fs::copy(path, &target).map_err(|e| e.to_string())?;
+        let _ = std::fs::remove_file(path);
+    }
+    let cover_path = dir.join("cover.png");
+    if let Some((bytes, mime)) = parsed.cover {
+        // SVG covers go through the webview's svg2png conversion instead.
+        if mime != "image/svg+xml" && !cover_path.exists() {
+            if let Err(e) = std::fs::write(&cover_path, bytes) {
+                log::warn!("failed to write cover of {}: {e}", parsed.hash);
+            }
+        }
+    }
+    Ok(PlacedBook {
+        hash: parsed.hash,
+        format,
+        path: Some(target),
+        duplicate: false,
+        title: Some(title),
+        authors: parsed.authors,
+    })
+}
+
+/// The title the webview importer gives a book: its metadata title, or the
+/// file name without extension when that is blank or just the file name.
+fn source_title(title: Option<&str>, file_name: &str) -> String {
+    match title {
+        Some(title) if !title.trim().is_empty() && title != file_name => title.to_string(),
+        _ => match file_name.rsplit_once('.') {
+            Some((base, _)) => base.to_string(),
+            None => file_name.to_string(),
+        },
+    }
+}
+
+/// Mirrors `makeSafeFilename` in src/utils/misc.ts, so the placed file is
+/// where `getLocalBookFilename` looks for it.
+fn make_safe_filename(name: &str) -> String {
+    const MAX_FILENAME_BYTES: usize = 250;
+    const RESERVED: [&str; 4] = ["con", "prn", "aux", "nul"];
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '%' | '#' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if (c as u32) < 0x20 => '_',
+            c => c,
+        })
+        .collect();
+    let mut safe = replaced.trim().to_string();
+    let lower = safe.to_ascii_lowercase();
+    let numbered = |prefix: &str| {
+        lower.len() == 4 && lower.starts_with(prefix) && matches!(lower.as_bytes()[3], b'1'..=b'9')
+    };
+    if RESERVED.contains(&lower.as_str()) || numbered("com") || numbered("lpt") {
+        safe.push('_');
+    }
+    while safe.len() > MAX_FILENAME_BYTES {
+        safe.pop();
+    }
+    safe
+}
+
+async fn parse_book(path: &Path, format: &str) -> Result<ParsedBook, String> {
+    let file_path = path.to_string_lossy().into_owned();
+    match format {
+        "EPUB" => {
+            let parsed = crate::epub_parser::parse_epub_metadata(file_path)
+                .await
+                .map_err(|e| e.to_string())?;
+            let (title, authors) = parsed
+                .metadata
+                .map(|m| {
+                    let title = m.titles.into_iter().next().map(|t| t.value);
+                    let authors = m
+                        .creators
+                        .into_iter()
+                        .filter(|c| c.roles.is_empty() || c.roles.iter().any(|r| r == "aut"))
+                        .map(|c| c.name)
+                        .collect();
+                    (title, authors)
+                })
+                .unwrap_or_default();
+            Ok(ParsedBook {
+                hash: parsed.partial_md5,
+                title,
+                authors,
+                cover: parsed.cover.zip(parsed.cover_mime),
+            })
+        }
+        "MOBI" | "AZW" | "AZW3" => {
+            let parsed = crate::mobi_parser::parse_mobi_metadata(file_path)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(ParsedBook {
+                hash: parsed.partial_md5,
+                title: parsed.title,
+                authors: parsed.author.into_iter().collect(),
+                cover: parsed.cover.map(|c| (c.bytes, c.mime)),
+            })
+        }
+        "FB2" => {
+            let parsed = crate::fb2_parser::parse_fb2_metadata(file_path).await?;
+            Ok(ParsedBook {
+                hash: parsed.partial_md5,
+                title: parsed.title,
+                authors: parsed.authors,
+                cover: parsed.cover.map(|c| (c.bytes, c.mime)),
+            })
+        }
+        _ => {
+            let path = path.to_path_buf();
+            let hash = tauri::async_runtime::spawn_blocking(move || compute_partial_md5(&path))
+                .await
+                .map_err(|e| format!("join error: {e}"))?
+                .map_err(|e| format!("partial_md5 failed: {e}"))?;
+            Ok(ParsedBook {
+                hash,
+                title: None,
+                authors: Vec::new(),
+                cover: None,
+            })
+        }
+    }
+}
+
+/// The `BookFormat` of a received file, from its leading bytes, with the
+/// extension deciding among containers that share a signature. `None` for
+/// TXT and anything else the pipeline leaves to the webview.
+fn sniff_format(path: &Path, file_name: &str) -> Option<&'static str> {
+    let mut head = [0u8; 1024];
+    let len = std::fs::File::open(path)
+        .and_then(|mut f| f.read(&mut head))
+        .ok()?;
+    let head = &head[..len];
+    let ext = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    if head.starts_with(b"%PDF-") {
+        return Some("PDF");
+    }
+    if head.get(60..68) == Some(b"BOOKMOBI".as_slice()) {
+        return Some(match ext.as_str() {
+            "azw" => "AZW",
+            "azw3" => "AZW3",
+            _ => "MOBI",
+        });
+    }
+    if head.starts_with(b"PK\x03\x04") {
+        // OCF puts an uncompressed `mimetype` entry first.
+        let ocf = head.get(30..38) == Some(b"mimetype".as_slice())
+            && head.get(38..58) == Some(b"application/epub+zip".as_slice());
+        return match ext.as_str() {
+            _ if ocf => Some("EPUB"),
+            "epub" => Some("EPUB"),
+            "cbz" => Some("CBZ"),
+            "fbz" => Some("FBZ"),
+            _ => None,
+        };
+    }
+    let text = String::from_utf8_lossy(head);
+    if text.contains("<FictionBook") {
+        return Some("FB2");
+    }
+    (ext == "md").then_some("MD")
+}
+
+#[derive(Deserialize)]
+struct LibraryRow {
+    #[serde(default)]
+    hash: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default, rename = "sourceTitle")]
+    source_title: Option<String>,
+    #[serde(default, rename = "deletedAt")]
+    deleted_at: Option<serde_json::Value>,
+}
+
+impl LibraryRow {
+    fn is_live(&self) -> bool {
+        self.deleted_at
+            .as_ref()
+            .map_or(true, serde_json::Value::is_null)
+    }
+
+    /// `book.sourceTitle || book.title`, as `getLocalBookFilename` reads it.
+    fn file_title(self) -> String {
+        self.source_title
+            .filter(|t| !t.is_empty())
+            .unwrap_or(self.title)
+    }
+}
+
+/// The `library.json` row for `hash`, live or deleted.
+fn library_row(books_dir: &Path, hash: &str) -> Option<LibraryRow> {
+    let bytes = std::fs::read(books_dir.join("library.json")).ok()?;
+    let rows: Vec<LibraryRow> = serde_json::from_slice(&bytes).unwrap_or_default();
+    rows.into_iter().find(|row| row.hash == hash)
+}
+
+pub fn journal_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join("localsend").join("imported.json"))
+        .map_err(|e| e.to_string())
+}
+
+fn load_journal(path: &Path) -> Vec<ReceiveImportedPayload> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn append_journal(path: &Path, entry: ReceiveImportedPayload) -> std::io::Result<()> {
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_journal(path);
+    entries.push(entry);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let bytes = serde_json::to_vec(&entries).map_err(std::io::Error::other)?;
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Removes and returns every journaled import, oldest first.
+pub fn take_journal(path: &Path) -> Vec<ReceiveImportedPayload> {
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = load_journal(path);
+    let _ = std::fs::remove_file(path);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ls-inbox-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn sniffs_formats_from_leading_bytes() {
+        let dir = temp_dir("sniff");
+        let sniff = |name: &str, bytes: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            sniff_format(&path, name)
+        };
+        let mut ocf = b"PK\x03\x04".to_vec();
+        ocf.resize(30, 0);
+        ocf.extend_from_slice(b"mimetypeapplication/epub+zip");
+        assert_eq!(sniff("book.bin", &ocf), Some("EPUB"));
+        assert_eq!(sniff("comic.cbz", b"PK\x03\x04rest"), Some("CBZ"));
+        assert_eq!(sniff("archive.zip", b"PK\x03\x04rest"), None);
+        assert_eq!(sniff("paper.PDF", b"%PDF-1.7\n"), Some("PDF"));
+        let mut mobi = vec![0u8; 60];
+        mobi.extend_from_slice(b"BOOKMOBI");
+        assert_eq!(sniff("novel.azw3", &mobi), Some("AZW3"));
+        assert_eq!(sniff("novel.prc", &mobi), Some("MOBI"));
+        assert_eq!(
+            sniff(
+                "tale.xml",
+                b"<?xml version=\"1.0\"?><FictionBook xmlns=\"\">"
+            ),
+            Some("FB2")
+        );
+        assert_eq!(sniff("notes.md", b"# Notes"), Some("MD"));
+        assert_eq!(sniff("story.txt", b"Once upon a time"), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dedups_against_live_library_rows() {
+        let dir = temp_dir("library");
+        let live = |hash: &str| library_row(&dir, hash).is_some_and(|row| row.is_live());
+        assert!(!live("abc"));
+        std::fs::write(
+            dir.join("library.json"),
+            r#"[{"hash":"abc","title":"A","deletedAt":null},
+                {"hash":"gone","title":"G","sourceTitle":"Gone","deletedAt":1700000000000},
+                {"hash":"plain","sourceTitle":""}]"#,
+        )
+        .unwrap();
+        assert!(live("abc"));
+        assert!(live("plain"));
+        assert!(!live("gone"));
+        assert!(!live("other"));
+        assert_eq!(library_row(&dir, "gone").unwrap().file_title(), "Gone");
+        assert_eq!(library_row(&dir, "abc").unwrap().file_title(), "A");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn names_files_like_the_webview_importer() {
+        assert_eq!(source_title(Some("Dune"), "dune.epub"), "Dune");
+        assert_eq!(source_title(Some("  "), "dune.epub"), "dune");
+        assert_eq!(source_title(Some("dune.epub"), "dune.epub"), "dune");
+        assert_eq!(source_title(None, "a.b.pdf"), "a.b");
+        assert_eq!(make_safe_filename(" What? A: B/C "), "What_ A_ B_C");
+        assert_eq!(make_safe_filename("100% #1"), "100_ _1");
+        assert_eq!(make_safe_filename("CON"), "CON_");
+        assert_eq!(make_safe_filename("lpt3"), "lpt3_");
+        assert_eq!(make_safe_filename("com0"), "com0");
+        let long = "é".repeat(200);
+        assert_eq!(make_safe_filename(&long), "é".repeat(125));
+    }
+
+    #[test]
+    fn journal_appends_and_drains() {
+        let dir = temp_dir("journal");
+        let path = dir.join("imported.json");
+        let entry = |id: &str| ReceiveImportedPayload {
+            session_id: "s".into(),
+            file_id: id.into(),
+            file_name: format!("{id}.epub"),
+            book_hash: id.into(),
+            format: "EPUB".into(),
+            path: Some(format!("/books/{id}/{id}.epub")),
+            duplicate: false,
+            title: None,
+            authors: Vec::new(),
+            group_name: None,
+        };
+        append_journal(&path, entry("a")).unwrap();
+        append_journal(&path, entry("b")).unwrap();
+        let drained = take_journal(&path);
+        assert_eq!(
+            drained
+                .iter()
+                .map(|e| e.file_id.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert!(take_journal(&path).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn places_new_books_and_discards_duplicates() {
+        let books = temp_dir("books");
+        let inbox = temp_dir("inbox");
+        let received = inbox.join("paper.pdf");
+        std::fs::write(&received, b"%PDF-1.7\nhello").unwrap();
+        let place = |file_name: &str| {
+            tauri::async_runtime::block_on(place_book(&books, &received, file_name)).unwrap()
+        };
+        let placed = place("paper.pdf");
+        let hash = compute_partial_md5(placed.path.as_deref().unwrap()).unwrap();
+        assert_eq!(placed.hash, hash);
+        assert_eq!(placed.format, "PDF");
+        assert_eq!(placed.path, Some(books.join(&hash).join("paper.pdf")));
+        assert_eq!(placed.title.as_deref(), Some("paper"));
+        assert!(!received.exists());
+
+        std::fs::write(
+            books.join("library.json"),
+            format!(r#"[{{"hash":"{hash}"}}]"#),
+        )
+        .unwrap();
+        std::fs::write(&received, b"%PDF-1.7\nhello").unwrap();
+        let again = place("paper.pdf");
+        assert!(again.duplicate);
+        assert_eq!(again.path, None);
+        assert!(!received.exists());
+        let _ = std::fs::remove_dir_all(&books);
+        let _ = std::fs::remove_dir_all(&inbox);
+    }
+
+    #[test]
+    fn removes_the_folders_a_folder_send_leaves_behind() {
+        let inbox = temp_dir("prune");
+        let saga = inbox.join("Dune Saga");
+        std::fs::create_dir_all(saga.join("Extras")).unwrap();
+        std::fs::create_dir_all(inbox.join("Other").join("Empty")).unwrap();
+        std::fs::write(saga.join("02 Dune Messiah.epub"), b"x").unwrap();
+
+        remove_empty_dirs(&saga.join("Extras"), &inbox);
+        assert!(!saga.join("Extras").exists());
+        assert!(saga.exists(), "still holds a book");
+
+        std::fs::remove_file(saga.join("02 Dune Messiah.epub")).unwrap();
+        remove_empty_subfolders(&inbox);
+        assert!(!saga.exists());
+        assert!(!inbox.join("Other").exists());
+        assert!(inbox.exists());
+        let _ = std::fs::remove_dir_all(&inbox);
+    }
+}
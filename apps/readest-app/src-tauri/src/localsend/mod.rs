@@ -1,7 +1,10 @@
 pub mod commands;
 pub mod events;
 pub mod identity;
+pub mod inbox;
 pub mod service;
+pub mod sidecar;
+pub mod trust;
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -2,8 +2,12 @@
 //! that translates protocol events into `localsend:*` webview events. The
 //! receive/send flows mirror the upstream LocalSend CLI (Apache-2.0).
 
+use crate::bandwidth;
 use crate::localsend::events::*;
 use crate::localsend::identity::Identity;
+use crate::localsend::inbox::{self, InboxImporter, InboxJob};
+use crate::localsend::sidecar;
+use crate::localsend::trust::{self, TrustList, TrustSlot};
 use localsend::discovery::{
     DeviceChannel, DiscoveredDevice, DiscoveryConfig, DiscoveryEvent, DiscoveryHandle, HttpChannel,
     DEFAULT_DISCOVERY_TIMEOUT,
@@ -18,7 +22,8 @@ use localsend::model::transfer::FileDto;
 use localsend::multicast::{
     InterfaceFilter, DEFAULT_MULTICAST_GROUP, DEFAULT_MULTICAST_GROUP_V6, DEFAULT_PORT,
 };
-use std::collections::HashMap;
+use localsend::util::filename::{sanitize_with, Options, Rules};
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
 use tauri::{AppHandle, Emitter, Manager, Runtime};
@@ -59,11 +64,16 @@ pub struct ReceiveSession {
     pub bytes_total: u64,
     pub finished_files: usize,
     pub failed_files: usize,
+    /// Readest sidecars among `finished_files`; not counted as received.
+    pub sidecar_files: usize,
     pub finalized_bytes: u64,
     pub in_progress: HashMap<String, Arc<AtomicU64>>,
     /// Set when the server reported the session end; the summary event is
     /// deferred until every in-flight per-file result has been emitted.
     pub ended: Option<SessionEndReasonV2>,
+    /// The native import queue; per-file results and the summary go through
+    /// it so they reach the webview after the imports before them.
+    pub inbox: Option<InboxImporter>,
 }
 
 /// Cancellation state of the (single) active send session.
@@ -79,6 +89,18 @@ pub struct SendCancel {
 pub struct SendFileJob {
     pub dto: FileDto,
     pub path: std::path::PathBuf,
+    pub sidecar: Option<SendSidecarInput>,
+}
+
+/// Sidecar manifests written for one send; removed when the send ends.
+struct OutgoingSidecars(Vec<std::path::PathBuf>);
+
+impl Drop for OutgoingSidecars {
+    fn drop(&mut self) {
+        for path in &self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
 
 pub struct RunningService {
@@ -91,13 +113,17 @@ pub struct RunningService {
     pub pending: PendingMap,
     pub receiving: ReceivingMap,
     pub send_cancel: SendCancelSlot,
+    pub trusted: TrustSlot,
+    pub inbox: Option<InboxImporter>,
     pub multicast_error: Option<String>,
+    pub options: LocalSendOptions,
 }
 
 pub async fn start<R: Runtime>(
     app: AppHandle<R>,
     alias: String,
     device_model: String,
+    options: LocalSendOptions,
 ) -> Result<RunningService, String> {
     let dir = app
         .path()
@@ -108,6 +134,16 @@ pub async fn start<R: Runtime>(
     let identity = Arc::new(
         Identity::load_or_generate(&dir, alias, device_model).map_err(|e| format!("{e:#}"))?,
     );
+    let trusted = TrustList::load(&trust::store_path(&app)?);
+    // Folders whose books the webview imported and deleted after the
+    // session ended.
+    inbox::remove_empty_subfolders(&staging_dir(&app));
+    let inbox = options
+        .books_dir
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| InboxImporter::spawn(app.clone(), std::path::PathBuf::from(d), staging_dir(&app)));
 
     // Bind the HTTPS server on Readest's own port range (see PORT_RANGE:
     // 53317 is left to the LocalSend app), walking it for the first free port.
@@ -122,7 +158,7 @@ pub async fn start<R: Runtime>(
             identity.client_info(),
             None,
             Some(ServerConfigV2 {
-                pin: None,
+                pin: options.pin(),
                 verify_checksums: true,
                 event_tx: server_tx.clone(),
             }),
@@ -135,7 +171,8 @@ pub async fn start<R: Runtime>(
             // to the body fingerprint, exactly like classic protocol v2.1.
             // The page itself is a bonus: browsers without LocalSend can
             // send books, gated by the same accept dialog as any transfer.
-            Some(WebConfig {
+            // Switched off, only senders with a certificate get through.
+            options.web_upload.then(|| WebConfig {
                 send: None,
                 upload: true,
                 i18n: WebI18n::default(),
@@ -156,15 +193,21 @@ pub async fn start<R: Runtime>(
 
     // Discovery: multicast plus the register answers to other devices'
     // announcements. Multicast failure is not fatal; the store still
-    // collects devices that contact this device over HTTP.
+    // collects devices that contact this device over HTTP. Receive-only
+    // keeps off the LocalSend group (port 0, like the tests) and stays
+    // silent; the commands still expect a discovery handle.
     let (discovery_tx, discovery_rx) = mpsc::channel::<DiscoveryEvent>(16);
     let (discovery_stop, discovery_stop_rx) = oneshot::channel::<()>();
     let discovery = Arc::new(
         localsend::discovery::start(
             DiscoveryConfig {
                 group: DEFAULT_MULTICAST_GROUP,
-                group_v6: Some(DEFAULT_MULTICAST_GROUP_V6),
-                port: DEFAULT_PORT,
+                group_v6: (!options.receive_only).then_some(DEFAULT_MULTICAST_GROUP_V6),
+                port: if options.receive_only {
+                    0
+                } else {
+                    DEFAULT_PORT
+                },
                 interface_filter: InterfaceFilter::default(),
                 device: identity.multicast_device(port),
                 identity: identity.device_identity(),
@@ -175,8 +218,12 @@ pub async fn start<R: Runtime>(
         )
         .await,
     );
-    let multicast_error = discovery.multicast_error().map(|e| format!("{e:#}"));
-    {
+    let multicast_error = if options.receive_only {
+        None
+    } else {
+        discovery.multicast_error().map(|e| format!("{e:#}"))
+    };
+    if !options.receive_only {
         // Announce this device; peers answer with an HTTP register request.
         let discovery = discovery.clone();
         tauri::async_runtime::spawn(async move { discovery.announce().await });
@@ -192,7 +239,10 @@ pub async fn start<R: Runtime>(
         pending: Arc::new(StdMutex::new(HashMap::new())),
         receiving: Arc::new(StdMutex::new(HashMap::new())),
         send_cancel: Arc::new(StdMutex::new(None)),
+        trusted: Arc::new(StdMutex::new(trusted)),
+        inbox,
         multicast_error,
+        options,
     };
     spawn_event_pump(app, &service, server_rx, discovery_rx);
     Ok(service)
@@ -220,11 +270,16 @@ fn spawn_event_pump<R: Runtime>(
     let pending = service.pending.clone();
     let receiving = service.receiving.clone();
     let send_cancel = service.send_cancel.clone();
+    let trusted = service.trusted.clone();
+    let inbox = service.inbox.clone();
     let self_fingerprint = service.identity.fingerprint.clone();
+    let receive_only = service.options.receive_only;
     tauri::async_runtime::spawn(async move {
         loop {
             tokio::select! {
                 event = server_rx.recv() => match event {
+                    // Receive-only tracks no peers.
+                    Some(ServerEventV2::Register { .. }) if receive_only => {}
                     Some(ServerEventV2::Register { ip, info }) => {
                         // A peer answering this device's announcement (or
                         // probing it during a scan) registers with the HTTP
@@ -241,9 +296,15 @@ fn spawn_event_pump<R: Runtime>(
                             register_peer(&discovery, &self_fingerprint, host, info).await;
                         });
                     }
-                    Some(event) => {
-                        handle_server_event(&app, &pending, &receiving, &send_cancel, event)
-                    }
+                    Some(event) => handle_server_event(
+                        &app,
+                        &pending,
+                        &receiving,
+                        &send_cancel,
+                        &trusted,
+                        &inbox,
+                        event,
+                    ),
                     None => break,
                 },
                 event = discovery_rx.recv() => match event {
@@ -327,6 +388,8 @@ fn handle_server_event<R: Runtime>(
     pending: &PendingMap,
     receiving: &ReceivingMap,
     send_cancel: &SendCancelSlot,
+    trusted: &TrustSlot,
+    inbox: &Option<InboxImporter>,
     event: ServerEventV2,
 ) {
     match event {
@@ -340,6 +403,11 @@ fn handle_server_event<R: Runtime>(
             files,
             decision_tx,
         } => {
+            let auto_accept = trusted
+                .lock()
+                .unwrap()
+                .auto_accepts(cert_fingerprint.as_deref(), &files);
+            let verified = cert_fingerprint.is_some();
             let sender = SenderTarget {
                 host: ip.ip.to_string(),
                 port: info.port,
@@ -353,6 +421,7 @@ fn handle_server_event<R: Runtime>(
                     device_model: info.device_model.clone(),
                     device_type: device_type_str(&info.device_type),
                     fingerprint: sender.fingerprint.clone(),
+                    verified,
                 },
                 files: files
                     .values()
@@ -365,14 +434,19 @@ fn handle_server_event<R: Runtime>(
                     })
                     .collect(),
             };
-            pending.lock().unwrap().insert(
-                session_id,
-                PendingReceive {
-                    sender,
-                    files,
-                    decision_tx,
-                },
-            );
+            let request = PendingReceive {
+                sender,
+                files,
+                decision_tx,
+            };
+            if auto_accept {
+                let ids = request.files.keys().cloned().collect();
+                if accept_pending(app, receiving, inbox.clone(), session_id, request, ids) {
+                    let _ = app.emit(EV_RECEIVE_AUTO_ACCEPTED, payload);
+                }
+                return;
+            }
+            pending.lock().unwrap().insert(session_id, request);
             let _ = app.emit(EV_RECEIVE_REQUEST, payload);
         }
         ServerEventV2::PrepareUploadAborted { session_id } => {
@@ -408,6 +482,49 @@ fn handle_server_event<R: Runtime>(
     }
 }
 
+/// Accepts `ids` of a pending request and starts tracking the session.
+/// Returns `false` when the request already ended on the wire.
+pub fn accept_pending<R: Runtime>(
+    app: &AppHandle<R>,
+    receiving: &ReceivingMap,
+    inbox: Option<InboxImporter>,
+    session_id: String,
+    pending: PendingReceive,
+    ids: HashSet<String>,
+) -> bool {
+    let accepted: HashMap<String, FileDto> = pending
+        .files
+        .iter()
+        .filter(|(id, _)| ids.contains(*id))
+        .map(|(id, f)| (id.clone(), f.clone()))
+        .collect();
+    let bytes_total: u64 = accepted.values().map(|f| f.size).sum();
+    if pending
+        .decision_tx
+        .send(PrepareUploadDecisionV2::Accept(ids))
+        .is_err()
+    {
+        return false;
+    }
+    receiving.lock().unwrap().insert(
+        session_id.clone(),
+        ReceiveSession {
+            sender: pending.sender,
+            files: accepted,
+            bytes_total,
+            finished_files: 0,
+            failed_files: 0,
+            sidecar_files: 0,
+            finalized_bytes: 0,
+            in_progress: HashMap::new(),
+            ended: None,
+            inbox,
+        },
+    );
+    spawn_receive_progress_ticker(app.clone(), receiving.clone(), session_id);
+    true
+}
+
 fn staging_dir<R: Runtime>(app: &AppHandle<R>) -> std::path::PathBuf {
     app.path()
         .app_data_dir()
@@ -415,6 +532,43 @@ fn staging_dir<R: Runtime>(app: &AppHandle<R>) -> std::path::PathBuf {
         .unwrap_or_else(|_| std::env::temp_dir().join("readest-localsend-inbox"))
 }
 
+/// A peer-supplied file name as a path below the inbox. Protocol v2 allows
+/// directory components, which folder sends use to carry their tree; each
+/// is sanitized under `Rules::Universal`, and empty, `.`/`..` and root or
+/// drive segments are dropped, so the result never leaves the inbox.
+fn safe_relative_path(name: &str) -> std::path::PathBuf {
+    let mut path: std::path::PathBuf = name
+        .split(['/', '\\'])
+        .map(|segment| {
+            sanitize_with(
+                segment,
+                Rules::Universal,
+                &Options {
+                    replacement: "_",
+                    placeholder: "",
+                },
+            )
+        })
+        .filter(|segment| !segment.is_empty() && segment != "." && segment != "..")
+        .collect();
+    if path.as_os_str().is_empty() {
+        path.push("received.bin");
+    }
+    path
+}
+
+/// The folder of a file from a folder send, "/"-separated ("Dune Saga" or
+/// "Dune Saga/Extras"); the import files the book under the library group of
+/// that name, the one an imported folder gets. `None` for a loose file.
+fn group_name(relative: &std::path::Path) -> Option<String> {
+    let parent = relative.parent()?;
+    let segments: Vec<_> = parent
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
 /// "name.epub" -> "name (2).epub" until unused, like the upstream CLI.
 fn unique_path(dir: &std::path::Path, file_name: &str) -> std::path::PathBuf {
     let candidate = dir.join(file_name);
@@ -434,6 +588,63 @@ fn unique_path(dir: &std::path::Path, file_name: &str) -> std::path::PathBuf {
     unreachable!()
 }
 
+/// MIME type by extension, for the receiver's accept dialog.
+fn file_type(file_name: &str) -> &'static str {
+    let ext = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("epub") => "application/epub+zip",
+        Some("pdf") => "application/pdf",
+        Some("mobi") => "application/x-mobipocket-ebook",
+        Some("azw") => "application/vnd.amazon.ebook",
+        Some("azw3") => "application/vnd.amazon.mobi8-ebook",
+        Some("cbz") => "application/vnd.comicbook+zip",
+        Some("fb2") => "application/x-fictionbook+xml",
+        Some("txt") => "text/plain",
+        Some("md") => "text/markdown",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Every file below `root`, named `<root>/<relative path>` so the receiver
+/// recreates the tree. Hidden files and folders (`.DS_Store`, `.git`) are
+/// left out.
+pub fn folder_inputs(root: &std::path::Path) -> Result<Vec<SendFileInput>, String> {
+    let root_name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("{} is not a folder", root.display()))?;
+    let mut inputs = Vec::new();
+    for entry in walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+    {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).map_err(|e| e.to_string())?;
+        let mut file_name = root_name.clone();
+        for component in relative.components() {
+            file_name.push('/');
+            file_name.push_str(&component.as_os_str().to_string_lossy());
+        }
+        inputs.push(SendFileInput {
+            path: entry.path().to_string_lossy().into_owned(),
+            mime_type: file_type(&file_name).to_string(),
+            file_name,
+            preview: None,
+            sidecar: None,
+        });
+    }
+    if inputs.is_empty() {
+        return Err(format!("{} has no files to send", root.display()));
+    }
+    Ok(inputs)
+}
+
 fn handle_file_upload<R: Runtime>(
     app: &AppHandle<R>,
     receiving: &ReceivingMap,
@@ -454,9 +665,17 @@ fn handle_file_upload<R: Runtime>(
             .insert(file_id.clone(), progress.clone());
     }
 
-    let dir = staging_dir(app);
+    // Folder sends keep their tree: "Series/Vol 01.epub" lands in
+    // "<inbox>/Series/", deduplicated within that folder.
+    let relative = safe_relative_path(&file.file_name);
+    let dir = staging_dir(app).join(relative.parent().unwrap_or(std::path::Path::new("")));
     let _ = std::fs::create_dir_all(&dir);
-    let path = unique_path(&dir, &file.file_name);
+    let leaf = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let path = unique_path(&dir, &leaf);
+    let group_name = group_name(&relative);
 
     let (progress_tx, mut progress_rx) = mpsc::channel::<u64>(16);
     {
@@ -487,9 +706,42 @@ fn handle_file_upload<R: Runtime>(
             session.in_progress.remove(&file_id);
             let (saved_path, error) = match result {
                 Ok(()) => {
+                    let size = session.files.get(&file_id).map(|f| f.size).unwrap_or(0);
                     session.finished_files += 1;
-                    session.finalized_bytes +=
-                        session.files.get(&file_id).map(|f| f.size).unwrap_or(0);
+                    session.finalized_bytes += size;
+                    if let Some(book_hash) = sidecar::sidecar_hash(&file_name)
+                        .filter(|_| size <= sidecar::MAX_SIDECAR_BYTES)
+                    {
+                        // Not a book: the webview merges it into the one
+                        // imported from this session.
+                        session.sidecar_files += 1;
+                        let payload = ReceiveSidecarPayload {
+                            session_id: session_id.clone(),
+                            book_hash: book_hash.to_string(),
+                            path: path.to_string_lossy().to_string(),
+                        };
+                        match &session.inbox {
+                            Some(inbox) => inbox.push(InboxJob::Sidecar(payload)),
+                            None => {
+                                let _ = app.emit(EV_RECEIVE_SIDECAR, payload);
+                            }
+                        }
+                        maybe_emit_receive_end(&app, &mut sessions, &session_id);
+                        return;
+                    }
+                    if let Some(inbox) = &session.inbox {
+                        // The sanitized leaf: the importer falls back to it
+                        // for the title, which must not carry the folder.
+                        inbox.push(InboxJob::Book {
+                            session_id: session_id.clone(),
+                            file_id,
+                            file_name: leaf,
+                            group_name,
+                            path,
+                        });
+                        maybe_emit_receive_end(&app, &mut sessions, &session_id);
+                        return;
+                    }
                     (Some(path.to_string_lossy().to_string()), None)
                 }
                 Err(err) => {
@@ -504,6 +756,7 @@ fn handle_file_upload<R: Runtime>(
                     session_id: session_id.clone(),
                     file_id,
                     file_name,
+                    group_name,
                     path: saved_path,
                     error,
                 },
@@ -535,15 +788,18 @@ fn maybe_emit_receive_end<R: Runtime>(
         SessionEndReasonV2::Finished => "finished",
         SessionEndReasonV2::Cancelled => "cancelled",
     };
-    let _ = app.emit(
-        EV_RECEIVE_END,
-        ReceiveEndPayload {
-            session_id: session_id.to_string(),
-            reason: reason.to_string(),
-            received: session.finished_files,
-            failed: session.failed_files,
-        },
-    );
+    let payload = ReceiveEndPayload {
+        session_id: session_id.to_string(),
+        reason: reason.to_string(),
+        received: session.finished_files - session.sidecar_files,
+        failed: session.failed_files,
+    };
+    match &session.inbox {
+        Some(inbox) => inbox.push(InboxJob::End(payload)),
+        None => {
+            let _ = app.emit(EV_RECEIVE_END, payload);
+        }
+    }
 }
 
 /// Emits `localsend:receive-progress` every 250ms while the session exists.
@@ -580,6 +836,57 @@ pub fn spawn_receive_progress_ticker<R: Runtime>(
     });
 }
 
+/// Writes the sidecar of every job that has one and queues it for upload.
+fn attach_sidecars<R: Runtime>(
+    app: &AppHandle<R>,
+    jobs: &mut Vec<SendFileJob>,
+) -> OutgoingSidecars {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map(|d| d.join("localsend").join("outbox"))
+        .unwrap_or_else(|_| std::env::temp_dir().join("readest-localsend-outbox"));
+    let mut written = OutgoingSidecars(Vec::new());
+    let mut seen = HashSet::new();
+    let sidecars: Vec<SendSidecarInput> = jobs.iter().filter_map(|j| j.sidecar.clone()).collect();
+    for input in sidecars {
+        if !seen.insert(input.book_hash.clone()) {
+            continue;
+        }
+        let path = match sidecar::write_outgoing(&dir, &input.book_hash, &input.manifest) {
+            Ok(path) => path,
+            Err(err) => {
+                log::warn!("skipping LocalSend sidecar of {}: {err}", input.book_hash);
+                continue;
+            }
+        };
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let file_name = format!("{}{}", input.book_hash, sidecar::SIDECAR_SUFFIX);
+        written.0.push(path.clone());
+        if !sidecar::is_sidecar(&file_name, size) {
+            log::warn!(
+                "skipping LocalSend sidecar of {}: too large",
+                input.book_hash
+            );
+            continue;
+        }
+        jobs.push(SendFileJob {
+            dto: FileDto {
+                id: uuid::Uuid::new_v4().to_string(),
+                file_name,
+                size,
+                file_type: "application/json".into(),
+                sha256: None,
+                preview: None,
+                metadata: None,
+            },
+            path,
+            sidecar: None,
+        });
+    }
+    written
+}
+
 /// Sends the given files to a device: prepare-upload, then one upload per
 /// accepted file, sequentially. Progress and the final outcome are emitted
 /// as `localsend:send-progress` / `localsend:send-end` events. Always clears
@@ -589,7 +896,8 @@ pub async fn run_send<R: Runtime>(
     identity: Arc<Identity>,
     port: u16,
     device: localsend::discovery::StatefulDevice,
-    jobs: Vec<SendFileJob>,
+    mut jobs: Vec<SendFileJob>,
+    pin: Option<String>,
     cancel_slot: SendCancelSlot,
 ) {
     use futures_util::StreamExt;
@@ -632,6 +940,12 @@ pub async fn run_send<R: Runtime>(
         Ok(client) => client,
         Err(err) => return fail(format!("client setup failed: {err}")),
     };
+    // Held until the send ends, whichever way it does.
+    let sidecars = if sidecar::is_readest_peer(device.device.device_model.as_deref()) {
+        attach_sidecars(&app, &mut jobs)
+    } else {
+        OutgoingSidecars(Vec::new())
+    };
 
     let token = cancel_slot
         .lock()
@@ -647,6 +961,7 @@ pub async fn run_send<R: Runtime>(
         info: identity.register_dto(port),
         files: files.clone(),
     };
+    let wrong_pin = pin.is_some();
     let prepared = match client
         .prepare_upload(
             protocol,
@@ -654,7 +969,7 @@ pub async fn run_send<R: Runtime>(
             peer_port,
             None,
             payload,
-            None,
+            pin,
             token.clone(),
         )
         .await
@@ -670,10 +985,9 @@ pub async fn run_send<R: Runtime>(
         }
         Err(ClientError::StatusCode(err)) => {
             let (status, message) = match err.status {
-                401 => (
-                    "error",
-                    "PIN protected receivers are not supported yet".to_string(),
-                ),
+                // The webview asks for the PIN and sends again.
+                401 if wrong_pin => ("pin_required", "wrong PIN".to_string()),
+                401 => ("pin_required", String::new()),
                 403 => ("declined", String::new()),
                 409 => ("error", "busy with another transfer".to_string()),
                 429 => ("error", "too many requests".to_string()),
@@ -711,10 +1025,19 @@ pub async fn run_send<R: Runtime>(
     let files_total = response.files.len();
     let mut sent_bytes = 0u64;
     let mut sent_files = 0usize;
+    // Not reported as sent files.
+    let mut sent_sidecars = 0usize;
 
-    // Upload sequentially in a stable order.
+    // Upload sequentially in a stable order, sidecars after the books they
+    // describe so the receiver has imported the book when its sidecar lands.
     let mut file_ids: Vec<&String> = response.files.keys().collect();
-    file_ids.sort_by_key(|id| &files[*id].file_name);
+    file_ids.sort_by_key(|id| {
+        let file = &files[*id];
+        (
+            sidecar::is_sidecar(&file.file_name, file.size),
+            &file.file_name,
+        )
+    });
     for file_id in file_ids {
         let job = jobs.iter().find(|j| &j.dto.id == file_id).unwrap();
         let body = {
@@ -725,6 +1048,10 @@ pub async fn run_send<R: Runtime>(
             let mut streamed = 0u64;
             let mut last_emit = std::time::Instant::now();
             let stream = ReceiverStream::new(FileContent::Path(job.path.clone()).into_receiver())
+                .then(|chunk: bytes::Bytes| async move {
+                    bandwidth::throttle_bytes(&chunk).await;
+                    chunk
+                })
                 .map(move |chunk: bytes::Bytes| {
                     streamed += chunk.len() as u64;
                     if last_emit.elapsed() >= std::time::Duration::from_millis(250) {
@@ -761,6 +1088,9 @@ pub async fn run_send<R: Runtime>(
             Ok(()) => {
                 sent_files += 1;
                 sent_bytes += files[file_id].size;
+                if sidecars.0.contains(&job.path) {
+                    sent_sidecars += 1;
+                }
             }
             Err(ClientError::Cancelled) => {
                 let by_peer = cancel_slot
@@ -779,7 +1109,7 @@ pub async fn run_send<R: Runtime>(
                     session_id: Some(response.session_id),
                     status: "cancelled".into(),
                     error: None,
-                    files_sent: sent_files,
+                    files_sent: sent_files - sent_sidecars,
                 });
             }
             Err(err) => {
@@ -793,7 +1123,7 @@ pub async fn run_send<R: Runtime>(
                         "failed to upload {}: {err}",
                         files[file_id].file_name
                     )),
-                    files_sent: sent_files,
+                    files_sent: sent_files - sent_sidecars,
                 });
             }
         }
@@ -802,7 +1132,7 @@ pub async fn run_send<R: Runtime>(
         session_id: Some(response.session_id),
         status: "sent".into(),
         error: None,
-        files_sent: sent_files,
+        files_sent: sent_files - sent_sidecars,
     });
 }
 
@@ -822,6 +1152,68 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn safe_relative_path_keeps_the_tree_below_the_inbox() {
+        let path = |parts: &[&str]| parts.iter().collect::<std::path::PathBuf>();
+        assert_eq!(
+            safe_relative_path("Dune Saga/01 Dune.epub"),
+            path(&["Dune Saga", "01 Dune.epub"])
+        );
+        assert_eq!(
+            safe_relative_path("../../etc/./passwd"),
+            path(&["etc", "passwd"])
+        );
+        assert_eq!(
+            safe_relative_path("/abs\\win\\book.epub"),
+            path(&["abs", "win", "book.epub"])
+        );
+        assert_eq!(safe_relative_path("../.."), path(&["received.bin"]));
+        assert_eq!(safe_relative_path(""), path(&["received.bin"]));
+    }
+
+    #[test]
+    fn group_name_is_the_folder_of_the_file() {
+        let group = |name: &str| group_name(&safe_relative_path(name));
+        assert_eq!(
+            group("Dune Saga/01 Dune.epub").as_deref(),
+            Some("Dune Saga")
+        );
+        assert_eq!(
+            group("Dune Saga\\Extras\\map.pdf").as_deref(),
+            Some("Dune Saga/Extras")
+        );
+        assert_eq!(group("dune.epub"), None);
+        assert_eq!(group("../dune.epub"), None);
+    }
+
+    #[test]
+    fn folder_inputs_name_files_by_relative_path() {
+        let dir = std::env::temp_dir().join(format!("ls-fi-{}", std::process::id()));
+        let root = dir.join("Foundation");
+        std::fs::create_dir_all(root.join("Extras")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("02 Foundation and Empire.epub"), b"x").unwrap();
+        std::fs::write(root.join("01 Foundation.epub"), b"x").unwrap();
+        std::fs::write(root.join("Extras").join("map.pdf"), b"x").unwrap();
+        std::fs::write(root.join(".DS_Store"), b"x").unwrap();
+        std::fs::write(root.join(".hidden").join("a.epub"), b"x").unwrap();
+
+        let inputs = folder_inputs(&root).unwrap();
+        let names: Vec<&str> = inputs.iter().map(|i| i.file_name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Foundation/01 Foundation.epub",
+                "Foundation/02 Foundation and Empire.epub",
+                "Foundation/Extras/map.pdf",
+            ]
+        );
+        assert_eq!(inputs[0].mime_type, "application/epub+zip");
+        assert_eq!(inputs[2].mime_type, "application/pdf");
+        assert!(folder_inputs(&root.join("Extras").join("missing")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn port_range_avoids_localsend_default() {
         // 53317 is left free for the LocalSend app, which has no fallback.
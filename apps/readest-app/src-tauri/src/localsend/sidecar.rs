@@ -0,0 +1,99 @@
+//! Readest-to-Readest book sidecars. When both ends are Readest, each book
+//! with a manifest from the webview travels with `<book hash>.readest.json`
+//! (metadata, progress, annotations, cover), uploaded after the books. The
+//! receiver hands it to the webview instead of importing it, and the webview
+//! merges it into the book it just imported. Other LocalSend apps never see
+//! one.
+
+use std::path::{Path, PathBuf};
+
+pub const SIDECAR_SUFFIX: &str = ".readest.json";
+/// Larger sidecars are treated as ordinary files; a manifest with a cover
+/// thumbnail and thousands of notes stays well below this.
+pub const MAX_SIDECAR_BYTES: u64 = 8 * 1024 * 1024;
+
+/// The book hash named by a sidecar file name, if `file_name` is one.
+pub fn sidecar_hash(file_name: &str) -> Option<&str> {
+    let hash = file_name.strip_suffix(SIDECAR_SUFFIX)?;
+    (!hash.is_empty() && hash.len() <= 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then_some(hash)
+}
+
+pub fn is_sidecar(file_name: &str, size: u64) -> bool {
+    size <= MAX_SIDECAR_BYTES && sidecar_hash(file_name).is_some()
+}
+
+/// How a Readest device says so on the wire. The LocalSend DTOs have no
+/// application field, so the marker rides in `deviceModel`, after the OS name
+/// other clients show as the device tag: "macOS (Readest)". Only peers that
+/// carry it are sent sidecars; the KOReader helper leaves it off, since it
+/// imports nothing but the book file itself.
+pub const READEST_MODEL_SUFFIX: &str = " (Readest)";
+
+/// The `deviceModel` this device advertises for the OS name `model`.
+pub fn advertised_model(model: &str) -> String {
+    format!("{model}{READEST_MODEL_SUFFIX}")
+}
+
+pub fn is_readest_peer(device_model: Option<&str>) -> bool {
+    device_model.is_some_and(|model| model.ends_with(READEST_MODEL_SUFFIX))
+}
+
+/// Writes the manifest for `book_hash` into `dir` for upload.
+pub fn write_outgoing(
+    dir: &Path,
+    book_hash: &str,
+    manifest: &serde_json::Value,
+) -> Result<PathBuf, String> {
+    let file_name = format!("{book_hash}{SIDECAR_SUFFIX}");
+    if sidecar_hash(&file_name).is_none() {
+        return Err(format!("invalid book hash {book_hash:?}"));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(file_name);
+    let bytes = serde_json::to_vec(manifest).map_err(|e| e.to_string())?;
+    std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_sidecar_names() {
+        let hash = "0123456789abcdef0123456789ABCDEF";
+        assert_eq!(sidecar_hash(&format!("{hash}.readest.json")), Some(hash));
+        assert!(is_sidecar(&format!("{hash}.readest.json"), 1024));
+        assert!(!is_sidecar(
+            &format!("{hash}.readest.json"),
+            MAX_SIDECAR_BYTES + 1
+        ));
+        assert_eq!(sidecar_hash(".readest.json"), None);
+        assert_eq!(sidecar_hash("notes.readest.json"), None);
+        assert_eq!(sidecar_hash("Shelf/abc.readest.json"), None);
+        assert_eq!(sidecar_hash("abc.json"), None);
+    }
+
+    #[test]
+    fn readest_peers_carry_the_marker() {
+        assert!(is_readest_peer(Some(&advertised_model("macOS"))));
+        assert!(is_readest_peer(Some("Readest (Readest)")));
+        assert!(!is_readest_peer(Some("macOS")));
+        assert!(!is_readest_peer(Some("KOReader")));
+        assert!(!is_readest_peer(None));
+    }
+
+    #[test]
+    fn writes_outgoing_manifests_by_hash() {
+        let dir = std::env::temp_dir().join(format!("ls-sidecar-{}", std::process::id()));
+        let manifest = serde_json::json!({ "bookHash": "abc" });
+        let path = write_outgoing(&dir, "abc", &manifest).unwrap();
+        assert_eq!(path, dir.join("abc.readest.json"));
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written, manifest);
+        assert!(write_outgoing(&dir, "../abc", &manifest).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
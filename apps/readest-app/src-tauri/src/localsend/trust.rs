@@ -0,0 +1,211 @@
+//! Devices the user trusts to send without the accept dialog, keyed by
+//! certificate fingerprint and persisted in `<app_data>/localsend/trusted.json`.
+//! The file is the source of truth: the commands edit it and hand the running
+//! service a fresh copy, so the list can be managed while LocalSend is off.
+
+use super::sidecar;
+use localsend::model::transfer::FileDto;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Extensions a `books` policy accepts, as `SUPPORTED_BOOK_EXTS` lists them.
+const BOOK_EXTENSIONS: &[&str] = &[
+    "epub", "mobi", "azw", "azw3", "fb2", "zip", "cbz", "pdf", "txt", "md",
+];
+
+pub type TrustSlot = Arc<StdMutex<TrustList>>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TrustPolicy {
+    /// Accept every file.
+    Always,
+    /// Accept when every file is a book no larger than `max_bytes` (or a
+    /// Readest sidecar); ask otherwise.
+    Books {
+        #[serde(rename = "maxBytes")]
+        max_bytes: u64,
+    },
+    /// Remembered, but still asks.
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedDevice {
+    pub fingerprint: String,
+    /// The alias when the device was trusted, for the settings list.
+    pub alias: String,
+    pub policy: TrustPolicy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustList {
+    devices: Vec<TrustedDevice>,
+}
+
+fn is_book(file_name: &str) -> bool {
+    file_name.rsplit_once('.').is_some_and(|(_, ext)| {
+        BOOK_EXTENSIONS
+            .iter()
+            .any(|book| ext.eq_ignore_ascii_case(book))
+    })
+}
+
+impl TrustList {
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("ignoring unreadable LocalSend trust list: {e}");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(self).map_err(std::io::Error::other)?;
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    pub fn devices(&self) -> &[TrustedDevice] {
+        &self.devices
+    }
+
+    /// Adds `device`, or replaces the entry with its fingerprint.
+    pub fn upsert(&mut self, device: TrustedDevice) {
+        match self
+            .devices
+            .iter_mut()
+            .find(|d| d.fingerprint == device.fingerprint)
+        {
+            Some(existing) => *existing = device,
+            None => self.devices.push(device),
+        }
+    }
+
+    pub fn remove(&mut self, fingerprint: &str) -> bool {
+        let before = self.devices.len();
+        self.devices.retain(|d| d.fingerprint != fingerprint);
+        self.devices.len() != before
+    }
+
+    /// Whether a transfer of `files` from the device presenting the
+    /// certificate `cert_fingerprint` skips the accept dialog. Senders
+    /// without a client certificate only claim a fingerprint in the request
+    /// body, which anyone can copy, so they always ask.
+    pub fn auto_accepts(
+        &self,
+        cert_fingerprint: Option<&str>,
+        files: &HashMap<String, FileDto>,
+    ) -> bool {
+        let Some(fingerprint) = cert_fingerprint else {
+            return false;
+        };
+        let Some(device) = self.devices.iter().find(|d| d.fingerprint == fingerprint) else {
+            return false;
+        };
+        match device.policy {
+            TrustPolicy::Always => true,
+            TrustPolicy::Books { max_bytes } => files.values().all(|f| {
+                (is_book(&f.file_name) && f.size <= max_bytes)
+                    || sidecar::is_sidecar(&f.file_name, f.size)
+            }),
+            TrustPolicy::Ask => false,
+        }
+    }
+}
+
+pub fn store_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|d| d.join("localsend").join("trusted.json"))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(entries: &[(&str, u64)]) -> HashMap<String, FileDto> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, (name, size))| {
+                let dto = FileDto {
+                    id: i.to_string(),
+                    file_name: name.to_string(),
+                    size: *size,
+                    file_type: String::new(),
+                    sha256: None,
+                    preview: None,
+                    metadata: None,
+                };
+                (dto.id.clone(), dto)
+            })
+            .collect()
+    }
+
+    fn trusted(fingerprint: &str, policy: TrustPolicy) -> TrustedDevice {
+        TrustedDevice {
+            fingerprint: fingerprint.into(),
+            alias: "Laptop".into(),
+            policy,
+        }
+    }
+
+    #[test]
+    fn policies_decide_by_certificate_fingerprint() {
+        let mut list = TrustList::default();
+        list.upsert(trusted("laptop", TrustPolicy::Always));
+        list.upsert(trusted("phone", TrustPolicy::Books { max_bytes: 100 }));
+        list.upsert(trusted("tablet", TrustPolicy::Ask));
+
+        let anything = files(&[("notes.docx", 1_000)]);
+        let books = files(&[
+            ("Dune.EPUB", 100),
+            ("Emma.pdf", 10),
+            ("0123abcd.readest.json", 1_000),
+        ]);
+        assert!(list.auto_accepts(Some("laptop"), &anything));
+        assert!(list.auto_accepts(Some("phone"), &books));
+        assert!(!list.auto_accepts(Some("phone"), &anything));
+        assert!(!list.auto_accepts(Some("phone"), &files(&[("Dune.epub", 101)])));
+        assert!(!list.auto_accepts(Some("tablet"), &books));
+        assert!(!list.auto_accepts(Some("stranger"), &books));
+        // A body fingerprint alone proves nothing.
+        assert!(!list.auto_accepts(None, &books));
+    }
+
+    #[test]
+    fn upserts_removes_and_round_trips() {
+        let dir = std::env::temp_dir().join(format!("ls-trust-{}", std::process::id()));
+        let path = dir.join("trusted.json");
+        let mut list = TrustList::default();
+        list.upsert(trusted("laptop", TrustPolicy::Ask));
+        list.upsert(trusted("laptop", TrustPolicy::Books { max_bytes: 5 }));
+        list.upsert(trusted("phone", TrustPolicy::Always));
+        assert!(list.remove("phone"));
+        assert!(!list.remove("phone"));
+        list.save(&path).unwrap();
+
+        let loaded = TrustList::load(&path);
+        assert_eq!(
+            loaded.devices(),
+            &[trusted("laptop", TrustPolicy::Books { max_bytes: 5 })]
+        );
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json["devices"][0]["policy"]["kind"], "books");
+        assert_eq!(json["devices"][0]["policy"]["maxBytes"], 5);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -16,6 +16,9 @@
 // What `parse_mobi_metadata` still does on the import hot path:
 //   - compute partialMD5 over the file (matches `utils/md5.ts::partialMD5`,
 //     shared with `epub_parser` via `parser_common::compute_partial_md5`);
+//   - refuse Kindle / Mobipocket DRM (PalmDOC header encryption type 1 or
+//     2) with `ParseError::DrmProtected`, before the `mobi` crate reads
+//     the whole file;
 //   - parse the PalmDB / MobiHeader / EXTH headers via the `mobi` crate
 //     just enough to locate the cover record;
 //   - locate the cover image (EXTH `CoverOffset` 201 → `ThumbOffset` 202
@@ -35,9 +38,13 @@
 use mobi::headers::ExthRecord;
 use mobi::Mobi;
 use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 
-use crate::parser_common::{compute_partial_md5, maybe_resize_cover, RawCoverImage};
+use crate::parser_common::{
+    compute_partial_md5, maybe_resize_cover, sniff_image_mime, DrmScheme, ParseError, RawCoverImage,
+};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -48,6 +55,13 @@ pub struct ParsedMobi {
     /// in a Blob and overrides foliate's `getCover()` so the on-disk
     /// thumbnail wins over foliate's full-resolution decode.
     pub cover: Option<RawCoverImage>,
+    /// The header's title (EXTH updated title, else the PalmDB name) and
+    /// EXTH author, for the native LocalSend importer. The webview reads
+    /// metadata through foliate-js instead, so they stay off the IPC payload.
+    #[serde(skip)]
+    pub title: Option<String>,
+    #[serde(skip)]
+    pub author: Option<String>,
 }
 
 /// Tauri command: parse a MOBI/AZW/AZW3 file's partialMD5 + cover and
@@ -57,20 +71,26 @@ pub struct ParsedMobi {
 /// whole file synchronously and parsing a 50 MB AZW3 can take tens of
 /// milliseconds — long enough to want it off the Tauri main runtime.
 #[tauri::command]
-pub async fn parse_mobi_metadata(file_path: String) -> Result<ParsedMobi, String> {
+pub async fn parse_mobi_metadata(file_path: String) -> Result<ParsedMobi, ParseError> {
     tauri::async_runtime::spawn_blocking(move || parse_mobi_metadata_sync(&file_path))
         .await
         .map_err(|e| format!("join error: {e}"))?
 }
 
-fn parse_mobi_metadata_sync(file_path: &str) -> Result<ParsedMobi, String> {
+fn parse_mobi_metadata_sync(file_path: &str) -> Result<ParsedMobi, ParseError> {
     let path = Path::new(file_path);
     if !path.is_file() {
-        return Err(format!("file not found: {file_path}"));
+        return Err(format!("file not found: {file_path}").into());
     }
 
     let partial_md5 = compute_partial_md5(path).map_err(|e| format!("partial_md5 failed: {e}"))?;
 
+    // A header we can't read is left to the `mobi` crate to report; only a
+    // positive encryption flag short-circuits the import.
+    if read_palmdoc_encryption(path).is_ok_and(|enc| enc != 0) {
+        return Err(ParseError::DrmProtected(DrmScheme::Kindle));
+    }
+
     let mobi = Mobi::from_path(path).map_err(|e| format!("parse mobi: {e}"))?;
 
     let cover = extract_cover(&mobi).map(|raw| {
@@ -78,7 +98,15 @@ fn parse_mobi_metadata_sync(file_path: &str) -> Result<ParsedMobi, String> {
         RawCoverImage { bytes, mime }
     });
 
-    Ok(ParsedMobi { partial_md5, cover })
+    let title = Some(mobi.title()).filter(|t| !t.trim().is_empty());
+    let author = mobi.author().filter(|a| !a.trim().is_empty());
+
+    Ok(ParsedMobi {
+        partial_md5,
+        cover,
+        title,
+        author,
+    })
 }
 
 /// Extract the *original* (un-resized) cover bytes from a MOBI / AZW / AZW3.
@@ -178,6 +206,23 @@ fn extract_cover_inner(mobi: &Mobi) -> Option<RawCoverImage> {
     Some(RawCoverImage { bytes, mime })
 }
 
+/// Read the PalmDOC `encryption` field (0 = none, 1 = old Mobipocket,
+/// 2 = Mobipocket / Kindle DRM) without loading the file.
+///
+/// Layout: the PalmDB header is 78 bytes, followed by the record list whose
+/// first 4-byte entry is record 0's file offset. Record 0 starts with the
+/// 16-byte PalmDOC header; `encryption` is the big-endian u16 at offset 12.
+pub(crate) fn read_palmdoc_encryption(path: &Path) -> std::io::Result<u16> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; 82];
+    file.read_exact(&mut header)?;
+    let record0 = u32::from_be_bytes([header[78], header[79], header[80], header[81]]);
+    file.seek(SeekFrom::Start(u64::from(record0) + 12))?;
+    let mut encryption = [0u8; 2];
+    file.read_exact(&mut encryption)?;
+    Ok(u16::from_be_bytes(encryption))
+}
+
 /// Read the first occurrence of `record` and interpret its payload as
 /// a 4-byte big-endian u32. EXTH offset records (201 / 202 / 116, etc.)
 /// follow this convention. Returns `None` if the record is absent or
@@ -191,33 +236,6 @@ fn read_exth_u32(mobi: &Mobi, record: ExthRecord) -> Option<u32> {
     Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
 }
 
-/// Best-effort MIME sniffing from magic bytes for the formats MOBI
-/// covers are realistically stored as. Falls back to "image/jpeg" —
-/// the dominant case — when the magic is unknown, because
-/// `image::load_from_memory` (called downstream by
-/// `maybe_resize_cover`) will detect the real format anyway and the
-/// hint MIME is only used when we *don't* re-encode (small covers,
-/// kept verbatim).
-///
-/// BMP is included because some early KindleGen builds (and a few
-/// self-published .prc files) shipped BMP covers; the JS thumbnail
-/// pipeline can render BMP via the same downscale path.
-fn sniff_image_mime(bytes: &[u8]) -> &'static str {
-    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
-        "image/jpeg"
-    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
-        "image/png"
-    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
-        "image/gif"
-    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
-        "image/webp"
-    } else if bytes.starts_with(b"BM") {
-        "image/bmp"
-    } else {
-        "image/jpeg"
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,6 +263,46 @@ mod tests {
         assert_eq!(cover.map(|c| c.mime), Some("image/jpeg".to_string()));
     }
 
+    fn write_palmdb(encryption: u16) -> std::path::PathBuf {
+        // Minimal PalmDB: 78-byte header, one record-list entry pointing at
+        // offset 88 (8-byte entry + 2 bytes of padding), then a PalmDOC
+        // header carrying `encryption`.
+        let mut bytes = vec![0u8; 78];
+        bytes[60..68].copy_from_slice(b"BOOKMOBI");
+        bytes[77] = 1;
+        bytes.extend_from_slice(&88u32.to_be_bytes());
+        bytes.extend_from_slice(&[0; 6]);
+        let mut palmdoc = [0u8; 16];
+        palmdoc[12..14].copy_from_slice(&encryption.to_be_bytes());
+        bytes.extend_from_slice(&palmdoc);
+        let path = std::env::temp_dir().join(format!(
+            "readest-mobi-drm-{}-{encryption}.azw",
+            std::process::id()
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_palmdoc_encryption_reads_record0_header() {
+        for encryption in [0u16, 2] {
+            let path = write_palmdb(encryption);
+            assert_eq!(read_palmdoc_encryption(&path).unwrap(), encryption);
+            std::fs::remove_file(path).ok();
+        }
+    }
+
+    #[test]
+    fn parse_mobi_metadata_rejects_kindle_drm() {
+        let path = write_palmdb(2);
+        let err = parse_mobi_metadata_sync(path.to_str().unwrap()).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(err, ParseError::DrmProtected(DrmScheme::Kindle)));
+        assert!(err
+            .to_string()
+            .starts_with(crate::parser_common::DRM_ERROR_PREFIX));
+    }
+
     #[test]
     fn sniff_image_mime_jpeg() {
         assert_eq!(sniff_image_mime(&[0xFF, 0xD8, 0xFF, 0xE0]), "image/jpeg");
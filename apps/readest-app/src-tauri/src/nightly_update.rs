@@ -103,8 +103,11 @@ pub async fn install_nightly_update<R: tauri::Runtime>(
     use tauri_plugin_updater::UpdaterExt;
 
     let url = Url::parse(&endpoint).map_err(|e| e.to_string())?;
-    let updater = app
-        .updater_builder()
+    let mut builder = app.updater_builder();
+    if let Some(proxy) = crate::http_client::proxy_url() {
+        builder = builder.proxy(proxy);
+    }
+    let updater = builder
         .endpoints(vec![url])
         .map_err(|e| e.to_string())?
         .version_comparator(|current, release| {
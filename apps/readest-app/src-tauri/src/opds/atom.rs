@@ -0,0 +1,433 @@
+//! OPDS 1.2 (Atom) feeds and entries.
+
+use reqwest::Url;
+
+use super::xml::Element;
+use super::{
+    resolve, Acquisition, AcquisitionKind, Contributor, Facet, FacetGroup, Feed, Group, Link,
+    NavigationEntry, Price, Publication, REL_FACET, REL_IMAGE, REL_THUMBNAIL,
+};
+
+const REL_GROUP: &str = "http://opds-spec.org/group";
+const REL_COVER_LEGACY: &str = "http://opds-spec.org/cover";
+const REL_THUMBNAIL_LEGACY: &str = "http://opds-spec.org/thumbnail";
+const REL_STANZA_COVER: &str = "x-stanza-cover-image";
+const REL_STANZA_THUMBNAIL: &str = "x-stanza-cover-image-thumbnail";
+
+fn link(base: &Url, e: &Element) -> Option<Link> {
+    let href = e.attr("href")?;
+    Some(Link {
+        href: resolve(base, href),
+        rel: e
+            .attr("rel")
+            .map(|r| r.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        media_type: e.attr("type").map(str::to_string),
+        title: e.attr("title").map(str::to_string),
+        templated: false,
+        count: e.attr("count").and_then(|c| c.trim().parse().ok()),
+    })
+}
+
+fn acquisition(base: &Url, e: &Element, kind: AcquisitionKind) -> Option<Acquisition> {
+    let link = link(base, e)?;
+    let price = e.child("price").and_then(|p| {
+        Some(Price {
+            value: p.text().parse().ok()?,
+            currency: p.attr("currencycode").unwrap_or("USD").to_string(),
+        })
+    });
+    // Nested `<opds:indirectAcquisition type="…">`, outermost first.
+    let mut indirect_types = Vec::new();
+    let mut next = e.child("indirectAcquisition");
+    while let Some(indirect) = next {
+        if let Some(t) = indirect.attr("type") {
+            indirect_types.push(t.to_string());
+        }
+        next = indirect.child("indirectAcquisition");
+    }
+    Some(Acquisition {
+        href: link.href,
+        kind,
+        media_type: link.media_type,
+        title: link.title,
+        length: e.attr("length").and_then(|l| l.trim().parse().ok()),
+        price,
+        indirect_types,
+    })
+}
+
+/// Atom `summary` / `content`, as (plain text, HTML).
+fn descriptions(entry: &Element) -> (Option<String>, Option<String>) {
+    let summary = entry.child_text("summary");
+    let content = entry.child("content").and_then(|c| {
+        let html = match c.attr("type").unwrap_or("text") {
+            "xhtml" => c.inner_markup(),
+            "html" | "text/html" => c.text(),
+            _ => return None,
+        };
+        (!html.is_empty()).then_some(html)
+    });
+    // A plain-text <content> is a summary by another name.
+    let summary = summary.or_else(|| {
+        entry
+            .child("content")
+            .filter(|c| matches!(c.attr("type"), None | Some("text")))
+            .map(Element::text)
+            .filter(|t| !t.is_empty())
+    });
+    (summary, content)
+}
+
+fn is_image(link: &Link) -> bool {
+    link.has_rel(REL_IMAGE) || link.has_rel(REL_COVER_LEGACY) || link.has_rel(REL_STANZA_COVER)
+}
+
+fn is_thumbnail(link: &Link) -> bool {
+    link.has_rel(REL_THUMBNAIL)
+        || link.has_rel(REL_THUMBNAIL_LEGACY)
+        || link.has_rel(REL_STANZA_THUMBNAIL)
+}
+
+/// An entry is a publication as soon as it has an acquisition link;
+/// otherwise it points at another feed.
+fn is_publication(entry: &Element) -> bool {
+    entry.children_named("link").any(|l| {
+        l.attr("rel")
+            .into_iter()
+            .flat_map(str::split_whitespace)
+            .any(|r| AcquisitionKind::from_rel(r).is_some())
+    })
+}
+
+pub(super) fn publication(base: &Url, entry: &Element) -> Publication {
+    let (summary, content) = descriptions(entry);
+    let mut publication = Publication {
+        id: entry.child_text("id"),
+        title: entry.child_text("title").unwrap_or_default(),
+        subtitle: entry.child_text("subtitle"),
+        authors: entry
+            .children_named("author")
+            .filter_map(|a| {
+                Some(Contributor {
+                    name: a.child_text("name")?,
+                    href: a.child_text("uri").map(|u| resolve(base, &u)),
+                })
+            })
+            .collect(),
+        // Dublin Core, as Calibre and Kavita emit it.
+        publisher: entry.child_text("publisher"),
+        published: entry
+            .child_text("issued")
+            .or_else(|| entry.child_text("published")),
+        updated: entry.child_text("updated"),
+        languages: entry
+            .children_named("language")
+            .map(Element::text)
+            .filter(|l| !l.is_empty())
+            .collect(),
+        subjects: entry
+            .children_named("category")
+            .filter_map(|c| c.attr("label").or_else(|| c.attr("term")))
+            .map(str::to_string)
+            .collect(),
+        summary,
+        content,
+        ..Default::default()
+    };
+    publication.id = publication.id.or_else(|| entry.child_text("identifier"));
+
+    for e in entry.children_named("link") {
+        let Some(l) = link(base, e) else { continue };
+        if let Some(kind) = l.rel.iter().find_map(|r| AcquisitionKind::from_rel(r)) {
+            publication.acquisitions.extend(acquisition(base, e, kind));
+        } else if is_thumbnail(&l) {
+            publication.thumbnail.get_or_insert(l.href);
+        } else if is_image(&l) {
+            publication.cover.get_or_insert(l.href);
+        } else {
+            publication.links.push(l);
+        }
+    }
+    if publication.thumbnail.is_none() {
+        publication.thumbnail = publication.cover.clone();
+    }
+    publication
+}
+
+fn navigation(base: &Url, entry: &Element) -> Option<NavigationEntry> {
+    // Prefer the link that leads to another catalog feed.
+    let links: Vec<Link> = entry
+        .children_named("link")
+        .filter_map(|e| link(base, e))
+        .collect();
+    let target = links
+        .iter()
+        .find(|l| {
+            l.media_type
+                .as_deref()
+                .is_some_and(|t| t.contains("opds-catalog") || t.contains("atom+xml"))
+        })
+        .or_else(|| links.first())?;
+    let (summary, _) = descriptions(entry);
+    Some(NavigationEntry {
+        title: entry
+            .child_text("title")
+            .or_else(|| target.title.clone())
+            .unwrap_or_default(),
+        href: target.href.clone(),
+        media_type: target.media_type.clone(),
+        rel: target.rel.clone(),
+        summary,
+        count: target.count,
+    })
+}
+
+/// An entry's `http://opds-spec.org/group` link files it under a shelf.
+fn group_link(base: &Url, entry: &Element) -> Option<Link> {
+    entry
+        .children_named("link")
+        .filter_map(|e| link(base, e))
+        .find(|l| l.has_rel(REL_GROUP))
+}
+
+pub(super) fn feed(base: &Url, root: &Element) -> Feed {
+    let mut feed = Feed {
+        id: root.child_text("id"),
+        title: root.child_text("title").unwrap_or_default(),
+        subtitle: root.child_text("subtitle"),
+        updated: root.child_text("updated"),
+        ..Default::default()
+    };
+
+    for e in root.children_named("link") {
+        let Some(l) = link(base, e) else { continue };
+        if l.has_rel(REL_FACET) {
+            let title = e.attr("facetGroup").unwrap_or_default().to_string();
+            let facet = Facet {
+                active: e.attr("activeFacet") == Some("true"),
+                link: l,
+            };
+            match feed.facets.iter_mut().find(|g| g.title == title) {
+                Some(group) => group.facets.push(facet),
+                None => feed.facets.push(FacetGroup {
+                    title,
+                    facets: vec![facet],
+                }),
+            }
+        } else {
+            feed.links.push(l);
+        }
+    }
+
+    for entry in root.children_named("entry") {
+        let grouped = group_link(base, entry);
+        let group = grouped.map(|g| {
+            let title = g.title.clone().unwrap_or_default();
+            let index = match feed.groups.iter().position(|x| x.title == title) {
+                Some(i) => i,
+                None => {
+                    feed.groups.push(Group {
+                        title,
+                        href: Some(g.href),
+                        ..Default::default()
+                    });
+                    feed.groups.len() - 1
+                }
+            };
+            &mut feed.groups[index]
+        });
+        if is_publication(entry) {
+            let publication = publication(base, entry);
+            match group {
+                Some(group) => group.publications.push(publication),
+                None => feed.publications.push(publication),
+            }
+        } else if let Some(nav) = navigation(base, entry) {
+            match group {
+                Some(group) => group.navigation.push(nav),
+                None => feed.navigation.push(nav),
+            }
+        }
+    }
+
+    // OpenSearch response elements carry the page counts.
+    let count = |name: &str| root.child_text(name).and_then(|t| t.parse().ok());
+    feed.pagination.total_results = count("totalResults");
+    feed.pagination.items_per_page = count("itemsPerPage");
+    feed.pagination.start_index = count("startIndex");
+    feed.finish_links();
+    feed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{xml, AcquisitionKind};
+    use super::{feed, publication};
+    use reqwest::Url;
+
+    // Trimmed from a Calibre-Web "new books" page.
+    const ACQUISITION_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/terms/"
+      xmlns:opds="http://opds-spec.org/2010/catalog"
+      xmlns:opensearch="http://a9.com/-/spec/opensearch/1.1/"
+      xmlns:thr="http://purl.org/syndication/thread/1.0">
+  <id>urn:uuid:new</id>
+  <title>New Books</title>
+  <updated>2026-10-01T10:00:00+00:00</updated>
+  <link rel="self" href="/opds/new?offset=30" type="application/atom+xml;profile=opds-catalog;kind=acquisition"/>
+  <link rel="start" href="/opds" type="application/atom+xml;profile=opds-catalog;kind=navigation"/>
+  <link rel="search" href="/opds/osd" type="application/opensearchdescription+xml"/>
+  <link rel="next" href="/opds/new?offset=60" type="application/atom+xml;profile=opds-catalog;kind=acquisition"/>
+  <link rel="previous" href="/opds/new?offset=0" type="application/atom+xml;profile=opds-catalog;kind=acquisition"/>
+  <link rel="http://opds-spec.org/facet" href="/opds/new?sort=title" title="Title" opds:facetGroup="Sort" opds:activeFacet="true" thr:count="84"/>
+  <link rel="http://opds-spec.org/facet" href="/opds/new?sort=date" title="Date" opds:facetGroup="Sort"/>
+  <opensearch:totalResults>84</opensearch:totalResults>
+  <opensearch:itemsPerPage>30</opensearch:itemsPerPage>
+  <opensearch:startIndex>31</opensearch:startIndex>
+  <entry>
+    <title>Dune</title>
+    <id>urn:uuid:6b0c</id>
+    <updated>2026-09-30T08:00:00+00:00</updated>
+    <author><name>Frank Herbert</name><uri>/opds/author/12</uri></author>
+    <dc:language>en</dc:language>
+    <dc:publisher>Chilton</dc:publisher>
+    <dc:issued>1965</dc:issued>
+    <category term="sf" label="Science Fiction"/>
+    <summary>Arrakis.</summary>
+    <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>Spice &amp; sand</p></div></content>
+    <link type="image/jpeg" href="/opds/cover/7" rel="http://opds-spec.org/image"/>
+    <link type="image/jpeg" href="/opds/cover_240_240/7" rel="http://opds-spec.org/image/thumbnail"/>
+    <link rel="http://opds-spec.org/acquisition" href="/opds/download/7/epub/" length="523001" mtime="2026-09-30T08:00:00+00:00" type="application/epub+zip"/>
+    <link rel="http://opds-spec.org/acquisition/buy" href="https://shop.example/dune" type="text/html">
+      <opds:price currencycode="EUR">9.99</opds:price>
+      <opds:indirectAcquisition type="application/vnd.adobe.adept+xml">
+        <opds:indirectAcquisition type="application/epub+zip"/>
+      </opds:indirectAcquisition>
+    </link>
+  </entry>
+</feed>"#;
+
+    const NAVIGATION_FEED: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Catalog</title>
+  <entry>
+    <title>Authors</title>
+    <id>authors</id>
+    <content type="text">Books by author</content>
+    <link rel="subsection" href="authors/" type="application/atom+xml;profile=opds-catalog;kind=navigation"/>
+  </entry>
+  <entry>
+    <title>Hugo winners</title>
+    <link rel="http://opds-spec.org/group" href="shelves/hugo" title="Awards"/>
+    <link rel="http://opds-spec.org/acquisition/open-access" href="/free/1.epub" type="application/epub+zip"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn acquisition_feed_maps_entries_facets_and_pagination() {
+        let base = Url::parse("https://books.example/opds/new?offset=30").unwrap();
+        let root = xml::parse(ACQUISITION_FEED.as_bytes()).unwrap();
+        let feed = feed(&base, &root);
+
+        assert_eq!(feed.title, "New Books");
+        assert!(feed.navigation.is_empty());
+        assert_eq!(
+            feed.pagination.next.as_deref(),
+            Some("https://books.example/opds/new?offset=60")
+        );
+        assert_eq!(
+            feed.pagination.previous.as_deref(),
+            Some("https://books.example/opds/new?offset=0")
+        );
+        assert_eq!(feed.pagination.total_results, Some(84));
+        assert_eq!(feed.pagination.start_index, Some(31));
+        assert_eq!(
+            feed.search.as_ref().unwrap().href,
+            "https://books.example/opds/osd"
+        );
+
+        assert_eq!(feed.facets.len(), 1);
+        assert_eq!(feed.facets[0].title, "Sort");
+        assert!(feed.facets[0].facets[0].active);
+        assert_eq!(feed.facets[0].facets[0].link.count, Some(84));
+        assert!(!feed.facets[0].facets[1].active);
+
+        let dune = &feed.publications[0];
+        assert_eq!(dune.title, "Dune");
+        assert_eq!(dune.authors[0].name, "Frank Herbert");
+        assert_eq!(
+            dune.authors[0].href.as_deref(),
+            Some("https://books.example/opds/author/12")
+        );
+        assert_eq!(dune.languages, ["en"]);
+        assert_eq!(dune.publisher.as_deref(), Some("Chilton"));
+        assert_eq!(dune.published.as_deref(), Some("1965"));
+        assert_eq!(dune.subjects, ["Science Fiction"]);
+        assert_eq!(dune.summary.as_deref(), Some("Arrakis."));
+        assert_eq!(
+            dune.content.as_deref(),
+            Some("<div><p>Spice &amp; sand</p></div>")
+        );
+        assert_eq!(
+            dune.cover.as_deref(),
+            Some("https://books.example/opds/cover/7")
+        );
+        assert_eq!(
+            dune.thumbnail.as_deref(),
+            Some("https://books.example/opds/cover_240_240/7")
+        );
+
+        let epub = &dune.acquisitions[0];
+        assert_eq!(epub.kind, AcquisitionKind::Generic);
+        assert_eq!(epub.href, "https://books.example/opds/download/7/epub/");
+        assert_eq!(epub.media_type.as_deref(), Some("application/epub+zip"));
+        assert_eq!(epub.length, Some(523001));
+
+        let buy = &dune.acquisitions[1];
+        assert_eq!(buy.kind, AcquisitionKind::Buy);
+        assert_eq!(buy.price.as_ref().unwrap().currency, "EUR");
+        assert_eq!(
+            buy.indirect_types,
+            ["application/vnd.adobe.adept+xml", "application/epub+zip"]
+        );
+    }
+
+    #[test]
+    fn navigation_feed_and_groups() {
+        let base = Url::parse("https://books.example/opds/").unwrap();
+        let root = xml::parse(NAVIGATION_FEED.as_bytes()).unwrap();
+        let feed = feed(&base, &root);
+
+        assert_eq!(feed.navigation.len(), 1);
+        let authors = &feed.navigation[0];
+        assert_eq!(authors.title, "Authors");
+        assert_eq!(authors.href, "https://books.example/opds/authors/");
+        assert_eq!(authors.summary.as_deref(), Some("Books by author"));
+
+        assert!(feed.publications.is_empty());
+        assert_eq!(feed.groups.len(), 1);
+        assert_eq!(feed.groups[0].title, "Awards");
+        assert_eq!(
+            feed.groups[0].href.as_deref(),
+            Some("https://books.example/opds/shelves/hugo")
+        );
+        assert_eq!(
+            feed.groups[0].publications[0].acquisitions[0].kind,
+            AcquisitionKind::OpenAccess
+        );
+    }
+
+    #[test]
+    fn standalone_entry_uses_cover_as_thumbnail() {
+        let base = Url::parse("https://books.example/").unwrap();
+        let root = xml::parse(
+            br#"<entry xmlns="http://www.w3.org/2005/Atom"><title>Solo</title>
+  <link rel="x-stanza-cover-image" href="c.jpg"/>
+  <link rel="http://opds-spec.org/acquisition" href="solo.epub"/></entry>"#,
+        )
+        .unwrap();
+        let p = publication(&base, &root);
+        assert_eq!(p.thumbnail.as_deref(), Some("https://books.example/c.jpg"));
+        assert_eq!(p.acquisitions[0].href, "https://books.example/solo.epub");
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, State};
+
+use super::client::fetch;
+use super::opensearch::expand_template;
+use super::server::{self, Library, RunningServer, SharedBook};
+use super::{Error, Feed, OpdsAuth, OpdsDocument, OpdsServerState, Result};
+use crate::localsend::commands::local_ips;
+use crate::transfer_file::ensure_path_allowed;
+
+/// Fetch and parse one catalog document: a feed, a publication entry or an
+/// OpenSearch description.
+#[command]
+pub async fn opds_fetch(
+    url: String,
+    auth: Option<OpdsAuth>,
+    headers: Option<HashMap<String, String>>,
+) -> Result<OpdsDocument> {
+    fetch(&url, auth.as_ref(), &headers.unwrap_or_default()).await
+}
+
+/// Run a search from a feed's `search` link. `href` is either a templated
+/// OPDS 2.0 URL (`templated: true`) or an OpenSearch description, which is
+/// fetched first. `params` fills the template; `searchTerms` and `query`
+/// stand in for each other so callers needn't know which flavour the
+/// catalog speaks.
+#[command]
+pub async fn opds_search(
+    href: String,
+    templated: Option<bool>,
+    mut params: HashMap<String, String>,
+    auth: Option<OpdsAuth>,
+    headers: Option<HashMap<String, String>>,
+) -> Result<Feed> {
+    let headers = headers.unwrap_or_default();
+    let template = if templated.unwrap_or(false) {
+        href
+    } else {
+        match fetch(&href, auth.as_ref(), &headers).await? {
+            OpdsDocument::Search(description) => description.template,
+            _ => return Err(Error::NoSearch),
+        }
+    };
+
+    for (from, to) in [("searchTerms", "query"), ("query", "searchTerms")] {
+        if let Some(value) = params.get(from).cloned() {
+            params.entry(to.to_string()).or_insert(value);
+        }
+    }
+    let url = expand_template(&template, &params);
+    match fetch(&url, auth.as_ref(), &headers).await? {
+        OpdsDocument::Feed(feed) => Ok(feed),
+        _ => Err(Error::Parse("search did not return a feed".into())),
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpdsServerStatus {
+    pub running: bool,
+    pub port: u16,
+    /// The pairing PIN readers enter as the catalog password.
+    pub pin: String,
+    /// Non-loopback IPv4 addresses, to show `http://<ip>:<port>/opds`.
+    pub local_ips: Vec<String>,
+    pub book_count: usize,
+}
+
+fn status_of(server: Option<&RunningServer>) -> OpdsServerStatus {
+    match server {
+        Some(server) => OpdsServerStatus {
+            running: true,
+            port: server.port,
+            pin: server.pin.clone(),
+            local_ips: local_ips(),
+            book_count: server.library.read().unwrap().books.len(),
+        },
+        None => OpdsServerStatus {
+            running: false,
+            port: 0,
+            pin: String::new(),
+            local_ips: Vec::new(),
+            book_count: 0,
+        },
+    }
+}
+
+/// Start sharing the library. `pin` keeps a pairing across restarts; a new
+/// one is generated when it's missing. Starting a running server returns
+/// its status unchanged.
+#[command]
+pub async fn opds_server_start(
+    app: AppHandle,
+    state: State<'_, OpdsServerState>,
+    books_dir: String,
+    cache_dir: String,
+    pin: Option<String>,
+) -> std::result::Result<OpdsServerStatus, String> {
+    ensure_path_allowed(&app, &books_dir).map_err(|e| e.to_string())?;
+    ensure_path_allowed(&app, &cache_dir).map_err(|e| e.to_string())?;
+
+    let mut guard = state.0.lock().await;
+    if guard.is_none() {
+        let pin = pin
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or_else(server::generate_pin);
+        let library = Library {
+            books_dir: PathBuf::from(books_dir),
+            cache_dir: PathBuf::from(cache_dir),
+            books: Vec::new(),
+        };
+        *guard = Some(server::start(library, pin).await?);
+    }
+    Ok(status_of(guard.as_ref()))
+}
+
+#[command]
+pub async fn opds_server_stop(
+    state: State<'_, OpdsServerState>,
+) -> std::result::Result<(), String> {
+    if let Some(mut server) = state.0.lock().await.take() {
+        server.stop();
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn opds_server_status(
+    state: State<'_, OpdsServerState>,
+) -> std::result::Result<OpdsServerStatus, String> {
+    Ok(status_of(state.0.lock().await.as_ref()))
+}
+
+/// Replace the shared catalog. Deleted and cloud-only books should already be
+/// filtered out by the caller; a no-op while the server is stopped.
+#[command]
+pub async fn opds_server_set_library(
+    state: State<'_, OpdsServerState>,
+    books: Vec<SharedBook>,
+) -> std::result::Result<(), String> {
+    if let Some(server) = state.0.lock().await.as_ref() {
+        server.library.write().unwrap().books = books;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::client::tests::{block_on, serve};
+    use super::opds_search;
+    use std::collections::HashMap;
+
+    #[test]
+    fn searches_through_an_opensearch_description() {
+        let base = serve(|path, _| match path {
+            "/osd" => (
+                "200 OK",
+                "application/opensearchdescription+xml",
+                r#"<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+<Url type="application/atom+xml" template="/search?q={searchTerms}"/></OpenSearchDescription>"#
+                    .to_string(),
+            ),
+            "/search?q=dune%202" => (
+                "200 OK",
+                "application/atom+xml",
+                r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Results</title>
+<entry><title>Dune 2</title><link rel="http://opds-spec.org/acquisition" href="/d/2.epub"/></entry>
+</feed>"#
+                    .to_string(),
+            ),
+            _ => ("404 Not Found", "text/plain", String::new()),
+        });
+
+        let params = HashMap::from([("query".to_string(), "dune 2".to_string())]);
+        let feed = block_on(opds_search(format!("{base}/osd"), None, params, None, None)).unwrap();
+        assert_eq!(feed.title, "Results");
+        assert_eq!(
+            feed.publications[0].acquisitions[0].href,
+            format!("{base}/d/2.epub")
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! OPDS 2.0 (JSON) feeds and publications.
+//!
+//! The serde types below accept the loose shapes the Readium web publication
+//! manifest allows — a string or a language map for titles, a string, object
+//! or array for contributors — and are mapped onto the shared model.
+
+use std::collections::BTreeMap;
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{
+    resolve, resolve_template, Acquisition, AcquisitionKind, Contributor, Facet, FacetGroup, Feed,
+    Group, Link, NavigationEntry, OpdsDocument, Price, Publication, Series, REL_THUMBNAIL,
+};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> Default for OneOrMany<T> {
+    fn default() -> Self {
+        OneOrMany::Many(Vec::new())
+    }
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(one) => vec![one],
+            OneOrMany::Many(many) => many,
+        }
+    }
+}
+
+/// A plain string or a BCP 47 language map.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LangString {
+    Plain(String),
+    Map(BTreeMap<String, String>),
+}
+
+impl LangString {
+    fn into_string(self) -> String {
+        match self {
+            LangString::Plain(s) => s,
+            LangString::Map(mut map) => map
+                .remove("en")
+                .or_else(|| map.into_values().next())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Named {
+    Plain(String),
+    Object {
+        name: LangString,
+        #[serde(default)]
+        position: Option<f64>,
+        #[serde(default)]
+        links: Vec<JsonLink>,
+    },
+}
+
+#[derive(Deserialize)]
+struct IndirectAcquisition {
+    #[serde(rename = "type")]
+    media_type: String,
+    #[serde(default)]
+    child: Vec<IndirectAcquisition>,
+}
+
+#[derive(Deserialize)]
+struct JsonPrice {
+    value: f64,
+    currency: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Properties {
+    number_of_items: Option<u64>,
+    price: Option<JsonPrice>,
+    indirect_acquisition: Vec<IndirectAcquisition>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonLink {
+    href: String,
+    #[serde(rename = "type", default)]
+    media_type: Option<String>,
+    #[serde(default)]
+    rel: OneOrMany<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    templated: bool,
+    #[serde(default)]
+    properties: Properties,
+}
+
+impl JsonLink {
+    fn into_link(self, base: &Url) -> (Link, Properties) {
+        let href = if self.templated {
+            resolve_template(base, &self.href)
+        } else {
+            resolve(base, &self.href)
+        };
+        let link = Link {
+            href,
+            rel: self.rel.into_vec(),
+            media_type: self.media_type,
+            title: self.title,
+            templated: self.templated,
+            count: self.properties.number_of_items,
+        };
+        (link, self.properties)
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct BelongsTo {
+    series: OneOrMany<Named>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Metadata {
+    identifier: Option<String>,
+    title: Option<LangString>,
+    subtitle: Option<LangString>,
+    modified: Option<String>,
+    published: Option<String>,
+    author: OneOrMany<Named>,
+    publisher: OneOrMany<Named>,
+    language: OneOrMany<String>,
+    subject: OneOrMany<Named>,
+    description: Option<String>,
+    belongs_to: BelongsTo,
+    number_of_items: Option<u64>,
+    items_per_page: Option<u64>,
+    current_page: Option<u64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonPublication {
+    metadata: Metadata,
+    links: Vec<JsonLink>,
+    images: Vec<JsonLink>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonGroup {
+    metadata: Metadata,
+    links: Vec<JsonLink>,
+    navigation: Vec<JsonLink>,
+    publications: Vec<JsonPublication>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonFeed {
+    metadata: Metadata,
+    links: Vec<JsonLink>,
+    navigation: Option<Vec<JsonLink>>,
+    publications: Option<Vec<JsonPublication>>,
+    groups: Option<Vec<JsonGroup>>,
+    facets: Vec<JsonGroup>,
+}
+
+fn name(named: Named) -> String {
+    match named {
+        Named::Plain(s) => s,
+        Named::Object { name, .. } => name.into_string(),
+    }
+}
+
+fn contributor(base: &Url, named: Named) -> Contributor {
+    match named {
+        Named::Plain(name) => Contributor { name, href: None },
+        Named::Object { name, links, .. } => Contributor {
+            name: name.into_string(),
+            href: links.into_iter().next().map(|l| resolve(base, &l.href)),
+        },
+    }
+}
+
+/// Readium's plain `preview` rel marks a sample too.
+fn acquisition_kind(rel: &[String]) -> Option<AcquisitionKind> {
+    rel.iter().find_map(|r| match r.as_str() {
+        "preview" => Some(AcquisitionKind::Sample),
+        r => AcquisitionKind::from_rel(r),
+    })
+}
+
+fn indirect_types(mut indirect: Vec<IndirectAcquisition>, out: &mut Vec<String>) {
+    // Only the first chain: alternatives would need a tree, and readers
+    // pick the outermost type anyway.
+    if indirect.is_empty() {
+        return;
+    }
+    let first = indirect.swap_remove(0);
+    out.push(first.media_type);
+    indirect_types(first.child, out);
+}
+
+fn publication(base: &Url, p: JsonPublication) -> Publication {
+    let m = p.metadata;
+    let mut publication = Publication {
+        id: m.identifier,
+        title: m.title.map(LangString::into_string).unwrap_or_default(),
+        subtitle: m.subtitle.map(LangString::into_string),
+        authors: m
+            .author
+            .into_vec()
+            .into_iter()
+            .map(|a| contributor(base, a))
+            .collect(),
+        publisher: m.publisher.into_vec().into_iter().next().map(name),
+        published: m.published,
+        updated: m.modified,
+        languages: m.language.into_vec(),
+        subjects: m.subject.into_vec().into_iter().map(name).collect(),
+        series: m
+            .belongs_to
+            .series
+            .into_vec()
+            .into_iter()
+            .next()
+            .map(|s| match s {
+                Named::Plain(name) => Series {
+                    name,
+                    position: None,
+                },
+                Named::Object { name, position, .. } => Series {
+                    name: name.into_string(),
+                    position,
+                },
+            }),
+        summary: m.description,
+        ..Default::default()
+    };
+
+    for image in p.images {
+        let (link, _) = image.into_link(base);
+        if link.has_rel(REL_THUMBNAIL) {
+            publication.thumbnail.get_or_insert(link.href);
+        } else {
+            publication.cover.get_or_insert(link.href);
+        }
+    }
+    if publication.thumbnail.is_none() {
+        publication.thumbnail = publication.cover.clone();
+    }
+
+    for json_link in p.links {
+        let (link, properties) = json_link.into_link(base);
+        let Some(kind) = acquisition_kind(&link.rel) else {
+            publication.links.push(link);
+            continue;
+        };
+        let mut indirect = Vec::new();
+        indirect_types(properties.indirect_acquisition, &mut indirect);
+        publication.acquisitions.push(Acquisition {
+            href: link.href,
+            kind,
+            media_type: link.media_type,
+            title: link.title,
+            length: None,
+            price: properties.price.map(|p| Price {
+                value: p.value,
+                currency: p.currency,
+            }),
+            indirect_types: indirect,
+        });
+    }
+    publication
+}
+
+fn navigation(base: &Url, links: Vec<JsonLink>) -> Vec<NavigationEntry> {
+    links
+        .into_iter()
+        .map(|l| {
+            let (link, _) = l.into_link(base);
+            NavigationEntry {
+                title: link.title.unwrap_or_default(),
+                href: link.href,
+                media_type: link.media_type,
+                rel: link.rel,
+                summary: None,
+                count: link.count,
+            }
+        })
+        .collect()
+}
+
+fn feed(base: &Url, f: JsonFeed) -> Feed {
+    let m = f.metadata;
+    let mut feed = Feed {
+        id: m.identifier,
+        title: m.title.map(LangString::into_string).unwrap_or_default(),
+        subtitle: m.subtitle.map(LangString::into_string),
+        updated: m.modified,
+        links: f.links.into_iter().map(|l| l.into_link(base).0).collect(),
+        navigation: navigation(base, f.navigation.unwrap_or_default()),
+        publications: f
+            .publications
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| publication(base, p))
+            .collect(),
+        ..Default::default()
+    };
+    feed.groups = f
+        .groups
+        .unwrap_or_default()
+        .into_iter()
+        .map(|g| Group {
+            title: g
+                .metadata
+                .title
+                .map(LangString::into_string)
+                .unwrap_or_default(),
+            href: g
+                .links
+                .into_iter()
+                .map(|l| l.into_link(base).0)
+                .find(|l| l.has_rel("self"))
+                .map(|l| l.href),
+            navigation: navigation(base, g.navigation),
+            publications: g
+                .publications
+                .into_iter()
+                .map(|p| publication(base, p))
+                .collect(),
+        })
+        .collect();
+    feed.facets = f
+        .facets
+        .into_iter()
+        .map(|g| FacetGroup {
+            title: g
+                .metadata
+                .title
+                .map(LangString::into_string)
+                .unwrap_or_default(),
+            facets: g
+                .links
+                .into_iter()
+                .map(|l| {
+                    let (link, _) = l.into_link(base);
+                    Facet {
+                        active: link.has_rel("self"),
+                        link,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    feed.pagination.total_results = m.number_of_items;
+    feed.pagination.items_per_page = m.items_per_page;
+    if let (Some(page), Some(per_page)) = (m.current_page, m.items_per_page) {
+        feed.pagination.start_index = Some(page.saturating_sub(1) * per_page + 1);
+    }
+    feed.finish_links();
+    feed
+}
+
+/// A JSON document is a feed when it has any collection; otherwise it is a
+/// single publication manifest.
+pub(super) fn parse(base: &Url, bytes: &[u8]) -> Result<OpdsDocument, String> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let is_feed = ["navigation", "publications", "groups", "facets"]
+        .iter()
+        .any(|k| value.get(k).is_some());
+    if is_feed {
+        let f: JsonFeed = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        return Ok(OpdsDocument::Feed(feed(base, f)));
+    }
+    let p: JsonPublication = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    if p.links.is_empty() && p.metadata.title.is_none() {
+        return Err("no metadata, links or collections".into());
+    }
+    Ok(OpdsDocument::Publication(publication(base, p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AcquisitionKind, OpdsDocument};
+    use super::parse;
+    use reqwest::Url;
+
+    // Shaped after a Kavita / Readium test catalog.
+    const FEED: &str = r#"{
+  "metadata": {"title": "Library", "numberOfItems": 45, "itemsPerPage": 20, "currentPage": 2},
+  "links": [
+    {"rel": "self", "href": "/opds/v2/all?page=2", "type": "application/opds+json"},
+    {"rel": "next", "href": "/opds/v2/all?page=3", "type": "application/opds+json"},
+    {"rel": "search", "href": "/opds/v2/search{?query,author}", "type": "application/opds+json",
+     "templated": true}
+  ],
+  "navigation": [
+    {"href": "/opds/v2/series", "title": "Series", "type": "application/opds+json",
+     "properties": {"numberOfItems": 12}}
+  ],
+  "facets": [{
+    "metadata": {"title": "Sort"},
+    "links": [
+      {"href": "?sort=title", "title": "Title", "rel": "self"},
+      {"href": "?sort=added", "title": "Added"}
+    ]
+  }],
+  "publications": [{
+    "metadata": {
+      "identifier": "urn:isbn:9780441172719",
+      "title": {"fr": "Dune (fr)", "en": "Dune"},
+      "author": [{"name": "Frank Herbert", "links": [{"href": "/opds/v2/authors/12"}]}],
+      "publisher": "Chilton",
+      "language": "en",
+      "subject": ["Science Fiction", {"name": "Classics"}],
+      "belongsTo": {"series": {"name": "Dune Chronicles", "position": 1}},
+      "description": "Arrakis."
+    },
+    "links": [
+      {"rel": "http://opds-spec.org/acquisition", "href": "/api/download/7",
+       "type": "application/epub+zip"},
+      {"rel": "preview", "href": "/api/sample/7", "type": "application/epub+zip"},
+      {"rel": "http://opds-spec.org/acquisition/buy", "href": "https://shop.example/7",
+       "type": "text/html",
+       "properties": {"price": {"value": 9.99, "currency": "EUR"},
+         "indirectAcquisition": [{"type": "application/vnd.readium.lcp.license.v1.0+json",
+           "child": [{"type": "application/epub+zip"}]}]}}
+    ],
+    "images": [
+      {"href": "/covers/7.jpg", "type": "image/jpeg"},
+      {"href": "/covers/7-small.jpg", "type": "image/jpeg",
+       "rel": "http://opds-spec.org/image/thumbnail"}
+    ]
+  }]
+}"#;
+
+    #[test]
+    fn feed_maps_collections_and_pagination() {
+        let base = Url::parse("https://kavita.example/opds/v2/all?page=2").unwrap();
+        let OpdsDocument::Feed(feed) = parse(&base, FEED.as_bytes()).unwrap() else {
+            panic!("expected a feed");
+        };
+        assert_eq!(feed.title, "Library");
+        assert_eq!(
+            feed.pagination.next.as_deref(),
+            Some("https://kavita.example/opds/v2/all?page=3")
+        );
+        assert_eq!(feed.pagination.total_results, Some(45));
+        assert_eq!(feed.pagination.start_index, Some(21));
+
+        let search = feed.search.unwrap();
+        assert!(search.templated);
+        assert_eq!(
+            search.href,
+            "https://kavita.example/opds/v2/search{?query,author}"
+        );
+
+        assert_eq!(feed.navigation[0].title, "Series");
+        assert_eq!(feed.navigation[0].count, Some(12));
+        assert!(feed.facets[0].facets[0].active);
+        assert_eq!(
+            feed.facets[0].facets[1].link.href,
+            "https://kavita.example/opds/v2/all?sort=added"
+        );
+
+        let dune = &feed.publications[0];
+        assert_eq!(dune.title, "Dune");
+        assert_eq!(
+            dune.authors[0].href.as_deref(),
+            Some("https://kavita.example/opds/v2/authors/12")
+        );
+        assert_eq!(dune.publisher.as_deref(), Some("Chilton"));
+        assert_eq!(dune.languages, ["en"]);
+        assert_eq!(dune.subjects, ["Science Fiction", "Classics"]);
+        let series = dune.series.as_ref().unwrap();
+        assert_eq!(series.name, "Dune Chronicles");
+        assert_eq!(series.position, Some(1.0));
+        assert_eq!(
+            dune.cover.as_deref(),
+            Some("https://kavita.example/covers/7.jpg")
+        );
+        assert_eq!(
+            dune.thumbnail.as_deref(),
+            Some("https://kavita.example/covers/7-small.jpg")
+        );
+
+        let kinds: Vec<_> = dune.acquisitions.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                AcquisitionKind::Generic,
+                AcquisitionKind::Sample,
+                AcquisitionKind::Buy
+            ]
+        );
+        assert_eq!(
+            dune.acquisitions[0].href,
+            "https://kavita.example/api/download/7"
+        );
+        assert_eq!(dune.acquisitions[2].price.as_ref().unwrap().value, 9.99);
+        assert_eq!(
+            dune.acquisitions[2].indirect_types,
+            [
+                "application/vnd.readium.lcp.license.v1.0+json",
+                "application/epub+zip"
+            ]
+        );
+    }
+
+    #[test]
+    fn publication_document_and_garbage() {
+        let base = Url::parse("https://kavita.example/opds/v2/book/7").unwrap();
+        let doc = parse(
+            &base,
+            br#"{"metadata":{"title":"Solo","author":"Anon"},
+                "links":[{"rel":"http://opds-spec.org/acquisition/open-access","href":"solo.epub"}]}"#,
+        )
+        .unwrap();
+        let OpdsDocument::Publication(p) = doc else {
+            panic!("expected a publication");
+        };
+        assert_eq!(p.authors[0].name, "Anon");
+        assert_eq!(p.acquisitions[0].kind, AcquisitionKind::OpenAccess);
+        assert_eq!(
+            p.acquisitions[0].href,
+            "https://kavita.example/opds/v2/book/solo.epub"
+        );
+
+        assert!(parse(&base, b"{}").is_err());
+        assert!(parse(&base, b"[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//! Native OPDS catalog client.
+//!
+//! `commands::opds_fetch` GETs a catalog URL (with optional basic/bearer
+//! auth, see `client`) and parses whatever comes back: an OPDS 1.2 Atom feed
+//! or entry (`atom`), an OPDS 2.0 JSON feed or publication (`json`), or an
+//! OpenSearch description (`opensearch`). Both feed flavours are mapped onto
+//! one model — navigation, publications, groups, facets, pagination and a
+//! search link — with every href resolved against the document URL, so an
+//! acquisition's `href` can go straight to `download_file`.
+//! `commands::opds_search` expands a catalog's search link (OpenSearch
+//! template or OPDS 2.0 URI template) and fetches the results.
+//!
+//! The other direction lives in `server`: an optional PIN-protected OPDS 2.0
+//! catalog of the local library for readers elsewhere on the LAN.
+
+mod atom;
+mod client;
+pub mod commands;
+mod json;
+mod opensearch;
+pub mod server;
+mod xml;
+
+use serde::{ser::Serializer, Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+pub const REL_ACQUISITION: &str = "http://opds-spec.org/acquisition";
+pub const REL_FACET: &str = "http://opds-spec.org/facet";
+pub const REL_IMAGE: &str = "http://opds-spec.org/image";
+pub const REL_THUMBNAIL: &str = "http://opds-spec.org/image/thumbnail";
+
+/// Tauri managed state: the running OPDS server, or `None` while sharing is
+/// off.
+#[derive(Default)]
+pub struct OpdsServerState(pub Arc<Mutex<Option<server::RunningServer>>>);
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("invalid URL: {0}")]
+    Url(String),
+    /// The catalog wants credentials; `challenge` is the `WWW-Authenticate`
+    /// header, so the UI can tell basic auth from a bearer token.
+    #[error("authentication required ({challenge})")]
+    Unauthorized { challenge: String },
+    #[error("request failed with status code {0}: {1}")]
+    HttpErrorCode(u16, String),
+    #[error("not an OPDS document: {0}")]
+    Parse(String),
+    #[error("the catalog has no search")]
+    NoSearch,
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+/// Credentials for a catalog. Calibre-Web uses basic auth; token-based
+/// servers take a bearer token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OpdsAuth {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    pub href: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rel: Vec<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// OPDS 2.0: `href` is an RFC 6570 URI template.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub templated: bool,
+    /// Items behind a navigation or facet link (`thr:count` /
+    /// `numberOfItems`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+}
+
+impl Link {
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel.iter().any(|r| r == rel)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributor {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Price {
+    pub value: f64,
+    pub currency: String,
+}
+
+/// How an acquisition link gets the book, from its rel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AcquisitionKind {
+    Generic,
+    OpenAccess,
+    Borrow,
+    Buy,
+    Sample,
+    Subscribe,
+}
+
+impl AcquisitionKind {
+    /// `None` for rels that aren't acquisitions.
+    pub fn from_rel(rel: &str) -> Option<Self> {
+        let rest = rel.strip_prefix(REL_ACQUISITION)?;
+        Some(match rest {
+            "" => Self::Generic,
+            "/open-access" => Self::OpenAccess,
+            "/borrow" => Self::Borrow,
+            "/buy" => Self::Buy,
+            "/sample" | "/preview" => Self::Sample,
+            "/subscribe" => Self::Subscribe,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Acquisition {
+    /// Absolute URL of the file (or of the next step, for indirect
+    /// acquisitions).
+    pub href: String,
+    pub kind: AcquisitionKind,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Price>,
+    /// Media types behind `href` when it isn't the book itself, outermost
+    /// first (e.g. an ACSM wrapping an EPUB).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indirect_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Series {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Publication {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    pub authors: Vec<Contributor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<Series>,
+    /// Plain-text summary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// HTML description, when the feed has a richer one than `summary`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    pub acquisitions: Vec<Acquisition>,
+    /// Everything else: alternate/related links, the entry's own document.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigationEntry {
+    pub title: String,
+    pub href: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rel: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Facet {
+    pub link: Link,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetGroup {
+    pub title: String,
+    pub facets: Vec<Facet>,
+}
+
+/// An OPDS 2.0 group (or an OPDS 1.2 `http://opds-spec.org/group`
+/// collection): a titled shelf with an optional "more" link.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub navigation: Vec<NavigationEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub publications: Vec<Publication>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_results: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items_per_page: Option<u64>,
+    /// 1-based index of the first item on this page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<u64>,
+}
+
+impl Pagination {
+    fn from_links(links: &[Link]) -> Self {
+        let find = |rel: &str| {
+            links
+                .iter()
+                .find(|l| l.has_rel(rel))
+                .map(|l| l.href.clone())
+        };
+        Pagination {
+            next: find("next"),
+            previous: find("previous").or_else(|| find("prev")),
+            first: find("first"),
+            last: find("last"),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Feed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    pub links: Vec<Link>,
+    pub navigation: Vec<NavigationEntry>,
+    pub publications: Vec<Publication>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<FacetGroup>,
+    pub pagination: Pagination,
+    /// The feed's `search` link: an OpenSearch description or a templated
+    /// OPDS 2.0 search URL. Pass it to `opds_search`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<Link>,
+}
+
+impl Feed {
+    /// Fill `pagination` and `search` from `links`, keeping any counts the
+    /// format parser already set.
+    fn finish_links(&mut self) {
+        let counts = std::mem::take(&mut self.pagination);
+        self.pagination = Pagination {
+            total_results: counts.total_results,
+            items_per_page: counts.items_per_page,
+            start_index: counts.start_index,
+            ..Pagination::from_links(&self.links)
+        };
+        self.search = self.links.iter().find(|l| l.has_rel("search")).cloned();
+    }
+}
+
+/// An OpenSearch description, reduced to the template we query with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchDescription {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Absolute URL template, e.g. `https://host/search?q={searchTerms}`.
+    pub template: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// Parameter names the template takes, e.g. `searchTerms`,
+    /// `atom:author`.
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OpdsDocument {
+    Feed(Feed),
+    /// A standalone entry / publication document.
+    Publication(Publication),
+    Search(SearchDescription),
+}
+
+/// Resolve `href` against the document URL; absolute hrefs pass through.
+fn resolve(base: &reqwest::Url, href: &str) -> String {
+    base.join(href.trim())
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| href.to_string())
+}
+
+/// [`resolve`] for a URI template: `Url::join` would percent-encode the
+/// `{…}` expressions, so only the part before the first one is resolved.
+fn resolve_template(base: &reqwest::Url, href: &str) -> String {
+    let href = href.trim();
+    if reqwest::Url::parse(href).is_ok() {
+        return href.to_string();
+    }
+    let (head, tail) = href.split_at(href.find('{').unwrap_or(href.len()));
+    resolve(base, head) + tail
+}
@@ -0,0 +1,181 @@
+//! OpenSearch descriptions and search-template expansion.
+//!
+//! OPDS 1.2 catalogs link an OpenSearch description whose `Url` template
+//! uses `{searchTerms}`-style parameters; OPDS 2.0 catalogs link a templated
+//! URL in RFC 6570 form (`{?query,author}`). `expand_template` fills both.
+
+use std::collections::HashMap;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Url;
+
+use super::xml::Element;
+use super::{resolve_template, SearchDescription};
+
+/// RFC 3986 unreserved characters stay as they are.
+const VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Results we can render, best first.
+const RESULT_TYPES: &[&str] = &[
+    "application/opds+json",
+    "opds-catalog",
+    "application/atom+xml",
+];
+
+pub(super) fn description(base: &Url, root: &Element) -> Result<SearchDescription, String> {
+    let rank = |url: &&Element| {
+        let t = url.attr("type").unwrap_or_default();
+        RESULT_TYPES
+            .iter()
+            .position(|r| t.contains(r))
+            .unwrap_or(RESULT_TYPES.len())
+    };
+    let url = root
+        .children_named("Url")
+        .filter(|u| u.attr("template").is_some())
+        .min_by_key(rank)
+        .ok_or("OpenSearch description has no Url template")?;
+    let template = resolve_template(base, url.attr("template").unwrap_or_default());
+    Ok(SearchDescription {
+        short_name: root.child_text("ShortName"),
+        description: root.child_text("Description"),
+        params: params(&template),
+        template,
+        media_type: url.attr("type").map(str::to_string),
+    })
+}
+
+/// The `{…}` expressions of a template, without their braces.
+fn expressions(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(expr, _)| expr))
+}
+
+/// Parameter names a template takes, in order.
+fn params(template: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for expr in expressions(template) {
+        let expr = expr.trim_start_matches(['?', '&']);
+        for name in expr.split(',') {
+            let name = name.trim_end_matches('?').to_string();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// The value for `name`: an exact key, else the local part of a prefixed
+/// OpenSearch name (`atom:author` → `author`). Paging parameters default
+/// to the first page.
+fn lookup<'a>(params: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    let local = name.rsplit(':').next().unwrap_or(name);
+    params
+        .get(name)
+        .or_else(|| params.get(local))
+        .map(String::as_str)
+        .or(match local {
+            "startPage" | "startIndex" => Some("1"),
+            _ => None,
+        })
+}
+
+/// Fill a search template. Unset OpenSearch parameters expand to nothing,
+/// and unset RFC 6570 query parameters are left out.
+pub(super) fn expand_template(template: &str, params: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let expr = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+
+        if let Some(names) = expr.strip_prefix(['?', '&']) {
+            let mut first = expr.starts_with('?');
+            for name in names.split(',') {
+                let Some(value) = lookup(params, name) else {
+                    continue;
+                };
+                out.push(if first { '?' } else { '&' });
+                first = false;
+                out.push_str(name);
+                out.push('=');
+                out.extend(utf8_percent_encode(value, VALUE));
+            }
+        } else {
+            let values = expr
+                .split(',')
+                .filter_map(|name| lookup(params, name.trim_end_matches('?')))
+                .map(|value| utf8_percent_encode(value, VALUE).to_string())
+                .collect::<Vec<_>>();
+            out.push_str(&values.join(","));
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::xml;
+    use super::{description, expand_template};
+    use reqwest::Url;
+    use std::collections::HashMap;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn description_prefers_an_opds_result_type() {
+        let base = Url::parse("https://books.example/opds/osd").unwrap();
+        let root = xml::parse(
+            br#"<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <ShortName>Calibre-Web</ShortName>
+  <Url type="text/html" template="/search?q={searchTerms}"/>
+  <Url type="application/atom+xml;profile=opds-catalog" template="/opds/search/{searchTerms}?p={startPage?}&amp;a={atom:author?}"/>
+</OpenSearchDescription>"#,
+        )
+        .unwrap();
+        let d = description(&base, &root).unwrap();
+        assert_eq!(d.short_name.as_deref(), Some("Calibre-Web"));
+        assert_eq!(
+            d.template,
+            "https://books.example/opds/search/{searchTerms}?p={startPage?}&a={atom:author?}"
+        );
+        assert_eq!(d.params, ["searchTerms", "startPage", "atom:author"]);
+        assert_eq!(
+            expand_template(&d.template, &params(&[("searchTerms", "dune & co")])),
+            "https://books.example/opds/search/dune%20%26%20co?p=1&a="
+        );
+    }
+
+    #[test]
+    fn expands_rfc6570_query_expressions() {
+        let template = "https://kavita.example/opds/v2/search{?query,author}{&lang}";
+        assert_eq!(
+            expand_template(template, &params(&[("query", "Frank Herbert")])),
+            "https://kavita.example/opds/v2/search?query=Frank%20Herbert"
+        );
+        assert_eq!(
+            expand_template(
+                template,
+                &params(&[("author", "Herbert"), ("lang", "en"), ("query", "é")])
+            ),
+            "https://kavita.example/opds/v2/search?query=%C3%A9&author=Herbert&lang=en"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! A minimal element tree over quick-xml, enough for Atom and OpenSearch
+//! documents. Names are kept as local names (`opds:price` → `price`): OPDS
+//! servers are loose about namespace prefixes, and no two vocabularies we
+//! read reuse a local name in a conflicting way.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::parser_common::{local_name, strip_xml_bom};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Concatenated descendant text, trimmed.
+    pub fn text(&self) -> String {
+        fn collect(element: &Element, out: &mut String) {
+            for node in &element.children {
+                match node {
+                    Node::Text(t) => out.push_str(t),
+                    Node::Element(e) => collect(e, out),
+                }
+            }
+        }
+        let mut out = String::new();
+        collect(self, &mut out);
+        out.trim().to_string()
+    }
+
+    /// Trimmed text of the first `name` child, if it has any.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name)
+            .map(Element::text)
+            .filter(|t| !t.is_empty())
+    }
+
+    /// The children re-serialized as markup, for Atom `type="xhtml"` content.
+    pub fn inner_markup(&self) -> String {
+        fn write(node: &Node, out: &mut String) {
+            match node {
+                Node::Text(t) => out.push_str(&escape(t, false)),
+                Node::Element(e) => {
+                    out.push('<');
+                    out.push_str(&e.name);
+                    for (name, value) in &e.attrs {
+                        out.push_str(&format!(" {name}=\"{}\"", escape(value, true)));
+                    }
+                    if e.children.is_empty() && VOID_ELEMENTS.contains(&e.name.as_str()) {
+                        out.push_str("/>");
+                        return;
+                    }
+                    out.push('>');
+                    for child in &e.children {
+                        write(child, out);
+                    }
+                    out.push_str(&format!("</{}>", e.name));
+                }
+            }
+        }
+        let mut out = String::new();
+        for child in &self.children {
+            write(child, &mut out);
+        }
+        out.trim().to_string()
+    }
+}
+
+/// Written as `<br/>`; an HTML parser reads `<br></br>` as two breaks.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+fn escape(s: &str, attr: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attr => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn start_element(e: &BytesStart) -> Element {
+    let attrs = e
+        .attributes()
+        .flatten()
+        .filter(|a| {
+            let key = a.key.as_ref();
+            key != b"xmlns" && !key.starts_with(b"xmlns:")
+        })
+        .map(|a| {
+            let name = String::from_utf8_lossy(local_name(a.key.as_ref())).into_owned();
+            let value = a
+                .unescape_value()
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&a.value).into_owned());
+            (name, value)
+        })
+        .collect();
+    Element {
+        name: String::from_utf8_lossy(local_name(e.name().as_ref())).into_owned(),
+        attrs,
+        children: Vec::new(),
+    }
+}
+
+/// Parse a whole document into its root element.
+pub(super) fn parse(bytes: &[u8]) -> Result<Element, String> {
+    let normalized = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => stack.push(start_element(&e)),
+            Ok(Event::End(_)) => {
+                let Some(done) = stack.pop() else {
+                    return Err("xml: unbalanced end tag".into());
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(done)),
+                    None => return Ok(done),
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let Some(parent) = stack.last_mut() {
+                    let text = e
+                        .unescape()
+                        .map(|t| t.into_owned())
+                        .unwrap_or_else(|_| String::from_utf8_lossy(&e).into_owned());
+                    parent.children.push(Node::Text(text));
+                }
+            }
+            Ok(Event::CData(e)) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent
+                        .children
+                        .push(Node::Text(String::from_utf8_lossy(&e).into_owned()));
+                }
+            }
+            Ok(Event::Eof) => return Err("xml: no root element".into()),
+            Err(e) => return Err(format!("xml: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parse_keeps_local_names_text_and_markup() {
+        let root = parse(
+            br#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:opds="http://opds-spec.org/2010/catalog">
+  <opds:price currencycode="USD">1.99</opds:price>
+  <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml">A &amp; <b>B</b><br/></div></content>
+  <title><![CDATA[Raw <title>]]></title>
+</feed>"#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "feed");
+        assert!(root.attrs.is_empty());
+        let price = root.child("price").unwrap();
+        assert_eq!(price.attr("currencycode"), Some("USD"));
+        assert_eq!(price.text(), "1.99");
+        assert_eq!(
+            root.child("content").unwrap().inner_markup(),
+            "<div>A &amp; <b>B</b><br/></div>"
+        );
+        assert_eq!(root.child_text("title").as_deref(), Some("Raw <title>"));
+    }
+}
@@ -0,0 +1,664 @@
+// Structured OPF `<metadata>` extraction for the native EPUB import path.
+//
+// `parse_epub_metadata` used to ship only the raw OPF bytes and leave all
+// Dublin Core parsing to foliate-js, which meant one DOMParser round-trip
+// per book before bulk import could dedupe, group or sort anything. This
+// module parses the same bytes once in Rust and returns a typed view:
+//   - titles with EPUB3 `refines` (title-type / file-as / display-seq);
+//   - creators and contributors with MARC relator roles and file-as,
+//     from either EPUB2 `opf:role` / `opf:file-as` attributes or EPUB3
+//     `<meta refines="#id" property="role">`;
+//   - series from `belongs-to-collection` (+ collection-type and
+//     group-position), falling back to calibre's `calibre:series` /
+//     `calibre:series_index` legacy metas;
+//   - identifiers with a scheme (EPUB2 `opf:scheme`, EPUB3
+//     `identifier-type`, or sniffed from a `urn:isbn:` / `urn:uuid:` /
+//     `doi:` prefix or a checksum-valid bare ISBN);
+//   - subjects, publisher, description, rights, dates, languages;
+//   - rendition (layout / orientation / spread / flow) and schema.org
+//     accessibility metadata.
+//
+// foliate-js stays the source of truth for the reader's `Book.metadata`
+// (and therefore the metaHash); this view feeds bulk import and the
+// library sort keys, where a lossless-enough typed summary is all that's
+// needed.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::parser_common::{local_name, strip_xml_bom};
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfMetadata {
+    /// `<package version="...">`, e.g. "2.0" or "3.0".
+    pub version: Option<String>,
+    /// Titles in document order, with the `main` title (if typed) first.
+    pub titles: Vec<OpfTitle>,
+    pub creators: Vec<OpfContributor>,
+    pub contributors: Vec<OpfContributor>,
+    pub series: Vec<OpfCollection>,
+    pub identifiers: Vec<OpfIdentifier>,
+    pub subjects: Vec<String>,
+    pub publisher: Option<String>,
+    pub description: Option<String>,
+    pub rights: Option<String>,
+    /// Publication date (`dc:date`, preferring `opf:event="publication"`),
+    /// as written.
+    pub published: Option<String>,
+    /// `dcterms:modified`, as written.
+    pub modified: Option<String>,
+    pub languages: Vec<String>,
+    pub rendition: OpfRendition,
+    pub accessibility: OpfAccessibility,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfTitle {
+    pub value: String,
+    /// EPUB3 `title-type`: main / subtitle / short / collection / edition /
+    /// expanded.
+    pub title_type: Option<String>,
+    pub file_as: Option<String>,
+    pub display_seq: Option<u32>,
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfContributor {
+    pub name: String,
+    pub file_as: Option<String>,
+    /// MARC relator codes (`aut`, `edt`, `trl`, `ill`, ...), lower-cased.
+    pub roles: Vec<String>,
+    pub display_seq: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfCollection {
+    pub name: String,
+    /// EPUB3 `collection-type` (`series` / `set`), `None` when untyped.
+    pub collection_type: Option<String>,
+    /// `group-position` / `calibre:series_index`, parsed as a float.
+    pub position: Option<f64>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfIdentifier {
+    /// Identifier with any recognised URN prefix stripped
+    /// (`urn:isbn:9780...` -> `9780...`).
+    pub value: String,
+    /// Upper-cased scheme (`ISBN`, `UUID`, `DOI`, `ASIN`, ...), when known.
+    pub scheme: Option<String>,
+    /// `true` for the identifier `<package unique-identifier>` points at.
+    pub unique: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfRendition {
+    /// `reflowable` or `pre-paginated`.
+    pub layout: Option<String>,
+    pub orientation: Option<String>,
+    pub spread: Option<String>,
+    pub flow: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpfAccessibility {
+    pub access_modes: Vec<String>,
+    pub access_modes_sufficient: Vec<String>,
+    pub features: Vec<String>,
+    pub hazards: Vec<String>,
+    pub summary: Option<String>,
+    /// `dcterms:conformsTo` / `a11y:certifiedBy`-style conformance claims.
+    pub conforms_to: Vec<String>,
+}
+
+/// One element under `<metadata>`, flattened before `refines` resolution.
+#[derive(Debug, Default)]
+struct RawElement {
+    /// Local name (`title`, `creator`, `meta`, ...).
+    name: Vec<u8>,
+    /// Attributes keyed by local name (`opf:role` -> `role`,
+    /// `xml:lang` -> `lang`).
+    attrs: HashMap<String, String>,
+    text: String,
+}
+
+impl RawElement {
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .get(key)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn value(&self) -> Option<String> {
+        let text = collapse_whitespace(&self.text);
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// A `<meta refines="#id" property="...">value</meta>` refinement.
+struct Refinement {
+    property: String,
+    value: String,
+}
+
+pub fn parse_opf_metadata(bytes: &[u8]) -> Result<OpfMetadata, String> {
+    let normalized = strip_xml_bom(bytes);
+    let mut reader = Reader::from_reader(normalized.as_ref());
+    // See `epub_parser::locate_toc_sources` (#5455): treat `<meta/>` and
+    // `<meta></meta>` identically.
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut version = None;
+    let mut unique_id = None;
+    let mut elements: Vec<RawElement> = Vec::new();
+    let mut in_metadata = false;
+    // Depth relative to `<metadata>`; direct children sit at 1. Nested
+    // markup inside a child (rare, but `dc:description` sometimes carries
+    // unescaped XHTML) is folded into that child's text.
+    let mut depth = 0usize;
+    let mut current: Option<RawElement> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref()).to_vec();
+                if !in_metadata {
+                    if name == b"package" {
+                        for a in e.attributes().flatten() {
+                            let value = String::from_utf8_lossy(&a.value).into_owned();
+                            match a.key.as_ref() {
+                                b"version" => version = Some(value),
+                                b"unique-identifier" => unique_id = Some(value),
+                                _ => {}
+                            }
+                        }
+                    } else if name == b"metadata" {
+                        in_metadata = true;
+                        depth = 0;
+                    }
+                    buf.clear();
+                    continue;
+                }
+                depth += 1;
+                if depth == 1 {
+                    let attrs = e
+                        .attributes()
+                        .flatten()
+                        .map(|a| {
+                            let key =
+                                String::from_utf8_lossy(local_name(a.key.as_ref())).into_owned();
+                            let value = match a.unescape_value() {
+                                Ok(v) => v.into_owned(),
+                                Err(_) => String::from_utf8_lossy(&a.value).into_owned(),
+                            };
+                            (key, value)
+                        })
+                        .collect();
+                    current = Some(RawElement {
+                        name,
+                        attrs,
+                        text: String::new(),
+                    });
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let Some(el) = current.as_mut() {
+                    match e.unescape() {
+                        Ok(t) => el.text.push_str(&t),
+                        Err(_) => el.text.push_str(&String::from_utf8_lossy(&e)),
+                    }
+                }
+            }
+            Ok(Event::CData(e)) => {
+                if let Some(el) = current.as_mut() {
+                    el.text.push_str(&String::from_utf8_lossy(&e));
+                }
+            }
+            Ok(Event::End(e)) => {
+                if !in_metadata {
+                    buf.clear();
+                    continue;
+                }
+                if depth == 0 {
+                    // `</metadata>`; the rest of the OPF is irrelevant here.
+                    if local_name(e.name().as_ref()) == b"metadata" {
+                        break;
+                    }
+                } else {
+                    if depth == 1 {
+                        if let Some(el) = current.take() {
+                            elements.push(el);
+                        }
+                    } else if let Some(el) = current.as_mut() {
+                        // Keep words from adjacent nested elements apart.
+                        el.text.push(' ');
+                    }
+                    depth -= 1;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("xml: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(build_metadata(version, unique_id.as_deref(), &elements))
+}
+
+fn build_metadata(
+    version: Option<String>,
+    unique_id: Option<&str>,
+    elements: &[RawElement],
+) -> OpfMetadata {
+    let mut refines: HashMap<&str, Vec<Refinement>> = HashMap::new();
+    for el in elements {
+        if el.name != b"meta" {
+            continue;
+        }
+        let (Some(target), Some(property)) = (el.attr("refines"), el.attr("property")) else {
+            continue;
+        };
+        if let Some(value) = el.value() {
+            refines
+                .entry(target.trim_start_matches('#'))
+                .or_default()
+                .push(Refinement {
+                    property: property.to_string(),
+                    value,
+                });
+        }
+    }
+    let refined = |el: &RawElement, property: &str| -> Option<String> {
+        let id = el.attr("id")?;
+        refines
+            .get(id)?
+            .iter()
+            .find(|r| r.property == property)
+            .map(|r| r.value.clone())
+    };
+    let refined_all = |el: &RawElement, property: &str| -> Vec<String> {
+        el.attr("id")
+            .and_then(|id| refines.get(id))
+            .map(|rs| {
+                rs.iter()
+                    .filter(|r| r.property == property)
+                    .map(|r| r.value.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut md = OpfMetadata {
+        version,
+        ..Default::default()
+    };
+    let mut calibre_series: Option<String> = None;
+    let mut calibre_series_index: Option<f64> = None;
+    let mut dates: Vec<(Option<String>, String)> = Vec::new();
+
+    for el in elements {
+        match el.name.as_slice() {
+            b"title" => {
+                let Some(value) = el.value() else { continue };
+                md.titles.push(OpfTitle {
+                    value,
+                    title_type: refined(el, "title-type"),
+                    file_as: el
+                        .attr("file-as")
+                        .map(str::to_string)
+                        .or_else(|| refined(el, "file-as")),
+                    display_seq: refined(el, "display-seq").and_then(|s| s.parse().ok()),
+                    lang: el.attr("lang").map(str::to_string),
+                });
+            }
+            b"creator" | b"contributor" => {
+                let Some(name) = el.value() else { continue };
+                let mut roles: Vec<String> = el
+                    .attr("role")
+                    .map(|r| vec![r.to_ascii_lowercase()])
+                    .unwrap_or_default();
+                for role in refined_all(el, "role") {
+                    let role = role.to_ascii_lowercase();
+                    if !roles.contains(&role) {
+                        roles.push(role);
+                    }
+                }
+                let person = OpfContributor {
+                    name,
+                    file_as: el
+                        .attr("file-as")
+                        .map(str::to_string)
+                        .or_else(|| refined(el, "file-as")),
+                    roles,
+                    display_seq: refined(el, "display-seq").and_then(|s| s.parse().ok()),
+                };
+                if el.name == b"creator" {
+                    md.creators.push(person);
+                } else {
+                    md.contributors.push(person);
+                }
+            }
+            b"identifier" => {
+                let Some(raw) = el.value() else { continue };
+                let declared = el
+                    .attr("scheme")
+                    .map(str::to_string)
+                    .or_else(|| refined(el, "identifier-type"));
+                let (value, scheme) = classify_identifier(&raw, declared.as_deref());
+                md.identifiers.push(OpfIdentifier {
+                    value,
+                    scheme,
+                    unique: unique_id.is_some() && el.attr("id") == unique_id,
+                });
+            }
+            b"subject" => {
+                if let Some(value) = el.value() {
+                    if !md.subjects.contains(&value) {
+                        md.subjects.push(value);
+                    }
+                }
+            }
+            b"publisher" if md.publisher.is_none() => md.publisher = el.value(),
+            b"description" if md.description.is_none() => md.description = el.value(),
+            b"rights" if md.rights.is_none() => md.rights = el.value(),
+            b"language" => {
+                if let Some(value) = el.value() {
+                    md.languages.push(value);
+                }
+            }
+            b"date" => {
+                if let Some(value) = el.value() {
+                    dates.push((el.attr("event").map(str::to_ascii_lowercase), value));
+                }
+            }
+            b"meta" => {
+                if el.attr("refines").is_some() {
+                    continue;
+                }
+                if let Some(property) = el.attr("property") {
+                    apply_meta_property(&mut md, el, property, &refined);
+                } else if let (Some(name), Some(content)) = (el.attr("name"), el.attr("content")) {
+                    match name {
+                        "calibre:series" => calibre_series = Some(content.to_string()),
+                        "calibre:series_index" => calibre_series_index = content.parse().ok(),
+                        // Amazon's pre-EPUB3 fixed-layout flag.
+                        "fixed-layout" if content.eq_ignore_ascii_case("true") => {
+                            md.rendition
+                                .layout
+                                .get_or_insert_with(|| "pre-paginated".to_string());
+                        }
+                        "orientation-lock" if md.rendition.orientation.is_none() => {
+                            md.rendition.orientation = Some(content.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // EPUB3 `title-type="main"` wins the first slot; otherwise document
+    // order (which is what reading systems fall back to) is kept.
+    if let Some(i) = md
+        .titles
+        .iter()
+        .position(|t| t.title_type.as_deref() == Some("main"))
+    {
+        let main = md.titles.remove(i);
+        md.titles.insert(0, main);
+    }
+
+    if md.series.is_empty() {
+        if let Some(name) = calibre_series.filter(|s| !s.trim().is_empty()) {
+            md.series.push(OpfCollection {
+                name,
+                collection_type: Some("series".to_string()),
+                position: calibre_series_index,
+            });
+        }
+    }
+
+    md.published = dates
+        .iter()
+        .find(|(event, _)| event.as_deref() == Some("publication"))
+        .or_else(|| dates.iter().find(|(event, _)| event.is_none()))
+        .or_else(|| dates.first())
+        .map(|(_, value)| value.clone());
+
+    md
+}
+
+/// EPUB3 `<meta property="...">` entries that aren't refinements.
+fn apply_meta_property(
+    md: &mut OpfMetadata,
+    el: &RawElement,
+    property: &str,
+    refined: &dyn Fn(&RawElement, &str) -> Option<String>,
+) {
+    let Some(value) = el.value() else { return };
+    let a11y = &mut md.accessibility;
+    match property {
+        "belongs-to-collection" => md.series.push(OpfCollection {
+            name: value,
+            collection_type: refined(el, "collection-type"),
+            position: refined(el, "group-position").and_then(|p| p.trim().parse().ok()),
+        }),
+        "dcterms:modified" => md.modified = Some(value),
+        "rendition:layout" => md.rendition.layout = Some(value),
+        "rendition:orientation" => md.rendition.orientation = Some(value),
+        "rendition:spread" => md.rendition.spread = Some(value),
+        "rendition:flow" => md.rendition.flow = Some(value),
+        "schema:accessMode" => a11y.access_modes.push(value),
+        "schema:accessModeSufficient" => a11y.access_modes_sufficient.push(value),
+        "schema:accessibilityFeature" => a11y.features.push(value),
+        "schema:accessibilityHazard" => a11y.hazards.push(value),
+        "schema:accessibilitySummary" => a11y.summary = Some(value),
+        "dcterms:conformsTo" | "a11y:certifiedBy" => a11y.conforms_to.push(value),
+        _ => {}
+    }
+}
+
+/// Strip a recognised URN-style prefix and work out the scheme. A declared
+/// scheme (`opf:scheme` / `identifier-type`) wins over sniffing; calibre
+/// writes `opf:scheme="calibre"` for its internal ids and those keep it.
+fn classify_identifier(raw: &str, declared: Option<&str>) -> (String, Option<String>) {
+    const PREFIXES: &[(&str, &str)] = &[
+        ("urn:isbn:", "ISBN"),
+        ("isbn:", "ISBN"),
+        ("urn:uuid:", "UUID"),
+        ("uuid:", "UUID"),
+        ("urn:doi:", "DOI"),
+        ("doi:", "DOI"),
+        ("urn:asin:", "ASIN"),
+        ("asin:", "ASIN"),
+    ];
+    let trimmed = raw.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    let mut value = trimmed.to_string();
+    let mut sniffed = None;
+    for (prefix, scheme) in PREFIXES {
+        if lower.starts_with(prefix) {
+            value = trimmed[prefix.len()..].trim().to_string();
+            sniffed = Some(*scheme);
+            break;
+        }
+    }
+    if sniffed.is_none() && is_valid_isbn(&value) {
+        sniffed = Some("ISBN");
+    }
+    let scheme = declared
+        .map(|s| s.trim().to_ascii_uppercase())
+        .filter(|s| !s.is_empty())
+        .or_else(|| sniffed.map(str::to_string));
+    (value, scheme)
+}
+
+/// ISBN-10 / ISBN-13 checksum validation, ignoring hyphens and spaces.
+fn is_valid_isbn(value: &str) -> bool {
+    let chars: Vec<char> = value.chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+    match chars.len() {
+        10 => {
+            let mut sum = 0u32;
+            for (i, c) in chars.iter().enumerate() {
+                let digit = match c {
+                    'X' | 'x' if i == 9 => 10,
+                    c => match c.to_digit(10) {
+                        Some(d) => d,
+                        None => return false,
+                    },
+                };
+                sum += digit * (10 - i as u32);
+            }
+            sum % 11 == 0
+        }
+        13 => {
+            let mut sum = 0u32;
+            for (i, c) in chars.iter().enumerate() {
+                let Some(d) = c.to_digit(10) else {
+                    return false;
+                };
+                sum += if i % 2 == 0 { d } else { d * 3 };
+            }
+            sum % 10 == 0
+        }
+        _ => false,
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_opf_metadata_resolves_epub3_refines() {
+        let xml = br##"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="uid">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="uid">urn:uuid:1b2c3d4e-0000-4000-8000-000000000001</dc:identifier>
+    <dc:identifier id="isbn">9780306406157</dc:identifier>
+    <dc:title id="sub">A Subtitle</dc:title>
+    <dc:title id="t">The Main Title</dc:title>
+    <meta refines="#t" property="title-type">main</meta>
+    <meta refines="#t" property="file-as">Main Title, The</meta>
+    <meta refines="#sub" property="title-type">subtitle</meta>
+    <dc:creator id="c1">Jane Doe</dc:creator>
+    <meta refines="#c1" property="role" scheme="marc:relators">aut</meta>
+    <meta refines="#c1" property="file-as">Doe, Jane</meta>
+    <dc:contributor id="c2">John Roe</dc:contributor>
+    <meta refines="#c2" property="role" scheme="marc:relators">trl</meta>
+    <meta property="belongs-to-collection" id="s1">The Saga</meta>
+    <meta refines="#s1" property="collection-type">series</meta>
+    <meta refines="#s1" property="group-position">2</meta>
+    <dc:subject>Fantasy</dc:subject>
+    <dc:subject>Fantasy</dc:subject>
+    <dc:language>en</dc:language>
+    <dc:publisher>Tor</dc:publisher>
+    <dc:date>2020-05-01</dc:date>
+    <meta property="dcterms:modified">2026-01-01T00:00:00Z</meta>
+    <meta property="rendition:layout">pre-paginated</meta>
+    <meta property="rendition:spread">landscape</meta>
+    <meta property="schema:accessMode">textual</meta>
+    <meta property="schema:accessibilityFeature">tableOfContents</meta>
+    <meta property="schema:accessibilityHazard">none</meta>
+    <meta property="schema:accessibilitySummary">Fully accessible.</meta>
+  </metadata>
+  <manifest/>
+</package>"##;
+        let md = parse_opf_metadata(xml).expect("parses");
+        assert_eq!(md.version.as_deref(), Some("3.0"));
+        assert_eq!(md.titles.len(), 2);
+        assert_eq!(md.titles[0].value, "The Main Title");
+        assert_eq!(md.titles[0].file_as.as_deref(), Some("Main Title, The"));
+        assert_eq!(md.titles[1].title_type.as_deref(), Some("subtitle"));
+        assert_eq!(md.creators[0].name, "Jane Doe");
+        assert_eq!(md.creators[0].roles, vec!["aut"]);
+        assert_eq!(md.creators[0].file_as.as_deref(), Some("Doe, Jane"));
+        assert_eq!(md.contributors[0].roles, vec!["trl"]);
+        assert_eq!(md.series.len(), 1);
+        assert_eq!(md.series[0].name, "The Saga");
+        assert_eq!(md.series[0].collection_type.as_deref(), Some("series"));
+        assert_eq!(md.series[0].position, Some(2.0));
+        assert_eq!(md.identifiers.len(), 2);
+        assert_eq!(md.identifiers[0].scheme.as_deref(), Some("UUID"));
+        assert_eq!(
+            md.identifiers[0].value,
+            "1b2c3d4e-0000-4000-8000-000000000001"
+        );
+        assert!(md.identifiers[0].unique);
+        assert_eq!(md.identifiers[1].scheme.as_deref(), Some("ISBN"));
+        assert!(!md.identifiers[1].unique);
+        assert_eq!(md.subjects, vec!["Fantasy"]);
+        assert_eq!(md.languages, vec!["en"]);
+        assert_eq!(md.publisher.as_deref(), Some("Tor"));
+        assert_eq!(md.published.as_deref(), Some("2020-05-01"));
+        assert_eq!(md.modified.as_deref(), Some("2026-01-01T00:00:00Z"));
+        assert_eq!(md.rendition.layout.as_deref(), Some("pre-paginated"));
+        assert_eq!(md.rendition.spread.as_deref(), Some("landscape"));
+        assert_eq!(md.accessibility.access_modes, vec!["textual"]);
+        assert_eq!(md.accessibility.features, vec!["tableOfContents"]);
+        assert_eq!(md.accessibility.hazards, vec!["none"]);
+        assert_eq!(
+            md.accessibility.summary.as_deref(),
+            Some("Fully accessible.")
+        );
+    }
+
+    #[test]
+    fn parse_opf_metadata_reads_epub2_attributes_and_calibre_series() {
+        let xml = br#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="BookId">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+    <dc:title>Old Book</dc:title>
+    <dc:creator opf:role="aut" opf:file-as="Smith, Ann">Ann Smith</dc:creator>
+    <dc:identifier id="BookId" opf:scheme="ISBN">urn:isbn:0-306-40615-2</dc:identifier>
+    <dc:identifier opf:scheme="calibre">4f1e</dc:identifier>
+    <dc:date opf:event="modification">2011-01-01</dc:date>
+    <dc:date opf:event="publication">1999</dc:date>
+    <dc:description>Line one
+      &lt;b&gt;two&lt;/b&gt;</dc:description>
+    <meta name="calibre:series" content="Chronicles"/>
+    <meta name="calibre:series_index" content="3.5"/>
+    <meta name="cover" content="cover-img"/>
+  </metadata>
+</package>"#;
+        let md = parse_opf_metadata(xml).expect("parses");
+        assert_eq!(md.titles[0].value, "Old Book");
+        assert_eq!(md.creators[0].roles, vec!["aut"]);
+        assert_eq!(md.creators[0].file_as.as_deref(), Some("Smith, Ann"));
+        assert_eq!(md.identifiers[0].value, "0-306-40615-2");
+        assert_eq!(md.identifiers[0].scheme.as_deref(), Some("ISBN"));
+        assert!(md.identifiers[0].unique);
+        assert_eq!(md.identifiers[1].scheme.as_deref(), Some("CALIBRE"));
+        assert_eq!(md.published.as_deref(), Some("1999"));
+        assert_eq!(md.description.as_deref(), Some("Line one <b>two</b>"));
+        assert_eq!(md.series[0].name, "Chronicles");
+        assert_eq!(md.series[0].position, Some(3.5));
+    }
+
+    #[test]
+    fn is_valid_isbn_checks_checksums() {
+        assert!(is_valid_isbn("0306406152"));
+        assert!(is_valid_isbn("0-8044-2957-X"));
+        assert!(is_valid_isbn("978-0-306-40615-7"));
+        assert!(!is_valid_isbn("9780306406158"));
+        assert!(!is_valid_isbn("12345"));
+    }
+}
@@ -1,14 +1,16 @@
 // Shared helpers for the native import fast-path.
 //
-// Both the EPUB parser (`epub_parser`) and the MOBI/AZW/AZW3 parser
-// (`mobi_parser`) need to:
+// The EPUB parser (`epub_parser`), the MOBI/AZW/AZW3 parser
+// (`mobi_parser`) and the FictionBook parser (`fb2_parser`) all need to:
 //   - compute the same `partialMD5` over the input file as `utils/md5.ts`,
 //     so the on-disk `Books/<hash>/...` layout stays stable regardless of
 //     which parser produced the entry,
 //   - clamp oversized cover artwork to the library-grid thumbnail size,
-//     re-encoding as JPEG q85 when downscaling actually fires.
+//     re-encoding as JPEG q85 when downscaling actually fires,
+//   - report DRM-protected input with the same typed error, so the JS
+//     importer can show one message whatever the format.
 //
-// Keeping these in a single module avoids drift between the two import
+// Keeping these in a single module avoids drift between the import
 // paths (a divergent partialMD5 implementation would silently re-import
 // every existing book under a new hash on the first run after a change).
 //
@@ -19,7 +21,9 @@
 
 use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, GenericImageView};
 use md5::{Digest, Md5};
-use serde::Serialize;
+use serde::{ser::Serializer, Serialize};
+use std::borrow::Cow;
+use std::fmt;
 use std::fs::File;
 use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
@@ -93,6 +97,33 @@ pub fn maybe_resize_cover(bytes: Vec<u8>, hint_mime: &str) -> (Vec<u8>, String)
     (out, "image/jpeg".to_string())
 }
 
+/// Best-effort MIME sniffing from magic bytes for the formats MOBI
+/// covers and comic-archive pages are realistically stored as. Falls
+/// back to "image/jpeg" — the dominant case — when the magic is unknown,
+/// because `image::load_from_memory` (called downstream by
+/// `maybe_resize_cover`) will detect the real format anyway and the
+/// hint MIME is only used when we *don't* re-encode (small covers,
+/// kept verbatim).
+///
+/// BMP is included because some early KindleGen builds (and a few
+/// self-published .prc files) shipped BMP covers; the JS thumbnail
+/// pipeline can render BMP via the same downscale path.
+pub fn sniff_image_mime(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        "image/png"
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        "image/webp"
+    } else if bytes.starts_with(b"BM") {
+        "image/bmp"
+    } else {
+        "image/jpeg"
+    }
+}
+
 /// Mirror of `utils/md5.ts::partialMD5`:
 ///
 /// ```ts
@@ -147,3 +178,130 @@ pub fn compute_partial_md5(path: &Path) -> std::io::Result<String> {
 
     Ok(format!("{:x}", hasher.finalize()))
 }
+
+// ---------------------------------------------------------------------------
+// XML helpers
+//
+// Shared by every quick-xml consumer on the import path (EPUB OPF /
+// container.xml, FB2 documents), so a BOM / UTF-16 quirk fixed for one
+// format is fixed for all of them.
+// ---------------------------------------------------------------------------
+
+/// Normalize the byte payload of an XML document for `quick-xml`:
+///
+///   - strip a leading UTF-8 BOM (EF BB BF) — quick-xml otherwise emits a
+///     spurious `Text` event before the prolog and some declarations fail
+///     to parse;
+///   - if the document begins with a UTF-16 BOM (FE FF or FF FE), transcode
+///     to UTF-8 lossily so the rest of our pipeline can keep treating bytes
+///     as UTF-8. Real-world EPUBs are very rarely UTF-16 but a handful of
+///     publisher tools (notably old Adobe InDesign exports) still emit it.
+///
+/// Returns a `Cow` so the common (UTF-8, no BOM) case stays zero-copy.
+pub fn strip_xml_bom(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if bytes.len() >= 3 && bytes[0] == 0xEF && bytes[1] == 0xBB && bytes[2] == 0xBF {
+        return Cow::Borrowed(&bytes[3..]);
+    }
+    if bytes.len() >= 2 {
+        let big_endian = bytes[0] == 0xFE && bytes[1] == 0xFF;
+        let little_endian = bytes[0] == 0xFF && bytes[1] == 0xFE;
+        if big_endian || little_endian {
+            let body = &bytes[2..];
+            // chunks_exact silently drops a trailing odd byte, which is what
+            // we want — a malformed UTF-16 stream still produces a best-
+            // effort UTF-8 transcoding rather than failing the whole import.
+            let units: Vec<u16> = body
+                .chunks_exact(2)
+                .map(|c| {
+                    if big_endian {
+                        u16::from_be_bytes([c[0], c[1]])
+                    } else {
+                        u16::from_le_bytes([c[0], c[1]])
+                    }
+                })
+                .collect();
+            let s = String::from_utf16_lossy(&units);
+            return Cow::Owned(s.into_bytes());
+        }
+    }
+    Cow::Borrowed(bytes)
+}
+
+pub fn local_name(qname: &[u8]) -> &[u8] {
+    match qname.iter().rposition(|b| *b == b':') {
+        Some(idx) => &qname[idx + 1..],
+        None => qname,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DRM detection result
+//
+// DRM-protected books look healthy at the container level — the OPF and
+// the PalmDB / MOBI headers are never encrypted — but every section body is
+// ciphertext, so they used to import "successfully" and open as blank
+// pages. The EPUB and MOBI parsers check for the common schemes up-front
+// and fail with `ParseError::DrmProtected`. It crosses IPC as a plain
+// string (like every other parser error) that starts with
+// `DRM_ERROR_PREFIX`, which is what the JS bridges match on.
+// ---------------------------------------------------------------------------
+
+/// Prefix of the serialized `ParseError::DrmProtected` message. Keep in
+/// sync with `DRM_ERROR_PREFIX` in `src/services/errors.ts`.
+pub const DRM_ERROR_PREFIX: &str = "DRM-protected";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrmScheme {
+    /// Adobe ADEPT (Adobe Digital Editions): `META-INF/rights.xml` plus an
+    /// `encryption.xml` whose keys point at the Adobe namespace.
+    AdobeAdept,
+    /// Readium LCP: `META-INF/license.lcpl`.
+    ReadiumLcp,
+    /// Mobipocket / Kindle encryption flagged in the PalmDOC header.
+    Kindle,
+    /// `encryption.xml` lists non-font resources but names no known scheme.
+    Unknown,
+}
+
+impl DrmScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DrmScheme::AdobeAdept => "adobe-adept",
+            DrmScheme::ReadiumLcp => "readium-lcp",
+            DrmScheme::Kindle => "kindle",
+            DrmScheme::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for DrmScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned by the native parser commands. Everything except DRM is
+/// still a free-form message; `From<String>` keeps the existing
+/// `map_err(|e| format!(..))?` call sites unchanged.
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("DRM-protected ({0}): the book is encrypted and cannot be opened")]
+    DrmProtected(DrmScheme),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        ParseError::Other(message)
+    }
+}
+
+impl Serialize for ParseError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
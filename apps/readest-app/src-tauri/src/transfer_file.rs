@@ -4,24 +4,37 @@
 
 //! Upload files from disk to a remote server over HTTP.
 //!
-//! Download files from a remote HTTP server to disk.
+//! Download files from a remote HTTP server to disk. Multi-part downloads
+//! keep a `<file>.download.json` sidecar of the parts already on disk, so an
+//! interrupted download resumes instead of starting over. Finished downloads
+//! are checked against the caller's expected size/checksums and the server's
+//! `Content-Length` / `Content-MD5` / `x-amz-checksum-sha256`.
 
+use base64::Engine;
 use futures_util::TryStreamExt;
-use serde::{ser::Serializer, Serialize};
+use md5::Md5;
+use serde::{ser::Serializer, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tauri::{command, ipc::Channel, AppHandle};
 use tauri_plugin_fs::FsExt;
 use tokio::{
-    fs::File,
+    fs::{File, OpenOptions},
     io::{AsyncWriteExt, BufWriter},
 };
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 use read_progress_stream::ReadProgressStream;
 
-use std::time::Instant;
-use std::{collections::HashMap, sync::Arc};
+use crate::bandwidth;
+use crate::http_client;
 
-type Result<T> = std::result::Result<T, Error>;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 // The TransferStats struct tracks both transfer speed and cumulative transfer progress.
 pub struct TransferStats {
@@ -85,6 +98,36 @@ pub enum Error {
     HttpErrorCode(u16, String),
     #[error("permission denied: path not in filesystem scope: {0}")]
     Forbidden(String),
+    #[error("the remote file changed while downloading")]
+    RemoteChanged,
+    #[error("upload failed: {0}")]
+    Upload(String),
+    #[error("download incomplete: {missing} of {parts} parts failed ({reason})")]
+    Incomplete {
+        missing: usize,
+        parts: u64,
+        reason: String,
+    },
+    /// Keep the message prefix in sync with `INTEGRITY_ERROR_PREFIX` in
+    /// `src/services/errors.ts`.
+    #[error("integrity check failed: {check} mismatch (expected {expected}, got {actual})")]
+    Integrity {
+        check: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl Error {
+    /// Transient failures worth retrying a transfer part for: network errors,
+    /// short bodies, timeouts, throttling and server-side errors.
+    pub(crate) fn is_retryable(&self) -> bool {
+        match self {
+            Error::Request(_) | Error::ContentLength(_) => true,
+            Error::HttpErrorCode(code, _) => *code >= 500 || *code == 408 || *code == 429,
+            _ => false,
+        }
+    }
 }
 
 /// Reject paths the webview must not be allowed to target: relative paths and
@@ -143,9 +186,383 @@ impl Serialize for Error {
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProgressPayload {
-    progress: u64,
+    pub(crate) progress: u64,
+    pub(crate) total: u64,
+    pub(crate) transfer_speed: u64,
+}
+
+/// Where transfer progress goes: the invoking command's `Channel`, or the
+/// transfer manager's `transfer:progress` event.
+pub(crate) type ProgressSink = Arc<dyn Fn(ProgressPayload) + Send + Sync>;
+
+pub(crate) fn channel_sink(channel: Channel<ProgressPayload>) -> ProgressSink {
+    Arc::new(move |payload| {
+        let _ = channel.send(payload);
+    })
+}
+
+/// Arguments of a download, shared by `download_file` and queued transfers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRequest {
+    pub url: String,
+    pub file_path: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    pub single_threaded: Option<bool>,
+    pub skip_ssl_verification: Option<bool>,
+    pub expected_size: Option<u64>,
+    pub expected_sha256: Option<String>,
+    pub expected_md5: Option<String>,
+}
+
+/// Arguments of a single-request upload, shared by `upload_file` and queued
+/// transfers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadRequest {
+    pub url: String,
+    pub file_path: String,
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// What a finished download must match. Every field is optional; the
+/// caller's values take precedence over the ones the server advertised.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct IntegrityExpectations {
+    size: Option<u64>,
+    /// Lowercase hex.
+    sha256: Option<String>,
+    /// Lowercase hex.
+    md5: Option<String>,
+}
+
+impl IntegrityExpectations {
+    /// Expectations from response headers (lowercase names, as collected into
+    /// `resp_headers`). Checksums describe the body of *that* response, so they
+    /// are only used when it carried the whole file, not a probed range.
+    fn from_headers(headers: &HashMap<String, String>, full_body: bool) -> Self {
+        if !full_body {
+            return Self::default();
+        }
+        Self {
+            size: headers
+                .get("content-length")
+                .and_then(|v| v.trim().parse().ok()),
+            // S3 multipart objects carry a checksum-of-checksums
+            // (`<base64>-<parts>`), which no single-pass hash reproduces.
+            sha256: headers
+                .get("x-amz-checksum-sha256")
+                .filter(|v| !v.contains('-'))
+                .and_then(|v| base64_to_hex(v)),
+            md5: headers.get("content-md5").and_then(|v| base64_to_hex(v)),
+        }
+    }
+
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            size: self.size.or(fallback.size),
+            sha256: self.sha256.or(fallback.sha256),
+            md5: self.md5.or(fallback.md5),
+        }
+    }
+}
+
+fn base64_to_hex(value: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .ok()?;
+    Some(to_hex(&bytes))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Check the file on disk against `expected`. On mismatch the file (and any
+/// resume sidecar) is deleted, so a truncated or corrupted download never
+/// reaches the importer.
+async fn verify_download(file_path: &str, expected: IntegrityExpectations) -> Result<()> {
+    let result = check_integrity(file_path, expected).await;
+    if let Err(e @ Error::Integrity { .. }) = &result {
+        log::warn!("discarding {file_path}: {e}");
+        let _ = tokio::fs::remove_file(file_path).await;
+        let _ = tokio::fs::remove_file(DownloadManifest::path_for(file_path)).await;
+    }
+    result
+}
+
+async fn check_integrity(file_path: &str, expected: IntegrityExpectations) -> Result<()> {
+    if let Some(size) = expected.size {
+        let actual = tokio::fs::metadata(file_path).await?.len();
+        if actual != size {
+            return Err(Error::Integrity {
+                check: "size",
+                expected: size.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+    if expected.sha256.is_none() && expected.md5.is_none() {
+        return Ok(());
+    }
+
+    let path = file_path.to_string();
+    let want_sha256 = expected.sha256.is_some();
+    let want_md5 = expected.md5.is_some();
+    let (sha256, md5) = tauri::async_runtime::spawn_blocking(move || {
+        hash_file(Path::new(&path), want_sha256, want_md5)
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+
+    for (check, expected, actual) in [
+        ("sha256", expected.sha256, sha256),
+        ("md5", expected.md5, md5),
+    ] {
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            if !expected.eq_ignore_ascii_case(&actual) {
+                return Err(Error::Integrity {
+                    check,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Single pass over the file feeding whichever digests are wanted.
+fn hash_file(
+    path: &Path,
+    sha256: bool,
+    md5: bool,
+) -> std::io::Result<(Option<String>, Option<String>)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut sha256 = sha256.then(Sha256::new);
+    let mut md5 = md5.then(Md5::new);
+    let mut buf = vec![0u8; 256 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(hasher) = sha256.as_mut() {
+            hasher.update(&buf[..n]);
+        }
+        if let Some(hasher) = md5.as_mut() {
+            hasher.update(&buf[..n]);
+        }
+    }
+    Ok((
+        sha256.map(|h| to_hex(&h.finalize())),
+        md5.map(|h| to_hex(&h.finalize())),
+    ))
+}
+
+/// Attempts per part before a download or chunked upload gives up on it.
+pub(crate) const PART_ATTEMPTS: u32 = 5;
+/// First retry delay; doubled on every further attempt.
+pub(crate) const PART_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Run `op` until it succeeds, fails with a non-retryable error, or
+/// [`PART_ATTEMPTS`] are used up, backing off exponentially in between.
+/// `what` names the operation in the log.
+pub(crate) async fn retry_transient<T, F, Fut>(what: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(e) if e.is_retryable() && attempt + 1 < PART_ATTEMPTS => {
+                let delay = PART_RETRY_DELAY * 2u32.pow(attempt);
+                log::warn!("{what} failed: {e}; retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Sidecar manifest of a multi-part download, saved next to the target as
+/// `<file>.download.json` after every completed part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DownloadManifest {
+    /// Download URL without query or fragment: presigned URLs carry a fresh
+    /// signature on every request, the validators below pin the content.
+    url: String,
     total: u64,
-    transfer_speed: u64,
+    part_size: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    completed: BTreeSet<u64>,
+}
+
+impl DownloadManifest {
+    fn new(url: &str, total: u64, part_size: u64, headers: &reqwest::header::HeaderMap) -> Self {
+        let header = |name: reqwest::header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            url: manifest_url(url),
+            total,
+            part_size,
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            completed: BTreeSet::new(),
+        }
+    }
+
+    pub(crate) fn path_for(file_path: &str) -> PathBuf {
+        PathBuf::from(format!("{file_path}.download.json"))
+    }
+
+    /// `If-Range` value: a strong ETag, else Last-Modified. Weak ETags are
+    /// not allowed in `If-Range`. Without either, parts fetched now cannot
+    /// be proven to belong to the same file as parts fetched after a restart.
+    fn validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+
+    /// Whether the parts recorded in `self` are still valid for the file the
+    /// server describes in `fresh`.
+    fn can_resume_as(&self, fresh: &DownloadManifest) -> bool {
+        self.validator().is_some()
+            && self.url == fresh.url
+            && self.total == fresh.total
+            && self.part_size == fresh.part_size
+            && self.etag == fresh.etag
+            && self.last_modified == fresh.last_modified
+    }
+
+    fn part_count(&self) -> u64 {
+        self.total.div_ceil(self.part_size)
+    }
+
+    fn part_range(&self, part: u64) -> (u64, u64) {
+        let start = part * self.part_size;
+        (start, (start + self.part_size).min(self.total) - 1)
+    }
+
+    fn missing_parts(&self) -> Vec<u64> {
+        (0..self.part_count())
+            .filter(|part| !self.completed.contains(part))
+            .collect()
+    }
+
+    fn completed_bytes(&self) -> u64 {
+        self.completed
+            .iter()
+            .map(|&part| {
+                let (start, end) = self.part_range(part);
+                end - start + 1
+            })
+            .sum()
+    }
+
+    async fn load(path: &Path) -> Option<Self> {
+        let bytes = tokio::fs::read(path).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(
+            &tmp,
+            serde_json::to_vec(self).map_err(std::io::Error::other)?,
+        )
+        .await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+fn manifest_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_query(None);
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Fetch the inclusive byte range `start..=end`, guarded by `If-Range` so a
+/// file replaced on the server is reported instead of spliced into ours.
+async fn fetch_part(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+    validator: Option<&str>,
+    start: u64,
+    end: u64,
+) -> Result<bytes::Bytes> {
+    let mut req = client
+        .get(url)
+        .header("Range", format!("bytes={start}-{end}"));
+    if let Some(validator) = validator {
+        req = req.header("If-Range", validator);
+    }
+    for (key, value) in headers {
+        req = req.header(key, value);
+    }
+
+    let resp = req.send().await?;
+    match resp.status() {
+        reqwest::StatusCode::PARTIAL_CONTENT => {}
+        // A full-body 200 means `If-Range` no longer matched (or the server
+        // ignored the range); either way the bytes don't belong at `start`.
+        reqwest::StatusCode::OK if validator.is_some() => return Err(Error::RemoteChanged),
+        status => {
+            return Err(Error::HttpErrorCode(
+                status.as_u16(),
+                resp.text().await.unwrap_or_default(),
+            ))
+        }
+    }
+
+    let mut bytes = bytes::BytesMut::with_capacity((end - start + 1) as usize);
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.try_next().await? {
+        bandwidth::throttle_bytes(&chunk).await;
+        bytes.extend_from_slice(&chunk);
+    }
+    if bytes.len() as u64 != end - start + 1 {
+        return Err(Error::ContentLength(format!(
+            "part {start}-{end}: got {} bytes",
+            bytes.len()
+        )));
+    }
+    Ok(bytes.freeze())
+}
+
+async fn fetch_part_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+    validator: Option<&str>,
+    start: u64,
+    end: u64,
+) -> Result<bytes::Bytes> {
+    retry_transient(&format!("download part {start}-{end}"), || {
+        fetch_part(client, url, headers, validator, start, end)
+    })
+    .await
 }
 
 #[command]
@@ -158,20 +575,61 @@ pub async fn download_file(
     body: Option<String>,
     single_threaded: Option<bool>,
     skip_ssl_verification: Option<bool>,
+    expected_size: Option<u64>,
+    expected_sha256: Option<String>,
+    expected_md5: Option<String>,
     on_progress: Channel<ProgressPayload>,
+) -> Result<HashMap<String, String>> {
+    ensure_path_allowed(&app, file_path)?;
+
+    let request = DownloadRequest {
+        url: url.to_string(),
+        file_path: file_path.to_string(),
+        headers,
+        body,
+        single_threaded,
+        skip_ssl_verification,
+        expected_size,
+        expected_sha256,
+        expected_md5,
+    };
+    download(&request, channel_sink(on_progress)).await
+}
+
+/// The body of `download_file`; the caller has already checked the path.
+pub(crate) async fn download(
+    request: &DownloadRequest,
+    on_progress: ProgressSink,
 ) -> Result<HashMap<String, String>> {
     use futures::stream::{self, StreamExt};
-    use std::cmp::min;
     use tokio::io::AsyncSeekExt;
 
-    ensure_path_allowed(&app, file_path)?;
+    let DownloadRequest {
+        url,
+        file_path,
+        headers,
+        body,
+        single_threaded,
+        skip_ssl_verification,
+        expected_size,
+        expected_sha256,
+        expected_md5,
+    } = request;
+    let (url, file_path) = (url.as_str(), file_path.as_str());
+
+    let expected = IntegrityExpectations {
+        size: *expected_size,
+        sha256: expected_sha256.as_ref().map(|h| h.to_lowercase()),
+        md5: expected_md5.as_ref().map(|h| h.to_lowercase()),
+    };
 
     const PART_SIZE: u64 = 1024 * 1024;
 
-    let client = reqwest::ClientBuilder::new()
-        .danger_accept_invalid_certs(skip_ssl_verification.unwrap_or(false))
-        .danger_accept_invalid_hostnames(skip_ssl_verification.unwrap_or(false))
-        .build()?;
+    let client = if skip_ssl_verification.unwrap_or(false) {
+        http_client::insecure_client()?
+    } else {
+        http_client::client()?
+    };
     let force_single = single_threaded.unwrap_or(false);
 
     async fn single_threaded_download(
@@ -180,7 +638,7 @@ pub async fn download_file(
         file_path: &str,
         headers: &HashMap<String, String>,
         body: &Option<String>,
-        on_progress: Channel<ProgressPayload>,
+        on_progress: ProgressSink,
     ) -> Result<HashMap<String, String>> {
         let mut request = if let Some(body) = body {
             client.post(url).body(body.clone())
@@ -213,9 +671,10 @@ pub async fn download_file(
 
         let mut stats = TransferStats::default();
         while let Some(chunk) = stream.try_next().await? {
+            bandwidth::throttle_bytes(&chunk).await;
             file.write_all(&chunk).await?;
             stats.record_chunk_transfer(chunk.len());
-            let _ = on_progress.send(ProgressPayload {
+            on_progress(ProgressPayload {
                 progress: stats.total_transferred,
                 total,
                 transfer_speed: stats.transfer_speed,
@@ -227,8 +686,11 @@ pub async fn download_file(
     }
 
     if force_single {
-        return single_threaded_download(&client, url, file_path, &headers, &body, on_progress)
-            .await;
+        let resp_headers =
+            single_threaded_download(&client, url, file_path, headers, body, on_progress).await?;
+        let from_server = IntegrityExpectations::from_headers(&resp_headers, true);
+        verify_download(file_path, expected.or(from_server)).await?;
+        return Ok(resp_headers);
     }
 
     // Check if server supports range requests
@@ -259,73 +721,120 @@ pub async fn download_file(
     }
 
     if !accept_ranges || total == 0 {
-        return single_threaded_download(&client, url, file_path, &headers, &body, on_progress)
-            .await;
+        let resp_headers =
+            single_threaded_download(&client, url, file_path, headers, body, on_progress).await?;
+        let from_server = IntegrityExpectations::from_headers(&resp_headers, true);
+        verify_download(file_path, expected.or(from_server)).await?;
+        return Ok(resp_headers);
     }
 
-    // Multi-part download with range access
-    let part_count = total.div_ceil(PART_SIZE);
-    let file = File::create(file_path).await?;
-    file.set_len(total).await?;
+    // Multi-part download with range access. Resume from the sidecar when
+    // it describes the same remote file and the target is still allocated.
+    let fresh = DownloadManifest::new(url, total, PART_SIZE, range_resp.headers());
+    let manifest_path = DownloadManifest::path_for(file_path);
+    let target_len = tokio::fs::metadata(file_path).await.map(|m| m.len()).ok();
+    let manifest = match DownloadManifest::load(&manifest_path).await {
+        Some(saved) if saved.can_resume_as(&fresh) && target_len == Some(total) => saved,
+        _ => fresh,
+    };
+    let resumable = manifest.validator().is_some();
+    let validator = manifest.validator().map(str::to_string);
+    let part_count = manifest.part_count();
+    let missing = manifest.missing_parts();
+
+    let file = if manifest.completed.is_empty() {
+        let file = File::create(file_path).await?;
+        file.set_len(total).await?;
+        file
+    } else {
+        log::info!(
+            "resuming download of {file_path}: {} of {part_count} parts on disk",
+            manifest.completed.len()
+        );
+        OpenOptions::new().write(true).open(file_path).await?
+    };
+    if resumable {
+        manifest.save(&manifest_path).await?;
+    }
 
+    let stats = TransferStats {
+        total_transferred: manifest.completed_bytes(),
+        ..TransferStats::default()
+    };
     let file = Arc::new(tokio::sync::Mutex::new(file));
-    let progress = Arc::new(tokio::sync::Mutex::new(TransferStats::default()));
+    let progress = Arc::new(tokio::sync::Mutex::new(stats));
+    let manifest = Arc::new(tokio::sync::Mutex::new(manifest));
 
-    stream::iter(0..part_count)
-        .for_each_concurrent(8, |i| {
-            let client = client.clone();
+    let errors: Vec<Error> = stream::iter(missing)
+        .map(|i| {
+            let client = &client;
+            let validator = validator.as_deref();
             let file = Arc::clone(&file);
             let progress = Arc::clone(&progress);
-            let headers = headers.clone();
-            let url = url.to_string();
-            let on_progress = on_progress.clone();
+            let manifest = Arc::clone(&manifest);
+            let manifest_path = &manifest_path;
+            let on_progress = Arc::clone(&on_progress);
 
             async move {
-                let start = i * PART_SIZE;
-                let end = min(start + PART_SIZE - 1, total - 1);
-                let range_header = format!("bytes={start}-{end}");
+                let (start, end) = manifest.lock().await.part_range(i);
+                let bytes =
+                    fetch_part_with_retry(client, url, headers, validator, start, end).await?;
 
-                let mut req = client.get(&url).header("Range", range_header);
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
-
-                let resp = match req.send().await {
-                    Ok(r) => r,
-                    Err(_) => return,
-                };
-
-                if !resp.status().is_success()
-                    && resp.status() != reqwest::StatusCode::PARTIAL_CONTENT
                 {
-                    return;
+                    let mut f = file.lock().await;
+                    f.seek(std::io::SeekFrom::Start(start)).await?;
+                    f.write_all(&bytes).await?;
+                    // Make sure the write reached the OS before the part is
+                    // recorded as done.
+                    f.flush().await?;
                 }
 
-                let bytes = match resp.bytes().await {
-                    Ok(b) => b,
-                    Err(_) => return,
-                };
-
-                {
-                    let mut f = file.lock().await;
-                    f.seek(std::io::SeekFrom::Start(start)).await.unwrap();
-                    f.write_all(&bytes).await.unwrap();
+                if resumable {
+                    let mut manifest = manifest.lock().await;
+                    manifest.completed.insert(i);
+                    if let Err(e) = manifest.save(manifest_path).await {
+                        log::warn!("failed to save download manifest: {e}");
+                    }
                 }
 
                 {
                     let mut stat = progress.lock().await;
                     stat.record_chunk_transfer(bytes.len());
-                    let _ = on_progress.send(ProgressPayload {
+                    on_progress(ProgressPayload {
                         progress: stat.total_transferred,
                         total,
                         transfer_speed: stat.transfer_speed,
                     });
                 }
+                Ok(())
             }
         })
+        .buffer_unordered(8)
+        .filter_map(|result: Result<()>| async move { result.err() })
+        .collect()
         .await;
 
-    Ok(resp_headers)
+    if errors.is_empty() {
+        let _ = tokio::fs::remove_file(&manifest_path).await;
+        // The probe only saw a one-byte range; `total` from its
+        // Content-Range stands in for Content-Length.
+        let from_server = IntegrityExpectations {
+            size: Some(total),
+            ..IntegrityExpectations::from_headers(&resp_headers, false)
+        };
+        verify_download(file_path, expected.or(from_server)).await?;
+        return Ok(resp_headers);
+    }
+    if errors.iter().any(|e| matches!(e, Error::RemoteChanged)) {
+        // The parts on disk are from an older revision; start over next time.
+        let _ = tokio::fs::remove_file(&manifest_path).await;
+        return Err(Error::RemoteChanged);
+    }
+    Err(Error::Incomplete {
+        missing: errors.len(),
+        parts: part_count,
+        reason: errors[0].to_string(),
+    })
 }
 
 #[command]
@@ -339,22 +848,33 @@ pub async fn upload_file(
 ) -> Result<String> {
     ensure_path_allowed(&app, file_path)?;
 
-    let file = File::open(file_path).await?;
+    let request = UploadRequest {
+        url: url.to_string(),
+        file_path: file_path.to_string(),
+        method: method.to_string(),
+        headers,
+    };
+    upload(&request, channel_sink(on_progress)).await
+}
+
+/// The body of `upload_file`; the caller has already checked the path.
+pub(crate) async fn upload(upload: &UploadRequest, on_progress: ProgressSink) -> Result<String> {
+    let file = File::open(&upload.file_path).await?;
     let file_len = file.metadata().await.unwrap().len();
 
-    let client = reqwest::Client::new();
-    let mut request = match method.to_uppercase().as_str() {
-        "POST" => client.post(url),
-        "PUT" => client.put(url),
+    let client = http_client::client()?;
+    let mut request = match upload.method.to_uppercase().as_str() {
+        "POST" => client.post(&upload.url),
+        "PUT" => client.put(&upload.url),
         _ => return Err(Error::ContentLength("Invalid HTTP method".into())),
     };
 
     request = request
         .header(reqwest::header::CONTENT_LENGTH, file_len)
-        .body(file_to_body(on_progress.clone(), file, file_len));
+        .body(file_to_body(on_progress, file, file_len));
 
-    for (key, value) in headers {
-        request = request.header(&key, value);
+    for (key, value) in &upload.headers {
+        request = request.header(key, value);
     }
 
     let response = request.send().await?;
@@ -368,15 +888,23 @@ pub async fn upload_file(
     }
 }
 
-fn file_to_body(channel: Channel<ProgressPayload>, file: File, file_len: u64) -> reqwest::Body {
-    let stream = FramedRead::new(file, BytesCodec::new()).map_ok(|r| r.freeze());
+fn file_to_body(on_progress: ProgressSink, file: File, file_len: u64) -> reqwest::Body {
+    // Pinned: `ReadProgressStream` needs an `Unpin` stream.
+    let stream = Box::pin(
+        FramedRead::new(file, BytesCodec::new())
+            .map_ok(|r| r.freeze())
+            .and_then(|chunk| async move {
+                bandwidth::throttle_bytes(&chunk).await;
+                Ok(chunk)
+            }),
+    );
 
     let mut stats = TransferStats::default();
     reqwest::Body::wrap_stream(ReadProgressStream::new(
         stream,
         Box::new(move |progress_chunk, _progress_total| {
             stats.record_chunk_transfer(progress_chunk as usize);
-            let _ = channel.send(ProgressPayload {
+            on_progress(ProgressPayload {
                 progress: stats.total_transferred,
                 total: file_len,
                 transfer_speed: stats.transfer_speed,
@@ -387,7 +915,128 @@ fn file_to_body(channel: Channel<ProgressPayload>, file: File, file_len: u64) ->
 
 #[cfg(test)]
 mod tests {
-    use super::{has_disallowed_components, is_within_app_storage};
+    use super::{
+        has_disallowed_components, hash_file, is_within_app_storage, DownloadManifest,
+        IntegrityExpectations,
+    };
+    use reqwest::header::{HeaderMap, HeaderValue, ETAG, LAST_MODIFIED};
+    use std::collections::HashMap;
+
+    #[test]
+    fn integrity_expectations_from_full_response_headers() {
+        let headers: HashMap<String, String> = [
+            ("content-length", "11"),
+            // md5("hello world") and sha256("hello world"), base64-encoded.
+            ("content-md5", "XrY7u+Ae7tCTyyK7j1rNww=="),
+            (
+                "x-amz-checksum-sha256",
+                "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=",
+            ),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let expected = IntegrityExpectations::from_headers(&headers, true);
+        assert_eq!(expected.size, Some(11));
+        assert_eq!(
+            expected.md5.as_deref(),
+            Some("5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+        assert_eq!(
+            expected.sha256.as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+        // A ranged probe response says nothing about the whole file.
+        assert_eq!(
+            IntegrityExpectations::from_headers(&headers, false),
+            IntegrityExpectations::default()
+        );
+    }
+
+    #[test]
+    fn integrity_expectations_skip_composite_checksums_and_prefer_caller() {
+        let headers: HashMap<String, String> =
+            [("x-amz-checksum-sha256".to_string(), "abc=-3".to_string())]
+                .into_iter()
+                .collect();
+        assert_eq!(
+            IntegrityExpectations::from_headers(&headers, true).sha256,
+            None
+        );
+
+        let caller = IntegrityExpectations {
+            size: Some(5),
+            ..Default::default()
+        };
+        let server = IntegrityExpectations {
+            size: Some(6),
+            md5: Some("00".into()),
+            ..Default::default()
+        };
+        let merged = caller.or(server);
+        assert_eq!(merged.size, Some(5));
+        assert_eq!(merged.md5.as_deref(), Some("00"));
+    }
+
+    #[test]
+    fn hash_file_computes_requested_digests_only() {
+        let path = std::env::temp_dir().join(format!("readest-hash-{}", std::process::id()));
+        std::fs::write(&path, b"hello world").unwrap();
+        let (sha256, md5) = hash_file(&path, false, true).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(sha256, None);
+        assert_eq!(md5.as_deref(), Some("5eb63bbbe01eeed093cb22bb8f5acdc3"));
+    }
+
+    fn manifest(url: &str, etag: Option<&'static str>) -> DownloadManifest {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(ETAG, HeaderValue::from_static(etag));
+        }
+        DownloadManifest::new(url, 2500, 1000, &headers)
+    }
+
+    #[test]
+    fn manifest_tracks_parts_and_bytes() {
+        let mut m = manifest("https://example.com/a.m4b", Some("\"v1\""));
+        assert_eq!(m.part_count(), 3);
+        assert_eq!(m.part_range(2), (2000, 2499));
+        m.completed.extend([0, 2]);
+        assert_eq!(m.missing_parts(), [1]);
+        assert_eq!(m.completed_bytes(), 1500);
+    }
+
+    #[test]
+    fn manifest_resumes_only_the_same_remote_file() {
+        let saved = manifest(
+            "https://cdn.example.com/a.cbz?X-Amz-Signature=1",
+            Some("\"v1\""),
+        );
+        // A re-signed URL for the same object is still the same file.
+        assert!(saved.can_resume_as(&manifest(
+            "https://cdn.example.com/a.cbz?X-Amz-Signature=2",
+            Some("\"v1\"")
+        )));
+        assert!(!saved.can_resume_as(&manifest("https://cdn.example.com/a.cbz", Some("\"v2\""))));
+        assert!(!saved.can_resume_as(&manifest("https://cdn.example.com/b.cbz", Some("\"v1\""))));
+    }
+
+    #[test]
+    fn manifest_without_strong_validator_is_not_resumable() {
+        let weak = manifest("https://example.com/a.epub", Some("W/\"v1\""));
+        assert_eq!(weak.validator(), None);
+        assert!(!weak.can_resume_as(&weak.clone()));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("W/\"v1\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        let dated = DownloadManifest::new("https://example.com/a.epub", 10, 5, &headers);
+        assert_eq!(dated.validator(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+    }
 
     #[test]
     fn app_storage_fallback_accepts_app_paths() {
@@ -0,0 +1,718 @@
+//! Queued, cancellable transfers.
+//!
+//! `download_file`, `upload_file` and `upload_file_chunked` start as soon as
+//! JS invokes them and can't be stopped, so a library sync fires every
+//! transfer at once. `transfer_enqueue` hands the same requests to a queue:
+//!
+//!   - each transfer gets an ID and starts, first in first out, once a slot
+//!     under the global concurrency limit is free;
+//!   - `transfer_pause` / `transfer_cancel` abort the running task. Resuming
+//!     a download or chunked upload continues from its resume sidecar or
+//!     checkpoint; a plain upload starts over;
+//!   - the queue (requests, states, limit) lives in
+//!     `<app_data_dir>/transfers.json` and is restored on launch, with
+//!     transfers that were running put back in the queue. Request headers
+//!     carry bearer tokens and signatures, so they are never written: a
+//!     restored transfer that had some comes back paused with
+//!     `awaitingHeaders`, and `transfer_resume` takes them again. Restored
+//!     paths are re-checked against the fs scope;
+//!   - nothing starts before the webview pushed its network config and
+//!     bandwidth policy (`config_pushed`), so no transfer runs with the
+//!     default proxy, CA roots or policy;
+//!   - state changes are emitted as `transfer:state`, progress as
+//!     `transfer:progress`. Finished, failed and cancelled transfers leave
+//!     the queue once their final state event is out.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::bandwidth;
+use crate::chunked_upload::{upload_chunked, ChunkedUploadRequest};
+use crate::transfer_file::{
+    download, ensure_path_allowed, upload, DownloadManifest, DownloadRequest, ProgressPayload,
+    ProgressSink, UploadRequest,
+};
+
+pub const EV_TRANSFER_STATE: &str = "transfer:state";
+pub const EV_TRANSFER_PROGRESS: &str = "transfer:progress";
+/// Reason sent with `queued` while the metered-network policy holds the queue.
+pub const METERED_HOLD: &str = "Waiting for an unmetered connection";
+
+/// Transfers running at once unless the user picks another limit. Low on
+/// purpose: a full-library sync must not saturate a mobile connection.
+const DEFAULT_CONCURRENCY: usize = 2;
+const MAX_CONCURRENCY: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum TransferRequest {
+    Download(DownloadRequest),
+    Upload(UploadRequest),
+    ChunkedUpload(ChunkedUploadRequest),
+}
+
+impl TransferRequest {
+    fn file_path(&self) -> &str {
+        match self {
+            TransferRequest::Download(r) => &r.file_path,
+            TransferRequest::Upload(r) => &r.file_path,
+            TransferRequest::ChunkedUpload(r) => &r.file_path,
+        }
+    }
+
+    fn headers_mut(&mut self) -> &mut HashMap<String, String> {
+        match self {
+            TransferRequest::Download(r) => &mut r.headers,
+            TransferRequest::Upload(r) => &mut r.headers,
+            TransferRequest::ChunkedUpload(r) => &mut r.headers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferState {
+    Queued,
+    Active,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferInfo {
+    pub id: String,
+    pub request: TransferRequest,
+    pub state: TransferState,
+    /// Bytes transferred / expected, as last reported (0 when unknown).
+    pub progress: u64,
+    pub total: u64,
+    /// Restored without the request headers it was queued with; stays
+    /// paused until `transfer_resume` supplies them.
+    #[serde(default)]
+    pub awaiting_headers: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferStatePayload {
+    pub id: String,
+    pub state: TransferState,
+    /// On failure, the error; on `queued`, why the transfer isn't running
+    /// yet (`METERED_HOLD`).
+    pub error: Option<String>,
+    /// On completion: the download's response headers, the upload's response
+    /// body, or the chunked upload's result.
+    pub result: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgressPayload {
+    pub id: String,
+    pub progress: u64,
+    pub total: u64,
+    pub transfer_speed: u64,
+}
+
+/// The persisted part of the manager.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferQueue {
+    concurrency: usize,
+    transfers: Vec<TransferInfo>,
+}
+
+impl Default for TransferQueue {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            transfers: Vec::new(),
+        }
+    }
+}
+
+impl TransferQueue {
+    /// The queue as written to disk: without request headers.
+    fn for_disk(&self) -> Self {
+        let transfers = self
+            .transfers
+            .iter()
+            .map(|transfer| {
+                let mut transfer = transfer.clone();
+                let headers = transfer.request.headers_mut();
+                transfer.awaiting_headers |= !headers.is_empty();
+                headers.clear();
+                transfer
+            })
+            .collect();
+        Self {
+            concurrency: self.concurrency,
+            transfers,
+        }
+    }
+
+    /// A queue loaded from disk: whatever was running when the app quit is
+    /// queued again, unless it needs its headers back first.
+    fn restored(mut self) -> Self {
+        self.transfers.retain(|t| {
+            matches!(
+                t.state,
+                TransferState::Queued | TransferState::Active | TransferState::Paused
+            )
+        });
+        for transfer in &mut self.transfers {
+            transfer.state = if transfer.awaiting_headers {
+                TransferState::Paused
+            } else if transfer.state == TransferState::Active {
+                TransferState::Queued
+            } else {
+                transfer.state
+            };
+        }
+        self.concurrency = self.concurrency.clamp(1, MAX_CONCURRENCY);
+        self
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut TransferInfo> {
+        self.transfers.iter_mut().find(|t| t.id == id)
+    }
+
+    fn remove(&mut self, id: &str) -> Option<TransferInfo> {
+        let index = self.transfers.iter().position(|t| t.id == id)?;
+        Some(self.transfers.remove(index))
+    }
+
+    /// Put running transfers back in the queue and return their IDs.
+    fn hold_active(&mut self) -> Vec<String> {
+        self.transfers
+            .iter_mut()
+            .filter(|t| t.state == TransferState::Active)
+            .map(|t| {
+                t.state = TransferState::Queued;
+                t.id.clone()
+            })
+            .collect()
+    }
+
+    /// Mark as many queued transfers active as there are free slots, oldest
+    /// first, and return them.
+    fn start_next(&mut self) -> Vec<(String, TransferRequest)> {
+        let active = self
+            .transfers
+            .iter()
+            .filter(|t| t.state == TransferState::Active)
+            .count();
+        let free = self.concurrency.saturating_sub(active);
+        self.transfers
+            .iter_mut()
+            .filter(|t| t.state == TransferState::Queued)
+            .take(free)
+            .map(|t| {
+                t.state = TransferState::Active;
+                (t.id.clone(), t.request.clone())
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    queue: TransferQueue,
+    running: HashMap<String, JoinHandle<()>>,
+    /// `<app_data_dir>/transfers.json`; `None` until `restore` ran.
+    store: Option<PathBuf>,
+    network_pushed: bool,
+    bandwidth_pushed: bool,
+}
+
+impl Inner {
+    fn persist(&self) {
+        let Some(path) = &self.store else {
+            return;
+        };
+        let result = (|| -> std::io::Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            let bytes =
+                serde_json::to_vec(&self.queue.for_disk()).map_err(std::io::Error::other)?;
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, path)
+        })();
+        if let Err(e) = result {
+            log::warn!("failed to save transfer queue: {e}");
+        }
+    }
+}
+
+/// Tauri managed state. The lock is never held across an `.await`.
+#[derive(Default)]
+pub struct TransferManagerState(Mutex<Inner>);
+
+/// The webview's startup configuration pushes that gate the queue.
+pub(crate) enum ConfigPush {
+    Network,
+    Bandwidth,
+}
+
+/// Record a configuration push from the webview and, once both arrived,
+/// start what the queue holds.
+pub(crate) fn config_pushed(app: &AppHandle, push: ConfigPush) {
+    if let Some(state) = app.try_state::<TransferManagerState>() {
+        if let Ok(mut inner) = state.0.lock() {
+            match push {
+                ConfigPush::Network => inner.network_pushed = true,
+                ConfigPush::Bandwidth => inner.bandwidth_pushed = true,
+            }
+        }
+    }
+    schedule(app);
+}
+
+/// Load the persisted queue. Called once from setup; restored transfers wait
+/// for `config_pushed`.
+pub fn restore(app: &AppHandle) {
+    let store = match app.path().app_data_dir() {
+        Ok(dir) => dir.join("transfers.json"),
+        Err(e) => {
+            log::warn!("transfer queue not persisted: {e}");
+            return;
+        }
+    };
+    let mut queue = std::fs::read(&store)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<TransferQueue>(&bytes).ok())
+        .map(TransferQueue::restored)
+        .unwrap_or_default();
+    // The scope may have shrunk since the transfer was queued.
+    queue.transfers.retain(|t| {
+        let allowed = ensure_path_allowed(app, t.request.file_path()).is_ok();
+        if !allowed {
+            log::warn!("dropping restored transfer {}: path not allowed", t.id);
+        }
+        allowed
+    });
+    {
+        let state = app.state::<TransferManagerState>();
+        let Ok(mut inner) = state.0.lock() else {
+            return;
+        };
+        inner.queue = queue;
+        inner.store = Some(store);
+        inner.persist();
+    }
+}
+
+/// Queue a transfer and return its ID.
+#[tauri::command]
+pub async fn transfer_enqueue(
+    app: AppHandle,
+    state: State<'_, TransferManagerState>,
+    request: TransferRequest,
+) -> Result<String, String> {
+    ensure_path_allowed(&app, request.file_path()).map_err(|e| e.to_string())?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    {
+        let mut inner = state.0.lock().map_err(|e| e.to_string())?;
+        inner.queue.transfers.push(TransferInfo {
+            id: id.clone(),
+            request,
+            state: TransferState::Queued,
+            progress: 0,
+            total: 0,
+            awaiting_headers: false,
+        });
+        inner.persist();
+    }
+    emit_state(&app, &id, TransferState::Queued, held_reason(), None);
+    schedule(&app);
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn transfer_pause(
+    app: AppHandle,
+    state: State<'_, TransferManagerState>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let mut inner = state.0.lock().map_err(|e| e.to_string())?;
+        let transfer = inner
+            .queue
+            .get_mut(&id)
+            .ok_or_else(|| format!("Unknown transfer: {id}"))?;
+        if !matches!(
+            transfer.state,
+            TransferState::Queued | TransferState::Active
+        ) {
+            return Ok(());
+        }
+        transfer.state = TransferState::Paused;
+        if let Some(handle) = inner.running.remove(&id) {
+            handle.abort();
+        }
+        inner.persist();
+    }
+    emit_state(&app, &id, TransferState::Paused, None, None);
+    // The paused transfer's slot is free again.
+    schedule(&app);
+    Ok(())
+}
+
+/// `headers` replaces the request's headers; a transfer restored with
+/// `awaitingHeaders` can't resume without them.
+#[tauri::command]
+pub async fn transfer_resume(
+    app: AppHandle,
+    state: State<'_, TransferManagerState>,
+    id: String,
+    headers: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    {
+        let mut inner = state.0.lock().map_err(|e| e.to_string())?;
+        let transfer = inner
+            .queue
+            .get_mut(&id)
+            .ok_or_else(|| format!("Unknown transfer: {id}"))?;
+        if transfer.state != TransferState::Paused {
+            return Ok(());
+        }
+        match headers {
+            Some(headers) => {
+                *transfer.request.headers_mut() = headers;
+                transfer.awaiting_headers = false;
+            }
+            None if transfer.awaiting_headers => {
+                return Err(format!("Transfer {id} needs its request headers again"));
+            }
+            None => {}
+        }
+        transfer.state = TransferState::Queued;
+        inner.persist();
+    }
+    emit_state(&app, &id, TransferState::Queued, held_reason(), None);
+    schedule(&app);
+    Ok(())
+}
+
+/// Stop and forget a transfer. A download that already started has its
+/// partial file (and resume sidecar) deleted.
+#[tauri::command]
+pub async fn transfer_cancel(
+    app: AppHandle,
+    state: State<'_, TransferManagerState>,
+    id: String,
+) -> Result<(), String> {
+    let transfer = {
+        let mut inner = state.0.lock().map_err(|e| e.to_string())?;
+        let transfer = inner
+            .queue
+            .remove(&id)
+            .ok_or_else(|| format!("Unknown transfer: {id}"))?;
+        if let Some(handle) = inner.running.remove(&id) {
+            handle.abort();
+        }
+        inner.persist();
+        transfer
+    };
+
+    let started = transfer.state == TransferState::Active || transfer.progress > 0;
+    if let (TransferRequest::Download(request), true) = (&transfer.request, started) {
+        let _ = tokio::fs::remove_file(&request.file_path).await;
+        let _ = tokio::fs::remove_file(DownloadManifest::path_for(&request.file_path)).await;
+    }
+    emit_state(&app, &id, TransferState::Cancelled, None, None);
+    schedule(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn transfer_list(
+    state: State<'_, TransferManagerState>,
+) -> Result<Vec<TransferInfo>, String> {
+    let inner = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(inner.queue.transfers.clone())
+}
+
+/// Change how many transfers run at once (1..=16). Lowering it lets running
+/// transfers finish; raising it starts queued ones right away.
+#[tauri::command]
+pub async fn transfer_set_concurrency(
+    app: AppHandle,
+    state: State<'_, TransferManagerState>,
+    limit: usize,
+) -> Result<(), String> {
+    {
+        let mut inner = state.0.lock().map_err(|e| e.to_string())?;
+        inner.queue.concurrency = limit.clamp(1, MAX_CONCURRENCY);
+        inner.persist();
+    }
+    schedule(&app);
+    Ok(())
+}
+
+fn held_reason() -> Option<String> {
+    bandwidth::is_paused().then(|| METERED_HOLD.to_string())
+}
+
+/// Start queued transfers while there are free slots. Nothing starts before
+/// the webview's configuration pushes. While the metered-network policy holds
+/// the queue, running transfers go back to `queued` (with `METERED_HOLD` as
+/// the state event's reason) and nothing new starts; `bandwidth` calls this
+/// again whenever the policy or the connection changes.
+pub(crate) fn schedule(app: &AppHandle) {
+    let Some(state) = app.try_state::<TransferManagerState>() else {
+        return;
+    };
+    let held = bandwidth::is_paused();
+    let (started, held_back) = {
+        let Ok(mut inner) = state.0.lock() else {
+            return;
+        };
+        if !(inner.network_pushed && inner.bandwidth_pushed) {
+            return;
+        }
+        if held {
+            let held_back = inner.queue.hold_active();
+            for id in &held_back {
+                if let Some(handle) = inner.running.remove(id) {
+                    handle.abort();
+                }
+            }
+            if !held_back.is_empty() {
+                inner.persist();
+            }
+            (Vec::new(), held_back)
+        } else {
+            let started = inner.queue.start_next();
+            // Spawned under the lock, so a task that finishes immediately still
+            // finds its handle registered before `finish` looks for it.
+            for (id, request) in &started {
+                let handle =
+                    tauri::async_runtime::spawn(run(app.clone(), id.clone(), request.clone()));
+                inner.running.insert(id.clone(), handle);
+            }
+            if !started.is_empty() {
+                inner.persist();
+            }
+            (started, Vec::new())
+        }
+    };
+    for id in held_back {
+        emit_state(app, &id, TransferState::Queued, held_reason(), None);
+    }
+    for (id, _) in started {
+        emit_state(app, &id, TransferState::Active, None, None);
+    }
+}
+
+async fn run(app: AppHandle, id: String, request: TransferRequest) {
+    let sink = progress_sink(app.clone(), id.clone());
+    let result = match request {
+        TransferRequest::Download(request) => download(&request, sink)
+            .await
+            .map(|headers| serde_json::json!(headers)),
+        TransferRequest::Upload(request) => {
+            upload(&request, sink).await.map(serde_json::Value::String)
+        }
+        TransferRequest::ChunkedUpload(request) => upload_chunked(&app, request, sink)
+            .await
+            .map(serde_json::Value::String),
+    };
+    finish(&app, &id, result.map_err(|e| e.to_string()));
+}
+
+fn finish(app: &AppHandle, id: &str, result: Result<serde_json::Value, String>) {
+    {
+        let state = app.state::<TransferManagerState>();
+        let Ok(mut inner) = state.0.lock() else {
+            return;
+        };
+        // Paused or cancelled while the last bytes were in flight.
+        if inner.queue.get_mut(id).map(|t| t.state) != Some(TransferState::Active) {
+            return;
+        }
+        inner.queue.remove(id);
+        inner.running.remove(id);
+        inner.persist();
+    }
+    match result {
+        Ok(value) => emit_state(app, id, TransferState::Completed, None, Some(value)),
+        Err(e) => emit_state(app, id, TransferState::Failed, Some(e), None),
+    }
+    schedule(app);
+}
+
+fn progress_sink(app: AppHandle, id: String) -> ProgressSink {
+    Arc::new(move |payload: ProgressPayload| {
+        if let Ok(mut inner) = app.state::<TransferManagerState>().0.lock() {
+            if let Some(transfer) = inner.queue.get_mut(&id) {
+                transfer.progress = payload.progress;
+                transfer.total = payload.total;
+            }
+        }
+        let _ = app.emit(
+            EV_TRANSFER_PROGRESS,
+            TransferProgressPayload {
+                id: id.clone(),
+                progress: payload.progress,
+                total: payload.total,
+                transfer_speed: payload.transfer_speed,
+            },
+        );
+    })
+}
+
+fn emit_state(
+    app: &AppHandle,
+    id: &str,
+    state: TransferState,
+    error: Option<String>,
+    result: Option<serde_json::Value>,
+) {
+    let _ = app.emit(
+        EV_TRANSFER_STATE,
+        TransferStatePayload {
+            id: id.to_string(),
+            state,
+            error,
+            result,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(id: &str, state: TransferState) -> TransferInfo {
+        TransferInfo {
+            id: id.to_string(),
+            request: TransferRequest::Upload(UploadRequest {
+                url: "https://example.com/up".into(),
+                file_path: format!("/tmp/{id}.epub"),
+                method: "PUT".into(),
+                headers: HashMap::new(),
+            }),
+            state,
+            progress: 0,
+            total: 0,
+            awaiting_headers: false,
+        }
+    }
+
+    #[test]
+    fn start_next_fills_free_slots_in_order() {
+        let mut queue = TransferQueue {
+            concurrency: 2,
+            transfers: vec![
+                transfer("a", TransferState::Active),
+                transfer("b", TransferState::Paused),
+                transfer("c", TransferState::Queued),
+                transfer("d", TransferState::Queued),
+            ],
+        };
+        let started: Vec<String> = queue.start_next().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(started, ["c"]);
+        assert_eq!(queue.transfers[2].state, TransferState::Active);
+        assert_eq!(queue.transfers[3].state, TransferState::Queued);
+        assert!(queue.start_next().is_empty());
+    }
+
+    #[test]
+    fn hold_active_requeues_running_transfers() {
+        let mut queue = TransferQueue {
+            concurrency: 2,
+            transfers: vec![
+                transfer("a", TransferState::Active),
+                transfer("b", TransferState::Paused),
+                transfer("c", TransferState::Active),
+            ],
+        };
+        assert_eq!(queue.hold_active(), ["a", "c"]);
+        let states: Vec<TransferState> = queue.transfers.iter().map(|t| t.state).collect();
+        assert_eq!(
+            states,
+            [
+                TransferState::Queued,
+                TransferState::Paused,
+                TransferState::Queued
+            ]
+        );
+    }
+
+    #[test]
+    fn restored_queue_requeues_interrupted_transfers() {
+        let queue = TransferQueue {
+            concurrency: 0,
+            transfers: vec![
+                transfer("a", TransferState::Active),
+                transfer("b", TransferState::Paused),
+                transfer("c", TransferState::Completed),
+            ],
+        };
+        let json = serde_json::to_vec(&queue).unwrap();
+        let restored = serde_json::from_slice::<TransferQueue>(&json)
+            .unwrap()
+            .restored();
+        assert_eq!(restored.concurrency, 1);
+        let states: Vec<TransferState> = restored.transfers.iter().map(|t| t.state).collect();
+        assert_eq!(states, [TransferState::Queued, TransferState::Paused]);
+        assert_eq!(restored.transfers[0].request, queue.transfers[0].request);
+    }
+
+    #[test]
+    fn persisted_queue_drops_request_headers() {
+        let mut authorized = transfer("a", TransferState::Active);
+        authorized
+            .request
+            .headers_mut()
+            .insert("Authorization".into(), "Bearer secret".into());
+        let queue = TransferQueue {
+            concurrency: 2,
+            transfers: vec![authorized, transfer("b", TransferState::Queued)],
+        };
+        let json = serde_json::to_string(&queue.for_disk()).unwrap();
+        assert!(!json.contains("secret"));
+        // The live queue keeps them.
+        assert!(!queue.transfers[0].awaiting_headers);
+
+        let restored = serde_json::from_str::<TransferQueue>(&json)
+            .unwrap()
+            .restored();
+        let states: Vec<(TransferState, bool)> = restored
+            .transfers
+            .iter()
+            .map(|t| (t.state, t.awaiting_headers))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (TransferState::Paused, true),
+                (TransferState::Queued, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_request_is_tagged_by_kind() {
+        let request: TransferRequest = serde_json::from_str(
+            r#"{"kind":"download","url":"https://example.com/b.epub","filePath":"/tmp/b.epub","expectedSize":42}"#,
+        )
+        .unwrap();
+        let TransferRequest::Download(download) = request else {
+            panic!("expected a download");
+        };
+        assert_eq!(download.expected_size, Some(42));
+        assert!(download.headers.is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+//! Commands over the shared `webdav-sync` crate (packages/webdav-sync), so
+//! progress, booknotes and book files can sync to a self-hosted WebDAV
+//! server through the app's HTTP client (proxy and custom CA aware).
+
+use futures_util::TryStreamExt;
+use tauri::{command, AppHandle};
+use tokio::fs::File;
+use tokio_util::codec::{BytesCodec, FramedRead};
+use webdav_sync::{Connection, RemoteBookConfig, SyncOutcome, WebDavClient};
+
+use crate::bandwidth;
+use crate::transfer_file::ensure_path_allowed;
+
+fn client(connection: &Connection) -> Result<WebDavClient, String> {
+    let http = crate::http_client::client().map_err(|e| e.to_string())?;
+    WebDavClient::new(http, connection).map_err(|e| e.to_string())
+}
+
+fn content_type(file_name: &str) -> &'static str {
+    let ext = file_name.rsplit('.').next().unwrap_or_default();
+    match ext.to_ascii_lowercase().as_str() {
+        "epub" => "application/epub+zip",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "json" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Merge this device's progress and booknotes for one book with the copy on
+/// the server and write the result back. The returned config is what the
+/// caller should apply locally.
+#[command]
+pub async fn webdav_sync_book_config(
+    connection: Connection,
+    config: RemoteBookConfig,
+) -> Result<SyncOutcome, String> {
+    let client = client(&connection)?;
+    webdav_sync::sync_book_config(&client, &connection.root_path, &config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Upload a book file or cover into the book's directory. Returns `false`
+/// when the server already has a file of that name, which is left alone.
+#[command]
+pub async fn webdav_upload_book_file(
+    app: AppHandle,
+    connection: Connection,
+    book_hash: String,
+    file_name: String,
+    file_path: String,
+) -> Result<bool, String> {
+    ensure_path_allowed(&app, &file_path).map_err(|e| e.to_string())?;
+    let client = client(&connection)?;
+    let file = File::open(&file_path).await.map_err(|e| e.to_string())?;
+    let stream = FramedRead::new(file, BytesCodec::new())
+        .map_ok(|chunk| chunk.freeze())
+        .and_then(|chunk| async move {
+            bandwidth::throttle_bytes(&chunk).await;
+            Ok(chunk)
+        });
+    webdav_sync::upload_book_file(
+        &client,
+        &connection.root_path,
+        &book_hash,
+        &file_name,
+        reqwest::Body::wrap_stream(stream),
+        content_type(&file_name),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -2,10 +2,10 @@ import { describe, expect, it } from 'vitest';
 import { partitionSupportedFiles } from '@/services/localsend/formats';
 import type { LocalSendFile } from '@/services/localsend/types';
 
-const file = (id: string, fileName: string): LocalSendFile => ({
+const file = (id: string, fileName: string, size = 1): LocalSendFile => ({
   id,
   fileName,
-  size: 1,
+  size,
   fileType: 'application/octet-stream',
   preview: null,
 });
@@ -24,9 +24,22 @@ describe('partitionSupportedFiles', () => {
     expect(skipped.map((f) => f.id)).toEqual(['2', '5', '6']);
   });
 
+  it('sets Readest sidecars apart from books', () => {
+    const { supported, sidecars, skipped } = partitionSupportedFiles([
+      file('1', 'novel.epub'),
+      file('2', '0123abcdef.readest.json'),
+      file('3', 'notes.readest.json'),
+      file('4', 'abc.readest.json', 9 * 1024 * 1024),
+    ]);
+    expect(supported.map((f) => f.id)).toEqual(['1']);
+    expect(sidecars.map((f) => f.id)).toEqual(['2']);
+    expect(skipped.map((f) => f.id)).toEqual(['3', '4']);
+  });
+
   it('handles an empty list', () => {
-    const { supported, skipped } = partitionSupportedFiles([]);
+    const { supported, sidecars, skipped } = partitionSupportedFiles([]);
     expect(supported).toEqual([]);
+    expect(sidecars).toEqual([]);
     expect(skipped).toEqual([]);
   });
 });
@@ -0,0 +1,74 @@
+import { describe, expect, it } from 'vitest';
+import { bookFromImported } from '@/services/localsend/imported';
+import { getLocalBookFilename } from '@/utils/book';
+import { md5Fingerprint } from '@/utils/md5';
+import type { ReceiveImported } from '@/services/localsend/types';
+import type { Book } from '@/types/book';
+
+const entry = (overrides: Partial<ReceiveImported> = {}): ReceiveImported => ({
+  sessionId: 's1',
+  fileId: 'f1',
+  fileName: 'dune.epub',
+  bookHash: 'abc123',
+  format: 'EPUB',
+  path: '/books/abc123/Dune_ Part One.epub',
+  duplicate: false,
+  title: 'Dune: Part One',
+  authors: ['Frank Herbert'],
+  ...overrides,
+});
+
+describe('bookFromImported', () => {
+  it('builds a row that locates the placed file', () => {
+    const book = bookFromImported(entry(), undefined, 1000);
+    expect(book).toMatchObject({
+      hash: 'abc123',
+      format: 'EPUB',
+      title: 'Dune: Part One',
+      sourceTitle: 'Dune: Part One',
+      author: 'Frank Herbert',
+      createdAt: 1000,
+      downloadedAt: 1000,
+      deletedAt: null,
+    });
+    expect(getLocalBookFilename(book)).toBe('abc123/Dune_ Part One.epub');
+  });
+
+  it('files a book from a folder send under the folder group', () => {
+    const book = bookFromImported(entry({ groupName: 'Dune Saga' }), undefined, 1000);
+    expect(book.groupName).toBe('Dune Saga');
+    expect(book.groupId).toBe(md5Fingerprint('Dune Saga'));
+    expect(bookFromImported(entry(), undefined, 1000).groupId).toBeUndefined();
+  });
+
+  it('falls back to the file name without a title', () => {
+    const book = bookFromImported(entry({ title: null, authors: [] }), undefined, 1000);
+    expect(book.title).toBe('dune.epub');
+    expect(book.author).toBe('');
+  });
+
+  it('revives a deleted row under its own title', () => {
+    const deleted = {
+      hash: 'abc123',
+      format: 'EPUB',
+      title: 'Dune (my edit)',
+      sourceTitle: 'Dune',
+      author: 'F. Herbert',
+      progress: [12, 400],
+      createdAt: 1,
+      updatedAt: 2,
+      deletedAt: 3,
+      fileSyncDeletionRequestedAt: 3,
+    } as Book;
+    const book = bookFromImported(entry(), deleted, 1000);
+    expect(book).toMatchObject({
+      title: 'Dune (my edit)',
+      sourceTitle: 'Dune',
+      progress: [12, 400],
+      deletedAt: null,
+      fileSyncDeletionRequestedAt: null,
+      updatedAt: 1000,
+    });
+    expect(getLocalBookFilename(book)).toBe('abc123/Dune.epub');
+  });
+});
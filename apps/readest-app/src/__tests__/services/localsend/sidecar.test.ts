@@ -0,0 +1,95 @@
+import { describe, expect, it } from 'vitest';
+import {
+  buildBookSidecar,
+  mergeBookSidecar,
+  parseBookSidecar,
+} from '@/services/localsend/sidecar';
+import type { Book, BookConfig, BookNote } from '@/types/book';
+
+const note = (id: string, updatedAt: number): BookNote => ({
+  id,
+  type: 'annotation',
+  cfi: `epubcfi(/6/4!/4/2/${id})`,
+  text: id,
+  note: '',
+  createdAt: 1,
+  updatedAt,
+});
+
+const senderBook = {
+  hash: 'abc123',
+  format: 'EPUB',
+  title: 'Dune (annotated)',
+  author: 'Frank Herbert',
+  filePath: '/Users/me/Dune.epub',
+  progress: [120, 400],
+  createdAt: 1,
+  updatedAt: 500,
+  metadataUpdatedAt: 400,
+} as Book;
+
+const senderConfig = {
+  progress: [120, 400],
+  location: 'epubcfi(/6/20)',
+  booknotes: [note('a', 300), note('b', 300)],
+  updatedAt: 500,
+} as BookConfig;
+
+const receiverBook = {
+  hash: 'abc123',
+  format: 'EPUB',
+  title: 'Dune',
+  author: 'Frank Herbert',
+  createdAt: 900,
+  updatedAt: 900,
+} as Book;
+
+describe('book sidecars', () => {
+  it('round-trips without device-local fields', () => {
+    const cover = new Uint8Array([137, 80, 78, 71]).buffer;
+    const sidecar = buildBookSidecar(senderBook, senderConfig, 'dev-1', cover);
+    expect(sidecar.book.filePath).toBeUndefined();
+    expect(sidecar.config.booknotes).toHaveLength(2);
+    expect(sidecar.cover).toBe('iVBORw==');
+
+    const parsed = parseBookSidecar(JSON.stringify(sidecar), 'abc123');
+    expect(parsed?.config.config.location).toBe('epubcfi(/6/20)');
+    expect(parseBookSidecar(JSON.stringify(sidecar), 'other')).toBeNull();
+    expect(parseBookSidecar('{"readestSidecar":2}', 'abc123')).toBeNull();
+    expect(parseBookSidecar('not json', 'abc123')).toBeNull();
+  });
+
+  it("applies the sender's state over a freshly imported book", () => {
+    const sidecar = buildBookSidecar(senderBook, senderConfig, 'dev-1', new ArrayBuffer(1));
+    const fresh = { updatedAt: 900, booknotes: [] } as unknown as BookConfig;
+    const merged = mergeBookSidecar(receiverBook, fresh, sidecar, true);
+    expect(merged.book.title).toBe('Dune (annotated)');
+    expect(merged.book.progress).toEqual([120, 400]);
+    expect(merged.book.updatedAt).toBe(900);
+    expect(merged.config.location).toBe('epubcfi(/6/20)');
+    expect(merged.config.booknotes?.map((n) => n.id).sort()).toEqual(['a', 'b']);
+    expect(merged.adoptCover).toBe(true);
+  });
+
+  it('keeps newer local state of a book already in the library', () => {
+    const sidecar = buildBookSidecar(senderBook, senderConfig, 'dev-1');
+    const local = {
+      progress: [300, 400],
+      location: 'epubcfi(/6/40)',
+      booknotes: [note('a', 600), note('c', 100)],
+      updatedAt: 900,
+    } as BookConfig;
+    const merged = mergeBookSidecar(
+      { ...receiverBook, metadataUpdatedAt: 800 },
+      local,
+      sidecar,
+      false,
+    );
+    expect(merged.book.title).toBe('Dune');
+    expect(merged.config.location).toBe('epubcfi(/6/40)');
+    const notes = merged.config.booknotes ?? [];
+    expect(notes.map((n) => n.id).sort()).toEqual(['a', 'b', 'c']);
+    expect(notes.find((n) => n.id === 'a')?.updatedAt).toBe(600);
+    expect(merged.adoptCover).toBe(false);
+  });
+});
@@ -0,0 +1,29 @@
+import { describe, expect, it } from 'vitest';
+import {
+  TRUST_BOOK_CAPS,
+  trustChoiceOf,
+  trustChoices,
+  trustPolicyOf,
+} from '@/services/localsend/trust';
+
+describe('trust choices', () => {
+  it('round-trips every policy', () => {
+    for (const choice of trustChoices()) {
+      expect(trustChoiceOf(trustPolicyOf(choice)!)).toBe(choice);
+    }
+    expect(trustPolicyOf('books:1048576')).toEqual({ kind: 'books', maxBytes: 1048576 });
+  });
+
+  it('rejects values it did not produce', () => {
+    expect(trustPolicyOf('books:')).toBeNull();
+    expect(trustPolicyOf('books:-5')).toBeNull();
+    expect(trustPolicyOf('never')).toBeNull();
+  });
+
+  it('keeps a cap that is not a preset', () => {
+    const choices = trustChoices({ kind: 'books', maxBytes: 5 });
+    expect(choices).toHaveLength(TRUST_BOOK_CAPS.length + 3);
+    expect(choices[1]).toBe('books:5');
+    expect(trustChoices({ kind: 'always' })).toHaveLength(TRUST_BOOK_CAPS.length + 2);
+  });
+});
@@ -1,4 +1,6 @@
 import { describe, expect, test } from 'vitest';
+import { readFileSync } from 'fs';
+import { resolve } from 'path';
 import {
   mergeNotes,
   mergeBookConfig,
@@ -174,6 +176,35 @@ describe('mergeBookConfig reference page count (issue #5716)', () => {
   });
 });
 
+// The same cases run against the Rust merge in packages/webdav-sync
+// (tests/merge_parity.rs), which the KOReader helper syncs through.
+describe('mergeBookConfig shared cases (webdav-sync parity)', () => {
+  interface MergeCase {
+    name: string;
+    local: RemoteBookConfig;
+    remote: RemoteBookConfig;
+    expected: { config: BookConfig; booknotes: BookNote[]; referencePageCount?: number };
+  }
+  const cases: MergeCase[] = JSON.parse(
+    readFileSync(resolve(__dirname, '../../../fixtures/data/webdav-merge-cases.json'), 'utf-8'),
+  );
+
+  test.each(cases.map((c) => [c.name, c] as const))('%s', (_name, c) => {
+    const local = {
+      ...c.local.config,
+      booknotes: c.local.booknotes,
+      ...(c.local.referencePageCount
+        ? { viewSettings: { referencePageCount: c.local.referencePageCount } }
+        : {}),
+    } as BookConfig;
+    const { config, notes } = mergeBookConfig(local, c.remote);
+    const { booknotes: _notes, viewSettings, ...scalars } = config;
+    expect(scalars).toEqual(c.expected.config);
+    expect(notes).toEqual(c.expected.booknotes);
+    expect(viewSettings?.referencePageCount).toBe(c.expected.referencePageCount);
+  });
+});
+
 describe('mergeBookMetadata (LWW field subset)', () => {
   test('overlays only metadata fields, preserves local file-system fields', () => {
     const local = {
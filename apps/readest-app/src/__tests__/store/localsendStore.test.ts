@@ -4,7 +4,13 @@ import type { ReceiveRequest, TransferProgress } from '@/services/localsend/type
 
 const request: ReceiveRequest = {
   sessionId: 's1',
-  sender: { alias: 'Phone', deviceModel: null, deviceType: 'mobile', fingerprint: 'F' },
+  sender: {
+    alias: 'Phone',
+    deviceModel: null,
+    deviceType: 'mobile',
+    fingerprint: 'F',
+    verified: true,
+  },
   files: [
     { id: 'f1', fileName: 'a.epub', size: 10, fileType: 'application/epub+zip', preview: null },
   ],
@@ -48,11 +54,21 @@ describe('localsendStore', () => {
 
   it('tracks send lifecycle', () => {
     const store = useLocalSendStore.getState();
-    store.startSend('Laptop');
+    store.startSend('Laptop', 'L');
     expect(useLocalSendStore.getState().sendState?.deviceAlias).toBe('Laptop');
     store.sendProgress(progress);
     expect(useLocalSendStore.getState().sendState?.progress?.bytesDone).toBe(5);
     store.sendEnded();
     expect(useLocalSendStore.getState().sendState).toBeNull();
   });
+
+  it('keeps a PIN request until the next send starts', () => {
+    const store = useLocalSendStore.getState();
+    store.startSend('Laptop', 'L');
+    store.sendEnded();
+    store.requestSendPin({ fingerprint: 'L', deviceAlias: 'Laptop', wrongPin: false });
+    expect(useLocalSendStore.getState().sendPinRequest?.fingerprint).toBe('L');
+    store.startSend('Laptop', 'L');
+    expect(useLocalSendStore.getState().sendPinRequest).toBeNull();
+  });
 });
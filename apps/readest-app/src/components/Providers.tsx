@@ -14,6 +14,9 @@ import { useSettingsStore } from '@/store/settingsStore';
 import { useCustomTextureStore } from '@/store/customTextureStore';
 import { useSafeAreaInsets } from '@/hooks/useSafeAreaInsets';
 import { useSettingsSync } from '@/hooks/useSettingsSync';
+import { useBandwidthPolicy } from '@/hooks/useBandwidthPolicy';
+import { useNativeNetworkConfig } from '@/hooks/useNativeNetworkConfig';
+import { useOpdsServer } from '@/hooks/useOpdsServer';
 import { useDefaultIconSize } from '@/hooks/useResponsiveSize';
 import { useBackgroundTexture } from '@/hooks/useBackgroundTexture';
 import { useEinkMode } from '@/hooks/useEinkMode';
@@ -115,6 +118,9 @@ const Providers = ({ children }: { children: React.ReactNode }) => {
   const [showTelemetryConsent, setShowTelemetryConsent] = useState(false);
   useSafeAreaInsets(); // Initialize safe area insets
   useSettingsSync(); // Adopt global settings broadcast by other windows (#4580)
+  useBandwidthPolicy(); // Feed the native transfer rate limiter and metered state
+  useNativeNetworkConfig(); // Proxy / extra CA roots for native HTTP
+  useOpdsServer(); // Share the library over the LAN when enabled
 
   useEffect(() => {
     const handlerLanguageChanged = (lng: string) => {
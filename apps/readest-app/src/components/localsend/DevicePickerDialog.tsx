@@ -11,12 +11,17 @@ import {
   cancelLocalSendSend,
   listLocalSendDevices,
   sendLocalSendFiles,
+  sendLocalSendFolder,
 } from '@/services/localsend/service';
 import { ipTag } from '@/services/localsend/deviceModel';
 import type { LocalSendDevice, SendFileInput } from '@/services/localsend/types';
+import { eventDispatcher } from '@/utils/event';
+import { getFilename } from '@/utils/path';
 
 interface DevicePickerDialogProps {
-  files: SendFileInput[];
+  files?: SendFileInput[];
+  /** A folder to send with its tree, in place of `files`. */
+  folder?: string;
   onClose: () => void;
 }
 
@@ -31,14 +36,16 @@ const deviceIcon = (deviceType: string | null) => {
  * peers, refreshes discovery on demand, and shows this window's send
  * progress with a cancel action once a transfer starts.
  */
-const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, onClose }) => {
+const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, folder, onClose }) => {
   const _ = useTranslation();
   const { appService } = useEnv();
   const { safeAreaInsets } = useThemeStore();
   const devices = useLocalSendStore((state) => state.devices);
   const status = useLocalSendStore((state) => state.status);
   const sendState = useLocalSendStore((state) => state.sendState);
+  const sendPinRequest = useLocalSendStore((state) => state.sendPinRequest);
   const [refreshing, setRefreshing] = useState(false);
+  const [pin, setPin] = useState('');
 
   const refresh = useCallback(async () => {
     setRefreshing(true);
@@ -64,15 +71,17 @@ const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, onClose
   }, [status?.running, refresh]);
 
   // Close once the transfer this dialog started has ended (the manager
-  // shows the outcome toast).
+  // shows the outcome toast), unless the receiver asked for a PIN.
   const startedRef = React.useRef(false);
   useEffect(() => {
     if (sendState) {
       startedRef.current = true;
-    } else if (startedRef.current) {
+    } else if (startedRef.current && !sendPinRequest) {
       onClose();
     }
-  }, [sendState, onClose]);
+  }, [sendState, sendPinRequest, onClose]);
+
+  useEffect(() => () => useLocalSendStore.getState().clearSendPinRequest(), []);
 
   const openLocalSendSettings = () => {
     const { setRequestedPanel, setRequestedSubPage, setSettingsDialogOpen } =
@@ -83,17 +92,38 @@ const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, onClose
     onClose();
   };
 
-  const pickDevice = async (device: LocalSendDevice) => {
+  const send = async (fingerprint: string, alias: string, pin?: string) => {
     if (sendState) return;
-    useLocalSendStore.getState().startSend(device.alias);
+    useLocalSendStore.getState().startSend(alias, fingerprint);
     try {
-      await sendLocalSendFiles(device.fingerprint, files);
+      if (folder) {
+        await sendLocalSendFolder(fingerprint, folder, pin);
+      } else {
+        await sendLocalSendFiles(fingerprint, files ?? [], pin);
+      }
     } catch (err) {
       useLocalSendStore.getState().sendEnded();
       console.error('LocalSend send failed:', err);
+      // Walking the folder fails before anything is sent, e.g. when it is
+      // empty, so there is no transfer outcome to report it.
+      if (folder) {
+        eventDispatcher.dispatch('toast', {
+          message: _('Failed to send {{name}}', { name: getFilename(folder) }),
+          type: 'error',
+        });
+      }
     }
   };
 
+  const pickDevice = (device: LocalSendDevice) => send(device.fingerprint, device.alias);
+
+  const submitPin = (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!sendPinRequest || !pin) return;
+    setPin('');
+    void send(sendPinRequest.fingerprint, sendPinRequest.deviceAlias, pin);
+  };
+
   const percent = sendState?.progress?.bytesTotal
     ? Math.floor((sendState.progress.bytesDone / sendState.progress.bytesTotal) * 100)
     : 0;
@@ -111,7 +141,9 @@ const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, onClose
       >
         <div className='flex items-center justify-between gap-2'>
           <h3 className='text-sm font-medium'>
-            {_('Send {{count}} book(s) to nearby device', { count: files.length })}
+            {folder
+              ? _('Send {{name}} to nearby device', { name: getFilename(folder) })
+              : _('Send {{count}} book(s) to nearby device', { count: files?.length ?? 0 })}
           </h3>
           {status?.running && !sendState && (
             <button
@@ -152,6 +184,28 @@ const DevicePickerDialog: React.FC<DevicePickerDialogProps> = ({ files, onClose
               </button>
             </div>
           </div>
+        ) : sendPinRequest ? (
+          <form className='flex flex-col gap-2 text-sm' onSubmit={submitPin}>
+            <span className='truncate'>
+              {sendPinRequest.wrongPin
+                ? _('Wrong PIN for {{alias}}, try again', { alias: sendPinRequest.deviceAlias })
+                : _('{{alias}} requires a PIN', { alias: sendPinRequest.deviceAlias })}
+            </span>
+            <input
+              type='password'
+              className='input input-bordered input-sm w-full'
+              placeholder={_('PIN')}
+              value={pin}
+              autoFocus
+              onChange={(e) => setPin(e.target.value)}
+              onKeyDown={(e) => e.stopPropagation()}
+            />
+            <div className='flex justify-end'>
+              <button type='submit' className='btn btn-contrast btn-sm' disabled={!pin}>
+                {_('Send')}
+              </button>
+            </div>
+          </form>
         ) : devices.length === 0 ? (
           <div className='text-base-content/70 text-sm'>
             {_('No devices found. Make sure LocalSend is open on the other device.')}
@@ -1,4 +1,5 @@
 import React, { useCallback, useEffect, useRef, useState } from 'react';
+import { getAllWindows, getCurrentWindow } from '@tauri-apps/api/window';
 import { useEnv } from '@/context/EnvContext';
 import { useAuth } from '@/context/AuthContext';
 import { useTranslation } from '@/hooks/useTranslation';
@@ -7,12 +8,18 @@ import { useLibraryStore } from '@/store/libraryStore';
 import { useLocalSendStore } from '@/store/localsendStore';
 import { isTauriAppPlatform } from '@/services/environment';
 import { ingestFile } from '@/services/ingestService';
-import { getLocalSendAlias, isLocalSendEnabled } from '@/services/localsend/devicePrefs';
+import {
+  getLocalSendAlias,
+  getLocalSendOptions,
+  isLocalSendEnabled,
+} from '@/services/localsend/devicePrefs';
 import {
   cancelLocalSendReceive,
   respondLocalSend,
+  setLocalSendTrusted,
   startLocalSend,
   stopLocalSend,
+  takeLocalSendImported,
 } from '@/services/localsend/service';
 import { partitionSupportedFiles } from '@/services/localsend/formats';
 import { localSendDeviceModel } from '@/services/localsend/deviceModel';
@@ -21,7 +28,9 @@ import {
   type LocalSendStatus,
   type ReceiveEnd,
   type ReceiveFileDone,
+  type ReceiveImported,
   type ReceiveRequest,
+  type ReceiveSidecar,
   type SendEnd,
   type SendFileInput,
   type TransferProgress,
@@ -30,6 +39,8 @@ import {
 import { eventDispatcher } from '@/utils/event';
 import { setMulticastLock } from '@/utils/bridge';
 import { resolveBookSendFile } from '@/services/localsend/bookFile';
+import { applyReceivedSidecar, resolveBookSidecar } from '@/services/localsend/sidecar';
+import { bookFromImported } from '@/services/localsend/imported';
 import type { Book } from '@/types/book';
 import DevicePickerDialog from './DevicePickerDialog';
 import ReceiveRequestDialog from './ReceiveRequestDialog';
@@ -45,10 +56,13 @@ interface ServerStatePayload {
  * and reader shells (Tauri only): starts/stops the Rust service to match the
  * per-device preference, feeds `localsend:*` events into the store, imports
  * received books, and renders the incoming-request and device-picker dialogs.
+ * Most books are already placed in `Books/<hash>/` by the native importer;
+ * this window only adds their library rows.
  *
  * Multi-window: every window shows the incoming-request dialog; the window
  * whose respond call claims the session becomes its owner and is the only one
  * that ingests files and reports progress (store `ownedSessions` rules).
+ * Sessions auto-accepted for a trusted device are owned by the first window.
  */
 const LocalSendManager: React.FC = () => {
   const _ = useTranslation();
@@ -59,6 +73,7 @@ const LocalSendManager: React.FC = () => {
 
   const pendingRequest = useLocalSendStore((state) => state.pendingRequest);
   const [sendFiles, setSendFiles] = useState<SendFileInput[] | null>(null);
+  const [sendFolder, setSendFolder] = useState<string | null>(null);
 
   const toast = useCallback(
     (message: string, type: 'info' | 'error' | 'success' | 'warning' = 'info') =>
@@ -91,7 +106,11 @@ const LocalSendManager: React.FC = () => {
         const isTablet =
           typeof screen !== 'undefined' && Math.min(screen.width, screen.height) >= 600;
         const deviceModel = localSendDeviceModel(appService.osPlatform, isTablet);
-        const status = await startLocalSend(alias, deviceModel);
+        const booksDir = await appService.resolveFilePath('', 'Books');
+        const status = await startLocalSend(alias, deviceModel, {
+          ...getLocalSendOptions(),
+          booksDir,
+        });
         useLocalSendStore.getState().setStatus(status);
       } else {
         await stopLocalSend();
@@ -133,10 +152,14 @@ const LocalSendManager: React.FC = () => {
       if (!appService) return;
       const { books } = event.detail as { books: Book[] };
       if (!books?.length) return;
+      const { settings } = useSettingsStore.getState();
+      const deviceId = useLocalSendStore.getState().status?.fingerprint ?? '';
       const resolved: SendFileInput[] = [];
       for (const book of books) {
         const file = await resolveBookSendFile(book, appService);
-        if (file) resolved.push(file);
+        if (!file) continue;
+        const manifest = await resolveBookSidecar(book, appService, settings, deviceId);
+        resolved.push(manifest ? { ...file, sidecar: { bookHash: book.hash, manifest } } : file);
       }
       if (resolved.length === 0) {
         toast(_('Book file is not available locally'), 'warning');
@@ -156,29 +179,125 @@ const LocalSendManager: React.FC = () => {
     return () => eventDispatcher.off('localsend-send-books', onSendBooks);
   }, [appService, toast, _]);
 
+  // "Send a Folder" in the LocalSend settings; the native side walks it.
+  useEffect(() => {
+    const onSendFolder = (event: CustomEvent) => {
+      const { path } = event.detail as { path: string };
+      if (path) setSendFolder(path);
+    };
+    eventDispatcher.on('localsend-send-folder', onSendFolder);
+    return () => eventDispatcher.off('localsend-send-folder', onSendFolder);
+  }, []);
+
+  // Imports run one at a time, and a sidecar waits in the same queue for the
+  // book it follows (the sender uploads sidecars last). The queue is shared
+  // by all sessions, since one journal drain may span several.
+  const importQueue = useRef(Promise.resolve());
+  // Hashes each session added to the library, for sidecar merges.
+  const freshHashes = useRef(new Map<string, Set<string>>());
+  const enqueueImport = useCallback((task: () => Promise<void>) => {
+    importQueue.current = importQueue.current.then(task);
+  }, []);
+
+  const markFresh = useCallback((sessionId: string, hash: string) => {
+    const fresh = freshHashes.current.get(sessionId) ?? new Set<string>();
+    freshHashes.current.set(sessionId, fresh.add(hash));
+  }, []);
+
   const onFileDone = useCallback(
-    async (payload: ReceiveFileDone) => {
+    (payload: ReceiveFileDone) => {
       const owned = useLocalSendStore.getState().ownedSessions[payload.sessionId];
       if (!owned || !appService) return;
       if (payload.error || !payload.path) return; // failures are summarized on receive-end
+      const path = payload.path;
+      const { groupName } = payload;
+      enqueueImport(async () => {
+        try {
+          const { library, getGroupId } = useLibraryStore.getState();
+          // A book from a folder send joins the folder's group, like an
+          // imported folder's books do.
+          const group = groupName ? { groupId: getGroupId(groupName), groupName } : {};
+          const book = await ingestFile(
+            { file: path, books: library, forceCopy: true, ...group },
+            {
+              appService,
+              settings: useSettingsStore.getState().settings,
+              isLoggedIn: !!userRef.current,
+            },
+          );
+          if (book) {
+            if (!library.some((b) => b.hash === book.hash && !b.deletedAt)) {
+              markFresh(payload.sessionId, book.hash);
+            }
+            await useLibraryStore.getState().updateBooks(envConfig, [book]);
+          }
+          await appService.deleteFile(path, 'None').catch(() => {});
+        } catch (err) {
+          console.error('LocalSend import failed:', err);
+          toast(_('Failed to import {{filename}}', { filename: payload.fileName }), 'error');
+        }
+      });
+    },
+    [appService, envConfig, enqueueImport, markFresh, toast, _],
+  );
+
+  // Adds library rows for books the native importer placed in Books/<hash>/.
+  // The journal hands each entry to one caller only, so a window drains it on
+  // every import of a session it owns and once on mount, for books received
+  // while no window was listening.
+  const drainImported = useCallback(async () => {
+    if (!appService) return;
+    let entries: ReceiveImported[];
+    try {
+      entries = await takeLocalSendImported();
+    } catch (err) {
+      console.error('LocalSend import journal unavailable:', err);
+      return;
+    }
+    for (const entry of entries) {
+      if (entry.duplicate || !entry.path) continue;
       try {
-        const { library } = useLibraryStore.getState();
-        const book = await ingestFile(
-          { file: payload.path, books: library, forceCopy: true },
-          {
-            appService,
-            settings: useSettingsStore.getState().settings,
-            isLoggedIn: !!userRef.current,
-          },
-        );
-        if (book) await useLibraryStore.getState().updateBooks(envConfig, [book]);
-        await appService.deleteFile(payload.path, 'None').catch(() => {});
+        // Drained on mount, possibly before the library store is loaded.
+        const { library, libraryLoaded } = useLibraryStore.getState();
+        const books = libraryLoaded ? library : await appService.loadLibraryBooks();
+        const existing = books.find((b) => b.hash === entry.bookHash);
+        const book = bookFromImported(entry, existing);
+        book.coverHash = await appService.computeCoverHash(book);
+        book.coverImageUrl = await appService.generateCoverImageUrl(book);
+        const isNew = !existing || !!existing.deletedAt;
+        if (isNew && useLocalSendStore.getState().ownedSessions[entry.sessionId]) {
+          markFresh(entry.sessionId, book.hash);
+        }
+        await useLibraryStore.getState().updateBooks(envConfig, [book]);
       } catch (err) {
         console.error('LocalSend import failed:', err);
-        toast(_('Failed to import {{filename}}', { filename: payload.fileName }), 'error');
+        toast(_('Failed to import {{filename}}', { filename: entry.fileName }), 'error');
       }
+    }
+  }, [appService, envConfig, markFresh, toast, _]);
+
+  const onSidecar = useCallback(
+    (payload: ReceiveSidecar) => {
+      const owned = useLocalSendStore.getState().ownedSessions[payload.sessionId];
+      if (!owned || !appService) return;
+      enqueueImport(async () => {
+        try {
+          const fresh = !!freshHashes.current.get(payload.sessionId)?.has(payload.bookHash);
+          const book = await applyReceivedSidecar(
+            payload.path,
+            payload.bookHash,
+            fresh,
+            useLibraryStore.getState().library,
+            appService,
+            useSettingsStore.getState().settings,
+          );
+          if (book) await useLibraryStore.getState().updateBooks(envConfig, [book]);
+        } catch (err) {
+          console.error('LocalSend sidecar merge failed:', err);
+        }
+      });
     },
-    [appService, envConfig, toast, _],
+    [appService, envConfig, enqueueImport],
   );
 
   // Tauri event subscriptions. Cleanup resolves the listen promises and calls
@@ -203,6 +322,9 @@ const LocalSendManager: React.FC = () => {
             deviceModel: current?.deviceModel ?? '',
             localIps: current?.localIps ?? [],
             multicastError: current?.multicastError ?? null,
+            pinProtected: current?.pinProtected ?? false,
+            webUpload: current?.webUpload ?? false,
+            receiveOnly: current?.receiveOnly ?? false,
             ...(event.payload.error ? { multicastError: event.payload.error } : {}),
           } as LocalSendStatus);
         }),
@@ -212,6 +334,12 @@ const LocalSendManager: React.FC = () => {
         listen<ReceiveRequest>(LOCALSEND_EVENTS.receiveRequest, (event) => {
           store().requestReceived(event.payload);
         }),
+        listen<ReceiveRequest>(LOCALSEND_EVENTS.receiveAutoAccepted, async (event) => {
+          const windows = await getAllWindows();
+          const first = windows.map((w) => w.label).sort((a, b) => a.localeCompare(b))[0];
+          if (first !== getCurrentWindow().label) return;
+          store().claimSession(event.payload.sessionId, event.payload.sender.alias);
+        }),
         listen<{ sessionId: string }>(LOCALSEND_EVENTS.receiveRequestClosed, (event) => {
           store().requestClosed(event.payload.sessionId);
         }),
@@ -219,13 +347,23 @@ const LocalSendManager: React.FC = () => {
           store().receiveProgress(event.payload);
         }),
         listen<ReceiveFileDone>(LOCALSEND_EVENTS.receiveFileDone, (event) => {
-          void onFileDone(event.payload);
+          onFileDone(event.payload);
+        }),
+        listen<ReceiveImported>(LOCALSEND_EVENTS.receiveImported, (event) => {
+          if (!store().ownedSessions[event.payload.sessionId]) return;
+          enqueueImport(drainImported);
+        }),
+        listen<ReceiveSidecar>(LOCALSEND_EVENTS.receiveSidecar, (event) => {
+          onSidecar(event.payload);
         }),
         listen<ReceiveEnd>(LOCALSEND_EVENTS.receiveEnd, (event) => {
           const { sessionId, reason, received, failed } = event.payload;
           const owned = store().ownedSessions[sessionId];
           store().receiveEnded(sessionId);
           if (!owned) return;
+          enqueueImport(async () => {
+            freshHashes.current.delete(sessionId);
+          });
           if (reason === 'cancelled') {
             toast(_('Transfer from {{alias}} cancelled', { alias: owned.alias }));
           } else if (failed > 0) {
@@ -263,6 +401,13 @@ const LocalSendManager: React.FC = () => {
               }),
               'success',
             );
+          } else if (status === 'pin_required') {
+            // The device picker asks for the PIN and sends again.
+            store().requestSendPin({
+              fingerprint: sendState.fingerprint,
+              deviceAlias: sendState.deviceAlias,
+              wrongPin: !!error,
+            });
           } else if (status === 'declined') {
             toast(_('{{alias}} declined the transfer', { alias: sendState.deviceAlias }));
           } else if (status === 'cancelled') {
@@ -272,6 +417,7 @@ const LocalSendManager: React.FC = () => {
           }
         }),
       );
+      enqueueImport(drainImported);
     };
     void subscribe();
     return () => {
@@ -280,7 +426,7 @@ const LocalSendManager: React.FC = () => {
         promise.then((unlisten) => unlisten()).catch(() => {});
       });
     };
-  }, [appService, onFileDone, toast, _]);
+  }, [appService, onFileDone, onSidecar, drainImported, enqueueImport, toast, _]);
 
   // A request with no importable files is declined without a dialog.
   useEffect(() => {
@@ -303,9 +449,15 @@ const LocalSendManager: React.FC = () => {
       {showRequestDialog && (
         <ReceiveRequestDialog
           request={pendingRequest}
-          onAccept={async (fileIds) => {
+          onAccept={async (fileIds, trust) => {
             const { sessionId, sender } = pendingRequest;
             useLocalSendStore.getState().requestClosed(sessionId);
+            if (trust) {
+              const { fingerprint, alias } = sender;
+              setLocalSendTrusted({ fingerprint, alias, policy: trust }).catch((err) =>
+                console.warn('Failed to trust LocalSend device:', err),
+              );
+            }
             const claimed = await respondLocalSend(sessionId, fileIds);
             if (claimed) {
               useLocalSendStore.getState().claimSession(sessionId, sender.alias);
@@ -319,6 +471,9 @@ const LocalSendManager: React.FC = () => {
         />
       )}
       {sendFiles && <DevicePickerDialog files={sendFiles} onClose={() => setSendFiles(null)} />}
+      {sendFolder && (
+        <DevicePickerDialog folder={sendFolder} onClose={() => setSendFolder(null)} />
+      )}
       <TransferProgressCard onCancelReceive={(id) => void cancelLocalSendReceive(id)} />
     </>
   );
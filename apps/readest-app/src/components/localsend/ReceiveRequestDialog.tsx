@@ -1,25 +1,30 @@
-import React, { useMemo } from 'react';
+import React, { useMemo, useState } from 'react';
 import { useTranslation } from '@/hooks/useTranslation';
 import { useThemeStore } from '@/store/themeStore';
 import { partitionSupportedFiles } from '@/services/localsend/formats';
 import { previewDataUrl } from '@/services/localsend/preview';
-import type { ReceiveRequest } from '@/services/localsend/types';
+import { trustChoiceLabel, trustChoices, trustPolicyOf } from '@/services/localsend/trust';
+import type { LocalSendTrustPolicy, ReceiveRequest } from '@/services/localsend/types';
 import { formatBytes } from '@/utils/book';
 import Alert from '@/components/Alert';
+import Select from '@/components/Select';
 import clsx from 'clsx';
 
 const MAX_LISTED_FILES = 8;
 
 interface ReceiveRequestDialogProps {
   request: ReceiveRequest;
-  onAccept: (fileIds: string[]) => void;
+  /** `trust` is the policy to remember the sender with, or null to leave it. */
+  onAccept: (fileIds: string[], trust: LocalSendTrustPolicy | null) => void;
   onDecline: () => void;
 }
 
 /**
  * Incoming LocalSend transfer prompt. Lists only the book files Readest can
  * import; other offered files are declined via protocol partial-accept, with
- * a note so the user knows the sender sees the split.
+ * a note so the user knows the sender sees the split. A sender with a client
+ * certificate can be trusted from here, so its next transfers
+ * skip this prompt; the trusted list is managed in the LocalSend settings.
  */
 const ReceiveRequestDialog: React.FC<ReceiveRequestDialogProps> = ({
   request,
@@ -28,12 +33,15 @@ const ReceiveRequestDialog: React.FC<ReceiveRequestDialogProps> = ({
 }) => {
   const _ = useTranslation();
   const { safeAreaInsets } = useThemeStore();
-  const { supported, skipped } = useMemo(
+  const { supported, sidecars, skipped } = useMemo(
     () => partitionSupportedFiles(request.files),
     [request.files],
   );
 
   const totalSize = supported.reduce((sum, file) => sum + file.size, 0);
+  const [trustChoice, setTrustChoice] = useState('ask');
+  // "Ask every time" leaves the sender as it is rather than remembering it.
+  const trust = trustChoice === 'ask' ? null : trustPolicyOf(trustChoice);
 
   return (
     <div
@@ -53,7 +61,7 @@ const ReceiveRequestDialog: React.FC<ReceiveRequestDialogProps> = ({
         confirmLabel={_('Accept')}
         confirmButtonClassName='btn-contrast'
         onCancel={onDecline}
-        onConfirm={() => onAccept(supported.map((file) => file.id))}
+        onConfirm={() => onAccept([...supported, ...sidecars].map((file) => file.id), trust)}
       >
         <div className='flex flex-col gap-1 ps-9 text-sm'>
           {supported.slice(0, MAX_LISTED_FILES).map((file) => {
@@ -86,6 +94,21 @@ const ReceiveRequestDialog: React.FC<ReceiveRequestDialogProps> = ({
               {_('{{count}} unsupported file(s) will be skipped', { count: skipped.length })}
             </div>
           )}
+          {request.sender.verified && (
+            <div className='flex items-center justify-between gap-3'>
+              <span className='text-base-content/70 shrink-0 text-xs'>
+                {_('Next time from this device')}
+              </span>
+              <Select
+                value={trustChoice}
+                onChange={(e) => setTrustChoice(e.target.value)}
+                options={trustChoices().map((value) => ({
+                  value,
+                  label: trustChoiceLabel(value, _),
+                }))}
+              />
+            </div>
+          )}
         </div>
       </Alert>
     </div>
@@ -18,6 +18,9 @@ import {
   RiMicrosoftLine,
   RiAppleLine,
   RiHeadphoneLine,
+  RiPauseCircleLine,
+  RiServerLine,
+  RiSpeedLine,
 } from 'react-icons/ri';
 import { useEnv } from '@/context/EnvContext';
 import { useAuth } from '@/context/AuthContext';
@@ -45,6 +48,7 @@ import ReadwiseForm from './integrations/ReadwiseForm';
 import HardcoverForm from './integrations/HardcoverForm';
 import SendToReadestForm from './integrations/SendToReadestForm';
 import LocalSendForm from './integrations/LocalSendForm';
+import OPDSServerForm from './integrations/OPDSServerForm';
 import WebDAVForm from './integrations/WebDAVForm';
 import GoogleDriveForm from './integrations/GoogleDriveForm';
 import OneDriveForm from './integrations/OneDriveForm';
@@ -66,7 +70,14 @@ import {
 import type { FileSyncBackendKind } from '@/services/sync/file/providerRegistry';
 import { canBackendRun } from '@/services/sync/file/runLibrarySync';
 import SubPageHeader from './SubPageHeader';
-import { BoxedList, NavigationRow, SectionTitle, SettingLabel, Tips } from './primitives';
+import {
+  BoxedList,
+  NavigationRow,
+  SectionTitle,
+  SettingLabel,
+  SettingsSelect,
+  Tips,
+} from './primitives';
 
 type SubPage =
   | 'kosync'
@@ -83,6 +94,7 @@ type SubPage =
   | 'audiobookshelf'
   | 'send'
   | 'localsend'
+  | 'opds-server'
   | null;
 
 /**
@@ -174,6 +186,24 @@ const IntegrationsPanel: React.FC = () => {
     onCancel: () => setSubPage(null),
   });
 
+  const togglePauseTransfersOnMetered = () => {
+    saveSysSettings(envConfig, 'pauseTransfersOnMetered', !settings.pauseTransfersOnMetered);
+  };
+
+  const handleTransferRateLimitChange = (e: React.ChangeEvent<HTMLSelectElement>) => {
+    saveSysSettings(envConfig, 'transferRateLimitKBps', Number(e.target.value));
+  };
+
+  const transferRateLimitOptions = [0, 256, 512, 1024, 2048, 5120].map((kbps) => ({
+    value: String(kbps),
+    label:
+      kbps === 0
+        ? _('Unlimited')
+        : kbps < 1024
+          ? _('{{n}} KB/s', { n: kbps })
+          : _('{{n}} MB/s', { n: kbps / 1024 }),
+  }));
+
   const toggleDiscordPresence = () => {
     const discordRichPresenceEnabled = !settings.discordRichPresenceEnabled;
     saveSysSettings(envConfig, 'discordRichPresenceEnabled', discordRichPresenceEnabled);
@@ -215,7 +245,8 @@ const IntegrationsPanel: React.FC = () => {
       requestedSubPage === 'opds' ||
       requestedSubPage === 'audiobookshelf' ||
       requestedSubPage === 'send' ||
-      requestedSubPage === 'localsend'
+      requestedSubPage === 'localsend' ||
+      requestedSubPage === 'opds-server'
     ) {
       setSubPage(requestedSubPage);
     } else if (requestedSubPage === 'cloudsync') {
@@ -241,6 +272,12 @@ const IntegrationsPanel: React.FC = () => {
         <LocalSendForm onBack={() => setSubPage(null)} />
       </div>
     );
+  if (subPage === 'opds-server')
+    return (
+      <div className='my-4 w-full'>
+        <OPDSServerForm onBack={() => setSubPage(null)} />
+      </div>
+    );
   if (subPage === 'bookorbit')
     return (
       <div className='my-4 w-full'>
@@ -775,10 +812,57 @@ const IntegrationsPanel: React.FC = () => {
                 onClick={() => setSubPage('localsend')}
               />
             )}
+            {isTauriAppPlatform() && (
+              <IntegrationRow
+                icon={RiServerLine}
+                title={_('OPDS Server')}
+                status={settings.opdsServerEnabled ? _('On') : _('Off')}
+                onClick={() => setSubPage('opds-server')}
+              />
+            )}
           </div>
         </div>
       </div>
 
+      {isTauriAppPlatform() && (
+        <div className='w-full' data-setting-id='settings.integrations.transfers'>
+          <SectionTitle className='mb-2'>{_('Transfers')}</SectionTitle>
+          <div className='card eink-bordered border-base-200 bg-base-100 overflow-hidden border'>
+            <div className='divide-base-200 divide-y'>
+              <IntegrationToggleRow
+                icon={RiPauseCircleLine}
+                title={_('Pause on Metered Networks')}
+                description={_('Hold queued sync transfers on cellular data and hotspots')}
+                checked={settings.pauseTransfersOnMetered ?? false}
+                onChange={togglePauseTransfersOnMetered}
+              />
+              <div className='flex w-full items-center gap-3 px-4 py-3'>
+                <span
+                  className={clsx(
+                    'flex h-9 w-9 flex-shrink-0 items-center justify-center rounded-full',
+                    'bg-base-200 text-base-content/70',
+                  )}
+                >
+                  <RiSpeedLine className='h-5 w-5' />
+                </span>
+                <div className='flex min-w-0 flex-1 flex-col gap-0.5'>
+                  <SettingLabel>{_('Transfer Speed Limit')}</SettingLabel>
+                  <span className='text-base-content/65 truncate text-[0.85em]'>
+                    {_('Shared by all downloads and uploads')}
+                  </span>
+                </div>
+                <SettingsSelect
+                  value={String(settings.transferRateLimitKBps ?? 0)}
+                  onChange={handleTransferRateLimitChange}
+                  options={transferRateLimitOptions}
+                  ariaLabel={_('Transfer Speed Limit')}
+                />
+              </div>
+            </div>
+          </div>
+        </div>
+      )}
+
       {appService?.isDesktopApp && (
         <div className='w-full' data-setting-id='settings.integrations.discord'>
           <SectionTitle className='mb-2'>{_('Discord')}</SectionTitle>
@@ -1,5 +1,8 @@
 import React, { useEffect, useState } from 'react';
+import { MdClose } from 'react-icons/md';
+import { useEnv } from '@/context/EnvContext';
 import { useTranslation } from '@/hooks/useTranslation';
+import { useSettingsStore } from '@/store/settingsStore';
 import { useLocalSendStore } from '@/store/localsendStore';
 import {
   getLocalSendAlias,
@@ -7,12 +10,30 @@ import {
   setLocalSendAlias,
   setLocalSendEnabled,
 } from '@/services/localsend/devicePrefs';
-import { getLocalSendStatus } from '@/services/localsend/service';
+import {
+  getLocalSendStatus,
+  listLocalSendTrusted,
+  removeLocalSendTrusted,
+  setLocalSendTrusted,
+} from '@/services/localsend/service';
 import { ipTag } from '@/services/localsend/deviceModel';
-import type { LocalSendStatus } from '@/services/localsend/types';
+import {
+  trustChoiceLabel,
+  trustChoiceOf,
+  trustChoices,
+  trustPolicyOf,
+} from '@/services/localsend/trust';
+import type { LocalSendStatus, LocalSendTrustedDevice } from '@/services/localsend/types';
 import { eventDispatcher } from '@/utils/event';
 import SubPageHeader from '../SubPageHeader';
-import { BoxedList, SettingsInput, SettingsRow, SettingsSwitchRow, Tips } from '../primitives';
+import {
+  BoxedList,
+  SettingsInput,
+  SettingsRow,
+  SettingsSelect,
+  SettingsSwitchRow,
+  Tips,
+} from '../primitives';
 
 /**
  * "#120 macOS"-style tag: the last octet of this host's IPv4 address plus
@@ -36,9 +57,11 @@ interface LocalSendFormProps {
  */
 const LocalSendForm: React.FC<LocalSendFormProps> = ({ onBack }) => {
   const _ = useTranslation();
+  const { appService } = useEnv();
   const status = useLocalSendStore((state) => state.status);
   const [enabled, setEnabled] = useState(() => isLocalSendEnabled());
   const [alias, setAlias] = useState(() => getLocalSendAlias());
+  const [trusted, setTrusted] = useState<LocalSendTrustedDevice[]>([]);
 
   const toggleEnabled = () => {
     const next = !enabled;
@@ -54,6 +77,34 @@ const LocalSendForm: React.FC<LocalSendFormProps> = ({ onBack }) => {
     if (enabled) eventDispatcher.dispatch('localsend-alias-changed', {});
   };
 
+  // The trust list is a file of its own, editable while LocalSend is off.
+  useEffect(() => {
+    listLocalSendTrusted().then(setTrusted).catch(() => {});
+  }, []);
+
+  const changeTrust = (device: LocalSendTrustedDevice, choice: string) => {
+    const policy = trustPolicyOf(choice);
+    if (!policy) return;
+    setLocalSendTrusted({ ...device, policy })
+      .then(setTrusted)
+      .catch((err) => console.warn('Failed to update LocalSend trust:', err));
+  };
+
+  const removeTrust = (fingerprint: string) => {
+    removeLocalSendTrusted(fingerprint)
+      .then(setTrusted)
+      .catch((err) => console.warn('Failed to remove LocalSend trust:', err));
+  };
+
+  // The manager opens the device picker for the folder; the settings dialog
+  // would cover it.
+  const sendFolder = async () => {
+    const path = await appService?.selectDirectory('read').catch(() => '');
+    if (!path) return;
+    useSettingsStore.getState().setSettingsDialogOpen(false);
+    eventDispatcher.dispatch('localsend-send-folder', { path });
+  };
+
   // Refresh the status shown below the toggle when this page opens; the
   // manager also pushes updates through localsend:server-state events.
   useEffect(() => {
@@ -107,6 +158,49 @@ const LocalSendForm: React.FC<LocalSendFormProps> = ({ onBack }) => {
               {_('Port {{port}}', { port: status.port })}
             </span>
           </SettingsRow>
+          {appService?.isDesktopApp && !status.receiveOnly && (
+            <SettingsRow
+              label={_('Send a Folder')}
+              description={_('Sends every file in it, keeping its subfolders')}
+              asLabel={false}
+            >
+              <button
+                type='button'
+                className='btn btn-ghost btn-sm eink-bordered'
+                onClick={() => void sendFolder()}
+              >
+                {_('Choose Folder')}
+              </button>
+            </SettingsRow>
+          )}
+        </BoxedList>
+      )}
+
+      {trusted.length > 0 && (
+        <BoxedList title={_('Trusted Devices')}>
+          {trusted.map((device) => (
+            <SettingsRow key={device.fingerprint} label={device.alias} asLabel={false}>
+              <div className='flex min-w-0 items-center justify-end gap-2'>
+                <SettingsSelect
+                  value={trustChoiceOf(device.policy)}
+                  onChange={(event) => changeTrust(device, event.target.value)}
+                  options={trustChoices(device.policy).map((value) => ({
+                    value,
+                    label: trustChoiceLabel(value, _),
+                  }))}
+                  ariaLabel={_('Transfers from {{alias}}', { alias: device.alias })}
+                />
+                <button
+                  type='button'
+                  className='btn btn-ghost btn-sm eink-bordered'
+                  onClick={() => removeTrust(device.fingerprint)}
+                  aria-label={_('Remove')}
+                >
+                  <MdClose className='h-4 w-4' />
+                </button>
+              </div>
+            </SettingsRow>
+          ))}
         </BoxedList>
       )}
 
@@ -115,6 +209,7 @@ const LocalSendForm: React.FC<LocalSendFormProps> = ({ onBack }) => {
           {_('Incoming books are added to your library after you accept each transfer request.')}
         </li>
         <li>{_('Only book files are accepted; other file types are declined automatically.')}</li>
+        <li>{_('Trust a device from its transfer request to skip the prompt next time.')}</li>
         {status?.multicastError && (
           <li>
             {_('Device discovery via multicast is unavailable; devices may need a manual refresh.')}
@@ -0,0 +1,87 @@
+import React, { useEffect, useState } from 'react';
+import { useEnv } from '@/context/EnvContext';
+import { useTranslation } from '@/hooks/useTranslation';
+import { useSettingsStore } from '@/store/settingsStore';
+import { saveSysSettings } from '@/helpers/settings';
+import { nativeOpdsServer, type OPDSServerStatus } from '@/services/opds/native';
+import SubPageHeader from '../SubPageHeader';
+import { BoxedList, SettingsRow, SettingsSwitchRow, Tips } from '../primitives';
+
+interface OPDSServerFormProps {
+  onBack: () => void;
+}
+
+/**
+ * Settings for the built-in OPDS server. The toggle flips
+ * `opdsServerEnabled`; `useOpdsServer` starts or stops the Rust server to
+ * match and saves the PIN it generates, which re-reads the status shown here.
+ */
+const OPDSServerForm: React.FC<OPDSServerFormProps> = ({ onBack }) => {
+  const _ = useTranslation();
+  const { envConfig } = useEnv();
+  const { settings } = useSettingsStore();
+  const enabled = !!settings.opdsServerEnabled;
+  const [status, setStatus] = useState<OPDSServerStatus | null>(null);
+
+  useEffect(() => {
+    if (!enabled) {
+      setStatus(null);
+      return;
+    }
+    nativeOpdsServer
+      .status()
+      .then(setStatus)
+      .catch(() => setStatus(null));
+  }, [enabled, settings.opdsServerPin]);
+
+  const toggleEnabled = () => {
+    saveSysSettings(envConfig, 'opdsServerEnabled', !enabled);
+  };
+
+  return (
+    <div className='w-full space-y-6'>
+      <SubPageHeader
+        parentLabel={_('Integrations')}
+        currentLabel={_('OPDS Server')}
+        description={_('Share your library with OPDS readers on your local network.')}
+        onBack={onBack}
+      />
+
+      <BoxedList>
+        <SettingsSwitchRow
+          label={_('Share Library')}
+          description={_('Serve downloaded books while Readest is open')}
+          checked={enabled}
+          onChange={toggleEnabled}
+          data-setting-id='settings.integrations.opdsServer.enabled'
+        />
+      </BoxedList>
+
+      {enabled && status?.running && (
+        <BoxedList>
+          {status.localIps.map((ip) => (
+            <SettingsRow key={ip} label={_('Catalog URL')} asLabel={false}>
+              <span className='text-base-content/70 select-text text-sm'>
+                {`http://${ip}:${status.port}/opds`}
+              </span>
+            </SettingsRow>
+          ))}
+          <SettingsRow label={_('PIN')} description={_('Any user name')} asLabel={false}>
+            <span className='select-text font-mono text-sm'>{status.pin}</span>
+          </SettingsRow>
+          <SettingsRow label={_('Shared Books')} asLabel={false}>
+            <span className='text-base-content/70 text-sm'>{status.bookCount}</span>
+          </SettingsRow>
+        </BoxedList>
+      )}
+
+      <Tips>
+        <li>{_('Add the catalog URL in your reader and enter the PIN as the password.')}</li>
+        <li>{_('A device that keeps entering a wrong PIN is locked out for a while.')}</li>
+        <li>{_('Only books downloaded to this device are shared.')}</li>
+      </Tips>
+    </div>
+  );
+};
+
+export default OPDSServerForm;
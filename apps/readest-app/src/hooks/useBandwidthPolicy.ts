@@ -0,0 +1,70 @@
+import { addPluginListener, type PluginListener } from '@tauri-apps/api/core';
+import { useEffect } from 'react';
+import { useEnv } from '@/context/EnvContext';
+import { isTauriAppPlatform } from '@/services/environment';
+import { useSettingsStore } from '@/store/settingsStore';
+import {
+  getNetworkStatus,
+  NETWORK_STATUS_CHANGED_EVENT,
+  type GetNetworkStatusResponse,
+} from '@/utils/bridge';
+import { isMetered } from '@/utils/network';
+import { nativeBandwidth } from '@/utils/transfer';
+
+const reportMetered = (metered: boolean) => {
+  nativeBandwidth.setMetered(metered).catch((err) => {
+    console.warn('[bandwidth] failed to report metered state:', err);
+  });
+};
+
+/**
+ * Pushes the transfer rate limit and pause-on-metered policy to the native
+ * limiter, and keeps it told whether the active network is metered: from the
+ * native bridge on mobile (which sees cellular, hotspots and Low Data Mode),
+ * from the Network Information API on desktop. The policy waits for settings
+ * to load: its first push, with the network config's, releases the native
+ * transfer queue.
+ */
+export const useBandwidthPolicy = () => {
+  const { appService } = useEnv();
+  const { settings } = useSettingsStore();
+  const limitKBps = settings?.transferRateLimitKBps ?? 0;
+  const pauseOnMetered = settings?.pauseTransfersOnMetered ?? false;
+  const isMobileApp = !!appService?.isMobileApp;
+  const loaded = !!settings?.version;
+
+  useEffect(() => {
+    if (!isTauriAppPlatform() || !loaded) return;
+    nativeBandwidth
+      .setPolicy({ limitBytesPerSec: limitKBps > 0 ? limitKBps * 1024 : null, pauseOnMetered })
+      .catch((err) => console.warn('[bandwidth] failed to apply policy:', err));
+  }, [limitKBps, pauseOnMetered, loaded]);
+
+  useEffect(() => {
+    if (!isTauriAppPlatform() || !appService) return;
+
+    if (isMobileApp) {
+      let disposed = false;
+      getNetworkStatus()
+        .then((status) => !disposed && reportMetered(status.metered))
+        .catch(() => !disposed && reportMetered(isMetered()));
+      const listener: Promise<PluginListener> = addPluginListener<GetNetworkStatusResponse>(
+        'native-bridge',
+        NETWORK_STATUS_CHANGED_EVENT,
+        (status) => {
+          if (!disposed) reportMetered(status.metered);
+        },
+      );
+      return () => {
+        disposed = true;
+        listener.then((registered) => registered.unregister()).catch(() => {});
+      };
+    }
+
+    const connection = (navigator as Navigator & { connection?: EventTarget }).connection;
+    const onChange = () => reportMetered(isMetered());
+    onChange();
+    connection?.addEventListener('change', onChange);
+    return () => connection?.removeEventListener('change', onChange);
+  }, [appService, isMobileApp]);
+};
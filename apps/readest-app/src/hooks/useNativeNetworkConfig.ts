@@ -0,0 +1,28 @@
+import { useEffect } from 'react';
+import { isTauriAppPlatform } from '@/services/environment';
+import { useSettingsStore } from '@/store/settingsStore';
+import { setNativeNetworkConfig, type NativeNetworkConfig } from '@/utils/transfer';
+
+/**
+ * Pushes the proxy and extra trusted certificates from settings to the native
+ * HTTP client factory, which downloads, uploads, the updater and the clip
+ * webview share. Keyed on the serialized config so unrelated settings saves
+ * don't rebuild the native client and drop its pooled connections. Waits for
+ * settings to load: the first push releases the native transfer queue.
+ */
+export const useNativeNetworkConfig = () => {
+  const { settings } = useSettingsStore();
+  const config: NativeNetworkConfig = {
+    proxy: settings?.networkProxy ?? { mode: 'system' },
+    extraRootCertificates: settings?.extraRootCertificates ?? [],
+  };
+  const configKey = JSON.stringify(config);
+  const loaded = !!settings?.version;
+
+  useEffect(() => {
+    if (!isTauriAppPlatform() || !loaded) return;
+    setNativeNetworkConfig(JSON.parse(configKey) as NativeNetworkConfig).catch((err) => {
+      console.warn('[network] invalid proxy or certificate settings:', err);
+    });
+  }, [configKey, loaded]);
+};
@@ -0,0 +1,80 @@
+import { useEffect, useMemo } from 'react';
+import { useEnv } from '@/context/EnvContext';
+import { saveSysSettings } from '@/helpers/settings';
+import { isTauriAppPlatform } from '@/services/environment';
+import { nativeOpdsServer, type SharedOPDSBook } from '@/services/opds/native';
+import { useLibraryStore } from '@/store/libraryStore';
+import { useSettingsStore } from '@/store/settingsStore';
+import type { Book } from '@/types/book';
+import { formatTitle, getLocalBookFilename } from '@/utils/book';
+
+const toSharedBook = (book: Book): SharedOPDSBook => {
+  const metadata = book.metadata;
+  const subject = metadata?.subject;
+  const subjects = (Array.isArray(subject) ? subject : subject ? [subject] : []).map((s) =>
+    typeof s === 'string' ? s : formatTitle(s.name),
+  );
+  return {
+    hash: book.hash,
+    title: book.title,
+    author: book.author,
+    format: book.format,
+    fileName: getLocalBookFilename(book),
+    coverHash: book.coverHash,
+    language: Array.isArray(metadata?.language) ? metadata.language[0] : metadata?.language,
+    publisher: typeof metadata?.publisher === 'string' ? metadata.publisher : undefined,
+    description: metadata?.description,
+    subjects,
+    series: metadata?.series,
+    seriesIndex: metadata?.seriesIndex,
+    createdAt: book.createdAt,
+  };
+};
+
+// Only books whose file lives in the books directory can be served:
+// cloud-only, deleted, in-place and Audiobookshelf books are left out.
+const sharedBooksOf = (library: Book[]) =>
+  library
+    .filter((b) => !b.deletedAt && b.downloadedAt && !b.filePath && b.format !== 'ABS')
+    .map(toSharedBook);
+
+/**
+ * Runs the built-in OPDS server while `opdsServerEnabled` is on and keeps its
+ * catalog in step with the library. The PIN the server generates on first
+ * start is saved so readers stay paired across restarts.
+ */
+export const useOpdsServer = () => {
+  const { envConfig, appService } = useEnv();
+  const { settings } = useSettingsStore();
+  const { library, libraryLoaded } = useLibraryStore();
+  const enabled = !!settings?.opdsServerEnabled;
+
+  const sharedBooks = useMemo(() => sharedBooksOf(library), [library]);
+
+  useEffect(() => {
+    if (!isTauriAppPlatform() || !appService) return;
+    if (!enabled) {
+      nativeOpdsServer.stop().catch(() => {});
+      return;
+    }
+    (async () => {
+      const cacheDir = await appService.resolveFilePath('', 'Cache');
+      const status = await nativeOpdsServer.start(
+        appService.localBooksDir,
+        cacheDir,
+        useSettingsStore.getState().settings.opdsServerPin,
+      );
+      await saveSysSettings(envConfig, 'opdsServerPin', status.pin);
+      // The library effect may have run before the server was up.
+      const { library, libraryLoaded } = useLibraryStore.getState();
+      if (libraryLoaded) await nativeOpdsServer.setLibrary(sharedBooksOf(library));
+    })().catch((err) => console.warn('[opds] failed to start the library server:', err));
+  }, [enabled, appService, envConfig]);
+
+  useEffect(() => {
+    if (!isTauriAppPlatform() || !enabled || !libraryLoaded) return;
+    nativeOpdsServer
+      .setLibrary(sharedBooks)
+      .catch((err) => console.warn('[opds] failed to update the shared library:', err));
+  }, [enabled, libraryLoaded, sharedBooks]);
+};
@@ -0,0 +1,26 @@
+import { invoke } from '@tauri-apps/api/core';
+import { BooknoteGroup } from '@/types/book';
+import { AnnotationLinkType } from '@/utils/deeplink';
+import { ReadestAnnotationBook } from './providers/readest';
+
+// Native export (src-tauri/src/annotation_export.rs): renders the groups and
+// writes the file atomically, so scheduled exports into a notes vault never
+// leave a half-written file behind.
+
+export type NativeAnnotationExportFormat = 'markdown' | 'obsidian' | 'readwise-csv' | 'json';
+
+export interface NativeAnnotationExportPayload {
+  book: ReadestAnnotationBook;
+  /** Already filtered by color/style, as the export dialog shows them. */
+  groups: BooknoteGroup[];
+  progress?: [number, number];
+  location?: string;
+  linkType?: AnnotationLinkType;
+}
+
+/** Write `payload` to `filePath`; resolves to the number of annotations written. */
+export const exportAnnotationsNative = (
+  payload: NativeAnnotationExportPayload,
+  format: NativeAnnotationExportFormat,
+  filePath: string,
+) => invoke<number>('export_annotations', { payload, format, filePath });
@@ -0,0 +1,53 @@
+import { invoke } from '@tauri-apps/api/core';
+import { HighlightColor, HighlightStyle } from '@/types/book';
+
+// Native highlight import (src-tauri/src/highlight_import): parses a Kindle
+// "My Clippings.txt" or KOReader sidecars and matches each book to the
+// library. Annotations carry a text anchor rather than a CFI; the reader
+// resolves it once the matched book is open.
+
+export interface ImportLibraryBook {
+  hash: string;
+  title: string;
+  author?: string;
+}
+
+export interface ImportedTextAnchor {
+  exact: string;
+  xpointer0?: string;
+  xpointer1?: string;
+  /** Kindle location range. */
+  location?: [number, number];
+  page?: number;
+  chapter?: string;
+  /** Position through the book, 0..1, when the source knows it. */
+  fraction?: number;
+}
+
+export interface ImportedAnnotation {
+  id: string;
+  type: 'annotation' | 'bookmark';
+  anchor: ImportedTextAnchor;
+  note: string;
+  style?: HighlightStyle;
+  color?: HighlightColor;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface ImportedBook {
+  title: string;
+  author: string;
+  partialMd5?: string;
+  /** The matched library book, or null when none (or several) matched. */
+  bookHash: string | null;
+  sourcePath: string;
+  annotations: ImportedAnnotation[];
+}
+
+export const importKindleClippings = (filePath: string, library: ImportLibraryBook[]) =>
+  invoke<ImportedBook[]>('import_kindle_clippings', { filePath, library });
+
+/** `path` is a sidecar, a `.sdr` folder, or a folder to search for them. */
+export const importKOReaderSidecars = (path: string, library: ImportLibraryBook[]) =>
+  invoke<ImportedBook[]>('import_koreader_sidecars', { path, library });
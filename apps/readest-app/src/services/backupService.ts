@@ -40,6 +40,11 @@ export const BACKUP_SETTINGS_BLACKLIST = [
   'autoImportFolders',
   'autoImportFlattenFolders',
   'savedBookCoverForLockScreenPath',
+  // Device network setup — a proxy/CA for one network is wrong on another.
+  'networkProxy',
+  'extraRootCertificates',
+  // LAN library sharing opens a port; opting in is per device.
+  'opdsServerEnabled',
   // Per-device identity — restoring causes sync identity / HLC collisions.
   'replicaDeviceId',
   'kosync.deviceId',
@@ -90,6 +95,7 @@ export const BACKUP_SETTINGS_CREDENTIAL_FIELDS = [
   'bookorbit.username',
   'bookorbit.userkey',
   'bookorbit.password',
+  'opdsServerPin',
   'readwise.accessToken',
   'hardcover.accessToken',
   // S3 access keys are strong, long-lived cloud credentials — strip them from
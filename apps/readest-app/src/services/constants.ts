@@ -197,6 +197,9 @@ export const DEFAULT_SYSTEM_SETTINGS: Partial<SystemSettings> = {
   autoImportBooksOnOpen: false,
   telemetryEnabled: true,
   discordRichPresenceEnabled: false,
+  transferRateLimitKBps: 0,
+  pauseTransfersOnMetered: false,
+  opdsServerEnabled: false,
   libraryViewMode: 'grid',
   librarySortBy: LibrarySortByType.Updated,
   librarySortAscending: false,
@@ -7,6 +7,33 @@ export class BookFileNotFoundError extends Error {
   }
 }
 
+/**
+ * Prefix of the error string the native parsers (`parse_epub_metadata`,
+ * `parse_epub_full`, `parse_mobi_metadata`) return for DRM-protected books.
+ * Keep in sync with `DRM_ERROR_PREFIX` in `src-tauri/src/parser_common.rs`.
+ */
+export const DRM_ERROR_PREFIX = 'DRM-protected';
+
+export const isDrmProtectedError = (err: unknown): boolean =>
+  (err instanceof Error ? err.message : String(err)).startsWith(DRM_ERROR_PREFIX);
+
+export class DrmProtectedError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = 'DrmProtectedError';
+  }
+}
+
+/**
+ * Prefix of the error string `download_file` returns when the downloaded
+ * file fails its size/checksum check (the partial file is already deleted).
+ * Keep in sync with `Error::Integrity` in `src-tauri/src/transfer_file.rs`.
+ */
+export const INTEGRITY_ERROR_PREFIX = 'integrity check failed';
+
+export const isIntegrityError = (err: unknown): boolean =>
+  (err instanceof Error ? err.message : String(err)).startsWith(INTEGRITY_ERROR_PREFIX);
+
 export class ImportError extends Error {
   constructor(cause: unknown) {
     const msg = cause instanceof Error ? cause.message : String(cause);
@@ -22,6 +49,7 @@ const IMPORT_ERROR_MAP: [string, string][] = [
   ['Failed to open file', _('Failed to open the book file')],
   ['Invalid or empty book file', _('The book file is empty')],
   ['Unsupported or corrupted book file', _('The book file is corrupted')],
+  [DRM_ERROR_PREFIX, _('This book is DRM-protected and cannot be opened')],
 ];
 
 export const getImportErrorMessage = (errorMsg: string): string => {
@@ -1,8 +1,11 @@
 // Per-device LocalSend preferences, stored in localStorage (not synced
 // across devices): each device opts into being a LocalSend peer on its own.
 
+import type { LocalSendOptions } from './types';
+
 const ENABLED_KEY = 'readest-localsend-enabled';
 const ALIAS_KEY = 'readest-localsend-alias';
+const OPTIONS_KEY = 'readest-localsend-options';
 
 /** Whether this device runs the LocalSend service. Defaults to false (opt-in). */
 export function isLocalSendEnabled(): boolean {
@@ -37,3 +40,20 @@ export function setLocalSendAlias(alias: string): void {
     /* localStorage unavailable — default alias stands */
   }
 }
+
+/** PIN, upload page and receive-only switches; empty means the defaults. */
+export function getLocalSendOptions(): LocalSendOptions {
+  try {
+    return JSON.parse(localStorage.getItem(OPTIONS_KEY) ?? '{}') as LocalSendOptions;
+  } catch {
+    return {};
+  }
+}
+
+export function setLocalSendOptions(options: LocalSendOptions): void {
+  try {
+    localStorage.setItem(OPTIONS_KEY, JSON.stringify(options));
+  } catch {
+    /* localStorage unavailable — defaults stand */
+  }
+}
@@ -1,22 +1,33 @@
 import { SUPPORTED_BOOK_EXTS } from '@/services/constants';
 import type { LocalSendFile } from './types';
 
+/** `<book hash>.readest.json`, matching `SIDECAR_SUFFIX` in the Rust client. */
+const SIDECAR_NAME = /^[0-9a-f]{1,64}\.readest\.json$/i;
+const MAX_SIDECAR_BYTES = 8 * 1024 * 1024;
+
 /**
- * Split an incoming LocalSend file list into book files Readest can import
- * and files to decline. The receive dialog accepts only `supported`;
- * partial accept is native to the protocol, so the sender sees the split.
+ * Split an incoming LocalSend file list into book files Readest can import,
+ * Readest book sidecars, and files to decline. The receive dialog lists only
+ * `supported` and accepts `sidecars` along with them; partial accept is
+ * native to the protocol, so the sender sees the split.
  */
 export function partitionSupportedFiles(files: LocalSendFile[]): {
   supported: LocalSendFile[];
+  sidecars: LocalSendFile[];
   skipped: LocalSendFile[];
 } {
   const supported: LocalSendFile[] = [];
+  const sidecars: LocalSendFile[] = [];
   const skipped: LocalSendFile[] = [];
   for (const file of files) {
+    if (SIDECAR_NAME.test(file.fileName) && file.size <= MAX_SIDECAR_BYTES) {
+      sidecars.push(file);
+      continue;
+    }
     const ext = file.fileName.split('.').pop()?.toLowerCase() ?? '';
     (file.fileName.includes('.') && SUPPORTED_BOOK_EXTS.includes(ext) ? supported : skipped).push(
       file,
     );
   }
-  return { supported, skipped };
+  return { supported, sidecars, skipped };
 }
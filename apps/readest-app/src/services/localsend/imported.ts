@@ -0,0 +1,43 @@
+import type { Book, BookFormat } from '@/types/book';
+import { formatAuthors } from '@/utils/book';
+import { md5Fingerprint } from '@/utils/md5';
+import type { ReceiveImported } from './types';
+
+/**
+ * The library row for a book the native importer placed
+ * (src-tauri/src/localsend/inbox.rs). The file already sits where
+ * `getLocalBookFilename` looks for it, so nothing is parsed or copied here:
+ * a new book gets its row from the journaled title and authors, and a
+ * deleted row with the same hash comes back as it was, since the file was
+ * named after its own title. A book from a folder send is filed under the
+ * group an imported folder of that name gets. `Book.metadata` is filled in
+ * when the book is first opened.
+ */
+export function bookFromImported(entry: ReceiveImported, existing?: Book, now = Date.now()): Book {
+  if (existing) {
+    return {
+      ...existing,
+      deletedAt: null,
+      fileSyncDeletionRequestedAt: null,
+      createdAt: now,
+      updatedAt: now,
+      downloadedAt: now,
+    };
+  }
+  const title = entry.title || entry.fileName;
+  return {
+    hash: entry.bookHash,
+    format: entry.format as BookFormat,
+    title,
+    sourceTitle: title,
+    author: entry.authors.length ? formatAuthors(entry.authors) : '',
+    createdAt: now,
+    updatedAt: now,
+    uploadedAt: null,
+    deletedAt: null,
+    downloadedAt: now,
+    ...(entry.groupName
+      ? { groupId: md5Fingerprint(entry.groupName), groupName: entry.groupName }
+      : {}),
+  };
+}
@@ -1,8 +1,19 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { LocalSendDevice, LocalSendStatus, SendFileInput } from './types';
+import type {
+  LocalSendDevice,
+  LocalSendOptions,
+  LocalSendStatus,
+  LocalSendTrustedDevice,
+  ReceiveImported,
+  SendFileInput,
+} from './types';
 
-export async function startLocalSend(alias: string, deviceModel: string): Promise<LocalSendStatus> {
-  return invoke<LocalSendStatus>('localsend_start', { alias, deviceModel });
+export async function startLocalSend(
+  alias: string,
+  deviceModel: string,
+  options?: LocalSendOptions,
+): Promise<LocalSendStatus> {
+  return invoke<LocalSendStatus>('localsend_start', { alias, deviceModel, options });
 }
 
 export async function stopLocalSend(): Promise<void> {
@@ -38,13 +49,49 @@ export async function cancelLocalSendReceive(sessionId: string): Promise<void> {
   await invoke('localsend_cancel_receive', { sessionId });
 }
 
+/** `pin` answers a receiver that ended the previous attempt with `pin_required`. */
 export async function sendLocalSendFiles(
   fingerprint: string,
   files: SendFileInput[],
+  pin?: string,
 ): Promise<void> {
-  await invoke('localsend_send_files', { fingerprint, files });
+  await invoke('localsend_send_files', { fingerprint, files, pin });
+}
+
+/** Send everything below `path` as one session, keeping the folder tree. */
+export async function sendLocalSendFolder(
+  fingerprint: string,
+  path: string,
+  pin?: string,
+): Promise<void> {
+  await invoke('localsend_send_folder', { fingerprint, path, pin });
 }
 
 export async function cancelLocalSendSend(): Promise<void> {
   await invoke('localsend_cancel_send');
 }
+
+export async function listLocalSendTrusted(): Promise<LocalSendTrustedDevice[]> {
+  return invoke<LocalSendTrustedDevice[]>('localsend_list_trusted');
+}
+
+/** Trust a device, or change its policy; resolves to the updated list. */
+export async function setLocalSendTrusted(
+  device: LocalSendTrustedDevice,
+): Promise<LocalSendTrustedDevice[]> {
+  return invoke<LocalSendTrustedDevice[]>('localsend_set_trusted', { device });
+}
+
+export async function removeLocalSendTrusted(
+  fingerprint: string,
+): Promise<LocalSendTrustedDevice[]> {
+  return invoke<LocalSendTrustedDevice[]>('localsend_remove_trusted', { fingerprint });
+}
+
+/**
+ * Drain the native import journal. Each import is returned once, including
+ * those announced while no window was listening.
+ */
+export async function takeLocalSendImported(): Promise<ReceiveImported[]> {
+  return invoke<ReceiveImported[]>('localsend_take_imported');
+}
@@ -0,0 +1,151 @@
+import type { Book, BookConfig } from '@/types/book';
+import type { AppService } from '@/types/system';
+import type { SystemSettings } from '@/types/settings';
+import { getCoverFilename } from '@/utils/book';
+import { mergeBookConfig, mergeBookMetadata } from '@/services/sync/file/merge';
+import {
+  buildRemotePayload,
+  stripDeviceLocalFields,
+  type RemoteBookConfig,
+} from '@/services/sync/file/wire';
+
+/**
+ * Readest-to-Readest book sidecar (`<hash>.readest.json`). The sender offers
+ * one with every book; the Rust client only uploads it to peers it recognizes
+ * as Readest, after the books, and the receiver merges it into the book it
+ * just imported. Progress and annotations reuse the file-sync wire envelope
+ * and merge policies, so a transfer converges exactly like a sync would.
+ */
+export interface BookSidecar {
+  readestSidecar: 1;
+  bookHash: string;
+  /** The sender's library row, without device-local fields. */
+  book: Book;
+  config: RemoteBookConfig;
+  /** Base64 cover.png, so a custom cover survives the trip. */
+  cover?: string;
+}
+
+/** Covers larger than this stay home; the receiver keeps the extracted one. */
+const MAX_SIDECAR_COVER_BYTES = 1024 * 1024;
+
+const toBase64 = (bytes: ArrayBuffer): string => {
+  const array = new Uint8Array(bytes);
+  let binary = '';
+  for (let i = 0; i < array.length; i += 0x8000) {
+    binary += String.fromCharCode.apply(null, Array.from(array.subarray(i, i + 0x8000)));
+  }
+  return btoa(binary);
+};
+
+const fromBase64 = (base64: string): ArrayBuffer => {
+  const binary = atob(base64);
+  const array = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) array[i] = binary.charCodeAt(i);
+  return array.buffer;
+};
+
+export function buildBookSidecar(
+  book: Book,
+  config: BookConfig,
+  deviceId: string,
+  cover?: ArrayBuffer,
+): BookSidecar {
+  return {
+    readestSidecar: 1,
+    bookHash: book.hash,
+    book: stripDeviceLocalFields(book),
+    config: buildRemotePayload(book, config, deviceId),
+    ...(cover && cover.byteLength <= MAX_SIDECAR_COVER_BYTES ? { cover: toBase64(cover) } : {}),
+  };
+}
+
+/** Parses a received sidecar; null unless it describes `bookHash`. */
+export function parseBookSidecar(raw: string, bookHash: string): BookSidecar | null {
+  try {
+    const parsed = JSON.parse(raw) as BookSidecar;
+    if (!parsed || parsed.readestSidecar !== 1 || parsed.bookHash !== bookHash) return null;
+    if (!parsed.book || typeof parsed.book !== 'object') return null;
+    if (!parsed.config || parsed.config.schemaVersion !== 1) return null;
+    if (parsed.config.bookHash !== bookHash) return null;
+    if (parsed.cover !== undefined && typeof parsed.cover !== 'string') return null;
+    return parsed;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Merge a sidecar into the local copy of its book. `fresh` marks a book this
+ * transfer added to the library: its import-time clocks would beat every
+ * edit the sender made, so the sender's state is applied over it instead.
+ */
+export function mergeBookSidecar(
+  book: Book,
+  config: BookConfig,
+  sidecar: BookSidecar,
+  fresh: boolean,
+): { book: Book; config: BookConfig; adoptCover: boolean } {
+  const base = fresh ? { ...book, updatedAt: 0, metadataUpdatedAt: undefined } : book;
+  // The overlay copies a fixed field subset; device-local fields stay ours.
+  const merged = mergeBookMetadata(base, { ...sidecar.book, hash: book.hash });
+  merged.updatedAt = Math.max(merged.updatedAt ?? 0, book.updatedAt ?? 0);
+  const { config: mergedConfig } = mergeBookConfig(
+    fresh ? { ...config, updatedAt: 0 } : config,
+    sidecar.config,
+  );
+  const adoptCover =
+    !!sidecar.cover && (sidecar.book.metadataUpdatedAt ?? 0) > (base.metadataUpdatedAt ?? 0);
+  return { book: merged, config: mergedConfig, adoptCover };
+}
+
+/** The sidecar offered with `book`, or undefined when it cannot be built. */
+export async function resolveBookSidecar(
+  book: Book,
+  appService: AppService,
+  settings: SystemSettings,
+  deviceId: string,
+): Promise<BookSidecar | undefined> {
+  try {
+    const config = await appService.loadBookConfig(book, settings);
+    let cover: ArrayBuffer | undefined;
+    const coverPath = getCoverFilename(book);
+    if (await appService.exists(coverPath, 'Books')) {
+      const bytes = await appService.readFile(coverPath, 'Books', 'binary');
+      if (typeof bytes !== 'string') cover = bytes;
+    }
+    return buildBookSidecar(book, config, deviceId, cover);
+  } catch {
+    return undefined;
+  }
+}
+
+/**
+ * Applies a received sidecar file to the library book it names and deletes
+ * the file. Returns the updated book, or null when the sidecar is invalid or
+ * the book is not in the library.
+ */
+export async function applyReceivedSidecar(
+  path: string,
+  bookHash: string,
+  fresh: boolean,
+  library: Book[],
+  appService: AppService,
+  settings: SystemSettings,
+): Promise<Book | null> {
+  try {
+    const raw = await appService.readFile(path, 'None', 'text');
+    const sidecar = typeof raw === 'string' ? parseBookSidecar(raw, bookHash) : null;
+    const book = library.find((b) => b.hash === bookHash && !b.deletedAt);
+    if (!sidecar || !book) return null;
+    const local = await appService.loadBookConfig(book, settings);
+    const merged = mergeBookSidecar(book, local, sidecar, fresh);
+    await appService.saveBookConfig(merged.book, merged.config, settings);
+    if (merged.adoptCover && sidecar.cover) {
+      await appService.writeFile(getCoverFilename(book), 'Books', fromBase64(sidecar.cover));
+    }
+    return merged.book;
+  } finally {
+    await appService.deleteFile(path, 'None').catch(() => {});
+  }
+}
@@ -0,0 +1,42 @@
+import type { TranslationFunc } from '@/hooks/useTranslation';
+import { formatBytes } from '@/utils/book';
+import type { LocalSendTrustPolicy } from './types';
+
+const MB = 1024 * 1024;
+
+/** Per-file size caps offered for a `books` policy. */
+export const TRUST_BOOK_CAPS = [20 * MB, 100 * MB, 500 * MB];
+
+/**
+ * A trust policy as one `<select>` value, so the policy and its size cap are
+ * picked together: "ask", "always", or "books:<maxBytes>".
+ */
+export function trustChoiceOf(policy: LocalSendTrustPolicy): string {
+  return policy.kind === 'books' ? `books:${policy.maxBytes}` : policy.kind;
+}
+
+/** The inverse of `trustChoiceOf`; null for a value it did not produce. */
+export function trustPolicyOf(choice: string): LocalSendTrustPolicy | null {
+  if (choice === 'ask' || choice === 'always') return { kind: choice };
+  const maxBytes = Number(choice.startsWith('books:') ? choice.slice('books:'.length) : NaN);
+  return Number.isSafeInteger(maxBytes) && maxBytes > 0 ? { kind: 'books', maxBytes } : null;
+}
+
+/** The choices to offer, keeping a device's current cap when it is not a preset. */
+export function trustChoices(current?: LocalSendTrustPolicy): string[] {
+  const caps = [...TRUST_BOOK_CAPS];
+  if (current?.kind === 'books' && !caps.includes(current.maxBytes)) {
+    caps.push(current.maxBytes);
+    caps.sort((a, b) => a - b);
+  }
+  return ['ask', ...caps.map((maxBytes) => `books:${maxBytes}`), 'always'];
+}
+
+export function trustChoiceLabel(choice: string, _: TranslationFunc): string {
+  const policy = trustPolicyOf(choice);
+  if (policy?.kind === 'always') return _('Accept all files');
+  if (policy?.kind === 'books') {
+    return _('Accept books up to {{size}}', { size: formatBytes(policy.maxBytes) });
+  }
+  return _('Ask every time');
+}
@@ -1,3 +1,5 @@
+import type { BookSidecar } from './sidecar';
+
 export interface LocalSendStatus {
   running: boolean;
   alias: string;
@@ -8,6 +10,27 @@ export interface LocalSendStatus {
   /** Non-loopback IPv4 addresses; source of the "#<last-octet>" device tag. */
   localIps: string[];
   multicastError: string | null;
+  pinProtected: boolean;
+  webUpload: boolean;
+  receiveOnly: boolean;
+}
+
+/** `localsend_start` options; omitted fields keep the open defaults. */
+export interface LocalSendOptions {
+  /** Senders must enter this PIN; blank means none. */
+  pin?: string;
+  /**
+   * Serve the browser upload page (default on). Off, the server requires a
+   * TLS client certificate, which the stable LocalSend app doesn't present.
+   */
+  webUpload?: boolean;
+  /** Don't announce this device or track peers, and refuse to send. */
+  receiveOnly?: boolean;
+  /**
+   * Absolute Books directory. Set, received books are parsed, deduplicated
+   * and placed in `Books/<hash>/` natively, and announced as `ReceiveImported`.
+   */
+  booksDir?: string;
 }
 
 export interface LocalSendDevice {
@@ -38,6 +61,8 @@ export interface ReceiveRequest {
     deviceModel: string | null;
     deviceType: string | null;
     fingerprint: string;
+    /** A client-certificate fingerprint; only such senders can be trusted. */
+    verified: boolean;
   };
   files: LocalSendFile[];
 }
@@ -54,10 +79,40 @@ export interface ReceiveFileDone {
   sessionId: string;
   fileId: string;
   fileName: string;
+  /** The library group, for a book from a folder send. */
+  groupName: string | null;
   path: string | null;
   error: string | null;
 }
 
+/** A Readest book sidecar, received in place of a `ReceiveFileDone`. */
+export interface ReceiveSidecar {
+  sessionId: string;
+  bookHash: string;
+  /** The manifest in the inbox; delete it once merged. */
+  path: string;
+}
+
+/**
+ * A received book placed in the library folder by the native importer, in
+ * place of a `ReceiveFileDone`. The library row is still the webview's job.
+ */
+export interface ReceiveImported {
+  sessionId: string;
+  fileId: string;
+  fileName: string;
+  bookHash: string;
+  format: string;
+  /** The placed file; null for a duplicate, whose received copy was discarded. */
+  path: string | null;
+  duplicate: boolean;
+  /** Title and `sourceTitle` of a new library row; the file is named after it. */
+  title: string | null;
+  authors: string[];
+  /** The folder a book from a folder send sat in; a new row's group. */
+  groupName?: string | null;
+}
+
 export interface ReceiveEnd {
   sessionId: string;
   reason: 'finished' | 'cancelled';
@@ -67,26 +122,51 @@ export interface ReceiveEnd {
 
 export interface SendEnd {
   sessionId: string | null;
-  status: 'sent' | 'declined' | 'cancelled' | 'error';
+  /** `pin_required`: the receiver wants a PIN; `error` is set when one was wrong. */
+  status: 'sent' | 'declined' | 'cancelled' | 'pin_required' | 'error';
   error: string | null;
   filesSent: number;
 }
 
 export interface SendFileInput {
   path: string;
+  /** May carry `/`-separated folders ("Shelf/Title.epub"); the receiver recreates them. */
   fileName: string;
   mimeType: string;
   /** Base64 thumbnail shown by the receiver (LocalSend `preview` wire field). */
   preview?: string;
+  /** Uploaded as `<bookHash>.readest.json` when the receiver is Readest. */
+  sidecar?: { bookHash: string; manifest: BookSidecar };
+}
+
+/**
+ * What a trusted device may send without the accept dialog: anything, book
+ * files no larger than `maxBytes` each, or nothing (still asks).
+ */
+export type LocalSendTrustPolicy =
+  | { kind: 'always' }
+  | { kind: 'books'; maxBytes: number }
+  | { kind: 'ask' };
+
+export interface LocalSendTrustedDevice {
+  /** Certificate fingerprint; senders without a client certificate always ask. */
+  fingerprint: string;
+  alias: string;
+  policy: LocalSendTrustPolicy;
 }
 
 export const LOCALSEND_EVENTS = {
   serverState: 'localsend:server-state',
   devices: 'localsend:devices',
   receiveRequest: 'localsend:receive-request',
+  /** A `ReceiveRequest` from a trusted device, already accepted. */
+  receiveAutoAccepted: 'localsend:receive-auto-accepted',
   receiveRequestClosed: 'localsend:receive-request-closed',
   receiveProgress: 'localsend:receive-progress',
   receiveFileDone: 'localsend:receive-file-done',
+  receiveSidecar: 'localsend:receive-sidecar',
+  /** A `ReceiveImported`; drain them with `takeLocalSendImported`. */
+  receiveImported: 'localsend:receive-imported',
   receiveEnd: 'localsend:receive-end',
   sendProgress: 'localsend:send-progress',
   sendEnd: 'localsend:send-end',
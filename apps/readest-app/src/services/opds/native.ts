@@ -0,0 +1,165 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { OPDSCatalog } from '@/types/opds';
+
+// Typed results of the native `opds` client (src-tauri/src/opds). Every href
+// is absolute, so an acquisition's `href` can go straight to `download_file`.
+
+export type NativeOPDSAuth =
+  | { type: 'basic'; username: string; password: string }
+  | { type: 'bearer'; token: string };
+
+export interface NativeOPDSLink {
+  href: string;
+  rel?: string[];
+  type?: string;
+  title?: string;
+  templated?: boolean;
+  count?: number;
+}
+
+export type NativeAcquisitionKind =
+  | 'generic'
+  | 'open-access'
+  | 'borrow'
+  | 'buy'
+  | 'sample'
+  | 'subscribe';
+
+export interface NativeAcquisition {
+  href: string;
+  kind: NativeAcquisitionKind;
+  type?: string;
+  title?: string;
+  length?: number;
+  price?: { value: number; currency: string };
+  indirectTypes?: string[];
+}
+
+export interface NativePublication {
+  id?: string;
+  title: string;
+  subtitle?: string;
+  authors: { name: string; href?: string }[];
+  publisher?: string;
+  published?: string;
+  updated?: string;
+  languages?: string[];
+  subjects?: string[];
+  series?: { name: string; position?: number };
+  summary?: string;
+  content?: string;
+  cover?: string;
+  thumbnail?: string;
+  acquisitions: NativeAcquisition[];
+  links?: NativeOPDSLink[];
+}
+
+export interface NativeNavigationEntry {
+  title: string;
+  href: string;
+  type?: string;
+  rel?: string[];
+  summary?: string;
+  count?: number;
+}
+
+export interface NativeOPDSFeed {
+  id?: string;
+  title: string;
+  subtitle?: string;
+  updated?: string;
+  links: NativeOPDSLink[];
+  navigation: NativeNavigationEntry[];
+  publications: NativePublication[];
+  groups?: {
+    title: string;
+    href?: string;
+    navigation?: NativeNavigationEntry[];
+    publications?: NativePublication[];
+  }[];
+  facets?: { title: string; facets: { link: NativeOPDSLink; active: boolean }[] }[];
+  pagination: {
+    next?: string;
+    previous?: string;
+    first?: string;
+    last?: string;
+    totalResults?: number;
+    itemsPerPage?: number;
+    startIndex?: number;
+  };
+  search?: NativeOPDSLink;
+}
+
+export interface NativeSearchDescription {
+  shortName?: string;
+  description?: string;
+  template: string;
+  type?: string;
+  params: string[];
+}
+
+export type NativeOPDSDocument =
+  | ({ kind: 'feed' } & NativeOPDSFeed)
+  | ({ kind: 'publication' } & NativePublication)
+  | ({ kind: 'search' } & NativeSearchDescription);
+
+/** Basic auth from a catalog's stored credentials, if it has any. */
+export const catalogAuth = (catalog: OPDSCatalog): NativeOPDSAuth | undefined =>
+  catalog.username
+    ? { type: 'basic', username: catalog.username, password: catalog.password ?? '' }
+    : undefined;
+
+export const nativeOpds = {
+  fetch: (url: string, auth?: NativeOPDSAuth, headers?: Record<string, string>) =>
+    invoke<NativeOPDSDocument>('opds_fetch', { url, auth, headers }),
+  /** Search via a feed's `search` link; `params.query` fills either flavour. */
+  search: (
+    link: NativeOPDSLink,
+    params: Record<string, string>,
+    auth?: NativeOPDSAuth,
+    headers?: Record<string, string>,
+  ) =>
+    invoke<NativeOPDSFeed>('opds_search', {
+      href: link.href,
+      templated: !!link.templated,
+      params,
+      auth,
+      headers,
+    }),
+};
+
+// The built-in OPDS server (src-tauri/src/opds/server.rs) sharing this
+// library over the LAN.
+
+export interface OPDSServerStatus {
+  running: boolean;
+  port: number;
+  pin: string;
+  localIps: string[];
+  bookCount: number;
+}
+
+export interface SharedOPDSBook {
+  hash: string;
+  title: string;
+  author: string;
+  format: string;
+  /** Relative to the books directory, as `getLocalBookFilename` returns it. */
+  fileName: string;
+  coverHash?: string | null;
+  language?: string;
+  publisher?: string;
+  description?: string;
+  subjects?: string[];
+  series?: string;
+  seriesIndex?: number;
+  createdAt: number;
+}
+
+export const nativeOpdsServer = {
+  start: (booksDir: string, cacheDir: string, pin?: string) =>
+    invoke<OPDSServerStatus>('opds_server_start', { booksDir, cacheDir, pin }),
+  stop: () => invoke<void>('opds_server_stop'),
+  status: () => invoke<OPDSServerStatus>('opds_server_status'),
+  setLibrary: (books: SharedOPDSBook[]) => invoke<void>('opds_server_set_library', { books }),
+};
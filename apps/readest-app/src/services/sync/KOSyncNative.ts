@@ -0,0 +1,71 @@
+import { invoke } from '@tauri-apps/api/core';
+import { KOSyncSettings } from '@/types/settings';
+import { KoSyncProgress } from './KOSyncClient';
+
+// The native KOReader sync client and LAN server (src-tauri/src/kosync).
+// Unlike KOSyncClient, it maps XPointers to CFIs straight from the EPUB file,
+// so positions can be exchanged without opening the book.
+
+export interface NativeKoSyncProgress extends KoSyncProgress {
+  /** The XPointer as a CFI, when it resolves against the local EPUB. */
+  cfi: string | null;
+}
+
+export interface KoSyncServerStatus {
+  running: boolean;
+  port: number;
+  allowRegistration: boolean;
+  localIps: string[];
+}
+
+export interface KoSyncServerProgressEvent extends KoSyncProgress {
+  username: string;
+}
+
+export const KOSYNC_SERVER_PROGRESS_EVENT = 'kosync:server-progress';
+
+const accountOf = (settings: KOSyncSettings) => ({
+  serverUrl: settings.serverUrl,
+  username: settings.username,
+  userkey: settings.userkey,
+  deviceName: settings.deviceName,
+  deviceId: settings.deviceId,
+});
+
+export const nativeKOSync = {
+  connect: (settings: KOSyncSettings, register = false) =>
+    invoke<void>('kosync_connect', { account: accountOf(settings), register }),
+  /** `document` is the book hash; pass the EPUB's path to get a CFI back. */
+  pull: (settings: KOSyncSettings, document: string, filePath?: string) =>
+    invoke<NativeKoSyncProgress | null>('kosync_pull', {
+      account: accountOf(settings),
+      document,
+      filePath,
+    }),
+  /** Push a CFI (translated to an XPointer) or a page; returns what was sent. */
+  push: (
+    settings: KOSyncSettings,
+    document: string,
+    position: string,
+    percentage: number,
+    filePath?: string,
+  ) =>
+    invoke<string>('kosync_push', {
+      account: accountOf(settings),
+      document,
+      position,
+      percentage,
+      filePath,
+    }),
+  xpointerToCfi: (filePath: string, xpointer: string) =>
+    invoke<string>('kosync_xpointer_to_cfi', { filePath, xpointer }),
+  cfiToXPointer: (filePath: string, cfi: string) =>
+    invoke<string>('kosync_cfi_to_xpointer', { filePath, cfi }),
+  startServer: (allowRegistration = false) =>
+    invoke<KoSyncServerStatus>('kosync_server_start', { allowRegistration }),
+  /** Create an account on the running server, or reset its password. */
+  addServerUser: (username: string, password: string) =>
+    invoke<void>('kosync_server_add_user', { username, password }),
+  stopServer: () => invoke<void>('kosync_server_stop'),
+  serverStatus: () => invoke<KoSyncServerStatus>('kosync_server_status'),
+};
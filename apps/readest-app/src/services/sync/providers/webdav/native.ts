@@ -0,0 +1,50 @@
+import { invoke } from '@tauri-apps/api/core';
+import { WebDAVSettings } from '@/types/settings';
+import { RemoteBookConfig } from '@/services/sync/file/wire';
+import { normalizeRootPath } from './client';
+
+// The native WebDAV backend (packages/webdav-sync, exposed by
+// src-tauri/src/webdav.rs). It writes the same layout and merges with the
+// same rules as the TypeScript engine, and is what the KOReader helper runs.
+// The app's own sync still goes through the TypeScript engine, which stays
+// authoritative; these bindings only exist for callers that need the Rust
+// merge, so both implementations can be checked against the same server.
+// Both merges run the cases in __tests__/fixtures/data/webdav-merge-cases.json
+// (merge.test.ts and packages/webdav-sync/tests/merge_parity.rs).
+
+export type NativeWebDAVSyncStatus = 'created' | 'updated' | 'upToDate';
+
+export interface NativeWebDAVSyncOutcome {
+  status: NativeWebDAVSyncStatus;
+  /** The merged progress and booknotes, to apply locally. */
+  config: RemoteBookConfig;
+}
+
+const connectionOf = (settings: WebDAVSettings) => ({
+  url: settings.serverUrl,
+  username: settings.username,
+  password: settings.password,
+  rootPath: normalizeRootPath(settings.rootPath),
+});
+
+export const nativeWebDAV = {
+  /** Merge `config` with the server's copy and write the result back. */
+  syncBookConfig: (settings: WebDAVSettings, config: RemoteBookConfig) =>
+    invoke<NativeWebDAVSyncOutcome>('webdav_sync_book_config', {
+      connection: connectionOf(settings),
+      config,
+    }),
+  /** Upload a local file into the book's directory; false if already there. */
+  uploadBookFile: (
+    settings: WebDAVSettings,
+    bookHash: string,
+    fileName: string,
+    filePath: string,
+  ) =>
+    invoke<boolean>('webdav_upload_book_file', {
+      connection: connectionOf(settings),
+      bookHash,
+      fileName,
+      filePath,
+    }),
+};
@@ -13,10 +13,18 @@ interface OwnedSession {
 
 interface SendState {
   active: boolean;
+  fingerprint: string;
   deviceAlias: string;
   progress: TransferProgress | null;
 }
 
+/** A send the receiver turned away until the user enters its PIN. */
+interface SendPinRequest {
+  fingerprint: string;
+  deviceAlias: string;
+  wrongPin: boolean;
+}
+
 interface LocalSendStoreState {
   status: LocalSendStatus | null;
   devices: LocalSendDevice[];
@@ -28,6 +36,7 @@ interface LocalSendStoreState {
    */
   ownedSessions: Record<string, OwnedSession>;
   sendState: SendState | null;
+  sendPinRequest: SendPinRequest | null;
 }
 
 interface LocalSendStoreActions {
@@ -38,9 +47,11 @@ interface LocalSendStoreActions {
   claimSession: (sessionId: string, alias: string) => void;
   receiveProgress: (progress: TransferProgress) => void;
   receiveEnded: (sessionId: string) => void;
-  startSend: (deviceAlias: string) => void;
+  startSend: (deviceAlias: string, fingerprint: string) => void;
   sendProgress: (progress: TransferProgress) => void;
   sendEnded: () => void;
+  requestSendPin: (request: SendPinRequest) => void;
+  clearSendPinRequest: () => void;
 }
 
 export const initialLocalSendState: LocalSendStoreState = {
@@ -49,6 +60,7 @@ export const initialLocalSendState: LocalSendStoreState = {
   pendingRequest: null,
   ownedSessions: {},
   sendState: null,
+  sendPinRequest: null,
 };
 
 export const useLocalSendStore = create<LocalSendStoreState & LocalSendStoreActions>()(
@@ -84,12 +96,18 @@ export const useLocalSendStore = create<LocalSendStoreState & LocalSendStoreActi
         const { [sessionId]: _removed, ...rest } = state.ownedSessions;
         return { ownedSessions: rest };
       }),
-    startSend: (deviceAlias) => set({ sendState: { active: true, deviceAlias, progress: null } }),
+    startSend: (deviceAlias, fingerprint) =>
+      set({
+        sendState: { active: true, fingerprint, deviceAlias, progress: null },
+        sendPinRequest: null,
+      }),
     sendProgress: (progress) => {
       const sendState = get().sendState;
       if (!sendState) return;
       set({ sendState: { ...sendState, progress } });
     },
     sendEnded: () => set({ sendState: null }),
+    requestSendPin: (request) => set({ sendPinRequest: request }),
+    clearSendPinRequest: () => set({ sendPinRequest: null }),
   }),
 );
@@ -366,6 +366,19 @@ export interface HardwarePageTurnerSettings {
   };
 }
 
+export type NetworkProxySettings =
+  | { mode: 'system' }
+  | { mode: 'direct' }
+  | {
+      mode: 'manual';
+      /** `http://`, `https://`, `socks5://` or `socks5h://` with host and port. */
+      url: string;
+      username?: string;
+      password?: string;
+      /** Hosts reached directly, in `NO_PROXY` syntax. */
+      noProxy?: string[];
+    };
+
 export interface SystemSettings {
   version: number;
   migrationVersion: number;
@@ -422,6 +435,22 @@ export interface SystemSettings {
   savedBookCoverForLockScreenPath: string;
   telemetryEnabled: boolean;
   discordRichPresenceEnabled: boolean;
+  /** Cap shared by native downloads, uploads and LocalSend sends, in KiB/s; 0 is unlimited. */
+  transferRateLimitKBps: number;
+  /** Hold the background transfer queue while the network is metered (cellular, hotspot). */
+  pauseTransfersOnMetered: boolean;
+  /**
+   * Proxy for native downloads/uploads, the updater and the clip webview.
+   * Device-local (a corporate network setup), excluded from cloud settings
+   * backups via `BACKUP_SETTINGS_BLACKLIST`.
+   */
+  networkProxy?: NetworkProxySettings;
+  /** PEM certificates trusted for native HTTP in addition to the system roots. */
+  extraRootCertificates?: string[];
+  /** Share the local library over the LAN through the built-in OPDS server. */
+  opdsServerEnabled: boolean;
+  /** Pairing PIN readers enter as the OPDS catalog password; generated on first start. */
+  opdsServerPin?: string;
   libraryViewMode: LibraryViewModeType;
   librarySortBy: LibrarySortByType;
   librarySortAscending: boolean;
@@ -502,6 +502,19 @@ export interface ICloudEnsureDownloadedResponse {
   status: 'ready' | 'notFound' | 'timeout';
 }
 
+/** Mobile only; desktop rejects and callers fall back to `isMetered()`. */
+export interface GetNetworkStatusResponse {
+  connected: boolean;
+  metered: boolean;
+}
+
+/** Plugin event carrying a {@link GetNetworkStatusResponse} on every change. */
+export const NETWORK_STATUS_CHANGED_EVENT = 'network-status-changed';
+
+export async function getNetworkStatus(): Promise<GetNetworkStatusResponse> {
+  return invoke<GetNetworkStatusResponse>('plugin:native-bridge|get_network_status');
+}
+
 export async function getICloudContainerStatus(): Promise<ICloudContainerStatusResponse> {
   return invoke<ICloudContainerStatusResponse>('plugin:native-bridge|icloud_container_status');
 }
@@ -33,6 +33,7 @@
 // and is a no-op on the web platform.
 import { invoke } from '@tauri-apps/api/core';
 import { isTauriAppPlatform } from '@/services/environment';
+import { DrmProtectedError, isDrmProtectedError } from '@/services/errors';
 import type { BookDoc, BookMetadata } from '@/libs/document';
 
 // ─── shared helpers ──────────────────────────────────────────────────
@@ -169,6 +170,9 @@ export const tryNativeParseEpub = async (
       bookDoc: buildBookDocStub(metadata, coverBlob),
     };
   } catch (err) {
+    // The JS parsers would happily import the ciphertext and open it as
+    // blank pages, so a DRM verdict is final rather than a fallback cue.
+    if (isDrmProtectedError(err)) throw new DrmProtectedError(String(err));
     console.warn('[tauriEpubBridge] native parse failed, falling back to JS:', err);
     return null;
   }
@@ -36,6 +36,7 @@
 // boundary and is a no-op on the web platform.
 import { invoke } from '@tauri-apps/api/core';
 import { isTauriAppPlatform } from '@/services/environment';
+import { DrmProtectedError, isDrmProtectedError } from '@/services/errors';
 import type { BookDoc, BookMetadata } from '@/libs/document';
 import type { BookFormat } from '@/types/book';
 
@@ -186,6 +187,9 @@ export const tryNativeParseMobi = async (
       bookDoc: buildBookDocStub(metadata, coverBlob, getCover),
     };
   } catch (err) {
+    // The JS parsers would happily import the ciphertext and open it as
+    // blank pages, so a DRM verdict is final rather than a fallback cue.
+    if (isDrmProtectedError(err)) throw new DrmProtectedError(String(err));
     console.warn('[tauriMobiBridge] native parse failed, falling back to JS:', err);
     return null;
   }
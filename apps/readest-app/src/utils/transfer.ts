@@ -1,4 +1,5 @@
 import { invoke, Channel } from '@tauri-apps/api/core';
+import type { NetworkProxySettings } from '@/types/settings';
 
 export type UploadMethod = 'POST' | 'PUT';
 
@@ -194,6 +195,84 @@ export const tauriUpload = async (
   });
 };
 
+/**
+ * Chunked upload protocols understood by `upload_file_chunked`. For S3 the
+ * caller creates the multipart upload and presigns every part plus the
+ * completion request; `partUrls[i]` uploads part `i + 1`.
+ */
+export type ChunkedUpload =
+  | {
+      protocol: 's3-multipart';
+      uploadId: string;
+      partSize: number;
+      partUrls: string[];
+      completeUrl: string;
+    }
+  | {
+      protocol: 'tus';
+      endpoint: string;
+      chunkSize?: number;
+      metadata?: Record<string, string>;
+    };
+
+/** Checkpointed progress of an interrupted chunked upload. */
+export type UploadResumeState =
+  | {
+      protocol: 's3-multipart';
+      uploadId: string;
+      partSize: number;
+      parts: { partNumber: number; etag: string }[];
+    }
+  | { protocol: 'tus'; uploadUrl: string };
+
+/**
+ * Upload `filePath` in parts with per-part retry. `target` identifies the
+ * destination; an interrupted upload of the same file to the same target
+ * resumes from its checkpoint. Resolves to the S3 completion response body,
+ * or the tus upload URL.
+ */
+export const tauriUploadChunked = async (
+  filePath: string,
+  target: string,
+  upload: ChunkedUpload,
+  progressHandler?: ProgressHandler,
+  headers?: Record<string, string>,
+): Promise<string> => {
+  const onProgress = new Channel<ProgressPayload>();
+  if (progressHandler) {
+    onProgress.onmessage = progressHandler;
+  }
+
+  return await invoke('upload_file_chunked', {
+    filePath,
+    target,
+    upload,
+    headers: headers ?? {},
+    onProgress,
+  });
+};
+
+/**
+ * The checkpoint of an interrupted chunked upload, if any. S3 callers presign
+ * the remaining parts for `uploadId` instead of creating a new upload.
+ */
+export const getUploadResumeState = async (
+  filePath: string,
+  target: string,
+): Promise<UploadResumeState | null> =>
+  await invoke('upload_resume_info', { filePath, target });
+
+/**
+ * Checks `download_file` runs on the finished file, in addition to the
+ * server's own Content-Length / Content-MD5 / x-amz-checksum-sha256.
+ * Checksums are hex-encoded.
+ */
+export interface DownloadIntegrity {
+  size?: number;
+  sha256?: string;
+  md5?: string;
+}
+
 export const tauriDownload = async (
   url: string,
   filePath: string,
@@ -202,6 +281,7 @@ export const tauriDownload = async (
   body?: string,
   singleThreaded?: boolean,
   skipSslVerification?: boolean,
+  integrity?: DownloadIntegrity,
 ): Promise<Record<string, string>> => {
   const ids = new Uint32Array(1);
   window.crypto.getRandomValues(ids);
@@ -221,6 +301,112 @@ export const tauriDownload = async (
     body,
     singleThreaded,
     skipSslVerification,
+    expectedSize: integrity?.size,
+    expectedSha256: integrity?.sha256,
+    expectedMd5: integrity?.md5,
   });
   return responseHeaders;
 };
+
+/** Requests accepted by the native transfer queue (`transfer_enqueue`). */
+export type NativeTransferRequest =
+  | {
+      kind: 'download';
+      url: string;
+      filePath: string;
+      headers?: Record<string, string>;
+      body?: string;
+      singleThreaded?: boolean;
+      skipSslVerification?: boolean;
+      expectedSize?: number;
+      expectedSha256?: string;
+      expectedMd5?: string;
+    }
+  | {
+      kind: 'upload';
+      url: string;
+      filePath: string;
+      method: UploadMethod;
+      headers?: Record<string, string>;
+    }
+  | {
+      kind: 'chunked-upload';
+      filePath: string;
+      target: string;
+      upload: ChunkedUpload;
+      headers?: Record<string, string>;
+    };
+
+export type NativeTransferState =
+  | 'queued'
+  | 'active'
+  | 'paused'
+  | 'completed'
+  | 'failed'
+  | 'cancelled';
+
+export interface NativeTransferInfo {
+  id: string;
+  request: NativeTransferRequest;
+  state: NativeTransferState;
+  progress: number;
+  total: number;
+  /** Restored after a restart without its request headers; pass them to `resume`. */
+  awaitingHeaders: boolean;
+}
+
+/** Payload of the `transfer:state` event. */
+export interface NativeTransferStateEvent {
+  id: string;
+  state: NativeTransferState;
+  error: string | null;
+  result: unknown;
+}
+
+/** Payload of the `transfer:progress` event. */
+export interface NativeTransferProgressEvent extends ProgressPayload {
+  id: string;
+}
+
+export const NATIVE_TRANSFER_STATE_EVENT = 'transfer:state';
+export const NATIVE_TRANSFER_PROGRESS_EVENT = 'transfer:progress';
+
+export const nativeTransferQueue = {
+  enqueue: (request: NativeTransferRequest) => invoke<string>('transfer_enqueue', { request }),
+  pause: (id: string) => invoke<void>('transfer_pause', { id }),
+  resume: (id: string, headers?: Record<string, string>) =>
+    invoke<void>('transfer_resume', { id, headers }),
+  cancel: (id: string) => invoke<void>('transfer_cancel', { id }),
+  list: () => invoke<NativeTransferInfo[]>('transfer_list'),
+  setConcurrency: (limit: number) => invoke<void>('transfer_set_concurrency', { limit }),
+};
+
+export interface BandwidthPolicy {
+  /** Bytes per second across all native transfers; `null` or 0 is unlimited. */
+  limitBytesPerSec: number | null;
+  pauseOnMetered: boolean;
+}
+
+export interface BandwidthStatus extends BandwidthPolicy {
+  metered: boolean;
+  /** Queued transfers are held back until the network is unmetered. */
+  paused: boolean;
+}
+
+export const BANDWIDTH_STATUS_EVENT = 'bandwidth:status';
+
+export interface NativeNetworkConfig {
+  proxy: NetworkProxySettings;
+  extraRootCertificates: string[];
+}
+
+/** Rejects with a readable message when the proxy URL or a certificate is invalid. */
+export const setNativeNetworkConfig = (config: NativeNetworkConfig) =>
+  invoke<void>('set_network_config', { config });
+
+export const nativeBandwidth = {
+  setPolicy: (policy: BandwidthPolicy) =>
+    invoke<BandwidthStatus>('bandwidth_set_policy', { policy }),
+  setMetered: (metered: boolean) => invoke<BandwidthStatus>('network_set_metered', { metered }),
+  status: () => invoke<BandwidthStatus>('bandwidth_status'),
+};
@@ -1,5 +1,6 @@
 local Dispatcher = require("dispatcher")
 local InfoMessage = require("ui/widget/infomessage")
+local MultiInputDialog = require("ui/widget/multiinputdialog")
 local KeyValuePage = require("ui/widget/keyvaluepage")
 local WidgetContainer = require("ui/widget/container/widgetcontainer")
 local NetworkMgr = require("ui/network/manager")
@@ -42,6 +43,7 @@ ReadestSync.default_settings = {
     last_sync_at = nil,
     localsend_enabled = false,
     localsend_alias = nil,
+    webdav = nil,           -- { url, username, password, root_path }
 }
 
 -- ── Lifecycle ──────────────────────────────────────────────────────
@@ -608,6 +610,23 @@ function ReadestSync:addToMainMenu(menu_items)
                     return self.localsend:statusText()
                 end,
                 enabled_func = function() return false end,
+            },
+            {
+                text = _("WebDAV server"),
+                keep_menu_open = true,
+                callback = function()
+                    self:configureWebDAV()
+                end,
+            },
+            {
+                text = _("Sync reading progress via WebDAV"),
+                enabled_func = function()
+                    return self.settings.webdav ~= nil and self.ui.document ~= nil
+                        and self.localsend.running
+                end,
+                callback = function()
+                    self:syncProgressWebDAV(true)
+                end,
                 separator = true,
             },
             {
@@ -806,6 +825,115 @@ function ReadestSync:pullBookConfig(interactive)
     )
 end
 
+-- ── WebDAV sync ────────────────────────────────────────────────────
+
+function ReadestSync:configureWebDAV()
+    local util = require("util")
+    local webdav = self.settings.webdav or {}
+    local dialog
+    dialog = MultiInputDialog:new{
+        title = _("WebDAV server"),
+        fields = {
+            {
+                text = webdav.url,
+                hint = "https://dav.example.com/remote.php/dav/files/me",
+            },
+            {
+                text = webdav.username,
+                hint = _("Username"),
+            },
+            {
+                text = webdav.password,
+                hint = _("Password"),
+                text_type = "password",
+            },
+            {
+                text = webdav.root_path,
+                hint = _("Folder (optional)"),
+            },
+        },
+        buttons = {
+            {
+                {
+                    text = _("Cancel"),
+                    id = "close",
+                    callback = function()
+                        UIManager:close(dialog)
+                    end,
+                },
+                {
+                    text = _("Save"),
+                    callback = function()
+                        local url, username, password, root_path = unpack(dialog:getFields())
+                        url = util.trim(url)
+                        -- An empty URL turns WebDAV sync off.
+                        if url == "" then
+                            self.settings.webdav = nil
+                        else
+                            self.settings.webdav = {
+                                url = url,
+                                username = username ~= "" and util.trim(username) or nil,
+                                password = password ~= "" and password or nil,
+                                root_path = util.trim(root_path),
+                            }
+                        end
+                        G_reader_settings:saveSetting("readest_sync", self.settings)
+                        UIManager:close(dialog)
+                    end,
+                },
+            },
+        },
+    }
+    UIManager:show(dialog)
+    dialog:onShowKeyboard()
+end
+
+-- Merges this book's position with the server's config.json through the
+-- LocalSend helper, then jumps to the merged position if it is ahead.
+function ReadestSync:syncProgressWebDAV(interactive)
+    local webdav = self.settings.webdav
+    if not webdav or not self.ui.document then return end
+    local config = SyncConfig:getCurrentBookConfig(self.ui)
+    if not config then return end
+
+    local doc_readest_sync = self.ui.doc_settings:readSetting("readest_sync") or {}
+    local envelope = SyncConfig:buildWebDAVEnvelope(
+        config,
+        doc_readest_sync.progress_updated_at or 0,
+        G_reader_settings:readSetting("device_id") or "koreader"
+    )
+    local connection = {
+        url = webdav.url,
+        username = webdav.username,
+        password = webdav.password,
+        rootPath = webdav.root_path or "",
+    }
+    local started = self.localsend:webdavSync(connection, envelope, function(ev)
+        if ev.status == "error" then
+            logger.warn("ReadestSync: WebDAV sync failed:", ev.error)
+            if interactive then
+                UIManager:show(InfoMessage:new{
+                    text = T(_("WebDAV sync failed: %1"), ev.error or ""),
+                    timeout = 3,
+                })
+            end
+            return
+        end
+        -- The reader may have moved on to another book by now.
+        if ev.config and ev.config.config and self.ui.document
+            and SyncConfig:getDocumentIdentifier(self.ui) == ev.bookHash then
+            SyncConfig:applyBookConfig(self.ui, ev.config.config)
+        end
+        if interactive then SyncConfig:showSyncedMessage() end
+    end)
+    if not started and interactive then
+        UIManager:show(InfoMessage:new{
+            text = _("Turn on Receive via LocalSend to sync over WebDAV."),
+            timeout = 3,
+        })
+    end
+end
+
 -- ── Reading statistics sync ────────────────────────────────────────
 
 function ReadestSync:pushBookStats(interactive)
@@ -1054,6 +1182,9 @@ function ReadestSync:pushOpenBook(interactive)
 end
 
 function ReadestSync:onCloseDocument()
+    if self.settings.webdav and self.localsend and self.localsend.running then
+        self:syncProgressWebDAV(false)
+    end
     if self.settings.auto_sync and self.settings.access_token then
         NetworkMgr:goOnlineToRun(function()
             self:pushBookConfig(false)
@@ -1073,6 +1204,9 @@ function ReadestSync:onReadestPullBooks()
 end
 
 function ReadestSync:onPageUpdate(page)
+    if page and self.ui.doc_settings then
+        SyncConfig:touchProgress(self.ui)
+    end
     if self.settings.auto_sync and self.settings.access_token and page then
         if self.delayed_push_task then
             UIManager:unschedule(self.delayed_push_task)
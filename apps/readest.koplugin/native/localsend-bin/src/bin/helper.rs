@@ -8,7 +8,7 @@
 use localsend_bin::{
     config::StartConfig,
     events::{self, Event},
-    service,
+    service, webdav,
 };
 use serde::Deserialize;
 use std::io::Write as _;
@@ -31,6 +31,16 @@ struct StartCmd {
     device_type: String,
     data_dir: String,
     download_dir: String,
+    #[serde(default)]
+    pin: Option<String>,
+    #[serde(default = "default_true")]
+    web_upload: bool,
+    #[serde(default)]
+    receive_only: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Every subsequent line on the control socket.
@@ -41,6 +51,11 @@ struct Cmd {
     session_id: Option<String>,
     fingerprint: Option<String>,
     paths: Option<Vec<String>>,
+    /// `send`: the receiver's PIN, after a `pin_required` send end.
+    pin: Option<String>,
+    /// `webdav_sync`: the server, and this device's envelope for the book.
+    connection: Option<webdav_sync::Connection>,
+    config: Option<webdav_sync::RemoteBookConfig>,
 }
 
 fn parse_control_port() -> Option<u16> {
@@ -118,6 +133,9 @@ async fn main() {
         device_type: start_cmd.device_type,
         data_dir: start_cmd.data_dir,
         download_dir: start_cmd.download_dir,
+        pin: start_cmd.pin,
+        web_upload: start_cmd.web_upload,
+        receive_only: start_cmd.receive_only,
     };
 
     events::clear();
@@ -221,7 +239,7 @@ fn dispatch(line: &str, svc: &service::Service) -> bool {
         }
         "send" => {
             if let (Some(fingerprint), Some(paths)) = (cmd.fingerprint, cmd.paths) {
-                if let Err(err) = service::start_send(svc, &fingerprint, paths) {
+                if let Err(err) = service::start_send(svc, &fingerprint, paths, cmd.pin) {
                     events::push(&Event::SendEnd {
                         session_id: None,
                         status: "error".into(),
@@ -232,6 +250,11 @@ fn dispatch(line: &str, svc: &service::Service) -> bool {
             }
         }
         "cancel_send" => service::cancel_send(svc),
+        "webdav_sync" => {
+            if let (Some(connection), Some(config)) = (cmd.connection, cmd.config) {
+                webdav::start_sync(connection, config);
+            }
+        }
         "stop" => return true,
         // Unknown command: ignored.
         _ => {}
@@ -3,6 +3,10 @@
 use localsend::model::discovery::DeviceType;
 use serde::Deserialize;
 
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StartConfig {
@@ -15,6 +19,17 @@ pub struct StartConfig {
     pub data_dir: String,
     /// Where accepted files land; staging lives in .localsend-inbox below it.
     pub download_dir: String,
+    /// Senders must enter this PIN; blank means none. The options below
+    /// mirror the Tauri app's `LocalSendOptions`.
+    #[serde(default)]
+    pub pin: Option<String>,
+    /// Serve the browser upload page. Off, the server requires a TLS client
+    /// certificate, which the stable LocalSend app does not present.
+    #[serde(default = "default_true")]
+    pub web_upload: bool,
+    /// Neither announce this device nor track peers, and refuse to send.
+    #[serde(default)]
+    pub receive_only: bool,
 }
 
 impl StartConfig {
@@ -22,6 +37,14 @@ impl StartConfig {
         serde_json::from_str(json).map_err(|e| e.to_string())
     }
 
+    pub fn pin(&self) -> Option<String> {
+        self.pin
+            .as_deref()
+            .map(str::trim)
+            .filter(|pin| !pin.is_empty())
+            .map(str::to_string)
+    }
+
     pub fn device_type(&self) -> DeviceType {
         match self.device_type.as_str() {
             "desktop" => DeviceType::Desktop,
@@ -44,6 +67,20 @@ mod tests {
         assert_eq!(cfg.device_model, "KOReader");
         assert_eq!(cfg.download_dir, "/tmp/dl");
         assert!(matches!(cfg.device_type(), DeviceType::Mobile));
+        assert_eq!(cfg.pin(), None);
+        assert!(cfg.web_upload);
+        assert!(!cfg.receive_only);
+    }
+
+    #[test]
+    fn parses_protection_options() {
+        let cfg = StartConfig::parse(
+            r#"{"alias":"a","deviceModel":"m","deviceType":"mobile","dataDir":"/d","downloadDir":"/l","pin":" 4321 ","webUpload":false,"receiveOnly":true}"#,
+        )
+        .unwrap();
+        assert_eq!(cfg.pin().as_deref(), Some("4321"));
+        assert!(!cfg.web_upload);
+        assert!(cfg.receive_only);
     }
 
     #[test]
@@ -50,11 +50,19 @@ pub enum Event {
     },
     SendEnd {
         session_id: Option<String>,
-        /// "sent" | "declined" | "cancelled" | "error"
+        /// "sent" | "declined" | "cancelled" | "pin_required" | "error"
         status: String,
         error: Option<String>,
         files_sent: usize,
     },
+    WebdavSyncEnd {
+        book_hash: String,
+        /// "created" | "updated" | "up_to_date" | "error"
+        status: String,
+        /// The merged progress and booknotes to apply locally.
+        config: Option<webdav_sync::RemoteBookConfig>,
+        error: Option<String>,
+    },
     Error {
         message: String,
     },
@@ -12,9 +12,12 @@ use std::path::Path;
 
 pub struct Identity {
     pub alias: String,
-    /// Shown as the device tag by other LocalSend clients — the OS name
-    /// ("macOS", "iOS", "Android", ...) resolved by the frontend. Not
-    /// persisted; supplied on every start.
+    /// Shown as the device tag by other LocalSend clients ("KOReader", passed
+    /// by Lua). Not persisted; supplied on every start. Advertised as is:
+    /// unlike the app, the helper leaves off the Readest marker
+    /// (`READEST_MODEL_SUFFIX` in
+    /// apps/readest-app/src-tauri/src/localsend/sidecar.rs), so Readest never
+    /// sends it book sidecars it would not import.
     pub device_model: String,
     pub device_type: DeviceType,
     pub cert_pem: String,
@@ -230,6 +233,7 @@ mod tests {
         );
         let dto = a.register_dto(53318);
         assert_eq!(dto.alias, "KO");
+        // No " (Readest)" marker: the helper takes no sidecars.
         assert_eq!(dto.device_model.as_deref(), Some("KOReader"));
         assert_eq!(dto.fingerprint, a.fingerprint);
         assert_eq!(dto.port, 53318);
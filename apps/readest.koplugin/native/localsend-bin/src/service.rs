@@ -89,6 +89,7 @@ pub struct Service {
     pub send_cancel: SendCancelSlot,
     pub multicast_error: Option<String>,
     pub download_dir: PathBuf,
+    pub receive_only: bool,
 }
 
 /// Does not push `Event::Started` itself: the caller (`run_worker` in
@@ -130,14 +131,15 @@ pub async fn start(config: StartConfig) -> Result<Service, String> {
             identity.client_info(),
             None,
             Some(ServerConfigV2 {
-                pin: None,
+                pin: config.pin(),
                 verify_checksums: true,
                 event_tx: server_tx.clone(),
             }),
             // Cert-less senders (the stable LocalSend app) fall back to the
             // body fingerprint; without this WebConfig the server demands a
-            // TLS client certificate and resets their handshake.
-            Some(WebConfig {
+            // TLS client certificate and resets their handshake, which is
+            // what turning the upload page off opts into.
+            config.web_upload.then(|| WebConfig {
                 send: None,
                 upload: true,
                 i18n: WebI18n::default(),
@@ -158,13 +160,14 @@ pub async fn start(config: StartConfig) -> Result<Service, String> {
 
     // Multicast failure is not fatal: Readest senders also probe 53317/53318
     // during their subnet scan, which reaches this server directly.
+    // Receive-only keeps off the LocalSend group (port 0) and stays silent.
     let (discovery_stop, discovery_stop_rx) = oneshot::channel::<()>();
     let discovery = Arc::new(
         localsend::discovery::start(
             DiscoveryConfig {
                 group: DEFAULT_MULTICAST_GROUP,
-                group_v6: Some(DEFAULT_MULTICAST_GROUP_V6),
-                port: DEFAULT_PORT,
+                group_v6: (!config.receive_only).then_some(DEFAULT_MULTICAST_GROUP_V6),
+                port: if config.receive_only { 0 } else { DEFAULT_PORT },
                 interface_filter: InterfaceFilter::default(),
                 device: identity.multicast_device(port),
                 identity: identity.device_identity(),
@@ -178,8 +181,12 @@ pub async fn start(config: StartConfig) -> Result<Service, String> {
         )
         .await,
     );
-    let multicast_error = discovery.multicast_error().map(|e| format!("{e:#}"));
-    {
+    let multicast_error = if config.receive_only {
+        None
+    } else {
+        discovery.multicast_error().map(|e| format!("{e:#}"))
+    };
+    if !config.receive_only {
         // Announce this device; peers answer with an HTTP register request
         // that the crate's server responds to on its own.
         let discovery = discovery.clone();
@@ -199,6 +206,7 @@ pub async fn start(config: StartConfig) -> Result<Service, String> {
         send_cancel: Arc::new(Mutex::new(None)),
         multicast_error,
         download_dir,
+        receive_only: config.receive_only,
     };
     spawn_event_pump(&service, server_rx);
     Ok(service)
@@ -312,6 +320,7 @@ fn spawn_event_pump(service: &Service, mut server_rx: mpsc::Receiver<ServerEvent
     let download_dir = service.download_dir.clone();
     let discovery = service.discovery.clone();
     let self_fingerprint = service.identity.fingerprint.clone();
+    let receive_only = service.receive_only;
     tokio::spawn(async move {
         while let Some(event) = server_rx.recv().await {
             // Register is handled here, in the async pump, so it can await
@@ -322,6 +331,10 @@ fn spawn_event_pump(service: &Service, mut server_rx: mpsc::Receiver<ServerEvent
             // (e.g. macOS) ever appear; iOS (no multicast entitlement) and
             // Android answer over HTTP and would stay invisible.
             if let ServerEventV2::Register { ip, info } = event {
+                // Receive-only tracks no peers.
+                if receive_only {
+                    continue;
+                }
                 let host = match ip.scope_id {
                     Some(scope_id) => format!("{}%{scope_id}", ip.ip),
                     None => ip.ip.to_string(),
@@ -466,17 +479,24 @@ fn handle_file_upload(
     // directory components in it; sanitize before it ever touches a path so
     // a traversal payload (or an absolute path, which would make `join`
     // discard `staging`/`download_dir` entirely) cannot escape either dir.
-    let file_name = safe_file_name(&file.file_name);
+    // Folder sends keep their tree below `download_dir`; staging stays flat.
+    let relative = safe_relative_path(&file.file_name);
+    let file_name = relative.to_string_lossy().into_owned();
+    let leaf = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
 
     let staging = download_dir.join(STAGING_DIR);
     let _ = std::fs::create_dir_all(&staging);
-    let staging_path = unique_path(&staging, &file_name);
+    let staging_path = unique_path(&staging, &leaf);
 
     let (result_tx, result_rx) = oneshot::channel::<Result<(), String>>();
     {
         let receiving = receiving.clone();
         let download_dir = download_dir.to_path_buf();
         let file_name = file_name.clone();
+        let final_dir = download_dir.join(relative.parent().unwrap_or(Path::new("")));
         let staging_path = staging_path.clone();
         tokio::spawn(async move {
             let result = match result_rx.await {
@@ -490,7 +510,8 @@ fn handle_file_upload(
             };
             session.in_progress.remove(&file_id);
             let moved = result.and_then(|()| {
-                let final_path = unique_path(&download_dir, &file_name);
+                std::fs::create_dir_all(&final_dir).map_err(|e| e.to_string())?;
+                let final_path = unique_path(&final_dir, &leaf);
                 std::fs::rename(&staging_path, &final_path)
                     .map(|()| final_path)
                     .map_err(|e| e.to_string())
@@ -635,8 +656,17 @@ fn resolve_send_target(
 /// `localsend_send_files`
 /// (apps/readest-app/src-tauri/src/localsend/commands.rs): guard against a
 /// transfer already in progress, resolve the device, build the file jobs,
-/// install the cancellation state, then spawn `run_send`.
-pub fn start_send(service: &Service, fingerprint: &str, paths: Vec<String>) -> Result<(), String> {
+/// install the cancellation state, then spawn `run_send`. `pin` answers a
+/// receiver that ended the previous attempt with `pin_required`.
+pub fn start_send(
+    service: &Service,
+    fingerprint: &str,
+    paths: Vec<String>,
+    pin: Option<String>,
+) -> Result<(), String> {
+    if service.receive_only {
+        return Err("LocalSend is in receive-only mode".into());
+    }
     let device = resolve_send_target(&service.discovery, &service.send_cancel, fingerprint)?;
     let jobs = build_send_jobs(&paths)?;
     *lock(&service.send_cancel) = Some(SendCancel {
@@ -650,6 +680,7 @@ pub fn start_send(service: &Service, fingerprint: &str, paths: Vec<String>) -> R
         service.port,
         device,
         jobs,
+        pin.filter(|pin| !pin.is_empty()),
         service.send_cancel.clone(),
     ));
     Ok(())
@@ -674,6 +705,7 @@ pub async fn run_send(
     port: u16,
     device: localsend::discovery::StatefulDevice,
     jobs: Vec<SendFileJob>,
+    pin: Option<String>,
     cancel_slot: SendCancelSlot,
 ) {
     use futures_util::StreamExt;
@@ -728,6 +760,7 @@ pub async fn run_send(
         info: identity.register_dto(port),
         files: files.clone(),
     };
+    let wrong_pin = pin.is_some();
     let prepared = match client
         .prepare_upload(
             protocol,
@@ -735,7 +768,7 @@ pub async fn run_send(
             peer_port,
             None,
             payload,
-            None,
+            pin,
             token.clone(),
         )
         .await
@@ -744,10 +777,9 @@ pub async fn run_send(
         Err(ClientError::Cancelled) => return end(None, "cancelled", None, 0),
         Err(ClientError::StatusCode(err)) => {
             let (status, message) = match err.status {
-                401 => (
-                    "error",
-                    "PIN protected receivers are not supported yet".to_string(),
-                ),
+                // The plugin asks for the PIN and sends again.
+                401 if wrong_pin => ("pin_required", "wrong PIN".to_string()),
+                401 => ("pin_required", String::new()),
                 403 => ("declined", String::new()),
                 409 => ("error", "busy with another transfer".to_string()),
                 429 => ("error", "too many requests".to_string()),
@@ -854,31 +886,33 @@ pub async fn run_send(
     end(Some(response.session_id), "sent", None, sent_files);
 }
 
-/// Sanitizes a peer-supplied file name before it is ever joined onto a path.
-/// Mirrors `localsend::util::filename::sanitize_path` (take the last path
-/// segment, drop `.`/`..`/empty segments, sanitize under the strictest
-/// `Rules::Universal` set) but with an empty placeholder so a name that
-/// collapses to nothing (`".."`, all separators, empty) is detectable here
-/// and mapped to a fixed fallback name instead of the crate's own
-/// "untitled" placeholder.
-fn safe_file_name(name: &str) -> String {
-    let last = name
-        .rsplit(['/', '\\'])
-        .find(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
-        .unwrap_or("");
-    let sanitized = sanitize_with(
-        last,
-        Rules::Universal,
-        &Options {
-            replacement: "_",
-            placeholder: "",
-        },
-    );
-    if sanitized.is_empty() {
-        "received.bin".to_string()
-    } else {
-        sanitized
+/// Sanitizes a peer-supplied file name before it is ever joined onto a path,
+/// keeping its folders. Mirrors `safe_relative_path` in
+/// apps/readest-app/src-tauri/src/localsend/service.rs: every segment is
+/// sanitized under the strictest `Rules::Universal` set with an empty
+/// placeholder, and `.`/`..`/empty segments are dropped, so the result is
+/// always relative and never climbs. A name that collapses to nothing
+/// (`".."`, all separators, empty) maps to a fixed fallback name instead of
+/// the crate's own "untitled" placeholder.
+fn safe_relative_path(name: &str) -> PathBuf {
+    let mut path: PathBuf = name
+        .split(['/', '\\'])
+        .map(|segment| {
+            sanitize_with(
+                segment,
+                Rules::Universal,
+                &Options {
+                    replacement: "_",
+                    placeholder: "",
+                },
+            )
+        })
+        .filter(|segment| !segment.is_empty() && segment != "." && segment != "..")
+        .collect();
+    if path.as_os_str().is_empty() {
+        path.push("received.bin");
     }
+    path
 }
 
 /// "name.epub" -> "name (2).epub" until unused, like the upstream CLI.
@@ -918,21 +952,31 @@ mod tests {
     }
 
     #[test]
-    fn safe_file_name_flattens_traversal_to_last_segment() {
-        assert_eq!(safe_file_name("../../../../etc/init.d/rcS"), "rcS");
-        assert_eq!(safe_file_name("../../etc/passwd"), "passwd");
+    fn safe_relative_path_keeps_folders_but_drops_traversal() {
+        assert_eq!(
+            safe_relative_path("../../../../etc/init.d/rcS"),
+            Path::new("etc/init.d/rcS")
+        );
+        assert_eq!(
+            safe_relative_path("Dune Saga/./01 Dune.epub"),
+            Path::new("Dune Saga/01 Dune.epub")
+        );
     }
 
     #[test]
-    fn safe_file_name_drops_absolute_root() {
-        assert_eq!(safe_file_name("/etc/passwd"), "passwd");
+    fn safe_relative_path_drops_absolute_root() {
+        assert_eq!(safe_relative_path("/etc/passwd"), Path::new("etc/passwd"));
+        assert_eq!(
+            safe_relative_path("\\\\server\\share\\a.epub"),
+            Path::new("server/share/a.epub")
+        );
     }
 
     #[test]
-    fn safe_file_name_falls_back_when_nothing_safe_survives() {
-        assert_eq!(safe_file_name(""), "received.bin");
-        assert_eq!(safe_file_name(".."), "received.bin");
-        assert_eq!(safe_file_name("///"), "received.bin");
+    fn safe_relative_path_falls_back_when_nothing_safe_survives() {
+        assert_eq!(safe_relative_path(""), Path::new("received.bin"));
+        assert_eq!(safe_relative_path(".."), Path::new("received.bin"));
+        assert_eq!(safe_relative_path("///"), Path::new("received.bin"));
     }
 
     #[test]
@@ -0,0 +1,55 @@
+//! WebDAV sync for KOReader over the shared `webdav-sync` crate
+//! (packages/webdav-sync): the same remote layout and merge rules as
+//! `webdav_sync_book_config` in apps/readest-app/src-tauri/src/webdav.rs,
+//! so a book read on both sides converges on one `config.json`.
+
+use std::time::Duration;
+
+use webdav_sync::{Connection, RemoteBookConfig, SyncStatus, WebDavClient};
+
+use crate::events::{self, Event};
+
+/// Generous: e-reader Wi-Fi wakes up slowly.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+async fn sync(
+    connection: &Connection,
+    config: &RemoteBookConfig,
+) -> Result<webdav_sync::SyncOutcome, String> {
+    let http = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let client = WebDavClient::new(http, connection).map_err(|e| e.to_string())?;
+    webdav_sync::sync_book_config(&client, &connection.root_path, config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Syncs one book's progress and booknotes in the background; the outcome
+/// is queued as `Event::WebdavSyncEnd`.
+pub fn start_sync(connection: Connection, config: RemoteBookConfig) {
+    tokio::spawn(async move {
+        let book_hash = config.book_hash.clone();
+        let event = match sync(&connection, &config).await {
+            Ok(outcome) => Event::WebdavSyncEnd {
+                book_hash,
+                status: match outcome.status {
+                    SyncStatus::Created => "created",
+                    SyncStatus::Updated => "updated",
+                    SyncStatus::UpToDate => "up_to_date",
+                }
+                .into(),
+                config: Some(outcome.config),
+                error: None,
+            },
+            Err(err) => Event::WebdavSyncEnd {
+                book_hash,
+                status: "error".into(),
+                config: None,
+                error: Some(err),
+            },
+        };
+        events::push(&event);
+    });
+}
@@ -46,6 +46,7 @@ local LocalSend = {
     scanning_msg = nil,     -- "Scanning…" InfoMessage handle, or nil
     device_dialog = nil,    -- open device-picker ButtonDialog, or nil
     send_progress_msg = nil,-- "Sending…" InfoMessage handle, or nil
+    webdav_callbacks = {},  -- bookHash -> callback for its webdav_sync_end
 }
 
 function LocalSend:init(plugin)
@@ -139,6 +140,10 @@ function LocalSend:startService()
         -- Outside the plugin dir so self-update keeps the identity peers pin.
         dataDir = DataStorage:getSettingsDir() .. "/readest-localsend",
         downloadDir = dir,
+        -- Optional protection, mirrored from the app's LocalSend options.
+        pin = self.plugin.settings.localsend_pin,
+        webUpload = self.plugin.settings.localsend_web_upload ~= false,
+        receiveOnly = self.plugin.settings.localsend_receive_only == true,
     })
     self.running = true
     self:schedulePoll()
@@ -395,12 +400,47 @@ function LocalSend:showDevicePicker()
     UIManager:show(self.device_dialog)
 end
 
-function LocalSend:sendTo(fingerprint, path)
-    Helper.send(self.sock, { cmd = "send", fingerprint = fingerprint, paths = { path } })
+-- `pin` answers a receiver that ended the previous attempt with pin_required.
+function LocalSend:sendTo(fingerprint, path, pin)
+    self.last_send = { fingerprint = fingerprint, path = path }
+    Helper.send(self.sock, {
+        cmd = "send", fingerprint = fingerprint, paths = { path }, pin = pin,
+    })
     self.send_progress_msg = InfoMessage:new{ text = _("Sending…") }
     UIManager:show(self.send_progress_msg)
 end
 
+function LocalSend:askSendPin(wrong_pin)
+    local target = self.last_send
+    if not target then return end
+    local InputDialog = require("ui/widget/inputdialog")
+    local dialog
+    dialog = InputDialog:new{
+        title = wrong_pin and _("Wrong PIN, try again") or _("The other device requires a PIN"),
+        text_type = "password",
+        buttons = { {
+            {
+                text = _("Cancel"),
+                id = "close",
+                callback = function() UIManager:close(dialog) end,
+            },
+            {
+                text = _("Send"),
+                is_enter_default = true,
+                callback = function()
+                    local pin = dialog:getInputText() or ""
+                    UIManager:close(dialog)
+                    if pin ~= "" then
+                        self:sendTo(target.fingerprint, target.path, pin)
+                    end
+                end,
+            },
+        } },
+    }
+    UIManager:show(dialog)
+    dialog:onShowKeyboard()
+end
+
 function LocalSend:onSendProgress(ev)
     if not self.send_progress_msg then return end
     local text = _("Sending…")
@@ -419,6 +459,10 @@ function LocalSend:onSendEnd(ev)
         UIManager:close(self.send_progress_msg)
         self.send_progress_msg = nil
     end
+    if ev.status == "pin_required" then
+        self:askSendPin(ev.error ~= nil)
+        return
+    end
     local text
     if ev.status == "sent" then
         text = T(_("Sent %1 file(s)."), ev.filesSent or 0)
@@ -432,6 +476,16 @@ function LocalSend:onSendEnd(ev)
     UIManager:show(InfoMessage:new{ text = text, timeout = 4 })
 end
 
+-- WebDAV sync (packages/webdav-sync) runs in the helper too, so it needs
+-- the service running. `on_done` gets the book's webdav_sync_end event;
+-- returns false when there is no helper to ask.
+function LocalSend:webdavSync(connection, config, on_done)
+    if not (self.running and self.sock) then return false end
+    self.webdav_callbacks[config.bookHash] = on_done
+    Helper.send(self.sock, { cmd = "webdav_sync", connection = connection, config = config })
+    return true
+end
+
 LocalSend.handlers = {
     started = function(self, ev)
         logger.info("ReadestLocalSend: started on port " .. tostring(ev.port))
@@ -461,6 +515,11 @@ LocalSend.handlers = {
     devices = function(self, ev) self.device_list = ev.devices or {} end,
     send_progress = function(self, ev) self:onSendProgress(ev) end,
     send_end = function(self, ev) self:onSendEnd(ev) end,
+    webdav_sync_end = function(self, ev)
+        local on_done = self.webdav_callbacks[ev.bookHash]
+        self.webdav_callbacks[ev.bookHash] = nil
+        if on_done then on_done(ev) end
+    end,
     -- An error event means the helper failed (e.g. a bind error at start);
     -- there's no separate "is it actually still running" status to check
     -- (the FFI-era stale-error guard doesn't apply: this event only arrives
@@ -178,6 +178,10 @@ function SyncConfig:applyBookConfig(ui, config)
     local progress = config.progress
     local has_pages = ui.document.info.has_pages
     local progress_pattern = "^%[(%d+),(%d+)%]$"
+    -- The WebDAV envelope carries progress as a JSON array, not a string.
+    if type(progress) == "table" and progress[1] and progress[2] then
+        progress = string.format("[%d,%d]", progress[1], progress[2])
+    end
     if has_pages and progress then
         local page, _total_pages = progress:match(progress_pattern)
         local current_page = ui:getCurrentPage()
@@ -209,6 +213,37 @@ function SyncConfig:applyBookConfig(ui, config)
     end
 end
 
+-- Stamps when the position last changed, the `config.updatedAt` of the
+-- WebDAV envelope below.
+function SyncConfig:touchProgress(ui)
+    local doc_readest_sync = ui.doc_settings:readSetting("readest_sync") or {}
+    doc_readest_sync.progress_updated_at = os.time() * 1000
+    ui.doc_settings:saveSetting("readest_sync", doc_readest_sync)
+end
+
+-- The book's config.json envelope for the WebDAV backend
+-- (packages/webdav-sync, run by the LocalSend helper). Only the position
+-- goes out: booknotes are left off, and since the merge unions notes the
+-- server keeps its own. `config.updatedAt` is when the position last moved
+-- here rather than now, so a newer position from another device wins.
+function SyncConfig:buildWebDAVEnvelope(config, progress_updated_at, device_id)
+    local xpointer = config.xpointer
+    if xpointer == "" then xpointer = nil end
+    return {
+        schemaVersion = 1,
+        bookHash = config.bookHash,
+        metaHash = config.metaHash,
+        config = {
+            progress = config.progress,
+            xpointer = xpointer,
+            updatedAt = progress_updated_at,
+        },
+        writerDeviceId = device_id,
+        writerVersion = "readest-webdav-1",
+        updatedAt = config.updatedAt,
+    }
+end
+
 function SyncConfig:showSyncedMessage()
     UIManager:show(InfoMessage:new{
         text = _("Progress has been synchronized."),
@@ -159,11 +159,28 @@ describe("readest_localsend dispatch", function()
     it("exposes handlers for every event the helper protocol emits", function()
         for _, t in ipairs({ "started", "status", "receive_request", "receive_request_closed",
                              "receive_file_done", "receive_end", "error",
-                             "devices", "send_progress", "send_end" }) do
+                             "devices", "send_progress", "send_end", "webdav_sync_end" }) do
             assert.is_function(LocalSend.handlers[t], t)
         end
     end)
 
+    it("hands each webdav_sync_end to the callback of its book, once", function()
+        local got
+        LocalSend.webdav_callbacks.b1 = function(ev) got = ev end
+        LocalSend.handlers.webdav_sync_end(LocalSend, { bookHash = "b2", status = "created" })
+        assert.is_nil(got)
+        LocalSend.handlers.webdav_sync_end(LocalSend, { bookHash = "b1", status = "updated" })
+        assert.equals("updated", got.status)
+        assert.is_nil(LocalSend.webdav_callbacks.b1)
+    end)
+
+    it("does not ask for a WebDAV sync without a running helper", function()
+        local started = LocalSend:webdavSync({ url = "https://dav" }, { bookHash = "b1" },
+            function() end)
+        assert.is_false(started)
+        assert.is_nil(LocalSend.webdav_callbacks.b1)
+    end)
+
     it("error and started handlers run without shadowing the i18n function", function()
         -- Real handlers (not the dispatch test's temporary overrides). A
         -- first parameter named `_` would shadow the module-level i18n `_`
@@ -169,3 +169,30 @@ describe("SyncConfig:getMetaHash", function()
         assert.are.equal(sha2.md5("Dune||"), SyncConfig:getMetaHash(ui, store))
     end)
 end)
+
+describe("SyncConfig WebDAV envelope", function()
+    it("stamps config.updatedAt with when the position last moved", function()
+        local env = SyncConfig:buildWebDAVEnvelope({
+            bookHash = "b1",
+            metaHash = "m1",
+            progress = { 12, 300 },
+            xpointer = "",
+            updatedAt = 9000,
+        }, 4000, "kindle")
+        assert.are.equal(1, env.schemaVersion)
+        assert.are.equal("b1", env.bookHash)
+        assert.are.equal(4000, env.config.updatedAt)
+        assert.are.same({ 12, 300 }, env.config.progress)
+        assert.is_nil(env.config.xpointer)
+        assert.is_nil(env.booknotes)
+        assert.are.equal("kindle", env.writerDeviceId)
+        assert.are.equal(9000, env.updatedAt)
+    end)
+
+    it("records the page turn time in the book's settings", function()
+        local ui = fakeUI({ readest_sync = { meta_hash_v1 = "m1" } })
+        SyncConfig:touchProgress(ui)
+        assert.is_true(ui._values.readest_sync.progress_updated_at > 0)
+        assert.are.equal("m1", ui._values.readest_sync.meta_hash_v1)
+    end)
+end)
@@ -0,0 +1,382 @@
+//! A small WebDAV client: the handful of RFC 4918 methods the sync needs.
+//!
+//! Paths are absolute within the server URL (`/Readest/books/<hash>`), as
+//! [`crate::layout`] builds them; each segment is percent-encoded here.
+
+use percent_encoding::percent_decode_str;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{HeaderValue, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Body, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::ancestors_of;
+use crate::{Error, Result};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getetag/><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// Where and as whom to sync; the same fields the app's WebDAV settings hold.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    /// The server URL, e.g. `https://cloud.example/remote.php/dav/files/me`.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The folder under `url` that holds the `Readest` tree.
+    #[serde(default)]
+    pub root_path: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Depth {
+    /// The resource itself.
+    Zero,
+    /// The resource and its direct members.
+    One,
+}
+
+/// The condition a PUT is made on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Precondition {
+    /// Overwrite whatever is there.
+    None,
+    /// Only create; fail if the path already exists.
+    Absent,
+    /// Only replace the version with this ETag.
+    Matches(String),
+}
+
+/// One entry of a PROPFIND listing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    /// Decoded, absolute within the server URL, without a trailing slash.
+    pub path: String,
+    pub is_collection: bool,
+    pub etag: Option<String>,
+    pub content_length: Option<u64>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct WebDavClient {
+    http: reqwest::Client,
+    base: Url,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl WebDavClient {
+    /// `http` carries the caller's TLS, proxy and timeout settings.
+    pub fn new(http: reqwest::Client, connection: &Connection) -> Result<Self> {
+        let base = Url::parse(connection.url.trim()).map_err(|e| Error::Url(e.to_string()))?;
+        if base.cannot_be_a_base() || !matches!(base.scheme(), "http" | "https") {
+            return Err(Error::Url(connection.url.clone()));
+        }
+        Ok(Self {
+            http,
+            base,
+            username: connection.username.clone().filter(|u| !u.is_empty()),
+            password: connection.password.clone(),
+        })
+    }
+
+    /// The URL of `path`; collections get a trailing slash.
+    fn url(&self, path: &str, collection: bool) -> Url {
+        let mut url = self.base.clone();
+        {
+            // `base` was checked to be a base URL in `new`.
+            let mut segments = url.path_segments_mut().expect("base URL");
+            segments.pop_if_empty();
+            segments.extend(path.split('/').filter(|s| !s.is_empty()));
+            if collection {
+                segments.push("");
+            }
+        }
+        url
+    }
+
+    /// The base URL's path, decoded and without a trailing slash.
+    fn base_path(&self) -> String {
+        decode(self.base.path()).trim_end_matches('/').to_string()
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_deref()),
+            None => request,
+        }
+    }
+
+    fn error(method: &Method, path: &str, response: &Response) -> Error {
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized,
+            status => Error::Status {
+                method: method.to_string(),
+                path: path.to_string(),
+                status: status.as_u16(),
+            },
+        }
+    }
+
+    /// List `path` (and its members for [`Depth::One`]). Empty when `path`
+    /// does not exist.
+    pub async fn propfind(&self, path: &str, depth: Depth) -> Result<Vec<Resource>> {
+        let method = Method::from_bytes(b"PROPFIND").expect("valid method");
+        let response = self
+            .request(method.clone(), self.url(path, false))
+            .header(
+                "Depth",
+                match depth {
+                    Depth::Zero => "0",
+                    Depth::One => "1",
+                },
+            )
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Err(Self::error(&method, path, &response));
+        }
+        let body = response.bytes().await?;
+        let base = self.base_path();
+        Ok(parse_multistatus(&body)?
+            .into_iter()
+            .filter_map(|(href, mut resource)| {
+                let url = self.base.join(&href).ok()?;
+                let full = decode(url.path());
+                let relative = full.strip_prefix(&base)?.trim_end_matches('/');
+                resource.path = format!("/{}", relative.trim_start_matches('/'));
+                Some(resource)
+            })
+            .collect())
+    }
+
+    /// The body and ETag of `path`, or `None` when it does not exist.
+    pub async fn get(&self, path: &str) -> Result<Option<(Vec<u8>, Option<String>)>> {
+        let response = self
+            .request(Method::GET, self.url(path, false))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(Self::error(&Method::GET, path, &response));
+        }
+        let etag = header_str(response.headers().get(ETAG));
+        Ok(Some((response.bytes().await?.to_vec(), etag)))
+    }
+
+    /// Upload `body` to `path`. `Ok(false)` when the precondition failed
+    /// (412): someone else wrote first, or the file already exists.
+    pub async fn put(
+        &self,
+        path: &str,
+        body: impl Into<Body>,
+        content_type: &str,
+        precondition: Precondition,
+    ) -> Result<bool> {
+        let mut request = self
+            .request(Method::PUT, self.url(path, false))
+            .header("Content-Type", content_type)
+            .body(body);
+        request = match precondition {
+            Precondition::None => request,
+            Precondition::Absent => request.header(IF_NONE_MATCH, "*"),
+            Precondition::Matches(etag) => request.header(IF_MATCH, etag),
+        };
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(false),
+            status if status.is_success() => Ok(true),
+            _ => Err(Self::error(&Method::PUT, path, &response)),
+        }
+    }
+
+    /// Create the collection `path`. An existing one (405) is fine.
+    pub async fn mkcol(&self, path: &str) -> Result<()> {
+        let method = Method::from_bytes(b"MKCOL").expect("valid method");
+        let response = self
+            .request(method.clone(), self.url(path, true))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            status if status.is_success() => Ok(()),
+            _ => Err(Self::error(&method, path, &response)),
+        }
+    }
+
+    /// Create `path` and any missing parents, top-down.
+    pub async fn mkcol_all(&self, path: &str) -> Result<()> {
+        for ancestor in ancestors_of(path) {
+            self.mkcol(&ancestor).await?;
+        }
+        self.mkcol(path).await
+    }
+
+    /// Delete `path`; `Ok(false)` when it was already gone.
+    pub async fn delete(&self, path: &str) -> Result<bool> {
+        let response = self
+            .request(Method::DELETE, self.url(path, false))
+            .send()
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            _ => Err(Self::error(&Method::DELETE, path, &response)),
+        }
+    }
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+fn header_str(value: Option<&HeaderValue>) -> Option<String> {
+    value
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .filter(|v| !v.is_empty())
+}
+
+/// The `(href, properties)` pairs of a multistatus body. Only properties
+/// from a `200` propstat are kept.
+fn parse_multistatus(body: &[u8]) -> Result<Vec<(String, Resource)>> {
+    let mut reader = Reader::from_reader(body);
+    reader.config_mut().trim_text(true);
+
+    let mut out = Vec::new();
+    let mut buf = Vec::new();
+    let mut text = String::new();
+    let mut href = None;
+    let mut resource = Resource::default();
+    let mut pending = Resource::default();
+    let mut propstat_ok = false;
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| Error::Xml(e.to_string()))?;
+        match event {
+            Event::Start(e) => {
+                text.clear();
+                if e.local_name().as_ref() == b"collection" {
+                    pending.is_collection = true;
+                }
+            }
+            Event::Empty(e) if e.local_name().as_ref() == b"collection" => {
+                pending.is_collection = true;
+            }
+            Event::Text(t) => {
+                let t = t.unescape().map_err(|e| Error::Xml(e.to_string()))?;
+                text.push_str(&t);
+            }
+            Event::CData(t) => text.push_str(&String::from_utf8_lossy(&t)),
+            Event::End(e) => {
+                let value = std::mem::take(&mut text);
+                match e.local_name().as_ref() {
+                    b"href" if href.is_none() => href = Some(value),
+                    b"getetag" => pending.etag = Some(value).filter(|v| !v.is_empty()),
+                    b"getcontentlength" => pending.content_length = value.trim().parse().ok(),
+                    b"getlastmodified" => pending.last_modified = Some(value),
+                    b"status" => propstat_ok = value.split_whitespace().nth(1) == Some("200"),
+                    b"propstat" => {
+                        let props = std::mem::take(&mut pending);
+                        if std::mem::take(&mut propstat_ok) {
+                            resource = Resource {
+                                path: String::new(),
+                                is_collection: resource.is_collection || props.is_collection,
+                                etag: props.etag.or(resource.etag),
+                                content_length: props.content_length.or(resource.content_length),
+                                last_modified: props.last_modified.or(resource.last_modified),
+                            };
+                        }
+                    }
+                    b"response" => {
+                        let resource = std::mem::take(&mut resource);
+                        if let Some(href) = href.take() {
+                            out.push((href, resource));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_multistatus, Connection, WebDavClient};
+
+    #[test]
+    fn parses_a_multistatus_listing() {
+        let body = br#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/dav/Readest/books/abc/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:getcontentlength/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/dav/Readest/books/abc/Dune%20Messiah.epub</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype/>
+        <d:getetag>"5-abc"</d:getetag>
+        <d:getcontentlength>1024</d:getcontentlength>
+        <d:getlastmodified>Sat, 17 Oct 2026 10:00:00 GMT</d:getlastmodified>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let entries = parse_multistatus(body).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, "/dav/Readest/books/abc/");
+        assert!(entries[0].1.is_collection);
+        assert_eq!(entries[0].1.content_length, None);
+        let file = &entries[1].1;
+        assert!(!file.is_collection);
+        assert_eq!(file.etag.as_deref(), Some("\"5-abc\""));
+        assert_eq!(file.content_length, Some(1024));
+    }
+
+    #[test]
+    fn encodes_path_segments_under_the_base_url() {
+        let connection = Connection {
+            url: "https://cloud.example/remote.php/dav/files/me/".into(),
+            ..Default::default()
+        };
+        let client = WebDavClient::new(reqwest::Client::new(), &connection).unwrap();
+        assert_eq!(
+            client
+                .url("/Readest/books/abc/Dune #2.epub", false)
+                .as_str(),
+            "https://cloud.example/remote.php/dav/files/me/Readest/books/abc/Dune%20%232.epub"
+        );
+        assert_eq!(
+            client.url("/Readest/books", true).as_str(),
+            "https://cloud.example/remote.php/dav/files/me/Readest/books/"
+        );
+        assert_eq!(client.base_path(), "/remote.php/dav/files/me");
+    }
+}
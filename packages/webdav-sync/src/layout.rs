@@ -0,0 +1,92 @@
+//! The remote tree under the user's root path. Mirrors
+//! `src/services/sync/file/layout.ts`; the names are a FROZEN wire layout
+//! shared with the app's TypeScript engine, so they must stay byte-stable.
+
+pub const SYNC_BASE_DIR: &str = "Readest";
+pub const SYNC_BOOKS_DIR: &str = "books";
+pub const SYNC_LIBRARY_FILE: &str = "library.json";
+pub const SYNC_BOOK_CONFIG_FILE: &str = "config.json";
+pub const SYNC_BOOK_COVER_FILE: &str = "cover.png";
+
+/// A leading slash and no trailing slash; an empty root is `/`.
+pub fn normalize_root(root_path: &str) -> String {
+    let root = root_path.trim().trim_end_matches('/');
+    if root.is_empty() {
+        "/".to_string()
+    } else if root.starts_with('/') {
+        root.to_string()
+    } else {
+        format!("/{root}")
+    }
+}
+
+/// Join segments with single slashes, keeping a leading slash.
+fn join(parts: &[&str]) -> String {
+    let cleaned = parts
+        .iter()
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+    format!("/{}", cleaned.join("/"))
+}
+
+/// The Readest base directory, where `library.json` lives.
+pub fn base_path(root_path: &str) -> String {
+    join(&[&normalize_root(root_path), SYNC_BASE_DIR])
+}
+
+pub fn library_path(root_path: &str) -> String {
+    join(&[&base_path(root_path), SYNC_LIBRARY_FILE])
+}
+
+/// The per-book directory, keyed by the book hash.
+pub fn book_dir_path(root_path: &str, book_hash: &str) -> String {
+    join(&[&base_path(root_path), SYNC_BOOKS_DIR, book_hash])
+}
+
+/// A file inside the book directory: the book itself, its cover or config.
+pub fn book_file_path(root_path: &str, book_hash: &str, file_name: &str) -> String {
+    join(&[&book_dir_path(root_path, book_hash), file_name])
+}
+
+pub fn book_config_path(root_path: &str, book_hash: &str) -> String {
+    book_file_path(root_path, book_hash, SYNC_BOOK_CONFIG_FILE)
+}
+
+pub fn book_cover_path(root_path: &str, book_hash: &str) -> String {
+    book_file_path(root_path, book_hash, SYNC_BOOK_COVER_FILE)
+}
+
+/// The parents of an absolute path, top-down, excluding the path itself:
+/// `/a/b/c/file.json` → `/a`, `/a/b`, `/a/b/c`.
+pub fn ancestors_of(path: &str) -> Vec<String> {
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    (1..segments.len())
+        .map(|n| format!("/{}", segments[..n].join("/")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_typescript_layout() {
+        assert_eq!(normalize_root(""), "/");
+        assert_eq!(normalize_root(" dav/ "), "/dav");
+        assert_eq!(library_path("/"), "/Readest/library.json");
+        assert_eq!(
+            book_config_path("/sync/", "abc"),
+            "/sync/Readest/books/abc/config.json"
+        );
+        assert_eq!(
+            book_file_path("sync", "abc", "Dune.epub"),
+            "/sync/Readest/books/abc/Dune.epub"
+        );
+        assert_eq!(ancestors_of("/a/b/c/file.json"), ["/a", "/a/b", "/a/b/c"]);
+        assert!(ancestors_of("/file.json").is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+//! WebDAV sync backend shared by the Readest app (`src-tauri/src/webdav.rs`)
+//! and the KOReader helper (`apps/readest.koplugin/native/localsend-bin`).
+//!
+//! It speaks the same remote tree as the TypeScript file-sync engine
+//! (`src/services/sync/file`), so a library synced from either side reads
+//! the same on a self-hosted server:
+//!
+//! ```text
+//! <root>/Readest/books/<hash>/<title>.<ext>   the book file
+//! <root>/Readest/books/<hash>/cover.png       optional
+//! <root>/Readest/books/<hash>/config.json     progress + booknotes
+//! ```
+//!
+//! - [`client`]: PROPFIND, GET, conditional PUT (ETags), MKCOL, DELETE.
+//! - [`layout`]: the frozen path layout above.
+//! - [`merge`]: the `config.json` envelope and its timestamp merge rules.
+//! - [`sync`]: read-merge-write of a book's config, retried on lost races.
+
+pub mod client;
+pub mod layout;
+pub mod merge;
+pub mod sync;
+
+pub use client::{Connection, Depth, Precondition, Resource, WebDavClient};
+pub use merge::RemoteBookConfig;
+pub use sync::{sync_book_config, upload_book_file, SyncOutcome, SyncStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("invalid WebDAV URL: {0}")]
+    Url(String),
+    #[error("the WebDAV server rejected the credentials")]
+    Unauthorized,
+    #[error("{method} {path} failed with status code {status}")]
+    Status {
+        method: String,
+        path: String,
+        status: u16,
+    },
+    #[error("invalid PROPFIND response: {0}")]
+    Xml(String),
+    #[error("{0} kept changing on the server; gave up after {1} attempts")]
+    Contention(String, usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,250 @@
+//! The per-book `config.json` envelope and its merge rules, mirroring
+//! `src/services/sync/file/{wire,merge}.ts`:
+//!
+//! - config scalars: last-writer-wins on `config.updatedAt`, ties to the
+//!   remote side;
+//! - booknotes: union by `id`, each note won by the newer `updatedAt` or
+//!   `deletedAt`;
+//! - `referencePageCount`: the newer side's count, where an absent or zero
+//!   count is "no opinion".
+//!
+//! Configs and notes stay `serde_json` maps, so fields this crate doesn't
+//! know about survive a round trip through the KOReader helper.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const SCHEMA_VERSION: u32 = 1;
+/// An opaque tag kept byte-stable with the TypeScript writer.
+pub const WRITER_VERSION: &str = "readest-webdav-1";
+
+type Object = Map<String, Value>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteBookConfig {
+    pub schema_version: u32,
+    pub book_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_hash: Option<String>,
+    /// `progress`, `location`, `xpointer` and `updatedAt`.
+    #[serde(default)]
+    pub config: Object,
+    #[serde(default)]
+    pub booknotes: Vec<Object>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_page_count: Option<u32>,
+    pub writer_device_id: String,
+    #[serde(default = "writer_version")]
+    pub writer_version: String,
+    /// When the writer last touched the file (millis).
+    pub updated_at: i64,
+}
+
+fn writer_version() -> String {
+    WRITER_VERSION.to_string()
+}
+
+impl RemoteBookConfig {
+    /// A remote body, or `None` if it isn't a schema-1 envelope.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice::<Self>(bytes)
+            .ok()
+            .filter(|c| c.schema_version == SCHEMA_VERSION)
+    }
+
+    /// `config.updatedAt`, falling back to `fallback`.
+    fn config_updated_at(&self, fallback: f64) -> f64 {
+        self.config
+            .get("updatedAt")
+            .and_then(Value::as_f64)
+            .unwrap_or(fallback)
+    }
+
+    /// Whether two envelopes carry the same reading state, regardless of
+    /// who wrote them, when, and in which order the notes are listed.
+    pub fn same_content(&self, other: &Self) -> bool {
+        self.book_hash == other.book_hash
+            && self.meta_hash == other.meta_hash
+            && without_nulls(&self.config) == without_nulls(&other.config)
+            && self.reference_page_count.unwrap_or(0) == other.reference_page_count.unwrap_or(0)
+            && sorted_notes(self) == sorted_notes(other)
+    }
+}
+
+fn sorted_notes(config: &RemoteBookConfig) -> Vec<&Object> {
+    let mut notes = config.booknotes.iter().collect::<Vec<_>>();
+    notes.sort_by(|a, b| note_id(a).cmp(note_id(b)));
+    notes
+}
+
+fn note_id(note: &Object) -> &str {
+    note.get("id").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn timestamp(note: &Object, key: &str) -> f64 {
+    note.get(key).and_then(Value::as_f64).unwrap_or(0.0)
+}
+
+fn without_nulls(config: &Object) -> Object {
+    config
+        .iter()
+        .filter(|(_, v)| !v.is_null())
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+/// `base` with the fields of `overlay` on top.
+fn spread(base: &Object, overlay: &Object) -> Object {
+    let mut merged = base.clone();
+    merged.extend(overlay.iter().map(|(k, v)| (k.clone(), v.clone())));
+    merged
+}
+
+/// Union by id; when both sides hold a note, the side with the newer
+/// `updatedAt` or `deletedAt` wins field by field. Local order first, then
+/// notes only the remote has.
+pub fn merge_notes(local: &[Object], remote: &[Object]) -> Vec<Object> {
+    let mut merged = local.to_vec();
+    for r in remote {
+        let Some(l) = merged.iter_mut().find(|l| note_id(l) == note_id(r)) else {
+            merged.push(r.clone());
+            continue;
+        };
+        let remote_wins = timestamp(r, "updatedAt") > timestamp(l, "updatedAt")
+            || timestamp(r, "deletedAt") > timestamp(l, "deletedAt");
+        *l = if remote_wins {
+            spread(l, r)
+        } else {
+            spread(r, l)
+        };
+    }
+    merged
+}
+
+/// `resolveReferencePageCount` in `utils/progress.ts`.
+fn resolve_reference_page_count(
+    local: Option<u32>,
+    remote: Option<u32>,
+    remote_is_newer: bool,
+) -> Option<u32> {
+    match (local.filter(|&n| n > 0), remote.filter(|&n| n > 0)) {
+        (Some(local), Some(remote)) => Some(if remote_is_newer { remote } else { local }),
+        (local, remote) => local.or(remote),
+    }
+}
+
+/// Merge what's on the server into this device's envelope. The result is
+/// written back by this device (`local`'s writer fields, `updated_at = now`)
+/// and holds the state the caller should apply locally.
+pub fn merge(
+    local: &RemoteBookConfig,
+    remote: Option<&RemoteBookConfig>,
+    now: i64,
+) -> RemoteBookConfig {
+    let mut merged = local.clone();
+    merged.schema_version = SCHEMA_VERSION;
+    merged.writer_version = WRITER_VERSION.to_string();
+    merged.updated_at = now;
+    merged.config = without_nulls(&local.config);
+
+    if let Some(remote) = remote {
+        let remote_updated = remote.config_updated_at(remote.updated_at as f64);
+        let local_updated = local.config_updated_at(0.0);
+        let remote_config = without_nulls(&remote.config);
+        merged.config = if remote_updated >= local_updated {
+            spread(&merged.config, &remote_config)
+        } else {
+            spread(&remote_config, &merged.config)
+        };
+        merged.booknotes = merge_notes(&local.booknotes, &remote.booknotes);
+        // Strict `>`: a tie keeps the local count, as the TypeScript merge does.
+        merged.reference_page_count = resolve_reference_page_count(
+            local.reference_page_count,
+            remote.reference_page_count,
+            remote_updated > local_updated,
+        );
+        merged.meta_hash = local.meta_hash.clone().or_else(|| remote.meta_hash.clone());
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn envelope(value: Value) -> RemoteBookConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn local() -> RemoteBookConfig {
+        envelope(json!({
+            "schemaVersion": 1,
+            "bookHash": "abc",
+            "config": { "progress": [10, 100], "location": "epubcfi(/6/4)", "updatedAt": 2000 },
+            "booknotes": [
+                { "id": "n1", "text": "local edit", "updatedAt": 300 },
+                { "id": "n2", "text": "only local", "updatedAt": 100 }
+            ],
+            "referencePageCount": 320,
+            "writerDeviceId": "phone",
+            "writerVersion": "readest-webdav-1",
+            "updatedAt": 2000
+        }))
+    }
+
+    #[test]
+    fn newer_local_config_wins_and_notes_union() {
+        let remote = envelope(json!({
+            "schemaVersion": 1,
+            "bookHash": "abc",
+            "config": { "progress": [5, 100], "xpointer": "/body/p[3]", "location": null, "updatedAt": 1000 },
+            "booknotes": [
+                { "id": "n1", "text": "remote edit", "updatedAt": 200 },
+                { "id": "n3", "text": "only remote", "updatedAt": 150 }
+            ],
+            "referencePageCount": 400,
+            "writerDeviceId": "kindle",
+            "writerVersion": "readest-webdav-1",
+            "updatedAt": 1000
+        }));
+        let merged = merge(&local(), Some(&remote), 5000);
+        assert_eq!(merged.config["progress"], json!([10, 100]));
+        assert_eq!(merged.config["location"], json!("epubcfi(/6/4)"));
+        assert_eq!(merged.config["xpointer"], json!("/body/p[3]"));
+        let ids = merged.booknotes.iter().map(note_id).collect::<Vec<_>>();
+        assert_eq!(ids, ["n1", "n2", "n3"]);
+        assert_eq!(merged.booknotes[0]["text"], json!("local edit"));
+        assert_eq!(merged.reference_page_count, Some(320));
+        assert_eq!(merged.writer_device_id, "phone");
+        assert_eq!(merged.updated_at, 5000);
+    }
+
+    #[test]
+    fn remote_wins_ties_and_newer_deletions() {
+        let remote = envelope(json!({
+            "schemaVersion": 1,
+            "bookHash": "abc",
+            "config": { "progress": [50, 100], "updatedAt": 2000 },
+            "booknotes": [{ "id": "n2", "updatedAt": 100, "deletedAt": 900 }],
+            "writerDeviceId": "kindle",
+            "updatedAt": 2000
+        }));
+        let merged = merge(&local(), Some(&remote), 5000);
+        assert_eq!(merged.config["progress"], json!([50, 100]));
+        assert_eq!(merged.booknotes[1]["deletedAt"], json!(900));
+        assert_eq!(merged.booknotes[1]["text"], json!("only local"));
+        // A tie keeps the local count; an absent remote count has no say.
+        assert_eq!(merged.reference_page_count, Some(320));
+        assert!(merged.same_content(&merge(&merged, Some(&remote), 6000)));
+    }
+
+    #[test]
+    fn rejects_other_schema_versions() {
+        assert!(RemoteBookConfig::parse(br#"{"schemaVersion":2}"#).is_none());
+        assert!(RemoteBookConfig::parse(b"not json").is_none());
+        let bytes = serde_json::to_vec(&local()).unwrap();
+        assert_eq!(RemoteBookConfig::parse(&bytes), Some(local()));
+    }
+}
@@ -0,0 +1,114 @@
+//! Read-merge-write against the server. Writes are conditional on the ETag
+//! that was read, so two devices syncing the same book at once can't drop
+//! each other's notes: the loser of the race re-reads, re-merges and tries
+//! again.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use reqwest::Body;
+use serde::Serialize;
+
+use crate::client::{Precondition, WebDavClient};
+use crate::layout::{book_config_path, book_dir_path, book_file_path};
+use crate::merge::{merge, RemoteBookConfig};
+use crate::{Error, Result};
+
+/// How often a lost write race is retried before giving up.
+const MAX_ATTEMPTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStatus {
+    /// The server had no config for the book; ours was uploaded.
+    Created,
+    /// The merge changed the server's copy, which was replaced.
+    Updated,
+    /// The server already held the merged state; nothing was written.
+    UpToDate,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOutcome {
+    pub status: SyncStatus,
+    /// The merged state, to apply locally.
+    pub config: RemoteBookConfig,
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Sync one book's progress and booknotes with
+/// `<root>/Readest/books/<hash>/config.json`.
+pub async fn sync_book_config(
+    client: &WebDavClient,
+    root_path: &str,
+    local: &RemoteBookConfig,
+) -> Result<SyncOutcome> {
+    let path = book_config_path(root_path, &local.book_hash);
+    for _ in 0..MAX_ATTEMPTS {
+        let (remote, precondition) = match client.get(&path).await? {
+            // An unreadable or foreign file is replaced, but only the
+            // version we looked at.
+            Some((bytes, etag)) => (
+                RemoteBookConfig::parse(&bytes),
+                etag.map_or(Precondition::None, Precondition::Matches),
+            ),
+            None => (None, Precondition::Absent),
+        };
+
+        let merged = merge(local, remote.as_ref(), now_millis());
+        if remote.as_ref().is_some_and(|r| r.same_content(&merged)) {
+            return Ok(SyncOutcome {
+                status: SyncStatus::UpToDate,
+                config: merged,
+            });
+        }
+        if precondition == Precondition::Absent {
+            client
+                .mkcol_all(&book_dir_path(root_path, &local.book_hash))
+                .await?;
+        }
+
+        let created = precondition == Precondition::Absent;
+        let body = serde_json::to_vec(&merged).expect("serializable config");
+        if client
+            .put(&path, body, "application/json", precondition)
+            .await?
+        {
+            return Ok(SyncOutcome {
+                status: if created {
+                    SyncStatus::Created
+                } else {
+                    SyncStatus::Updated
+                },
+                config: merged,
+            });
+        }
+    }
+    Err(Error::Contention(path, MAX_ATTEMPTS))
+}
+
+/// Upload a file into the book's directory unless one of that name is
+/// already there; book files and covers are immutable per hash. Returns
+/// whether it was uploaded.
+pub async fn upload_book_file(
+    client: &WebDavClient,
+    root_path: &str,
+    book_hash: &str,
+    file_name: &str,
+    body: impl Into<Body>,
+    content_type: &str,
+) -> Result<bool> {
+    client
+        .mkcol_all(&book_dir_path(root_path, book_hash))
+        .await?;
+    let path = book_file_path(root_path, book_hash, file_name);
+    client
+        .put(&path, body, content_type, Precondition::Absent)
+        .await
+}
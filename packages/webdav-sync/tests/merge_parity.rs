@@ -0,0 +1,48 @@
+//! The merge cases `mergeBookConfig` is tested against in the app
+//! (src/__tests__/services/sync/file/merge.test.ts), run through
+//! [`webdav_sync::merge::merge`], so the KOReader helper and the TypeScript
+//! engine resolve the same pair of configs the same way.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use webdav_sync::merge::merge;
+use webdav_sync::RemoteBookConfig;
+
+const CASES: &str =
+    include_str!("../../../apps/readest-app/src/__tests__/fixtures/data/webdav-merge-cases.json");
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Expected {
+    config: Map<String, Value>,
+    booknotes: Vec<Map<String, Value>>,
+    reference_page_count: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Case {
+    name: String,
+    local: RemoteBookConfig,
+    remote: RemoteBookConfig,
+    expected: Expected,
+}
+
+#[test]
+fn merges_like_the_typescript_engine() {
+    let cases: Vec<Case> = serde_json::from_str(CASES).unwrap();
+    assert!(!cases.is_empty());
+    for case in cases {
+        let merged = merge(&case.local, Some(&case.remote), 1_000_000);
+        assert_eq!(merged.config, case.expected.config, "{}: config", case.name);
+        assert_eq!(
+            merged.booknotes, case.expected.booknotes,
+            "{}: booknotes",
+            case.name
+        );
+        assert_eq!(
+            merged.reference_page_count, case.expected.reference_page_count,
+            "{}: referencePageCount",
+            case.name
+        );
+    }
+}
@@ -0,0 +1,365 @@
+//! Syncs against an in-memory WebDAV stand-in: enough of RFC 4918 (GET,
+//! PUT with If-Match / If-None-Match, MKCOL, DELETE, PROPFIND) on a local
+//! port, with the tree served under `/dav` like a Nextcloud user root.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+use serde_json::json;
+use webdav_sync::{
+    sync_book_config, upload_book_file, Connection, Depth, Error, RemoteBookConfig, SyncStatus,
+    WebDavClient,
+};
+
+/// `reader:secret`
+const AUTH: &str = "Basic cmVhZGVyOnNlY3JldA==";
+
+#[derive(Default)]
+struct Tree {
+    files: HashMap<String, (Vec<u8>, u64)>,
+    dirs: HashSet<String>,
+    version: u64,
+    requests: Vec<String>,
+    /// Written over a path right after it is next served by GET, to lose a
+    /// write race on purpose.
+    tamper: Option<(String, Vec<u8>)>,
+}
+
+impl Tree {
+    fn write(&mut self, path: &str, body: Vec<u8>) {
+        self.version += 1;
+        self.files.insert(path.to_string(), (body, self.version));
+    }
+}
+
+type Shared = Arc<Mutex<Tree>>;
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn decode(path: &str) -> String {
+    percent_encoding::percent_decode_str(path)
+        .decode_utf8_lossy()
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn respond(stream: &mut TcpStream, status: &str, headers: &[(&str, String)], body: &[u8]) {
+    let mut head = format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body);
+}
+
+fn entry(href: &str, tree: &Tree, path: &str) -> String {
+    let props = match tree.files.get(path) {
+        Some((body, version)) => format!(
+            "<d:resourcetype/><d:getetag>\"v{version}\"</d:getetag>\
+             <d:getcontentlength>{}</d:getcontentlength>",
+            body.len()
+        ),
+        None => "<d:resourcetype><d:collection/></d:resourcetype>".to_string(),
+    };
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{props}</d:prop>\
+         <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        href.replace(' ', "%20")
+    )
+}
+
+fn handle(mut stream: TcpStream, tree: &Shared) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = decode(parts.next().unwrap_or_default());
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    let len = headers
+        .get("content-length")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).unwrap();
+
+    if headers.get("authorization").map(String::as_str) != Some(AUTH) {
+        return respond(&mut stream, "401 Unauthorized", &[], b"");
+    }
+
+    let mut tree = tree.lock().unwrap();
+    tree.requests.push(format!("{method} {path}"));
+    let etag = |tree: &Tree| tree.files.get(&path).map(|(_, v)| format!("\"v{v}\""));
+    match method.as_str() {
+        "GET" => match tree.files.get(&path).cloned() {
+            Some((body, _)) => {
+                let tag = etag(&tree).unwrap();
+                respond(&mut stream, "200 OK", &[("ETag", tag)], &body);
+                if tree.tamper.as_ref().is_some_and(|(p, _)| *p == path) {
+                    let (_, body) = tree.tamper.take().unwrap();
+                    tree.write(&path, body);
+                }
+            }
+            None => respond(&mut stream, "404 Not Found", &[], b""),
+        },
+        "PUT" => {
+            let current = etag(&tree);
+            let rejected = match (headers.get("if-none-match"), headers.get("if-match")) {
+                (Some(_), _) => current.is_some(),
+                (_, Some(expected)) => current.as_ref() != Some(expected),
+                _ => false,
+            };
+            if !tree.dirs.contains(parent(&path)) {
+                respond(&mut stream, "409 Conflict", &[], b"");
+            } else if rejected {
+                respond(&mut stream, "412 Precondition Failed", &[], b"");
+            } else {
+                let status = if current.is_some() {
+                    "204 No Content"
+                } else {
+                    "201 Created"
+                };
+                tree.write(&path, body);
+                respond(&mut stream, status, &[("ETag", etag(&tree).unwrap())], b"");
+            }
+        }
+        "MKCOL" => {
+            if tree.dirs.contains(&path) || tree.files.contains_key(&path) {
+                respond(&mut stream, "405 Method Not Allowed", &[], b"");
+            } else if !tree.dirs.contains(parent(&path)) {
+                respond(&mut stream, "409 Conflict", &[], b"");
+            } else {
+                tree.dirs.insert(path);
+                respond(&mut stream, "201 Created", &[], b"");
+            }
+        }
+        "DELETE" => {
+            let prefix = format!("{path}/");
+            let existed = tree.files.remove(&path).is_some() | tree.dirs.remove(&path);
+            tree.files.retain(|p, _| !p.starts_with(&prefix));
+            tree.dirs.retain(|p| !p.starts_with(&prefix));
+            let status = if existed {
+                "204 No Content"
+            } else {
+                "404 Not Found"
+            };
+            respond(&mut stream, status, &[], b"");
+        }
+        "PROPFIND" => {
+            if !tree.dirs.contains(&path) && !tree.files.contains_key(&path) {
+                return respond(&mut stream, "404 Not Found", &[], b"");
+            }
+            let mut xml = String::from(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">"#);
+            xml.push_str(&entry(&format!("{path}/"), &tree, &path));
+            if headers.get("depth").map(String::as_str) == Some("1") {
+                let mut members = tree
+                    .files
+                    .keys()
+                    .chain(tree.dirs.iter())
+                    .filter(|p| parent(p) == path)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                members.sort();
+                for member in members {
+                    xml.push_str(&entry(&member, &tree, &member));
+                }
+            }
+            xml.push_str("</d:multistatus>");
+            respond(&mut stream, "207 Multi-Status", &[], xml.as_bytes());
+        }
+        _ => respond(&mut stream, "405 Method Not Allowed", &[], b""),
+    }
+}
+
+/// Serve a fresh tree; returns the server URL and the tree to inspect.
+fn stand_in() -> (String, Shared) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let tree = Shared::default();
+    tree.lock().unwrap().dirs.insert("/dav".into());
+    let served = tree.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle(stream, &served);
+        }
+    });
+    (format!("http://127.0.0.1:{port}/dav"), tree)
+}
+
+fn client(url: &str, password: &str) -> WebDavClient {
+    let connection = Connection {
+        url: url.to_string(),
+        username: Some("reader".into()),
+        password: Some(password.into()),
+        root_path: "/sync".into(),
+    };
+    WebDavClient::new(reqwest::Client::new(), &connection).unwrap()
+}
+
+fn device(id: &str, progress: u32, updated_at: i64, notes: serde_json::Value) -> RemoteBookConfig {
+    serde_json::from_value(json!({
+        "schemaVersion": 1,
+        "bookHash": "abc",
+        "config": { "progress": [progress, 100], "updatedAt": updated_at },
+        "booknotes": notes,
+        "writerDeviceId": id,
+        "updatedAt": updated_at
+    }))
+    .unwrap()
+}
+
+const CONFIG: &str = "/dav/sync/Readest/books/abc/config.json";
+
+#[tokio::test]
+async fn two_devices_converge() {
+    let (url, tree) = stand_in();
+    let dav = client(&url, "secret");
+
+    let phone = device(
+        "phone",
+        10,
+        1000,
+        json!([{ "id": "n1", "updatedAt": 1000 }]),
+    );
+    let first = sync_book_config(&dav, "/sync", &phone).await.unwrap();
+    assert_eq!(first.status, SyncStatus::Created);
+    assert!(tree
+        .lock()
+        .unwrap()
+        .dirs
+        .contains("/dav/sync/Readest/books/abc"));
+
+    let kindle = device(
+        "kindle",
+        40,
+        2000,
+        json!([{ "id": "n2", "updatedAt": 1500 }]),
+    );
+    let second = sync_book_config(&dav, "/sync", &kindle).await.unwrap();
+    assert_eq!(second.status, SyncStatus::Updated);
+    assert_eq!(second.config.config["progress"], json!([40, 100]));
+    assert_eq!(second.config.booknotes.len(), 2);
+
+    // The phone picks up the newer position and both notes, and the server
+    // already holds that state.
+    let again = sync_book_config(&dav, "/sync", &phone).await.unwrap();
+    assert_eq!(again.status, SyncStatus::UpToDate);
+    assert_eq!(again.config.config["progress"], json!([40, 100]));
+    assert_eq!(again.config.booknotes.len(), 2);
+    let stored = RemoteBookConfig::parse(&tree.lock().unwrap().files[CONFIG].0).unwrap();
+    assert_eq!(stored.writer_device_id, "kindle");
+}
+
+#[tokio::test]
+async fn a_lost_write_race_is_merged_and_retried() {
+    let (url, tree) = stand_in();
+    let dav = client(&url, "secret");
+    sync_book_config(&dav, "/sync", &device("phone", 10, 1000, json!([])))
+        .await
+        .unwrap();
+
+    // Another device writes a note between our GET and our PUT.
+    let rival = device(
+        "tablet",
+        20,
+        1500,
+        json!([{ "id": "rival", "updatedAt": 1500 }]),
+    );
+    tree.lock().unwrap().tamper = Some((CONFIG.into(), serde_json::to_vec(&rival).unwrap()));
+
+    let kindle = device(
+        "kindle",
+        30,
+        2000,
+        json!([{ "id": "mine", "updatedAt": 2000 }]),
+    );
+    let outcome = sync_book_config(&dav, "/sync", &kindle).await.unwrap();
+    assert_eq!(outcome.status, SyncStatus::Updated);
+    let stored = RemoteBookConfig::parse(&tree.lock().unwrap().files[CONFIG].0).unwrap();
+    let mut ids = stored
+        .booknotes
+        .iter()
+        .map(|n| n["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, ["mine", "rival"]);
+    let puts = tree
+        .lock()
+        .unwrap()
+        .requests
+        .iter()
+        .filter(|r| r.starts_with("PUT"))
+        .count();
+    assert_eq!(puts, 3);
+}
+
+#[tokio::test]
+async fn uploads_book_files_once_and_lists_them() {
+    let (url, _tree) = stand_in();
+    let dav = client(&url, "secret");
+    let upload = |body: &'static [u8]| {
+        upload_book_file(
+            &dav,
+            "/sync",
+            "abc",
+            "Dune Messiah.epub",
+            body,
+            "application/epub+zip",
+        )
+    };
+    assert!(upload(b"epub").await.unwrap());
+    assert!(!upload(b"epub").await.unwrap());
+
+    let listing = dav
+        .propfind("/sync/Readest/books/abc", Depth::One)
+        .await
+        .unwrap();
+    let paths = listing.iter().map(|r| r.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            "/sync/Readest/books/abc",
+            "/sync/Readest/books/abc/Dune Messiah.epub"
+        ]
+    );
+    assert!(listing[0].is_collection);
+    assert_eq!(listing[1].content_length, Some(4));
+    assert!(dav
+        .propfind("/missing", Depth::Zero)
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert!(dav.delete("/sync/Readest").await.unwrap());
+    assert!(dav
+        .get("/sync/Readest/books/abc/Dune Messiah.epub")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn rejects_bad_credentials() {
+    let (url, _tree) = stand_in();
+    let err = client(&url, "wrong").get("/sync").await.unwrap_err();
+    assert!(matches!(err, Error::Unauthorized));
+}